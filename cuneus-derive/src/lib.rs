@@ -0,0 +1,79 @@
+//! `#[derive(UniformProvider)]` for cuneus. Use it through `cuneus::UniformProvider`
+//! rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields};
+
+/// Implement `cuneus::UniformProvider` for a `#[repr(C)]` `Pod` struct, uploading it
+/// with `bytemuck::bytes_of`.
+///
+/// Also asserts at compile time that the size is a multiple of 16 bytes and that every
+/// field sits where WGSL's uniform layout puts it, so a `[f32; 3]` that WGSL would move
+/// to the next 16 bytes fails to compile instead of garbling every field after it.
+#[proc_macro_derive(UniformProvider)]
+pub fn derive_uniform_provider(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(name, "UniformProvider needs a struct with named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(name, "UniformProvider can only be derived for structs")),
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "UniformProvider can't be derived for generic structs"));
+    }
+    let repr_c = input.attrs.iter().any(|attr| {
+        let mut c = false;
+        if attr.path().is_ident("repr") {
+            let _ = attr.parse_nested_meta(|meta| {
+                c |= meta.path.is_ident("C");
+                Ok(())
+            });
+        }
+        c
+    });
+    if !repr_c {
+        return Err(syn::Error::new_spanned(name, "UniformProvider needs #[repr(C)], so field offsets match WGSL"));
+    }
+
+    let size_message = format!("`{name}` size must be a multiple of 16 bytes (add padding fields)");
+    let field_checks = fields.iter().map(|field| {
+        let ty = &field.ty;
+        let field = field.ident.as_ref().expect("named field");
+        let message = format!(
+            "field `{name}::{field}` is not aligned the way WGSL lays out uniforms \
+             (vec2 on 8 bytes, vec3/vec4/arrays on 16); add padding before it"
+        );
+        quote! {
+            assert!(
+                ::core::mem::offset_of!(#name, #field) % <#ty as ::cuneus::WgslAlign>::ALIGN == 0,
+                #message
+            );
+        }
+    });
+
+    Ok(quote! {
+        impl ::cuneus::UniformProvider for #name {
+            fn as_bytes(&self) -> &[u8] {
+                ::cuneus::bytemuck::bytes_of(self)
+            }
+        }
+
+        // Structs nested in a uniform align on 16
+        impl ::cuneus::WgslAlign for #name {
+            const ALIGN: usize = 16;
+        }
+
+        const _: () = {
+            assert!(::core::mem::size_of::<#name>() % 16 == 0, #size_message);
+            #(#field_checks)*
+        };
+    })
+}
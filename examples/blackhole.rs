@@ -22,6 +22,7 @@ struct BlackHoleShader {
     base: RenderKit,
     compute_shader: ComputeShader,
     current_params: BlackHoleParams,
+    show_bloom_preview: bool,
 }
 
 impl ShaderManager for BlackHoleShader {
@@ -70,12 +71,13 @@ impl ShaderManager for BlackHoleShader {
             base,
             compute_shader,
             current_params: initial_params,
+            show_bloom_preview: false,
         }
     }
 
     fn update(&mut self, core: &Core) {
         let current_time = self.base.controls.get_time(&self.base.start_time);
-        let delta = 1.0 / 60.0;
+        let delta = self.base.frame_delta();
         self.compute_shader.set_time(current_time, delta, &core.queue);
         self.compute_shader.handle_export(core, &mut self.base);
     }
@@ -95,6 +97,12 @@ impl ShaderManager for BlackHoleShader {
             &core.size,
             self.base.fps_tracker.fps(),
         );
+        // Last frame's bloom, drawn into a thumbnail before this frame's dispatch
+        let bloom_preview = self
+            .show_bloom_preview
+            .then(|| self.compute_shader.buffer_texture("blur3_v"))
+            .flatten()
+            .map(|texture| self.base.egui_preview(core, &mut frame.encoder, "bloom", texture, 240));
 
         let full_output = if self.base.key_handler.show_ui {
             self.base.render_ui(core, |ctx| {
@@ -149,6 +157,10 @@ impl ShaderManager for BlackHoleShader {
                                 changed |= ui.add(egui::Slider::new(&mut params.gamma, 0.6..=2.2).text("Gamma")).changed();
                                 changed |= ui.add(egui::Slider::new(&mut params.bloom, 0.0..=0.4).text("Bloom")).changed();
                                 changed |= ui.add(egui::Slider::new(&mut params.star_density, 0.0..=2.0).text("Stars")).changed();
+                                ui.checkbox(&mut self.show_bloom_preview, "Preview Bloom Buffer");
+                                if let Some(preview) = bloom_preview {
+                                    ui.image(preview);
+                                }
                             });
 
                         ui.separator();
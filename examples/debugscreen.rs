@@ -57,7 +57,7 @@ impl ShaderManager for DebugScreen {
     fn update(&mut self, core: &Core) {
         // Update time
         let current_time = self.base.controls.get_time(&self.base.start_time);
-        let delta = 1.0 / 60.0;
+        let delta = self.base.frame_delta();
         self.compute_shader
             .set_time(current_time, delta, &core.queue);
 
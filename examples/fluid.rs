@@ -126,7 +126,7 @@ impl ShaderManager for FluidShader {
             );
         }
         let current_time = self.base.controls.get_time(&self.base.start_time);
-        let delta = 1.0 / 60.0;
+        let delta = self.base.frame_delta();
         self.compute_shader.set_time(current_time, delta, &core.queue);
         self.compute_shader.handle_export(core, &mut self.base);
     }
@@ -214,7 +214,7 @@ impl ShaderManager for FluidShader {
         if controls_request.should_clear_buffers {
             self.compute_shader.current_frame = 0;
         }
-        if !self.base.export_manager.is_exporting() {
+        if !self.base.export_manager.is_exporting() && self.base.controls.advance_frame() {
             self.compute_shader.dispatch(&mut frame.encoder, core);
         }
         self.base.renderer.render_to_view(&mut frame.encoder, &frame.view, &self.compute_shader.get_output_texture().bind_group);
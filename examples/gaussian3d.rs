@@ -163,9 +163,9 @@ impl Gaussian3DShader {
         self.preprocess.set_time(time, 1.0 / settings.fps as f32, &core.queue);
 
         if let Some(ref bg) = self.render_bind_group {
-            GaussianExporter::export_frame(
+            GaussianExporter::export_frame_to(
                 core, &mut self.preprocess, &self.sorter, &self.renderer,
-                bg, self.params.num_gaussians, frame, &settings, self.surface_format,
+                bg, self.params.num_gaussians, frame, &mut self.base.export_manager, self.surface_format,
             );
         }
     }
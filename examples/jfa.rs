@@ -113,7 +113,7 @@ impl ShaderManager for JfaShader {
         self.compute_shader.handle_export(core, &mut self.base);
 
         let current_time = self.base.controls.get_time(&self.base.start_time);
-        let delta = 1.0 / 60.0;
+        let delta = self.base.frame_delta();
         self.compute_shader
             .set_time(current_time, delta, &core.queue);
     }
@@ -1,8 +1,9 @@
+use cuneus::camera::OrbitCamera;
 use cuneus::compute::ComputeShader;
 use cuneus::{
-    Core, ExportManager, RenderKit, ShaderControls, ShaderManager,
+    Core, ExportManager, PresetManager, RenderKit, Session, ShaderControls, ShaderManager,
 };
-use log::debug;
+use log::{debug, error};
 use cuneus::WindowEvent;
 
 cuneus::uniform_params! {
@@ -56,20 +57,24 @@ cuneus::uniform_params! {
 }
 }
 
+const DEFAULT_STOP_THRESHOLD: f32 = 1e-6;
+/// Mostly flat regions can look converged after a handful of frames
+const MIN_SAMPLES_FOR_STOP: u32 = 16;
+
 struct MandelbulbShader {
     base: RenderKit,
     compute_shader: ComputeShader,
     frame_count: u32,
     should_reset_accumulation: bool,
     current_params: MandelbulbParams,
-    // Mouse tracking for delta-based rotation
-    previous_mouse_pos: [f32; 2],
+    /// Stop dispatching once this many frames are accumulated; `None` refines forever
+    max_samples: Option<u32>,
+    /// Stop once the per-frame change from `convergence_estimate` drops below this
+    stop_threshold: Option<f32>,
+    convergence: Option<f32>,
+    camera: OrbitCamera,
     mouse_enabled: bool,
-    mouse_initialized: bool,
-    // Accumulated rotation (persists across frames)
-    accumulated_rotation: [f32; 3],
-    // Accumulated zoom from mouse wheel
-    accumulated_zoom: f32,
+    presets: PresetManager,
 }
 
 impl MandelbulbShader {
@@ -77,6 +82,18 @@ impl MandelbulbShader {
         self.compute_shader.current_frame = 0;
         self.should_reset_accumulation = false;
         self.frame_count = 0;
+        self.compute_shader.reset_convergence();
+        self.convergence = None;
+    }
+
+    fn converged(&self) -> bool {
+        let below_threshold = self.frame_count >= MIN_SAMPLES_FOR_STOP
+            && self
+                .stop_threshold
+                .zip(self.convergence)
+                .is_some_and(|(threshold, estimate)| estimate < threshold);
+        self.current_params.accumulate > 0
+            && (self.max_samples.is_some_and(|max| self.frame_count >= max) || below_threshold)
     }
 }
 
@@ -161,11 +178,12 @@ impl ShaderManager for MandelbulbShader {
             frame_count: 0,
             should_reset_accumulation: true,
             current_params: initial_params,
-            previous_mouse_pos: [0.5, 0.5],
+            max_samples: Some(1024),
+            stop_threshold: Some(DEFAULT_STOP_THRESHOLD),
+            convergence: None,
+            camera: OrbitCamera::new(),
             mouse_enabled: false,
-            mouse_initialized: false,
-            accumulated_rotation: [0.0, 0.0, 0.0],
-            accumulated_zoom: 1.0,
+            presets: PresetManager::new("presets/mandelbulb"),
         }
     }
 
@@ -175,6 +193,37 @@ impl ShaderManager for MandelbulbShader {
         self.compute_shader.handle_export(core, &mut self.base);
     }
 
+    fn save_session(&self, session: &mut Session) {
+        self.base.save_session(session);
+        // Rotation and zoom live in the params, so the camera comes along
+        if let Err(e) = session.set_params(&self.current_params, MandelbulbParams::FIELD_NAMES) {
+            error!("Failed to store params in the session: {e:#}");
+        }
+        session.set("mouse_enabled", &self.mouse_enabled);
+        session.set("max_samples", &self.max_samples);
+        session.set("stop_threshold", &self.stop_threshold);
+    }
+
+    fn load_session(&mut self, core: &Core, session: &Session) {
+        self.base.load_session(core, session);
+        if let Some(params) = session.params::<MandelbulbParams>(MandelbulbParams::FIELD_NAMES) {
+            self.current_params = params;
+            self.camera.rotation = [params.rotation_x, -params.rotation_y, params.rotation_z];
+            self.camera.set_zoom(params.zoom);
+            self.compute_shader.set_custom_params(params, &core.queue);
+            self.should_reset_accumulation = true;
+        }
+        if let Some(mouse_enabled) = session.get("mouse_enabled") {
+            self.mouse_enabled = mouse_enabled;
+        }
+        if let Some(max_samples) = session.get("max_samples") {
+            self.max_samples = max_samples;
+        }
+        if let Some(stop_threshold) = session.get("stop_threshold") {
+            self.stop_threshold = stop_threshold;
+        }
+    }
+
     fn resize(&mut self, core: &Core) {
         self.base.default_resize(core, &mut self.compute_shader);
         debug!("Resizing to {:?}", core.size);
@@ -187,34 +236,19 @@ impl ShaderManager for MandelbulbShader {
         let mouse_wheel = self.base.mouse_tracker.uniform.wheel;
 
         if mouse_wheel[1].abs() > 0.001 {
-            let zoom_sensitivity = 0.1;
-            self.accumulated_zoom *= 1.0 - mouse_wheel[1] * zoom_sensitivity;
-            self.accumulated_zoom = self.accumulated_zoom.clamp(0.2, 5.0);
-            self.should_reset_accumulation = true;
+            self.camera.zoom(mouse_wheel[1]);
         }
 
         if self.mouse_enabled {
-            if !self.mouse_initialized {
-                self.previous_mouse_pos = current_mouse_pos;
-                self.mouse_initialized = true;
-            } else {
-                let delta_x: f32 = current_mouse_pos[0] - self.previous_mouse_pos[0];
-                let delta_y = current_mouse_pos[1] - self.previous_mouse_pos[1];
-                if delta_x.abs() > 0.0001 || delta_y.abs() > 0.0001 {
-                    let base_sensitivity = 5.0;
-                    let aspect = core.size.width as f32 / core.size.height as f32;
-                    self.accumulated_rotation[0] += delta_x * base_sensitivity;
-                    self.accumulated_rotation[1] += delta_y * base_sensitivity * aspect;
-                    self.should_reset_accumulation = true;
-                    self.previous_mouse_pos = current_mouse_pos;
-                }
-            }
+            let aspect = core.size.width as f32 / core.size.height as f32;
+            self.camera.drag(current_mouse_pos, aspect);
         }
 
         self.base.mouse_tracker.reset_wheel();
 
         let mut params = self.current_params;
         let mut changed = false;
+        let mut preset_loaded = false;
         let mut should_start_export = false;
         let mut export_request = self.base.export_manager.get_ui_request();
         let mut controls_request = self
@@ -223,10 +257,13 @@ impl ShaderManager for MandelbulbShader {
             .get_ui_request(&self.base.start_time, &core.size, self.base.fps_tracker.fps());
 
         let current_fps = self.base.fps_tracker.fps();
+        self.convergence = self.compute_shader.convergence_estimate(&core.device);
+        let converged = self.converged();
 
         let full_output = if self.base.key_handler.show_ui {
             self.base.render_ui(core, |ctx| {
                 RenderKit::apply_default_style(ctx);
+                ShaderControls::render_shader_error(ctx, self.compute_shader.shader_error());
 
                 egui::Window::new("Mandelbulb PathTracer")
                     .collapsible(true)
@@ -239,8 +276,9 @@ impl ShaderManager for MandelbulbShader {
                         egui::CollapsingHeader::new("Camera&View")
                             .default_open(false)
                             .show(ui, |ui| {
-                                if ui.add(egui::Slider::new(&mut self.accumulated_zoom, 0.2..=5.0).text("Zoom")).changed() {
-                                    self.should_reset_accumulation = true;
+                                let mut zoom = self.camera.zoom;
+                                if ui.add(egui::Slider::new(&mut zoom, 0.2..=5.0).text("Zoom")).changed() {
+                                    self.camera.set_zoom(zoom);
                                 }
                                 changed |= ui
                                     .add(
@@ -259,7 +297,7 @@ impl ShaderManager for MandelbulbShader {
                                 let old_mouse_enabled = self.mouse_enabled;
                                 ui.checkbox(&mut self.mouse_enabled, "Mouse Camera Control (M key)");
                                 if self.mouse_enabled != old_mouse_enabled {
-                                    self.mouse_initialized = false;
+                                    self.camera.end_drag();
                                 }
                                 if !self.mouse_enabled {
                                     ui.colored_label(
@@ -271,12 +309,10 @@ impl ShaderManager for MandelbulbShader {
                                 }
                                 ui.horizontal(|ui| {
                                     if ui.button("Reset Rotation").clicked() {
-                                        self.accumulated_rotation = [0.0, 0.0, 0.0];
-                                        self.should_reset_accumulation = true;
+                                        self.camera.reset_rotation();
                                     }
                                     if ui.button("Reset Zoom").clicked() {
-                                        self.accumulated_zoom = 1.0;
-                                        self.should_reset_accumulation = true;
+                                        self.camera.reset_zoom();
                                     }
                                 });
                             });
@@ -344,6 +380,30 @@ impl ShaderManager for MandelbulbShader {
                                     )
                                     .changed();
 
+                                ui.horizontal(|ui| {
+                                    let mut capped = self.max_samples.is_some();
+                                    ui.checkbox(&mut capped, "Sample Cap");
+                                    let mut max = self.max_samples.unwrap_or(1024);
+                                    ui.add_enabled(
+                                        capped,
+                                        egui::DragValue::new(&mut max).range(1..=1_000_000).speed(16),
+                                    );
+                                    self.max_samples = capped.then_some(max);
+                                });
+
+                                ui.horizontal(|ui| {
+                                    let mut auto_stop = self.stop_threshold.is_some();
+                                    ui.checkbox(&mut auto_stop, "Auto Stop");
+                                    let mut threshold = self.stop_threshold.unwrap_or(DEFAULT_STOP_THRESHOLD);
+                                    ui.add_enabled(
+                                        auto_stop,
+                                        egui::Slider::new(&mut threshold, 1e-8..=1e-3)
+                                            .logarithmic(true)
+                                            .custom_formatter(|v, _| format!("{v:.0e}")),
+                                    );
+                                    self.stop_threshold = auto_stop.then_some(threshold);
+                                });
+
                                 if ui.button("Reset Accumulation").clicked() {
                                     self.should_reset_accumulation = true;
                                     changed = true;
@@ -513,6 +573,19 @@ impl ShaderManager for MandelbulbShader {
 
                         ui.separator();
 
+                        egui::CollapsingHeader::new("Presets")
+                            .default_open(false)
+                            .show(ui, |ui| {
+                                preset_loaded = self.presets.render_preset_widget(
+                                    ui,
+                                    &mut params,
+                                    MandelbulbParams::FIELD_NAMES,
+                                );
+                                changed |= preset_loaded;
+                            });
+
+                        ui.separator();
+
                         ShaderControls::render_controls_widget(ui, &mut controls_request);
 
                         ui.separator();
@@ -521,7 +594,26 @@ impl ShaderManager for MandelbulbShader {
                             ExportManager::render_export_ui_widget(ui, &mut export_request);
 
                         ui.separator();
-                        ui.label(format!("Accumulated Samples: {}", self.frame_count));
+                        let samples = match self.max_samples {
+                            Some(max) => format!("{}/{max}", self.frame_count),
+                            None => self.frame_count.to_string(),
+                        };
+                        if converged {
+                            ui.label(format!("Accumulated Samples: {samples}, converged"));
+                        } else {
+                            ui.label(format!("Accumulated Samples: {samples}"));
+                        }
+                        if self.current_params.accumulate > 0
+                            && let Some(estimate) = self.convergence
+                        {
+                            // Noise falls as 1/samples, so the square root tracks progress evenly
+                            let threshold = self.stop_threshold.unwrap_or(DEFAULT_STOP_THRESHOLD);
+                            let progress = (threshold / estimate.max(f32::MIN_POSITIVE)).sqrt().min(1.0);
+                            ui.label(format!(
+                                "Convergence: {:.0}% ({estimate:.1e} change per frame)",
+                                progress * 100.0
+                            ));
+                        }
                         ui.label(format!(
                             "Resolution: {}x{}",
                             core.size.width, core.size.height
@@ -534,6 +626,9 @@ impl ShaderManager for MandelbulbShader {
         };
 
         self.base.export_manager.apply_ui_request(export_request);
+        if self.camera.take_changed() {
+            self.should_reset_accumulation = true;
+        }
         if controls_request.should_clear_buffers || self.should_reset_accumulation {
             self.reset_accumulation();
         }
@@ -543,11 +638,12 @@ impl ShaderManager for MandelbulbShader {
 
         self.base.time_uniform.data.time = current_time;
         self.base.time_uniform.data.frame = self.frame_count;
+        self.base.time_uniform.data.delta = self.base.frame_delta();
         self.base.time_uniform.update(&core.queue);
 
         // Update compute shader with the same time data
         self.compute_shader
-            .set_time(current_time, 1.0 / 60.0, &core.queue);
+            .set_time(current_time, self.base.frame_delta(), &core.queue);
         self.compute_shader.time_uniform.data.frame = self.frame_count;
         self.compute_shader.time_uniform.update(&core.queue);
 
@@ -555,24 +651,38 @@ impl ShaderManager for MandelbulbShader {
             self.current_params = params;
             self.should_reset_accumulation = true;
         }
+        if preset_loaded {
+            // The camera owns rotation and zoom, so move it to the preset's view
+            self.camera.rotation = [params.rotation_x, -params.rotation_y, params.rotation_z];
+            self.camera.set_zoom(params.zoom);
+        }
 
-        self.current_params.rotation_x = self.accumulated_rotation[0];
-        self.current_params.rotation_y = -self.accumulated_rotation[1];
-        self.current_params.rotation_z = self.accumulated_rotation[2];
-        self.current_params.zoom = self.accumulated_zoom;
+        let camera = self.camera.uniform();
+        self.current_params.rotation_x = camera.rotation[0];
+        self.current_params.rotation_y = -camera.rotation[1];
+        self.current_params.rotation_z = camera.rotation[2];
+        self.current_params.zoom = camera.zoom;
         self.compute_shader.set_custom_params(self.current_params, &core.queue);
 
         if should_start_export {
             self.base.export_manager.start_export();
         }
 
-        self.compute_shader.dispatch(&mut frame.encoder, core);
+        // Once converged the output keeps the final image; any change resets the count
+        let converged = self.converged();
+        if !converged {
+            self.compute_shader.dispatch(&mut frame.encoder, core);
+            if self.current_params.accumulate > 0 {
+                self.compute_shader
+                    .encode_convergence(&core.device, &mut frame.encoder, "accumulate");
+            }
+        }
 
         self.base.renderer.render_to_view(&mut frame.encoder, &frame.view, &self.compute_shader.get_output_texture().bind_group);
 
         self.base.end_frame(core, frame, full_output);
 
-        if self.current_params.accumulate > 0 {
+        if self.current_params.accumulate > 0 && !converged {
             self.frame_count += 1;
         }
 
@@ -603,49 +713,43 @@ impl ShaderManager for MandelbulbShader {
                     "m" | "M" => {
                         if event.state == winit::event::ElementState::Released {
                             self.mouse_enabled = !self.mouse_enabled;
-                            self.mouse_initialized = false;
+                            self.camera.end_drag();
                             return true;
                         }
                     }
                     "w" | "W" => {
                         if event.state == winit::event::ElementState::Pressed {
-                            self.accumulated_rotation[1] -= 0.1;
-                            self.should_reset_accumulation = true;
+                            self.camera.rotate(0.0, -0.1);
                             return true;
                         }
                     }
                     "s" | "S" => {
                         if event.state == winit::event::ElementState::Pressed {
-                            self.accumulated_rotation[1] += 0.1;
-                            self.should_reset_accumulation = true;
+                            self.camera.rotate(0.0, 0.1);
                             return true;
                         }
                     }
                     "a" | "A" => {
                         if event.state == winit::event::ElementState::Pressed {
-                            self.accumulated_rotation[0] -= 0.1;
-                            self.should_reset_accumulation = true;
+                            self.camera.rotate(-0.1, 0.0);
                             return true;
                         }
                     }
                     "d" | "D" => {
                         if event.state == winit::event::ElementState::Pressed {
-                            self.accumulated_rotation[0] += 0.1;
-                            self.should_reset_accumulation = true;
+                            self.camera.rotate(0.1, 0.0);
                             return true;
                         }
                     }
                     "q" | "Q" => {
                         if event.state == winit::event::ElementState::Pressed {
-                            self.accumulated_rotation[2] -= 0.1;
-                            self.should_reset_accumulation = true;
+                            self.camera.roll(-0.1);
                             return true;
                         }
                     }
                     "e" | "E" => {
                         if event.state == winit::event::ElementState::Pressed {
-                            self.accumulated_rotation[2] += 0.1;
-                            self.should_reset_accumulation = true;
+                            self.camera.roll(0.1);
                             return true;
                         }
                     }
@@ -671,6 +775,7 @@ impl ShaderManager for MandelbulbShader {
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
     let (app, event_loop) = cuneus::ShaderApp::new("Mandelbulb Path Tracer", 600, 400);
+    let app = app.with_session("sessions/mandelbulb.json");
 
     app.run(event_loop, MandelbulbShader::init)
 }
@@ -296,7 +296,7 @@ impl ShaderManager for Shader {
         }
 
         let current_time = self.base.controls.get_time(&self.base.start_time);
-        let delta_time = 1.0 / 60.0;
+        let delta_time = self.base.frame_delta();
         self.compute_shader.set_time(current_time, delta_time, &core.queue);
         self.compute_shader.update_mouse_uniform(&self.base.mouse_tracker.uniform, &core.queue);
 
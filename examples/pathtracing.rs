@@ -1,168 +1,8 @@
+use cuneus::camera::FlyCamera;
 use cuneus::compute::*;
 use cuneus::prelude::*;
 use log::error;
 
-struct CameraMovement {
-    forward: bool,
-    backward: bool,
-    left: bool,
-    right: bool,
-    up: bool,
-    down: bool,
-    speed: f32,
-    last_update: std::time::Instant,
-
-    yaw: f32,
-    pitch: f32,
-    mouse_sensitivity: f32,
-
-    last_mouse_x: f32,
-    last_mouse_y: f32,
-    mouse_initialized: bool,
-    mouse_look_enabled: bool,
-    look_changed: bool}
-
-impl Default for CameraMovement {
-    fn default() -> Self {
-        Self {
-            forward: false,
-            backward: false,
-            left: false,
-            right: false,
-            up: false,
-            down: false,
-            speed: 2.0,
-            last_update: std::time::Instant::now(),
-
-            yaw: 0.0,
-            pitch: 0.0,
-            mouse_sensitivity: 0.005,
-
-            last_mouse_x: 0.0,
-            last_mouse_y: 0.0,
-            mouse_initialized: false,
-            mouse_look_enabled: true,
-            look_changed: false}
-    }
-}
-
-impl CameraMovement {
-    fn update_camera(&mut self, params: &mut PathTracingParams) -> bool {
-        let now = std::time::Instant::now();
-        let dt = now.duration_since(self.last_update).as_secs_f32();
-        self.last_update = now;
-
-        let mut changed = false;
-
-        if self.look_changed {
-            changed = true;
-            self.look_changed = false;
-        }
-
-        let forward = [
-            self.pitch.cos() * self.yaw.cos(),
-            self.pitch.sin(),
-            self.pitch.cos() * self.yaw.sin(),
-        ];
-
-        let world_up = [0.0, 1.0, 0.0];
-        let right = [
-            forward[1] * world_up[2] - forward[2] * world_up[1],
-            forward[2] * world_up[0] - forward[0] * world_up[2],
-            forward[0] * world_up[1] - forward[1] * world_up[0],
-        ];
-
-        let right_len = (right[0] * right[0] + right[1] * right[1] + right[2] * right[2]).sqrt();
-        let right = [
-            right[0] / right_len,
-            right[1] / right_len,
-            right[2] / right_len,
-        ];
-
-        let delta = self.speed * dt;
-        let mut move_vec = [0.0, 0.0, 0.0];
-
-        if self.forward {
-            move_vec[0] += forward[0] * delta;
-            move_vec[1] += forward[1] * delta;
-            move_vec[2] += forward[2] * delta;
-            changed = true;
-        }
-        if self.backward {
-            move_vec[0] -= forward[0] * delta;
-            move_vec[1] -= forward[1] * delta;
-            move_vec[2] -= forward[2] * delta;
-            changed = true;
-        }
-        if self.right {
-            move_vec[0] += right[0] * delta;
-            move_vec[1] += right[1] * delta;
-            move_vec[2] += right[2] * delta;
-            changed = true;
-        }
-        if self.left {
-            move_vec[0] -= right[0] * delta;
-            move_vec[1] -= right[1] * delta;
-            move_vec[2] -= right[2] * delta;
-            changed = true;
-        }
-        if self.up {
-            move_vec[1] += delta;
-            changed = true;
-        }
-        if self.down {
-            move_vec[1] -= delta;
-            changed = true;
-        }
-
-        params.camera_pos_x += move_vec[0];
-        params.camera_pos_y += move_vec[1];
-        params.camera_pos_z += move_vec[2];
-
-        let look_distance = 1.0;
-        params.camera_target_x = params.camera_pos_x + forward[0] * look_distance;
-        params.camera_target_y = params.camera_pos_y + forward[1] * look_distance;
-        params.camera_target_z = params.camera_pos_z + forward[2] * look_distance;
-
-        changed
-    }
-
-    fn handle_mouse_movement(&mut self, x: f32, y: f32) -> bool {
-        if !self.mouse_look_enabled {
-            return false;
-        }
-
-        if !self.mouse_initialized {
-            self.last_mouse_x = x;
-            self.last_mouse_y = y;
-            self.mouse_initialized = true;
-            return false;
-        }
-
-        let dx = x - self.last_mouse_x;
-        let dy = y - self.last_mouse_y;
-
-        self.last_mouse_x = x;
-        self.last_mouse_y = y;
-
-        self.yaw += dx * self.mouse_sensitivity;
-        self.pitch -= dy * self.mouse_sensitivity;
-
-        self.pitch = self
-            .pitch
-            .clamp(-std::f32::consts::PI * 0.49, std::f32::consts::PI * 0.49);
-
-        self.look_changed = true;
-
-        true
-    }
-
-    fn toggle_mouse_look(&mut self) {
-        self.mouse_look_enabled = !self.mouse_look_enabled;
-        self.mouse_initialized = false;
-    }
-}
-
 cuneus::uniform_params! {
     struct PathTracingParams {
     camera_pos_x: f32,
@@ -191,7 +31,8 @@ struct PathTracingShader {
     base: RenderKit,
     compute_shader: ComputeShader,
     current_params: PathTracingParams,
-    camera_movement: CameraMovement,
+    camera: FlyCamera,
+    last_camera_update: std::time::Instant,
     frame_count: u32,
     should_reset_accumulation: bool}
 
@@ -247,7 +88,9 @@ impl ShaderManager for PathTracingShader {
             base,
             compute_shader,
             current_params: initial_params,
-            camera_movement: CameraMovement::default(),
+            // Facing -z and tilted down toward (0, 0, -1), the initial camera target
+            camera: FlyCamera::new([0.0, 1.0, 6.0], -std::f32::consts::FRAC_PI_2, -(1.0f32 / 7.0).atan()),
+            last_camera_update: std::time::Instant::now(),
             frame_count: 0,
             should_reset_accumulation: true}
     }
@@ -255,7 +98,7 @@ impl ShaderManager for PathTracingShader {
     fn update(&mut self, core: &Core) {
         // Update time
         let current_time = self.base.controls.get_time(&self.base.start_time);
-        let delta = 1.0 / 60.0;
+        let delta = self.base.frame_delta();
         self.compute_shader
             .set_time(current_time, delta, &core.queue);
 
@@ -269,7 +112,21 @@ impl ShaderManager for PathTracingShader {
             );
         }
 
-        if self.camera_movement.update_camera(&mut self.current_params) {
+        let now = std::time::Instant::now();
+        let dt = now.duration_since(self.last_camera_update).as_secs_f32();
+        self.last_camera_update = now;
+        if self.camera.update(dt) {
+            let view = self.camera.view();
+            [
+                self.current_params.camera_pos_x,
+                self.current_params.camera_pos_y,
+                self.current_params.camera_pos_z,
+            ] = view.position;
+            [
+                self.current_params.camera_target_x,
+                self.current_params.camera_target_y,
+                self.current_params.camera_target_z,
+            ] = view.target;
             self.compute_shader
                 .set_custom_params(self.current_params, &core.queue);
             self.should_reset_accumulation = true;
@@ -457,44 +314,12 @@ impl ShaderManager for PathTracingShader {
         }
 
         if let WindowEvent::KeyboardInput { event, .. } = event {
+            if self.camera.process_key(event) {
+                self.should_reset_accumulation = true;
+                return true;
+            }
             if let winit::keyboard::Key::Character(ch) = &event.logical_key {
                 match ch.as_str() {
-                    "w" | "W" => {
-                        self.camera_movement.forward =
-                            event.state == winit::event::ElementState::Pressed;
-                        self.should_reset_accumulation = true;
-                        return true;
-                    }
-                    "s" | "S" => {
-                        self.camera_movement.backward =
-                            event.state == winit::event::ElementState::Pressed;
-                        self.should_reset_accumulation = true;
-                        return true;
-                    }
-                    "a" | "A" => {
-                        self.camera_movement.left =
-                            event.state == winit::event::ElementState::Pressed;
-                        self.should_reset_accumulation = true;
-                        return true;
-                    }
-                    "d" | "D" => {
-                        self.camera_movement.right =
-                            event.state == winit::event::ElementState::Pressed;
-                        self.should_reset_accumulation = true;
-                        return true;
-                    }
-                    "q" | "Q" => {
-                        self.camera_movement.down =
-                            event.state == winit::event::ElementState::Pressed;
-                        self.should_reset_accumulation = true;
-                        return true;
-                    }
-                    "e" | "E" => {
-                        self.camera_movement.up =
-                            event.state == winit::event::ElementState::Pressed;
-                        self.should_reset_accumulation = true;
-                        return true;
-                    }
                     " " => {
                         if event.state == winit::event::ElementState::Released {
                             self.current_params.accumulate = 1 - self.current_params.accumulate;
@@ -515,7 +340,7 @@ impl ShaderManager for PathTracingShader {
 
             self.base.handle_mouse_input(core, event, false);
 
-            if self.camera_movement.handle_mouse_movement(x, y) {
+            if self.camera.process_mouse_position(x, y) {
                 self.should_reset_accumulation = true;
                 return true;
             }
@@ -525,7 +350,7 @@ impl ShaderManager for PathTracingShader {
             if *button == winit::event::MouseButton::Right
                 && *state == winit::event::ElementState::Released
             {
-                self.camera_movement.toggle_mouse_look();
+                self.camera.toggle_mouse_look();
                 return true;
             }
         }
@@ -68,7 +68,7 @@ impl ShaderManager for PhysarumShader {
 
     fn update(&mut self, core: &Core) {
         let current_time = self.base.controls.get_time(&self.base.start_time);
-        self.compute_shader.set_time(current_time, 1.0 / 60.0, &core.queue);
+        self.compute_shader.set_time(current_time, self.base.frame_delta(), &core.queue);
         self.compute_shader.handle_export(core, &mut self.base);
     }
 
@@ -179,8 +179,15 @@ impl ShaderManager for PhysarumShader {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
     let (app, event_loop) = ShaderApp::new("Physarum Engine", 1280, 720);
     app.run(event_loop, PhysarumShader::init)
+}
+
+// cargo build --example physarum --target wasm32-unknown-unknown --no-default-features --features wasm
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    cuneus::run_web("Physarum Engine", 1280, 720, PhysarumShader::init);
 }
\ No newline at end of file
@@ -0,0 +1,97 @@
+//! Radix sort benchmark: times `RadixSorter` with and without subgroup operations on
+//! random 32-bit keys and checks that both produce the same stable order.
+//!
+//! cargo run --release --example radix_sort_bench -- [count] [runs]
+
+use std::time::{Duration, Instant};
+
+use cuneus::radix_sort::RadixSorter;
+
+fn main() {
+    env_logger::init();
+    let mut args = std::env::args().skip(1);
+    let count: u32 = args.next().and_then(|s| s.parse().ok()).unwrap_or(1 << 22);
+    let runs: u32 = args.next().and_then(|s| s.parse().ok()).unwrap_or(20);
+
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        ..Default::default()
+    }))
+    .expect("No suitable GPU adapter found");
+    let info = adapter.get_info();
+    let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        label: Some("Radix Sort Bench"),
+        required_features: adapter.features() & wgpu::Features::SUBGROUP,
+        ..Default::default()
+    }))
+    .expect("Failed to create device");
+    println!(
+        "{} ({:?}), subgroups {}-{}, {count} keys, {runs} runs",
+        info.name, info.backend, info.subgroup_min_size, info.subgroup_max_size
+    );
+
+    // xorshift keys drawn from a small range, so equal keys exercise stability
+    let mut state = 0x9E37_79B9u32;
+    let keys: Vec<u32> = (0..count)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state % (count / 4).max(1)
+        })
+        .collect();
+    let payload: Vec<u32> = (0..count).collect();
+    let mut expected: Vec<(u32, u32)> = keys.iter().copied().zip(payload.iter().copied()).collect();
+    expected.sort_by_key(|&(key, _)| key);
+
+    let mut baseline = None;
+    for (name, sorter) in [
+        ("portable", RadixSorter::new_without_subgroups(&device)),
+        ("subgroups", RadixSorter::new(&device)),
+    ] {
+        if name == "subgroups" && !sorter.uses_subgroups() {
+            println!("{name:>9}: not supported by this adapter");
+            continue;
+        }
+        let buffers = sorter.create_sort_buffers(&device, count);
+        let mut times = Vec::with_capacity(runs as usize);
+        for run in 0..=runs {
+            queue.write_buffer(&buffers.keys_a, 0, bytemuck::cast_slice(&keys));
+            queue.write_buffer(&buffers.payload_a, 0, bytemuck::cast_slice(&payload));
+            queue.submit(None);
+            let _ = device.poll(wgpu::PollType::wait_indefinitely());
+
+            let start = Instant::now();
+            let mut encoder = device.create_command_encoder(&Default::default());
+            sorter.sort(&mut encoder, &queue, &buffers, count);
+            queue.submit(Some(encoder.finish()));
+            let _ = device.poll(wgpu::PollType::wait_indefinitely());
+            // The first run includes pipeline warm-up
+            if run > 0 {
+                times.push(start.elapsed());
+            }
+        }
+
+        let (sorted_keys, sorted_payload) = buffers.read_back(&device, &queue);
+        let correct = sorted_keys
+            .iter()
+            .zip(&sorted_payload)
+            .zip(&expected)
+            .all(|((&key, &value), &(expected_key, expected_value))| key == expected_key && value == expected_value);
+        let failed = sorter.check_failed(&device, &queue, &buffers);
+
+        times.sort();
+        let median = times[times.len() / 2];
+        let speedup = baseline.map_or(String::new(), |base: Duration| {
+            format!(", {:.2}x", base.as_secs_f64() / median.as_secs_f64())
+        });
+        println!(
+            "{name:>9}: median {:.3} ms, {:.1} Mkeys/s{speedup}, {}",
+            median.as_secs_f64() * 1e3,
+            count as f64 / median.as_secs_f64() / 1e6,
+            if correct && !failed { "stable and sorted" } else { "WRONG ORDER" }
+        );
+        baseline.get_or_insert(median);
+    }
+}
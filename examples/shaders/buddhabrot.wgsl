@@ -1,5 +1,6 @@
 // Experimental Buddhabrot Compute Shader, Enes Altun, 2025
 // A special rendering of the Mandelbrot set tracking escape trajectories
+//!include <cuneus/tonemap.wgsl>
 struct TimeUniform {
     time: f32,
     delta: f32,
@@ -121,23 +122,6 @@ fn complex_to_screen(p: v2) -> v2 {
     return uv * 0.5 + 0.5;
 }
 
-fn aces_tonemap(color: v3) -> v3 {
-    const m1 = m3(
-        0.59719, 0.07600, 0.02840,
-        0.35458, 0.90834, 0.13383,
-        0.04823, 0.01566, 0.83777
-    );
-    const m2 = m3(
-        1.60475, -0.10208, -0.00327,
-        -0.53108,  1.10813, -0.07276,
-        -0.07367, -0.00605,  1.07602
-    );
-    var v = m1 * color;
-    var a = v * (v + 0.0245786) - 0.000090537;
-    var b = v * (0.983729 * v + 0.4329510) + 0.238081;
-    return m2 * (a / b);
-}
-
 fn escape_count(c: v2, max_iters: u32) -> u32 {
     var z = v2(0.0, 0.0);
     for (var n: u32 = 0; n < max_iters; n++) {
@@ -277,7 +261,7 @@ fn main_image(@builtin(global_invocation_id) id: vec3<u32>) {
         col += v3(noise);
     }
 
-    col = aces_tonemap(col);
+    col = aces_fitted_unclamped(col);
     col = pow(max(v3(0.0), col), v3(1.0 / params.gamma));
 
     textureStore(output, vec2<i32>(id.xy), v4(col, 1.0));
@@ -1,6 +1,7 @@
 // Enes Altun, 2025 cc 3.0 
 // Spectral CIE-XYZ accumulation; the look was inspired by sintel's "spectral clusters"
 // (compute.toys/view/1517). 
+//!include <cuneus/tonemap.wgsl>
 struct TimeUniform { time: f32, delta: f32, frame: u32, _padding: u32 };
 @group(0) @binding(0) var<uniform> time_data: TimeUniform;
 
@@ -112,14 +113,6 @@ fn lens(_p: v2, pz: f32, focal: f32) -> v2 {
     return _p + sample_disk() * coc;
 }
 
-fn aces_tonemap(color: v3) -> v3 {
-    const m1 = m3(0.59719, 0.07600, 0.02840, 0.35458, 0.90834, 0.13383, 0.04823, 0.01566, 0.83777);
-    const m2 = m3(1.60475, -0.10208, -0.00327, -0.53108, 1.10813, -0.07276, -0.07367, -0.00605, 1.07602);
-    var v = m1 * color;
-    var a = v * (v + 0.0245786) - 0.000090537;
-    var b = v * (0.983729 * v + 0.4329510) + 0.238081;
-    return m2 * (a / b);
-}
 @compute @workgroup_size(256, 1, 1)
 fn Splat(@builtin(global_invocation_id) id: vec3<u32>) {
     let Ru = vec2<u32>(textureDimensions(output));
@@ -198,7 +191,7 @@ fn main_image(@builtin(global_invocation_id) id: vec3<u32>) {
     var col = xyz_to_rgb * col_xyz;
     col = col * f32(WH) * 2e-9 / 64.0;
     col = max(v3(0.0), col);
-    col = aces_tonemap(col);
+    col = aces_fitted_unclamped(col);
 
     let uv = v2(f32(id.x), f32(id.y)) / v2(res);
     col *= 1.0 - 0.28 * dot(uv - 0.5, uv - 0.5) * 2.0;
@@ -1,4 +1,5 @@
 // Enes Altun 2025, CC BY-NC-SA 3.0
+//!include <cuneus/tonemap.wgsl>
 struct TimeUniform {
     time: f32,
     delta: f32,
@@ -36,11 +37,6 @@ alias v2 = vec2<f32>;
 alias v3 = vec3<f32>;
 alias v4 = vec4<f32>;
 
-fn aces(x:v3)->v3{
-    let a=2.51; let b=.03; let c=2.43; let d=.59; let e=.14;
-    return clamp((x*(a*x+b))/(x*(c*x+d)+e),v3(0.),v3(1.));
-}
-
 fn rnd(s:v2)->f32{
     return fract(sin(dot(s,v2(12.9898,78.233)))*43758.5453);
 }
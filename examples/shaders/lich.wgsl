@@ -1,4 +1,5 @@
 // Lichtenberg noise math inspired by: Lichtenberg figure by rory618 2018, https://www.shadertoy.com/view/3sl3WH
+//!include <cuneus/tonemap.wgsl>
 
 // Group 0: Time uniform
 struct TimeUniform { time: f32, delta: f32, frame: u32, _padding: u32 };
@@ -118,10 +119,6 @@ fn process_color(base_color: vec3<f32>, wave: f32, spectrum_mix: f32) -> vec3<f3
     return mix(base_color, spectral, spectrum_mix);
 }
 
-fn aces_tonemap(x: vec3<f32>) -> vec3<f32> {
-    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
-}
-
 // Pass 1: Lightning generation pass
 @compute @workgroup_size(16, 16, 1)
 fn lightning(@builtin(global_invocation_id) id: vec3<u32>) {
@@ -253,7 +250,7 @@ fn main_image(@builtin(global_invocation_id) id: vec3<u32>) {
 
     colored_conduit += base_data.rgb * field_lum * params.glow_intensity * 0.6;
 
-    var final_color = aces_tonemap(colored_conduit);
+    var final_color = aces(colored_conduit);
 
     let gray = dot(final_color, vec3<f32>(0.2126, 0.7152, 0.0722));
     final_color = mix(vec3<f32>(gray), final_color, params.saturation);
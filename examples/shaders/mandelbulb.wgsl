@@ -9,6 +9,7 @@
 // - Surface offset technique: https://www.shadertoy.com/view/lsXGzH
 // - The idea for path tracing for 3D fractals: Kleinian Seahorse: https://www.shadertoy.com/view/Ns2fzy by tdhooper;
 // - http://blog.hvidtfeldts.net/index.php/2015/01/path-tracing-3d-fractals/
+//!include <cuneus/tonemap.wgsl>
 
 struct TimeUniform {
     time: f32,
@@ -441,16 +442,6 @@ fn accumulate(@builtin(global_invocation_id) global_id: vec3<u32>) {
     textureStore(output, vec2<i32>(global_id.xy), final_color);
 }
 
-// Tonemapping and gamma correction
-fn aces_tonemap(x: v3) -> v3 {
-    let a = 2.51;
-    let b = 0.03;
-    let c = 2.43;
-    let d = 0.59;
-    let e = 0.14;
-    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), v3(0.0), v3(1.0));
-}
-
 @compute @workgroup_size(16, 16, 1)
 fn main_image(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let dimensions = textureDimensions(output);
@@ -468,10 +459,10 @@ fn main_image(@builtin(global_invocation_id) global_id: vec3<u32>) {
     col *= params.exposure;
 
     // ACES tonemapping
-    col = aces_tonemap(col);
+    col = aces(col);
 
     // Gamma correction
-    col = pow(col, v3(1.0 / params.gamma));
+    col = gamma_encode(col, params.gamma);
 
     textureStore(output, vec2<i32>(global_id.xy), v4(col, 1.0));
 }
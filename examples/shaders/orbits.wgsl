@@ -1,6 +1,7 @@
 // Orbits 3D orbit trap Mandelbrot with PBR metal shading
 // Enes Altun, 2026; CC 4.0
 // Trap technique: https://iquilezles.org/articles/ftrapsgeometric/
+//!include <cuneus/tonemap.wgsl>
 struct TimeUniform {
     time: f32,
     delta: f32,
@@ -265,10 +266,6 @@ fn g(c: vec3<f32>, g: f32) -> vec3<f32> {
     return pow(c, vec3(1. / g));
 }
 
-fn aces(x: vec3<f32>) -> vec3<f32> {
-    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3(0.), vec3(1.));
-}
-
 // ggx ndf
 fn D_ggx(NoH: f32, a: f32) -> f32 {
     let a2 = a * a;
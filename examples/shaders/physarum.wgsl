@@ -1,5 +1,6 @@
 // Enes Altun, 2026;
 // This work is licensed under a Creative Commons Attribution-NonCommercial-ShareAlike 4.0 Unported License.
+//!include <cuneus/tonemap.wgsl>
 
 struct TimeUniform { time: f32, delta: f32, frame: u32, _padding: u32 };
 @group(0) @binding(0) var<uniform> u_t: TimeUniform;
@@ -232,7 +233,6 @@ fn turing_resolve(@builtin(global_invocation_id) id:u3){
 
 fn dT(x:f32)->f32{return tanh(log(1.+max(0.,x))*.8);}
 fn hRot(c:v3,a:f32)->v3{let k=v3(.57735);let ca=cos(a);let sa=sin(a);return c*ca+cross(k,c)*sa+k*dot(k,c)*(1.-ca);}
-fn aces(x:v3)->v3{return clamp((x*(2.51*x+.03))/(x*(2.43*x+.59)+.14),v3(0.),v3(1.));}
 
 @compute @workgroup_size(16,16,1)
 fn main_image(@builtin(global_invocation_id) id:u3){
@@ -1,5 +1,6 @@
 // Enes Altun, 2026;
 // This work is licensed under a Creative Commons Attribution-NonCommercial-ShareAlike 3.0 Unported License.
+//!include <cuneus/tonemap.wgsl>
 
 struct TimeUniform {
     time: f32,
@@ -403,11 +404,6 @@ fn accumulate(@builtin(global_invocation_id) global_id: vec3<u32>) {
     textureStore(out, vec2<i32>(global_id.xy), final_color);
 }
 
-fn aces_tonemap(x: v3) -> v3 {
-    let a = 2.51; let b = 0.03; let c = 2.43; let d = 0.59; let e = 0.14;
-    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), v3(0.0), v3(1.0));
-}
-
 @compute @workgroup_size(16, 16, 1)
 fn main_image(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let dim = textureDimensions(out);
@@ -417,7 +413,7 @@ fn main_image(@builtin(global_invocation_id) global_id: vec3<u32>) {
     var col = tex.rgb / max(tex.a, 1.0);
 
     col *= p.exposure;
-    col = aces_tonemap(col);
+    col = aces(col);
     
     let lum = dot(col, v3(0.299, 0.587, 0.114));
     col = mix(v3(lum), col, p.saturation);
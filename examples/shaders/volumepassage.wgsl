@@ -1,5 +1,6 @@
 // Enes Altun, 2025 
 // This work is licensed under a Creative Commons Attribution-NonCommercial-ShareAlike 3.0 Unported License.
+//!include <cuneus/tonemap.wgsl>
 
 struct TimeUniform {
     time: f32,
@@ -64,24 +65,6 @@ fn mm2(a: f32) -> m2 {
     return m2(c, -s, s, c);
 }
 
-fn aces_tonemap(color: v3) -> v3 {
-    const m1 = mat3x3<f32>(
-        0.59719, 0.07600, 0.02840,
-        0.35458, 0.90834, 0.13383,
-        0.04823, 0.01566, 0.83777
-    );
-    const m2 = mat3x3<f32>(
-        1.60475, -0.10208, -0.00327,
-        -0.53108,  1.10813, -0.07276,
-        -0.07367, -0.00605,  1.07602
-    );
-
-    var v = m1 * color;    
-    var a = v * (v + 0.0245786) - 0.000090537;
-    var b = v * (0.983729 * v + 0.4329510) + 0.238081;
-    return m2 * (a / b);
-}
-
 fn pmap(value: f32, domain: f32) -> f32 {
     let r = value % domain;
     return select(r + domain, r, r >= 0.0) - (domain * 0.5);
@@ -133,7 +116,7 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
     }
     
     var result = tanh(o * params.intensity);
-    result = aces_tonemap(result);
+    result = aces_fitted_unclamped(result);
     result = max(result, v3(0.0));
     result = pow(result, v3(1.0 / params.gamma));
     
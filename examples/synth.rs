@@ -135,7 +135,7 @@ impl ShaderManager for SynthManager {
     fn update(&mut self, core: &Core) {
         // lets drive the shader clock from the AUDIO clock
         let audio_time = self.audio_start.elapsed().as_secs_f32();
-        let delta = 1.0 / 60.0;
+        let delta = self.base.frame_delta();
         self.compute_shader
             .set_time(audio_time, delta, &core.queue);
 
@@ -321,10 +321,9 @@ impl ShaderManager for ExperimentShader {
         if controls_request.should_clear_buffers || self.should_reset_accumulation || changed {
             self.reset_accumulation();
         }
-        self.base.apply_control_request(controls_request);
 
         let current_time = self.base.controls.get_time(&self.base.start_time);
-        self.compute_shader.set_time(current_time, 1.0 / 60.0, &core.queue);
+        self.compute_shader.set_time(current_time, self.base.frame_delta(), &core.queue);
 
         if changed || self.should_reset_accumulation {
             self.current_params = params;
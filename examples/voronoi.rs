@@ -114,7 +114,7 @@ impl ShaderManager for Voronoi {
 
     fn update(&mut self, core: &Core) {
         let current_time = self.base.controls.get_time(&self.base.start_time);
-        let delta = 1.0 / 60.0;
+        let delta = self.base.frame_delta();
         self.compute_shader.set_time(current_time, delta, &core.queue);
 
         self.base.update_current_texture(core, &core.queue);
@@ -266,7 +266,7 @@ impl ShaderManager for Voronoi {
         }
 
         let current_time = self.base.controls.get_time(&self.base.start_time);
-        let delta_time = 1.0 / 60.0;
+        let delta_time = self.base.frame_delta();
         self.compute_shader.set_time(current_time, delta_time, &core.queue);
 
         self.compute_shader.dispatch(&mut frame.encoder, core);
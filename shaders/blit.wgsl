@@ -6,4 +6,19 @@ fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
     var color = textureSample(texture, tex_sampler, tex_coords);
     color.a = 1.0;
     return color;
-}
\ No newline at end of file
+}
+
+// Used for transparent exports: keeps the alpha written by the compute shader
+@fragment
+fn fs_main_alpha(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    return textureSample(texture, tex_sampler, tex_coords);
+}
+
+// Used for the display blit on surfaces without an sRGB format, which store what they're given
+@fragment
+fn fs_main_srgb(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(texture, tex_sampler, tex_coords).rgb;
+    let low = color * 12.92;
+    let high = 1.055 * pow(max(color, vec3<f32>(0.0)), vec3<f32>(1.0 / 2.4)) - 0.055;
+    return vec4<f32>(select(high, low, color <= vec3<f32>(0.0031308)), 1.0);
+}
@@ -0,0 +1,20 @@
+// Environment map lookups, included with `//!include <cuneus/envmap.wgsl>`.
+// Directions are world space with +Y up and don't need to be normalized.
+
+const ENV_PI: f32 = 3.14159265359;
+
+// Latitude/longitude coordinates of a direction: u wraps around +Y, v = 0 looks straight up
+fn equirect_uv(dir: vec3<f32>) -> vec2<f32> {
+    let d = normalize(dir);
+    return vec2<f32>(atan2(d.z, d.x) / (2.0 * ENV_PI) + 0.5, acos(clamp(d.y, -1.0, 1.0)) / ENV_PI);
+}
+
+// For a map from `TextureManager::load_equirect`. The explicit level avoids a seam where u wraps.
+fn sample_equirect(env: texture_2d<f32>, env_sampler: sampler, dir: vec3<f32>) -> vec3<f32> {
+    return textureSampleLevel(env, env_sampler, equirect_uv(dir), 0.0).rgb;
+}
+
+// For an `EnvironmentMap` cubemap
+fn sample_env(env: texture_cube<f32>, env_sampler: sampler, dir: vec3<f32>) -> vec3<f32> {
+    return textureSampleLevel(env, env_sampler, dir, 0.0).rgb;
+}
@@ -0,0 +1,35 @@
+// Text queued with `ComputeShader::draw_text`, included with `//!include <cuneus/text.wgsl>`.
+// The atlas and its sizes are arguments, so the shader keeps its own bindings.
+
+// Matches `cuneus::GlyphInstance`
+struct Glyph {
+    position: vec2<f32>,
+    size: vec2<f32>,
+    color: vec4<f32>,
+    code: u32,
+}
+
+// The `glyphs` buffer added by `.with_text(max_glyphs)`
+struct Glyphs {
+    count: u32,
+    glyphs: array<Glyph>,
+}
+
+// Coverage of atlas glyph `id` at `uv` (0..1 within its cell). `char_size` and `grid_size`
+// are the `FontUniforms` fields of the same names.
+fn sample_glyph(atlas: texture_2d<f32>, char_size: vec2<f32>, grid_size: vec2<f32>, id: u32, uv: vec2<f32>) -> f32 {
+    let grid = u32(grid_size.x);
+    let cell = vec2<f32>(f32(id % grid), f32(id / grid));
+    let texel = vec2<i32>((cell + uv * 0.9 + 0.05) * char_size);
+    return smoothstep(0.1, 0.9, textureLoad(atlas, texel, 0).r);
+}
+
+// `base` with `glyph` blended over it at `pixel`, unchanged outside the glyph's cell
+fn blend_glyph(atlas: texture_2d<f32>, char_size: vec2<f32>, grid_size: vec2<f32>, glyph: Glyph, pixel: vec2<f32>, base: vec3<f32>) -> vec3<f32> {
+    let uv = (pixel - glyph.position) / glyph.size;
+    if (any(uv < vec2<f32>(0.0)) || any(uv >= vec2<f32>(1.0))) {
+        return base;
+    }
+    let coverage = sample_glyph(atlas, char_size, grid_size, glyph.code, uv);
+    return mix(base, glyph.color.rgb, coverage * glyph.color.a);
+}
@@ -0,0 +1,95 @@
+// Shared tonemapping and color grading, included with `//!include <cuneus/tonemap.wgsl>`.
+// Inputs are linear HDR color; every operator returns linear color in [0, 1], except
+// `aces_fitted_unclamped`.
+
+// Mirrors `cuneus::ToneMapParams`
+struct ToneMapParams {
+    // Linear multiplier applied before the curve
+    exposure: f32,
+    // Display gamma for `gamma_encode`; 1.0 leaves the output linear
+    gamma: f32,
+    // 0 is grayscale, 1 unchanged
+    saturation: f32,
+    // 0 ACES, 1 ACES fitted, 2 Reinhard, 3 filmic, 4 clamp only
+    curve: u32,
+};
+
+// Narkowicz's single-curve ACES approximation
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Hill's fit of the ACES RRT + ODT, with the sRGB <-> ACEScg matrices. The matrices
+// can push saturated colors slightly outside [0, 1]; shaders that grade afterwards
+// may want to keep that range.
+fn aces_fitted_unclamped(color: vec3<f32>) -> vec3<f32> {
+    let to_aces = mat3x3<f32>(
+        0.59719, 0.07600, 0.02840,
+        0.35458, 0.90834, 0.13383,
+        0.04823, 0.01566, 0.83777
+    );
+    let from_aces = mat3x3<f32>(
+        1.60475, -0.10208, -0.00327,
+        -0.53108, 1.10813, -0.07276,
+        -0.07367, -0.00605, 1.07602
+    );
+    let v = to_aces * color;
+    let a = v * (v + 0.0245786) - 0.000090537;
+    let b = v * (0.983729 * v + 0.4329510) + 0.238081;
+    return from_aces * (a / b);
+}
+
+fn aces_fitted(color: vec3<f32>) -> vec3<f32> {
+    return clamp(aces_fitted_unclamped(color), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn reinhard(x: vec3<f32>) -> vec3<f32> {
+    return max(x, vec3<f32>(0.0)) / (1.0 + max(x, vec3<f32>(0.0)));
+}
+
+fn hable_curve(x: vec3<f32>) -> vec3<f32> {
+    let a = 0.15;
+    let b = 0.50;
+    let c = 0.10;
+    let d = 0.20;
+    let e = 0.02;
+    let f = 0.30;
+    return (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f;
+}
+
+// Hable's Uncharted 2 filmic curve, white point 11.2
+fn filmic(x: vec3<f32>) -> vec3<f32> {
+    let white = hable_curve(vec3<f32>(11.2));
+    return clamp(hable_curve(max(x, vec3<f32>(0.0)) * 2.0) / white, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Exact sRGB transfer curve, for writing into non-sRGB 8-bit targets
+fn srgb_encode(linear: vec3<f32>) -> vec3<f32> {
+    let x = max(linear, vec3<f32>(0.0));
+    let low = x * 12.92;
+    let high = 1.055 * pow(x, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, x <= vec3<f32>(0.0031308));
+}
+
+fn gamma_encode(linear: vec3<f32>, gamma: f32) -> vec3<f32> {
+    return pow(max(linear, vec3<f32>(0.0)), vec3<f32>(1.0 / gamma));
+}
+
+fn adjust_saturation(color: vec3<f32>, saturation: f32) -> vec3<f32> {
+    let luma = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    return mix(vec3<f32>(luma), color, saturation);
+}
+
+// Exposure, saturation, the chosen curve, then gamma
+fn tonemap(color: vec3<f32>, params: ToneMapParams) -> vec3<f32> {
+    let graded = adjust_saturation(color * params.exposure, params.saturation);
+    var mapped: vec3<f32>;
+    switch params.curve {
+        case 0u: { mapped = aces(graded); }
+        case 1u: { mapped = aces_fitted(graded); }
+        case 2u: { mapped = reinhard(graded); }
+        case 3u: { mapped = filmic(graded); }
+        default: { mapped = clamp(graded, vec3<f32>(0.0), vec3<f32>(1.0)); }
+    }
+    return gamma_encode(mapped, params.gamma);
+}
@@ -0,0 +1,275 @@
+//! Keyframe animation of custom uniform parameters.
+//!
+//! A [`Timeline`] holds one track of `(time, value)` keyframes per named `f32`
+//! parameter. Sample it with the same clock the shader sees (`controls.get_time` live,
+//! the export frame time during export) and the animation plays back identically in
+//! both.
+
+use std::collections::BTreeMap;
+
+/// How a keyframe blends into the next one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// Eases in and out of each keyframe
+    Smoothstep,
+    /// Holds the value until the next keyframe
+    Step,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    pub time: f32,
+    pub value: f32,
+    /// Curve from this keyframe to the next
+    pub interpolation: Interpolation,
+}
+
+/// Keyframes of one parameter, kept sorted by time
+#[derive(Debug, Clone, Default)]
+pub struct Track {
+    keyframes: Vec<Keyframe>,
+}
+
+impl Track {
+    /// Keyframes closer than this in time replace each other
+    const TIME_EPSILON: f32 = 1e-3;
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Insert a keyframe, replacing one at (almost) the same time
+    pub fn insert(&mut self, keyframe: Keyframe) {
+        match self
+            .keyframes
+            .iter()
+            .position(|k| k.time >= keyframe.time - Self::TIME_EPSILON)
+        {
+            Some(i) if (self.keyframes[i].time - keyframe.time).abs() < Self::TIME_EPSILON => {
+                self.keyframes[i] = keyframe;
+            }
+            Some(i) => self.keyframes.insert(i, keyframe),
+            None => self.keyframes.push(keyframe),
+        }
+    }
+
+    /// Remove the keyframe nearest to `time` within `tolerance` seconds
+    pub fn remove_near(&mut self, time: f32, tolerance: f32) -> bool {
+        let nearest = self
+            .keyframes
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (i, (k.time - time).abs()))
+            .filter(|(_, distance)| *distance <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, _)) = nearest {
+            self.keyframes.remove(i);
+        }
+        nearest.is_some()
+    }
+
+    /// Value at `time`; holds the first/last value outside the keyframed range
+    pub fn sample(&self, time: f32) -> Option<f32> {
+        let first = self.keyframes.first()?;
+        if time <= first.time {
+            return Some(first.value);
+        }
+        let next = self.keyframes.iter().position(|k| k.time > time);
+        let Some(next) = next else {
+            return self.keyframes.last().map(|k| k.value);
+        };
+        let (a, b) = (self.keyframes[next - 1], self.keyframes[next]);
+        let t = (time - a.time) / (b.time - a.time);
+        let t = match a.interpolation {
+            Interpolation::Linear => t,
+            Interpolation::Smoothstep => t * t * (3.0 - 2.0 * t),
+            Interpolation::Step => 0.0,
+        };
+        Some(a.value + (b.value - a.value) * t)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+}
+
+/// A track that [`Timeline::apply_to_params`] couldn't place in the params struct
+#[derive(Debug, thiserror::Error)]
+pub enum AnimError {
+    #[error("animated parameter `{0}` is not a field of the params struct")]
+    UnknownField(String),
+    #[error("animated parameter `{0}` at offset {1} doesn't fit an f32 in the params struct")]
+    OutOfBounds(String, usize),
+}
+
+/// Keyframe tracks for named `f32` parameters.
+///
+/// ```ignore
+/// // live, in render():
+/// let time = self.base.controls.get_time(&self.base.start_time);
+/// if let Err(e) = self.timeline.apply_to_params(time, &mut self.current_params, MyParams::FIELD_OFFSETS) {
+///     log::warn!("{e}");
+/// }
+///
+/// // right-click a slider to keyframe it:
+/// let response = ui.add(egui::Slider::new(&mut params.scale, 0.0..=2.0).text("Scale"));
+/// self.timeline.keyframe_menu(&response, "scale", time, params.scale);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    tracks: BTreeMap<String, Track>,
+    /// Interpolation for keyframes added through `add_keyframe` and the slider menu
+    pub default_interpolation: Interpolation,
+}
+
+impl Timeline {
+    /// Right-click distance, in seconds, within which "Remove keyframe" finds a key
+    const REMOVE_TOLERANCE: f32 = 0.1;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_keyframe(&mut self, name: &str, time: f32, value: f32) {
+        let interpolation = self.default_interpolation;
+        self.tracks.entry(name.to_string()).or_default().insert(Keyframe {
+            time,
+            value,
+            interpolation,
+        });
+    }
+
+    pub fn remove_keyframe(&mut self, name: &str, time: f32) -> bool {
+        let Some(track) = self.tracks.get_mut(name) else {
+            return false;
+        };
+        let removed = track.remove_near(time, Self::REMOVE_TOLERANCE);
+        if track.is_empty() {
+            self.tracks.remove(name);
+        }
+        removed
+    }
+
+    pub fn clear_track(&mut self, name: &str) {
+        self.tracks.remove(name);
+    }
+
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+    }
+
+    pub fn track(&self, name: &str) -> Option<&Track> {
+        self.tracks.get(name)
+    }
+
+    pub fn tracks(&self) -> impl Iterator<Item = (&str, &Track)> {
+        self.tracks.iter().map(|(name, track)| (name.as_str(), track))
+    }
+
+    pub fn is_animated(&self, name: &str) -> bool {
+        self.tracks.contains_key(name)
+    }
+
+    pub fn sample(&self, name: &str, time: f32) -> Option<f32> {
+        self.tracks.get(name)?.sample(time)
+    }
+
+    /// Overwrite `value` with the track's value at `time`, if `name` is animated
+    pub fn apply(&self, name: &str, time: f32, value: &mut f32) -> bool {
+        match self.sample(name, time) {
+            Some(sampled) => {
+                *value = sampled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Write every track into the matching `f32` field of `params`, found by name in
+    /// `fields`, a `(name, byte offset)` table such as `MyParams::FIELD_OFFSETS` from
+    /// `uniform_params!` (or one built with `std::mem::offset_of!`). Tracks must name
+    /// `f32` fields. Fails without touching `params` if a track has no field in the
+    /// table, or its offset doesn't leave room for an `f32`.
+    pub fn apply_to_params<T: bytemuck::Pod>(
+        &self,
+        time: f32,
+        params: &mut T,
+        fields: &[(&str, usize)],
+    ) -> Result<(), AnimError> {
+        let mut targets = Vec::with_capacity(self.tracks.len());
+        for (name, track) in &self.tracks {
+            let Some(&(_, offset)) = fields.iter().find(|(field, _)| field == name) else {
+                return Err(AnimError::UnknownField(name.clone()));
+            };
+            if offset + 4 > std::mem::size_of::<T>() {
+                return Err(AnimError::OutOfBounds(name.clone(), offset));
+            }
+            targets.push((offset, track));
+        }
+        let bytes = bytemuck::bytes_of_mut(params);
+        for (offset, track) in targets {
+            if let Some(value) = track.sample(time) {
+                bytes[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    /// Right-click menu for a slider: add a keyframe at `time` with the slider's
+    /// current value, remove the nearby one, or clear the track.
+    pub fn keyframe_menu(&mut self, response: &egui::Response, name: &str, time: f32, value: f32) {
+        response.context_menu(|ui| {
+            if ui.button(format!("Add keyframe at {time:.2}s")).clicked() {
+                self.add_keyframe(name, time, value);
+                ui.close();
+            }
+            let animated = self.is_animated(name);
+            if ui
+                .add_enabled(animated, egui::Button::new("Remove keyframe here"))
+                .clicked()
+            {
+                self.remove_keyframe(name, time);
+                ui.close();
+            }
+            if ui
+                .add_enabled(animated, egui::Button::new("Clear animation"))
+                .clicked()
+            {
+                self.clear_track(name);
+                ui.close();
+            }
+        });
+    }
+
+    /// Track list with keyframe counts, an interpolation picker and a Clear button
+    pub fn render_timeline_widget(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("New keys:");
+            for (interpolation, label) in [
+                (Interpolation::Linear, "Linear"),
+                (Interpolation::Smoothstep, "Smooth"),
+                (Interpolation::Step, "Step"),
+            ] {
+                ui.selectable_value(&mut self.default_interpolation, interpolation, label);
+            }
+        });
+        if self.tracks.is_empty() {
+            ui.label("Right-click a slider to add a keyframe");
+            return;
+        }
+        let mut cleared = None;
+        for (name, track) in &self.tracks {
+            ui.horizontal(|ui| {
+                ui.label(format!("{name}: {} keys", track.keyframes().len()));
+                if ui.small_button("Clear").clicked() {
+                    cleared = Some(name.clone());
+                }
+            });
+        }
+        if let Some(name) = cleared {
+            self.tracks.remove(&name);
+        }
+    }
+}
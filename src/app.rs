@@ -1,16 +1,26 @@
-use crate::{Core, ShaderManager};
-use log::error;
+use crate::{AdapterSelection, Core, CuneusError, IntoShader, Session, ShaderManager, NO_RESTORE_FLAG};
+use log::{error, info, warn};
+use std::path::PathBuf;
+use web_time::{Duration, Instant};
 use winit::{
     application::ApplicationHandler,
-    dpi::LogicalSize,
+    dpi::{LogicalSize, PhysicalPosition},
     event::*,
-    event_loop::{ActiveEventLoop, EventLoop},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     window::WindowAttributes,
 };
 
+/// Quiet time after the last `Resized` event before shader resources are reallocated
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(100);
+
 pub struct ShaderApp {
     window_title: String,
     window_size: (u32, u32),
+    adapter: AdapterSelection,
+    pause_when_unfocused: bool,
+    session_path: Option<PathBuf>,
+    /// Loaded by `with_session`, handed to the shader once it exists
+    session: Option<Session>,
     core: Option<Core>,
 }
 
@@ -24,25 +34,135 @@ impl ShaderApp {
         let app = Self {
             window_title: String::from(window_title),
             window_size: (width, height),
+            adapter: AdapterSelection::default(),
+            pause_when_unfocused: false,
+            session_path: None,
+            session: None,
             core: None,
         };
 
         (app, event_loop)
     }
 
-    pub fn run<S: ShaderManager + 'static>(
+    /// Like [`new`](Self::new), but creates the window and awaits the adapter and device
+    /// before the event loop starts instead of blocking inside it. Browsers only hand out
+    /// a device through a promise, so this is the constructor for wasm (see `run_web`);
+    /// natively it works under any executor, e.g. `pollster::block_on`. The adapter comes
+    /// from the default selection, as the builder methods run after construction.
+    pub async fn new_async(window_title: &str, width: u32, height: u32) -> (Self, EventLoop<()>) {
+        let (mut app, event_loop) = Self::new(window_title, width, height);
+        // Without a running loop there's no ActiveEventLoop to create the window from
+        #[allow(deprecated)]
+        let window = event_loop
+            .create_window(app.window_attributes())
+            .expect("Failed to create window");
+        window.set_window_level(winit::window::WindowLevel::AlwaysOnTop);
+        app.core = Some(Core::new_with_adapter(window, app.adapter).await);
+        (app, event_loop)
+    }
+
+    fn window_attributes(&self) -> WindowAttributes {
+        let attributes = WindowAttributes::default()
+            .with_inner_size(LogicalSize::new(self.window_size.0, self.window_size.1))
+            .with_title(&self.window_title)
+            .with_resizable(true);
+        let attributes = match self.session.as_ref().and_then(|session| session.window_position) {
+            Some((x, y)) => attributes.with_position(PhysicalPosition::new(x, y)),
+            None => attributes,
+        };
+        // Put the canvas on the page instead of leaving it detached
+        #[cfg(target_arch = "wasm32")]
+        let attributes = {
+            use winit::platform::web::WindowAttributesExtWebSys;
+            attributes.with_append(true)
+        };
+        attributes
+    }
+
+    /// Only consider adapters on these backends, e.g. `wgpu::Backends::VULKAN`.
+    /// `WGPU_BACKEND` takes precedence when set.
+    pub fn with_backends(mut self, backends: wgpu::Backends) -> Self {
+        self.adapter.backends = Some(backends);
+        self
+    }
+
+    /// `HighPerformance` to force the discrete GPU, `LowPower` for the integrated one.
+    /// `WGPU_POWER_PREF` takes precedence when set.
+    pub fn with_power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.adapter.power_preference = Some(power_preference);
+        self
+    }
+
+    /// Stop redrawing while the window is unfocused or minimized, so a shader left
+    /// in the background doesn't keep the GPU busy. Leave it off for visualizers
+    /// that should keep running behind other windows.
+    pub fn with_pause_when_unfocused(mut self, pause: bool) -> Self {
+        self.pause_when_unfocused = pause;
+        self
+    }
+
+    /// Save the session to `path` when the window closes and restore it on the next
+    /// launch: window size and position, the last media file, the clock, and whatever
+    /// the shader adds in `ShaderManager::save_session`. Passing `--no-restore` on the
+    /// command line starts clean; the session is still saved on exit. Call it before
+    /// `run`; with [`new_async`](Self::new_async) the window already exists, so only the
+    /// shader state is restored.
+    pub fn with_session(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        if std::env::args().any(|arg| arg == NO_RESTORE_FLAG) {
+            info!("{NO_RESTORE_FLAG}: not restoring {path:?}");
+        } else if path.exists() {
+            match Session::load(&path) {
+                Ok(session) => {
+                    if let Some(size) = session.window_size {
+                        self.window_size = size;
+                    }
+                    self.session = Some(session);
+                }
+                Err(e) => warn!("Starting without the saved session: {e:#}"),
+            }
+        }
+        self.session_path = Some(path);
+        self
+    }
+
+    /// Create the window and run the event loop until it closes. `shader_creator` is
+    /// usually `MyShader::init`, or `MyShader::init_result` to have a startup error
+    /// logged and returned here instead of panicking inside the loop.
+    pub fn run<S: ShaderManager + 'static, R: IntoShader<S>>(
         self,
         event_loop: EventLoop<()>,
-        shader_creator: impl FnOnce(&Core) -> S + 'static,
+        shader_creator: impl FnOnce(&Core) -> R + 'static,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        #[allow(unused_mut)]
         let mut handler = ShaderAppHandler {
             app: self,
-            shader_creator: Some(Box::new(shader_creator)),
+            shader_creator: Some(Box::new(|core: &Core| shader_creator(core).into_shader())),
+            init_error: None,
             shader: None,
             first_render: true,
+            next_frame: None,
+            focused: true,
+            occluded: false,
+            paused_since: None,
+            pending_resize: None,
         };
 
-        Ok(event_loop.run_app(&mut handler)?)
+        // The browser owns the loop, so hand the handler over and return right away
+        #[cfg(target_arch = "wasm32")]
+        {
+            use winit::platform::web::EventLoopExtWebSys;
+            event_loop.spawn_app(handler);
+            return Ok(());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            event_loop.run_app(&mut handler)?;
+            match handler.init_error.take() {
+                Some(e) => Err(e.into()),
+                None => Ok(()),
+            }
+        }
     }
 
     pub fn core(&self) -> Option<&Core> {
@@ -50,35 +170,110 @@ impl ShaderApp {
     }
 }
 
+/// Browser entry point: creates the app with [`ShaderApp::new_async`] on the page's
+/// executor and runs it once the GPU is ready. Call it from `main` on `wasm32`.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub fn run_web<S: ShaderManager + 'static, R: IntoShader<S>>(
+    window_title: &str,
+    width: u32,
+    height: u32,
+    shader_creator: impl FnOnce(&Core) -> R + 'static,
+) {
+    let window_title = window_title.to_string();
+    wasm_bindgen_futures::spawn_local(async move {
+        let (app, event_loop) = ShaderApp::new_async(&window_title, width, height).await;
+        if let Err(e) = app.run(event_loop, shader_creator) {
+            error!("Shader app failed: {e}");
+        }
+    });
+}
+
 // This struct implements ApplicationHandler to handle winit events
 struct ShaderAppHandler<S: ShaderManager> {
     app: ShaderApp,
-    shader_creator: Option<Box<dyn FnOnce(&Core) -> S + 'static>>,
+    shader_creator: Option<Box<dyn FnOnce(&Core) -> Result<S, CuneusError> + 'static>>,
     shader: Option<S>,
+    /// Why the shader couldn't be created, returned from `ShaderApp::run`
+    init_error: Option<CuneusError>,
     first_render: bool,
+    /// Redraw deadline while `Core::set_target_fps` caps the frame rate
+    next_frame: Option<Instant>,
+    focused: bool,
+    occluded: bool,
+    /// Set while `pause_when_unfocused` holds redraws back
+    paused_since: Option<Instant>,
+    /// When to call `ShaderManager::resize` for the latest window size
+    pending_resize: Option<Instant>,
+}
+
+impl<S: ShaderManager> ShaderAppHandler<S> {
+    fn update_pause(&mut self) {
+        let (Some(core), Some(shader)) = (&self.app.core, &mut self.shader) else {
+            return;
+        };
+        let window = core.window();
+        let size = window.inner_size();
+        let minimized = self.occluded
+            || window.is_minimized().unwrap_or(false)
+            || size.width == 0
+            || size.height == 0;
+        let paused = self.app.pause_when_unfocused && (!self.focused || minimized);
+        match (paused, self.paused_since) {
+            (true, None) => self.paused_since = Some(Instant::now()),
+            (false, Some(since)) => {
+                self.paused_since = None;
+                self.next_frame = None;
+                shader.resume(core, since.elapsed());
+                window.request_redraw();
+            }
+            _ => {}
+        }
+    }
 }
 
 impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window_attributes = WindowAttributes::default()
-            .with_inner_size(LogicalSize::new(
-                self.app.window_size.0,
-                self.app.window_size.1,
-            ))
-            .with_title(&self.app.window_title)
-            .with_resizable(true);
-        let window = event_loop
-            .create_window(window_attributes)
-            .expect("Failed to create window");
-        window.set_window_level(winit::window::WindowLevel::AlwaysOnTop);
-        let core = pollster::block_on(Core::new(window));
+        // `new_async` already created the window and device
+        if self.app.core.is_none() {
+            #[cfg(target_arch = "wasm32")]
+            {
+                error!("The GPU can't be requested synchronously on the web; create the app with ShaderApp::new_async");
+                event_loop.exit();
+                return;
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let window = event_loop
+                    .create_window(self.app.window_attributes())
+                    .expect("Failed to create window");
+                window.set_window_level(winit::window::WindowLevel::AlwaysOnTop);
+                match pollster::block_on(Core::try_new_with_adapter(window, self.app.adapter)) {
+                    Ok(core) => self.app.core = Some(core),
+                    Err(e) => {
+                        error!("Failed to initialize the GPU: {e}");
+                        self.init_error = Some(e);
+                        event_loop.exit();
+                        return;
+                    }
+                }
+            }
+        }
         // Initialize the shader with the core if it hasn't been initialized yet
-        if let Some(shader_creator) = self.shader_creator.take() {
-            let shader = shader_creator(&core);
-            self.shader = Some(shader);
+        if let (Some(core), Some(shader_creator)) = (&self.app.core, self.shader_creator.take()) {
+            match shader_creator(core) {
+                Ok(mut shader) => {
+                    if let Some(session) = self.app.session.take() {
+                        shader.load_session(core, &session);
+                    }
+                    self.shader = Some(shader);
+                }
+                Err(e) => {
+                    error!("Failed to initialize shader: {e}");
+                    self.init_error = Some(e);
+                    event_loop.exit();
+                }
+            }
         }
-
-        self.app.core = Some(core);
     }
 
     fn window_event(
@@ -87,11 +282,30 @@ impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
         window_id: winit::window::WindowId,
         event: WindowEvent,
     ) {
+        match event {
+            WindowEvent::Focused(focused) => self.focused = focused,
+            WindowEvent::Occluded(occluded) => self.occluded = occluded,
+            _ => {}
+        }
+        if matches!(
+            event,
+            WindowEvent::Focused(_) | WindowEvent::Occluded(_) | WindowEvent::Resized(_)
+        ) {
+            self.update_pause();
+        }
         // Only process events if core and shader are initialized
         if let (Some(core), Some(shader)) = (&self.app.core, &mut self.shader) {
             if window_id == core.window().id() && !shader.handle_input(core, &event) {
                 match event {
                     WindowEvent::CloseRequested => {
+                        if let Some(path) = &self.app.session_path {
+                            let mut session = Session::default();
+                            session.capture_window(core.window());
+                            shader.save_session(&mut session);
+                            if let Err(e) = session.save(path) {
+                                error!("Failed to save session: {e:#}");
+                            }
+                        }
                         event_loop.exit();
                     }
                     WindowEvent::Resized(size) => {
@@ -99,8 +313,12 @@ impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
                             if core.size == size {
                                 return;
                             }
+                            // The surface follows right away and the old frame is
+                            // stretched over it; shader resources wait for the drag to stop
                             core.resize(size);
-                            shader.resize(core);
+                            if core.size == size {
+                                self.pending_resize = Some(Instant::now() + RESIZE_DEBOUNCE);
+                            }
                         }
                     }
                     WindowEvent::RedrawRequested => {
@@ -110,6 +328,9 @@ impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
                                 if self.first_render {
                                     self.first_render = false;
                                 }
+                                if let Some(core) = &mut self.app.core {
+                                    core.apply_requested_present_mode();
+                                }
                             }
                             Err(crate::SurfaceError::SkipFrame) => {}
                             Err(crate::SurfaceError::Lost | crate::SurfaceError::Outdated) => {
@@ -129,10 +350,39 @@ impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
         }
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        if let Some(core) = &self.app.core {
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let (Some(core), Some(shader)) = (&self.app.core, &mut self.shader) else {
+            return;
+        };
+        let now = Instant::now();
+        if self.pending_resize.is_some_and(|at| now >= at) {
+            self.pending_resize = None;
+            shader.resize(core);
             core.window().request_redraw();
         }
+        let wake = if self.paused_since.is_some() {
+            // Sleep until focus or a restore event wakes the loop
+            self.next_frame = None;
+            None
+        } else if let Some(fps) = core.frame_limit() {
+            let deadline = self.next_frame.unwrap_or(now);
+            if now >= deadline {
+                core.window().request_redraw();
+                // Step from the deadline rather than now so the average rate holds,
+                // but don't try to catch up after a long stall
+                let period = Duration::from_secs_f32(1.0 / fps);
+                self.next_frame = Some((deadline + period).max(now));
+            }
+            self.next_frame
+        } else {
+            self.next_frame = None;
+            core.window().request_redraw();
+            None
+        };
+        match wake.into_iter().chain(self.pending_resize).min() {
+            Some(at) => event_loop.set_control_flow(ControlFlow::WaitUntil(at)),
+            None => event_loop.set_control_flow(ControlFlow::Wait),
+        }
     }
 
     fn new_events(&mut self, _event_loop: &ActiveEventLoop, _cause: StartCause) {
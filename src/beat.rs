@@ -0,0 +1,171 @@
+use crate::{ResolutionUniform, UniformProvider};
+use std::collections::VecDeque;
+
+/// Beat state for a shader uniform, e.g. as a field of your custom params.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct BeatUniform {
+    /// Smoothed overall energy, 0..1
+    pub energy: f32,
+    /// 1 on the update a beat was detected, else 0
+    pub beat: u32,
+    /// Estimated tempo, 0 until a few beats were seen
+    pub bpm: f32,
+    /// Jumps to 1 on a beat and decays towards 0, handy for pulses and flashes
+    pub beat_intensity: f32,
+    pub bass: f32,
+    pub mid: f32,
+    pub treble: f32,
+    pub _padding: f32,
+}
+
+impl UniformProvider for BeatUniform {
+    fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+}
+
+/// Onset detector over band energies: a beat is bass energy jumping above its
+/// recent average by `sensitivity`. Driven by explicit timestamps, so feeding it
+/// frame time during export gives the same beats every run.
+pub struct BeatDetector {
+    /// Ratio over the recent average bass energy that counts as a beat (lower = more beats)
+    pub sensitivity: f32,
+    /// Bass energy below this never triggers, so silence and noise stay quiet
+    pub min_energy: f32,
+    /// Shortest gap between beats in seconds (0.25 caps detection at 240 BPM)
+    pub min_interval: f64,
+    /// Length of the running average in seconds
+    pub history_secs: f64,
+    /// `beat_intensity` falloff per second
+    pub decay: f32,
+    history: VecDeque<(f64, f32)>,
+    intervals: VecDeque<f64>,
+    last_beat: Option<f64>,
+    last_time: Option<f64>,
+    uniform: BeatUniform,
+}
+
+impl Default for BeatDetector {
+    fn default() -> Self {
+        Self {
+            sensitivity: 1.4,
+            min_energy: 0.05,
+            min_interval: 0.25,
+            history_secs: 1.0,
+            decay: 4.0,
+            history: VecDeque::new(),
+            intervals: VecDeque::new(),
+            last_beat: None,
+            last_time: None,
+            uniform: BeatUniform::default(),
+        }
+    }
+}
+
+impl BeatDetector {
+    /// Inter-beat intervals kept for the tempo estimate
+    const TEMPO_WINDOW: usize = 8;
+    /// Energy smoothing factor per update
+    const ENERGY_SMOOTHING: f32 = 0.3;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed band energies (0..1) at `time` seconds. Returns true on a beat.
+    pub fn update(&mut self, time: f64, bass: f32, mid: f32, treble: f32) -> bool {
+        let dt = self.last_time.map_or(0.0, |last| (time - last).max(0.0)) as f32;
+        self.last_time = Some(time);
+
+        while self
+            .history
+            .front()
+            .is_some_and(|(t, _)| time - t > self.history_secs)
+        {
+            self.history.pop_front();
+        }
+        let average = if self.history.is_empty() {
+            bass
+        } else {
+            self.history.iter().map(|(_, e)| e).sum::<f32>() / self.history.len() as f32
+        };
+        self.history.push_back((time, bass));
+
+        let ready = self
+            .last_beat
+            .is_none_or(|last| time - last >= self.min_interval);
+        let beat = ready && bass >= self.min_energy && bass > average * self.sensitivity;
+        if beat {
+            if let Some(last) = self.last_beat.filter(|last| time > *last) {
+                self.intervals.push_back(time - last);
+                if self.intervals.len() > Self::TEMPO_WINDOW {
+                    self.intervals.pop_front();
+                }
+            }
+            self.last_beat = Some(time);
+        }
+
+        let energy = (bass * 1.5 + mid + treble) / 3.5;
+        let bpm = self.estimate_bpm();
+        let uniform = &mut self.uniform;
+        uniform.energy += (energy - uniform.energy) * Self::ENERGY_SMOOTHING;
+        uniform.beat = beat as u32;
+        uniform.beat_intensity = if beat {
+            1.0
+        } else {
+            (uniform.beat_intensity - self.decay * dt).max(0.0)
+        };
+        uniform.bass = bass;
+        uniform.mid = mid;
+        uniform.treble = treble;
+        uniform.bpm = bpm;
+        beat
+    }
+
+    /// Feed the energies the spectrum analyzer already wrote to `ResolutionUniform`.
+    /// When the source reports its own BPM (media files), that value is used instead
+    /// of the estimate.
+    pub fn update_from_resolution(&mut self, time: f64, resolution: &ResolutionUniform) -> bool {
+        let beat = self.update(
+            time,
+            resolution.bass_energy,
+            resolution.mid_energy,
+            resolution.high_energy,
+        );
+        if resolution.bpm > 0.0 {
+            self.uniform.bpm = resolution.bpm;
+        }
+        beat
+    }
+
+    pub fn uniform(&self) -> BeatUniform {
+        self.uniform
+    }
+
+    /// Forget history, e.g. when switching audio sources or starting an export.
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.intervals.clear();
+        self.last_beat = None;
+        self.last_time = None;
+        self.uniform = BeatUniform::default();
+    }
+
+    /// Median inter-beat interval folded into 70..180 BPM, where most music sits
+    fn estimate_bpm(&self) -> f32 {
+        if self.intervals.len() < 3 {
+            return 0.0;
+        }
+        let mut sorted: Vec<f64> = self.intervals.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let mut bpm = 60.0 / sorted[sorted.len() / 2];
+        while bpm < 70.0 {
+            bpm *= 2.0;
+        }
+        while bpm > 180.0 {
+            bpm /= 2.0;
+        }
+        bpm as f32
+    }
+}
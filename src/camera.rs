@@ -0,0 +1,312 @@
+use crate::UniformProvider;
+use std::f32::consts::PI;
+use winit::event::{ElementState, KeyEvent};
+use winit::keyboard::Key;
+
+/// Camera pose for a shader uniform: where the eye is and the point it looks at.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct FlyCameraView {
+    pub position: [f32; 3],
+    pub yaw: f32,
+    /// `position` plus the unit forward vector
+    pub target: [f32; 3],
+    pub pitch: f32,
+}
+
+impl UniformProvider for FlyCameraView {
+    fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+}
+
+/// First-person camera: WASD to move, Q/E down/up, mouse to look.
+///
+/// Feed it key events with `process_key` and cursor motion with `process_mouse_position`
+/// (or raw deltas with `process_mouse_delta`), then call `update` once per frame.
+pub struct FlyCamera {
+    pub position: [f32; 3],
+    /// Radians, 0 looks down +X
+    pub yaw: f32,
+    /// Radians, clamped just short of straight up/down
+    pub pitch: f32,
+    /// World units per second
+    pub speed: f32,
+    /// Radians per pixel of mouse motion
+    pub mouse_sensitivity: f32,
+    forward: bool,
+    backward: bool,
+    left: bool,
+    right: bool,
+    up: bool,
+    down: bool,
+    last_mouse: Option<[f32; 2]>,
+    mouse_look_enabled: bool,
+    look_changed: bool,
+}
+
+impl Default for FlyCamera {
+    fn default() -> Self {
+        Self::new([0.0, 0.0, 0.0], 0.0, 0.0)
+    }
+}
+
+impl FlyCamera {
+    const PITCH_LIMIT: f32 = PI * 0.49;
+
+    pub fn new(position: [f32; 3], yaw: f32, pitch: f32) -> Self {
+        Self {
+            position,
+            yaw,
+            pitch: pitch.clamp(-Self::PITCH_LIMIT, Self::PITCH_LIMIT),
+            speed: 2.0,
+            mouse_sensitivity: 0.005,
+            forward: false,
+            backward: false,
+            left: false,
+            right: false,
+            up: false,
+            down: false,
+            last_mouse: None,
+            mouse_look_enabled: true,
+            // So the first `update` reports the initial view
+            look_changed: true,
+        }
+    }
+
+    /// Track WASD/QE press and release. Returns true if the key is a movement key.
+    pub fn process_key(&mut self, event: &KeyEvent) -> bool {
+        let Key::Character(ch) = &event.logical_key else {
+            return false;
+        };
+        let pressed = event.state == ElementState::Pressed;
+        let flag = match ch.as_str() {
+            "w" | "W" => &mut self.forward,
+            "s" | "S" => &mut self.backward,
+            "a" | "A" => &mut self.left,
+            "d" | "D" => &mut self.right,
+            "q" | "Q" => &mut self.down,
+            "e" | "E" => &mut self.up,
+            _ => return false,
+        };
+        *flag = pressed;
+        true
+    }
+
+    /// Mouse-look from absolute cursor positions (e.g. `WindowEvent::CursorMoved`).
+    /// Returns true if the view changed.
+    pub fn process_mouse_position(&mut self, x: f32, y: f32) -> bool {
+        if !self.mouse_look_enabled {
+            return false;
+        }
+        let Some([last_x, last_y]) = self.last_mouse.replace([x, y]) else {
+            return false;
+        };
+        self.process_mouse_delta(x - last_x, y - last_y)
+    }
+
+    /// Mouse-look from relative motion in pixels (e.g. `DeviceEvent::MouseMotion`).
+    /// Returns true if the view changed.
+    pub fn process_mouse_delta(&mut self, dx: f32, dy: f32) -> bool {
+        if !self.mouse_look_enabled || (dx == 0.0 && dy == 0.0) {
+            return false;
+        }
+        self.yaw += dx * self.mouse_sensitivity;
+        self.pitch = (self.pitch - dy * self.mouse_sensitivity)
+            .clamp(-Self::PITCH_LIMIT, Self::PITCH_LIMIT);
+        self.look_changed = true;
+        true
+    }
+
+    pub fn toggle_mouse_look(&mut self) {
+        self.mouse_look_enabled = !self.mouse_look_enabled;
+        self.last_mouse = None;
+    }
+
+    pub fn mouse_look_enabled(&self) -> bool {
+        self.mouse_look_enabled
+    }
+
+    /// Unit view direction
+    pub fn forward(&self) -> [f32; 3] {
+        [
+            self.pitch.cos() * self.yaw.cos(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.sin(),
+        ]
+    }
+
+    /// Move by the held keys over `dt` seconds. Returns true if the camera moved or
+    /// looked around since the last update, i.e. when the uniform needs rewriting.
+    pub fn update(&mut self, dt: f32) -> bool {
+        let forward = self.forward();
+        // forward x world up, flattened to the ground plane
+        let right_len = (forward[0] * forward[0] + forward[2] * forward[2]).sqrt();
+        let right = [-forward[2] / right_len, 0.0, forward[0] / right_len];
+
+        let step = self.speed * dt;
+        let axis = |positive: bool, negative: bool| (positive as i32 - negative as i32) as f32 * step;
+        let along_forward = axis(self.forward, self.backward);
+        let along_right = axis(self.right, self.left);
+        let along_up = axis(self.up, self.down);
+
+        for i in 0..3 {
+            self.position[i] += forward[i] * along_forward + right[i] * along_right;
+        }
+        self.position[1] += along_up;
+
+        let moved = along_forward != 0.0 || along_right != 0.0 || along_up != 0.0;
+        std::mem::take(&mut self.look_changed) || moved
+    }
+
+    pub fn view(&self) -> FlyCameraView {
+        let forward = self.forward();
+        FlyCameraView {
+            position: self.position,
+            yaw: self.yaw,
+            target: [
+                self.position[0] + forward[0],
+                self.position[1] + forward[1],
+                self.position[2] + forward[2],
+            ],
+            pitch: self.pitch,
+        }
+    }
+}
+
+/// Orbit camera state for a shader uniform: rotation angles and zoom factor.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct OrbitCameraUniform {
+    /// Yaw, pitch, roll in radians
+    pub rotation: [f32; 3],
+    pub zoom: f32,
+}
+
+impl UniformProvider for OrbitCameraUniform {
+    fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+}
+
+/// Object-viewer camera that orbits a target: drag to rotate, scroll to zoom.
+///
+/// The shader decides what the angles mean (typically rotating the ray origin around
+/// the object); this only accumulates input and keeps it within limits.
+pub struct OrbitCamera {
+    /// Yaw, pitch, roll in radians
+    pub rotation: [f32; 3],
+    pub zoom: f32,
+    pub zoom_range: (f32, f32),
+    /// Clamp pitch to `±limit` radians; `None` lets the camera flip over the poles
+    pub pitch_limit: Option<f32>,
+    /// Radians per unit of cursor motion (cursor positions are usually normalized 0..1)
+    pub rotate_sensitivity: f32,
+    /// Fraction of zoom per wheel step
+    pub zoom_sensitivity: f32,
+    last_cursor: Option<[f32; 2]>,
+    changed: bool,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self {
+            rotation: [0.0; 3],
+            zoom: 1.0,
+            zoom_range: (0.2, 5.0),
+            pitch_limit: None,
+            rotate_sensitivity: 5.0,
+            zoom_sensitivity: 0.1,
+            last_cursor: None,
+            changed: true,
+        }
+    }
+}
+
+impl OrbitCamera {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rotate by `dx` radians around the vertical axis and tilt by `dy` radians
+    pub fn rotate(&mut self, dx: f32, dy: f32) {
+        if dx == 0.0 && dy == 0.0 {
+            return;
+        }
+        self.rotation[0] += dx;
+        self.rotation[1] += dy;
+        if let Some(limit) = self.pitch_limit {
+            self.rotation[1] = self.rotation[1].clamp(-limit, limit);
+        }
+        self.changed = true;
+    }
+
+    /// Roll around the view axis by `angle` radians
+    pub fn roll(&mut self, angle: f32) {
+        self.rotation[2] += angle;
+        self.changed = true;
+    }
+
+    /// Scale the zoom by wheel steps: `zoom *= 1 - delta * zoom_sensitivity`
+    pub fn zoom(&mut self, delta: f32) {
+        if delta == 0.0 {
+            return;
+        }
+        self.set_zoom(self.zoom * (1.0 - delta * self.zoom_sensitivity));
+    }
+
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom.clamp(self.zoom_range.0, self.zoom_range.1);
+        self.changed = true;
+    }
+
+    /// Rotate by the cursor's motion since the previous call, like a drag. `aspect`
+    /// (width / height) scales vertical motion so both axes feel the same speed
+    /// with normalized cursor coordinates. Returns true if the camera rotated.
+    pub fn drag(&mut self, cursor: [f32; 2], aspect: f32) -> bool {
+        let Some([last_x, last_y]) = self.last_cursor.replace(cursor) else {
+            return false;
+        };
+        let (dx, dy) = (cursor[0] - last_x, cursor[1] - last_y);
+        if dx.abs() <= 0.0001 && dy.abs() <= 0.0001 {
+            // Keep the anchor so slow motion still adds up
+            self.last_cursor = Some([last_x, last_y]);
+            return false;
+        }
+        self.rotate(dx * self.rotate_sensitivity, dy * aspect * self.rotate_sensitivity);
+        true
+    }
+
+    /// Forget the drag anchor, e.g. when mouse control is switched off, so the next
+    /// `drag` doesn't jump by the distance moved in between
+    pub fn end_drag(&mut self) {
+        self.last_cursor = None;
+    }
+
+    pub fn reset_rotation(&mut self) {
+        self.rotation = [0.0; 3];
+        self.changed = true;
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.zoom = 1.0;
+        self.changed = true;
+    }
+
+    pub fn reset(&mut self) {
+        self.reset_rotation();
+        self.reset_zoom();
+    }
+
+    /// True once after any change, for resetting accumulation or rewriting uniforms
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    pub fn uniform(&self) -> OrbitCameraUniform {
+        OrbitCameraUniform {
+            rotation: self.rotation,
+            zoom: self.zoom,
+        }
+    }
+}
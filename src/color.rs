@@ -0,0 +1,25 @@
+//! The sRGB transfer curves, shared by texture loading, readback, export and the UI.
+
+/// Decode an sRGB-encoded value in 0..1 to linear
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Clamp a linear value to 0..1 (NaN to 0) and sRGB-encode it
+pub(crate) fn linear_to_srgb(value: f32) -> f32 {
+    let v = if value.is_nan() { 0.0 } else { value.clamp(0.0, 1.0) };
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// [`linear_to_srgb`] quantized to 8 bits, as the display blit does
+pub(crate) fn linear_to_srgb8(value: f32) -> u8 {
+    (linear_to_srgb(value) * 255.0).round() as u8
+}
@@ -41,6 +41,16 @@ use wgpu;
 /// PassDescription::new("edge_detect", &["tensor_field", "kuwahara_filter"])
 /// // Works even though tensor_field is 2 passes earlier
 /// ```
+///
+/// # Deeper history
+///
+/// A pass that keeps [`with_history(n)`](Self::with_history) frames can be read
+/// further back with `"name[k]"`, `k` writes before the latest (`"name"` is `"name[0]"`):
+///
+/// ```rust,ignore
+/// // reproject reads its previous two frames
+/// PassDescription::new("reproject", &["reproject", "reproject[1]"]).with_history(1)
+/// ```
 #[derive(Debug, Clone)]
 pub struct PassDescription {
     /// The WGSL entry point name for this pass (e.g., `"compute_field"`, `"main_image"`).
@@ -51,12 +61,21 @@ pub struct PassDescription {
     /// dispatch dimensions from `screen_size / builder_workgroup_size`.
     /// If `Some`, the value is passed directly to `dispatch_workgroups(x, y, z)`.
     pub workgroup_size: Option<[u32; 3]>,
+    /// Optional per-pass threads-per-workgroup, overriding the builder's global size.
+    /// Used to derive this pass's dispatch count from its resolution.
+    pub group_size: Option<[u32; 3]>,
     /// Optional fixed resolution for this buffer `[width, height]`.
     /// If set, the buffer texture is created at this exact size regardless of screen size.
     pub resolution: Option<[u32; 2]>,
     /// Optional resolution scale factor relative to screen size (e.g., 0.5 = half-res).
     /// Applied on creation and resize. Ignored if `resolution` is set.
     pub resolution_scale: Option<f32>,
+    /// Extra frames of this buffer's output kept beyond the usual ping-pong pair,
+    /// readable as `"name[1]"` up to `"name[history]"`.
+    pub history: u32,
+    /// Samplers for `input_sampler0..N`, by position like `inputs`. Missing entries use
+    /// [`SamplerConfig::default`].
+    pub input_samplers: Vec<SamplerConfig>,
 }
 
 impl PassDescription {
@@ -71,8 +90,11 @@ impl PassDescription {
             name: name.to_string(),
             inputs: inputs.iter().map(|s| s.to_string()).collect(),
             workgroup_size: None,
+            group_size: None,
             resolution: None,
             resolution_scale: None,
+            history: 0,
+            input_samplers: Vec::new(),
         }
     }
 
@@ -86,6 +108,18 @@ impl PassDescription {
         self
     }
 
+    /// Override the threads-per-workgroup for this pass (the global one comes from
+    /// [`ComputeShaderBuilder::with_workgroup_size`]).
+    ///
+    /// Dispatch counts for this pass become `ceil(resolution / size)`, in `.dispatch()` and
+    /// `.dispatch_stage()` alike. The value must match the entry point's `@workgroup_size`;
+    /// if the shader declares `override workgroup_size_x/_y/_z` constants, they're set from it,
+    /// so one entry point can run at several sizes.
+    pub fn with_group_size(mut self, size: [u32; 3]) -> Self {
+        self.group_size = Some(size);
+        self
+    }
+
     /// Set a fixed resolution for this buffer's texture.
     ///
     /// The buffer will always be created at this exact size, independent of screen size.
@@ -107,6 +141,71 @@ impl PassDescription {
         self.resolution_scale = Some(scale);
         self
     }
+
+    /// Keep `frames` more of this buffer's past results, so passes can read
+    /// `"name[1]"` through `"name[frames]"` (e.g. temporal reprojection needing the
+    /// current and two previous frames).
+    ///
+    /// The buffer becomes a ring of `frames + 2` textures that rotates on every write;
+    /// resizing reallocates the whole ring.
+    pub fn with_history(mut self, frames: u32) -> Self {
+        self.history = frames;
+        self
+    }
+
+    /// Sample this pass's inputs with their own address/filter modes, by position:
+    /// `samplers[N]` becomes `input_samplerN`. Inputs past the end keep the default.
+    ///
+    /// ```rust,ignore
+    /// // tile the noise, but don't wrap the height field at the edges
+    /// PassDescription::new("displace", &["height", "noise"])
+    ///     .with_input_samplers(vec![SamplerConfig::CLAMP, SamplerConfig::REPEAT])
+    /// ```
+    pub fn with_input_samplers(mut self, samplers: Vec<SamplerConfig>) -> Self {
+        self.input_samplers = samplers;
+        self
+    }
+}
+
+/// Address and filter mode of a multi-pass input sampler (`input_samplerN`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerConfig {
+    pub address_mode: wgpu::AddressMode,
+    pub filter_mode: wgpu::FilterMode,
+}
+
+impl SamplerConfig {
+    /// Wrapping, linear filtering; the default for every input
+    pub const REPEAT: Self = Self::new(wgpu::AddressMode::Repeat, wgpu::FilterMode::Linear);
+    pub const CLAMP: Self = Self::new(wgpu::AddressMode::ClampToEdge, wgpu::FilterMode::Linear);
+    pub const MIRROR: Self = Self::new(wgpu::AddressMode::MirrorRepeat, wgpu::FilterMode::Linear);
+
+    pub const fn new(address_mode: wgpu::AddressMode, filter_mode: wgpu::FilterMode) -> Self {
+        Self { address_mode, filter_mode }
+    }
+
+    /// Same address mode with nearest filtering, e.g. for exact texel lookups
+    pub const fn nearest(self) -> Self {
+        Self::new(self.address_mode, wgpu::FilterMode::Nearest)
+    }
+
+    pub fn create_sampler(&self, device: &wgpu::Device) -> wgpu::Sampler {
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Input Sampler"),
+            address_mode_u: self.address_mode,
+            address_mode_v: self.address_mode,
+            address_mode_w: self.address_mode,
+            mag_filter: self.filter_mode,
+            min_filter: self.filter_mode,
+            ..Default::default()
+        })
+    }
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self::REPEAT
+    }
 }
 
 /// Specification for a user-defined storage buffer bound to Group 3.
@@ -128,6 +227,13 @@ impl StorageBufferSpec {
     }
 }
 
+/// An additional storage texture written alongside the main output (Group 1, binding 4+).
+#[derive(Debug, Clone)]
+pub struct OutputTextureSpec {
+    pub name: String,
+    pub format: wgpu::TextureFormat,
+}
+
 /// Configuration built by the builder
 #[derive(Debug)]
 pub struct ComputeConfiguration {
@@ -136,21 +242,31 @@ pub struct ComputeConfiguration {
     pub custom_uniform_size: Option<u64>,
     pub has_input_texture: bool,
     pub has_mouse: bool,
+    pub has_gamepad: bool,
     pub has_fonts: bool,
+    pub font_source: Option<crate::FontSource>,
+    pub max_text_glyphs: Option<u32>,
     pub has_audio: bool,
     pub has_atomic_buffer: bool,
     pub atomic_buffer_channels: u32,
     pub audio_buffer_size: usize,
     pub has_audio_spectrum: bool,
+    pub has_timestamps: bool,
     pub audio_spectrum_size: usize,
     pub storage_buffers: Vec<StorageBufferSpec>,
     pub workgroup_size: [u32; 3],
     pub dispatch_once: bool,
+    pub preserve_on_resize: bool,
     pub texture_format: wgpu::TextureFormat,
+    pub texture_dimension: wgpu::TextureDimension,
+    pub texture_size_3d: [u32; 3],
     pub label: String,
     pub num_channels: Option<u32>,
     pub hot_reload_path: Option<PathBuf>,
     pub max_input_deps: usize,
+    pub indirect_entry_points: Vec<String>,
+    pub push_constant_size: Option<u32>,
+    pub extra_outputs: Vec<OutputTextureSpec>,
 }
 
 /// Declarative builder for compute shader pipelines.
@@ -163,12 +279,12 @@ pub struct ComputeConfiguration {
 /// | Group | Contents | Builder methods |
 /// |-------|----------|-----------------|
 /// | 0 | Time / frame data | Always present |
-/// | 1 | Output texture, custom uniforms, input texture | [`with_custom_uniforms`], [`with_input_texture`] |
+/// | 1 | Output texture, custom uniforms, input texture, extra outputs | [`with_custom_uniforms`], [`with_input_texture`], [`with_output_texture`] |
 /// | 2 | Mouse, fonts, audio, atomics, channels | [`with_mouse`], [`with_fonts`], [`with_audio`], [`with_channels`], etc. |
 /// | 3 | Multi-pass input textures **or** storage buffers | [`with_multi_pass`], [`with_storage_buffer`] |
 ///
 /// Group 2 bindings are **dynamic** — resources are assigned in a fixed order
-/// (mouse → fonts → audio → audio_spectrum → atomics → channels) and only the
+/// (mouse → gamepad → fonts → audio → audio_spectrum → atomics → channels) and only the
 /// ones you enable get binding slots.
 ///
 /// # Example
@@ -194,21 +310,31 @@ impl ComputeShaderBuilder {
                 custom_uniform_size: None,
                 has_input_texture: false,
                 has_mouse: false,
+                has_gamepad: false,
                 has_fonts: false,
+                font_source: None,
+                max_text_glyphs: None,
                 has_audio: false,
                 has_atomic_buffer: false,
                 atomic_buffer_channels: 3,
                 audio_buffer_size: 1024,
                 has_audio_spectrum: false,
+                has_timestamps: false,
                 audio_spectrum_size: 128,
                 storage_buffers: Vec::new(),
                 workgroup_size: [16, 16, 1],
                 dispatch_once: false,
+                preserve_on_resize: false,
                 texture_format: wgpu::TextureFormat::Rgba16Float,
+                texture_dimension: wgpu::TextureDimension::D2,
+                texture_size_3d: [1, 1, 1],
                 label: "Compute Shader".to_string(),
                 num_channels: None,
                 hot_reload_path: None,
                 max_input_deps: 3,
+                indirect_entry_points: Vec::new(),
+                push_constant_size: None,
+                extra_outputs: Vec::new(),
             },
         }
     }
@@ -264,6 +390,20 @@ impl ComputeShaderBuilder {
         self
     }
 
+    /// Add a named output texture, written in the same dispatch as the main output
+    /// (multiple render targets, e.g. albedo/normal/depth for deferred effects).
+    ///
+    /// Extra outputs bind in Group 1 from `@binding(4)` onwards, in the order added, and are
+    /// reachable as `compute_shader.output_textures["name"]`. They follow the output size
+    /// and are reallocated together on resize.
+    pub fn with_output_texture(mut self, name: &str, format: wgpu::TextureFormat) -> Self {
+        self.config.extra_outputs.push(OutputTextureSpec {
+            name: name.to_string(),
+            format,
+        });
+        self
+    }
+
     /// Enable `N` external texture channels in Group 2 (video, webcam, HDRI).
     ///
     /// Each channel occupies 2 bindings (texture + sampler). Unlike `with_input_texture`,
@@ -279,12 +419,40 @@ impl ComputeShaderBuilder {
         self
     }
 
+    /// Enable gamepad uniform in Group 2, right after the mouse. Access as
+    /// `var<uniform> gamepad: GamepadUniform` in WGSL; feed it from
+    /// `RenderKit::gamepad_tracker` with `update_gamepad_uniform`.
+    pub fn with_gamepad(mut self) -> Self {
+        self.config.has_gamepad = true;
+        self
+    }
+
     /// Enable font texture + uniform in Group 2 (2 bindings).
     pub fn with_fonts(mut self) -> Self {
         self.config.has_fonts = true;
         self
     }
 
+    /// Enable fonts with the atlas rasterized from a TTF/OTF file path or font bytes
+    /// instead of the built-in texture. Same grid layout and bindings as [`with_fonts`];
+    /// if the font fails to load, the built-in atlas is used.
+    ///
+    /// [`with_fonts`]: Self::with_fonts
+    pub fn with_font(mut self, font: impl Into<crate::FontSource>) -> Self {
+        self.config.has_fonts = true;
+        self.config.font_source = Some(font.into());
+        self
+    }
+
+    /// Enable fonts plus a read-only `glyphs` storage buffer right after them (3 bindings),
+    /// filled from Rust with [`ComputeShader::draw_text`](crate::compute::ComputeShader::draw_text).
+    /// Holds up to `max_glyphs` characters per frame.
+    pub fn with_text(mut self, max_glyphs: u32) -> Self {
+        self.config.has_fonts = true;
+        self.config.max_text_glyphs = Some(max_glyphs);
+        self
+    }
+
     /// Enable a read-write audio buffer in Group 2 for GPU audio synthesis.
     ///
     /// The buffer is `storage, read_write` — your shader writes synthesis parameters,
@@ -309,8 +477,11 @@ impl ComputeShaderBuilder {
     ///
     /// `channels` is the number of `u32` values per pixel — the total buffer size is
     /// `width * height * channels * sizeof(u32)`. Use 1 for simple counters,
-    /// 2-3 for multi-channel histograms, 4 for RGBA, etc.
+    /// 2-3 for multi-channel histograms, 4 for RGBA, etc. The size follows the output
+    /// on resize and clear. In WGSL it is one flat `array<atomic<u32>>`; the examples
+    /// store channels as planes, `atomic_buffer[c * width * height + y * width + x]`.
     pub fn with_atomic_buffer(mut self, channels: u32) -> Self {
+        assert!(channels > 0, "with_atomic_buffer needs at least one channel");
         self.config.has_atomic_buffer = true;
         self.config.atomic_buffer_channels = channels;
         self
@@ -332,6 +503,32 @@ impl ComputeShaderBuilder {
         self
     }
 
+    /// Dispatch the listed entry points indirectly, with workgroup counts written on the GPU.
+    ///
+    /// Adds a `dispatch_args` buffer as the **last** Group 2 binding, holding one
+    /// `{ x: u32, y: u32, z: u32 }` slot per listed entry point (in the order given).
+    /// An earlier stage writes the counts; `.dispatch()` and `.dispatch_stage()` then use
+    /// `dispatch_workgroups_indirect` for these entry points. Slots start zeroed.
+    pub fn with_indirect_dispatch(mut self, entry_points: &[&str]) -> Self {
+        self.config.indirect_entry_points = entry_points.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// Reserve `size_of::<T>()` bytes of push constants, set per dispatch with
+    /// [`ComputeShader::dispatch_stage_pc`](crate::compute::ComputeShader::dispatch_stage_pc).
+    ///
+    /// Declare them in WGSL as `var<immediate> pc: MyConstants;`. On devices without
+    /// `Features::IMMEDIATES` (or with a smaller limit) the preprocessor declares it as a
+    /// uniform at `@group(0) @binding(1)` instead, written once per stage before the submit,
+    /// so `T` should also satisfy uniform layout rules.
+    #[cfg(feature = "push-constants")]
+    pub fn with_push_constants<T: bytemuck::Pod>(mut self) -> Self {
+        let size = std::mem::size_of::<T>() as u32;
+        assert!(size > 0 && size.is_multiple_of(4), "push constant size must be a non-zero multiple of 4");
+        self.config.push_constant_size = Some(size);
+        self
+    }
+
     /// Set the workgroup size `[x, y, z]` used to calculate dispatch dimensions.
     ///
     /// The engine dispatches `ceil(screen_width / x)` by `ceil(screen_height / y)` workgroups.
@@ -342,18 +539,50 @@ impl ComputeShaderBuilder {
         self
     }
 
+    /// Profile each pass of `.dispatch()`, and each single-stage dispatch, with GPU timestamp queries.
+    ///
+    /// Read the results with [`ComputeShader::last_pass_times`](crate::compute::ComputeShader::last_pass_times).
+    /// On devices without `Features::TIMESTAMP_QUERY` this is a no-op and the times stay empty.
+    pub fn with_timestamps(mut self) -> Self {
+        self.config.has_timestamps = true;
+        self
+    }
+
     /// Run the pipeline only once (useful for initialization or precomputation shaders).
     pub fn dispatch_once(mut self) -> Self {
         self.config.dispatch_once = true;
         self
     }
 
+    /// Keep the output and multi-pass buffer contents across a resize, scaled to the new
+    /// size, instead of clearing them. Useful for long accumulations (path tracing,
+    /// feedback trails) that shouldn't restart on an accidental window drag. The frame
+    /// counter isn't reset either. Atomic buffers are still cleared.
+    pub fn with_preserve_on_resize(mut self) -> Self {
+        self.config.preserve_on_resize = true;
+        self
+    }
+
     /// Set the output texture format. Default is `Rgba16Float`.
     pub fn with_texture_format(mut self, format: wgpu::TextureFormat) -> Self {
         self.config.texture_format = format;
         self
     }
 
+    /// Write into a fixed-size 3D volume instead of a screen-sized 2D texture.
+    ///
+    /// The output binding becomes `texture_storage_3d` at `@group(1) @binding(0)`, and
+    /// `.dispatch()`/`.dispatch_stage()` cover `ceil(size / workgroup_size)` in all three axes
+    /// (set a matching z with [`with_workgroup_size`]). The volume is exposed as
+    /// `compute_shader.volume_texture`, whose `bind_group` samples it as `texture_3d<f32>` in
+    /// later passes. It keeps its size on window resize.
+    pub fn with_texture_3d(mut self, size: [u32; 3], format: wgpu::TextureFormat) -> Self {
+        self.config.texture_dimension = wgpu::TextureDimension::D3;
+        self.config.texture_size_3d = size;
+        self.config.texture_format = format;
+        self
+    }
+
     /// Set a debug label (visible in GPU debuggers like RenderDoc).
     pub fn with_label(mut self, label: &str) -> Self {
         self.config.label = label.to_string();
@@ -4,17 +4,120 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use wgpu;
 
-use super::builder::ComputeConfiguration;
+use super::builder::{ComputeConfiguration, OutputTextureSpec, SamplerConfig};
 use super::multipass::MultiPassManager;
+use super::reduce::{ConvergenceReduction, MaxReduction};
 use super::resource::ResourceLayout;
-use crate::{Core, FontSystem, ShaderHotReload, TextureManager, UniformBinding};
+#[cfg(target_arch = "wasm32")]
+use crate::notify_stub as notify;
+use crate::color::{linear_to_srgb8, srgb_to_linear};
+use crate::{
+    Core, FontSystem, PreprocessOptions, PreprocessedShader, ShaderHotReload, ShaderLoadError, TextureManager,
+    UniformBinding, VolumeTexture,
+};
+
+/// Override constants for a pass's group size, limited to the
+/// `workgroup_size_x/_y/_z` overrides the shader actually declares
+fn group_size_constants(source: &str, group_size: Option<[u32; 3]>) -> Vec<(String, f64)> {
+    let Some(group_size) = group_size else {
+        return Vec::new();
+    };
+    let tokens: Vec<&str> = source.split_whitespace().collect();
+    let declared: Vec<&str> = tokens
+        .windows(2)
+        .filter(|pair| pair[0] == "override")
+        .map(|pair| pair[1].trim_end_matches(|c: char| !(c.is_alphanumeric() || c == '_')))
+        .collect();
+    ["workgroup_size_x", "workgroup_size_y", "workgroup_size_z"]
+        .into_iter()
+        .zip(group_size)
+        .filter(|(name, _)| declared.contains(name))
+        .map(|(name, size)| (name.to_string(), size as f64))
+        .collect()
+}
+
+/// Decode an IEEE half float
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => sign * f32::INFINITY,
+        31 => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+/// Clamp a value to 8-bit without color encoding (used for alpha)
+fn unorm8(value: f32) -> u8 {
+    if value.is_nan() { 0 } else { (value.clamp(0.0, 1.0) * 255.0).round() as u8 }
+}
+
+/// Timestamp queries written around each pass of `dispatch`, two per pipeline
+struct PassTimestamps {
+    query_set: wgpu::QuerySet,
+    count: u32,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    period_ns: f32,
+    resolved: bool,
+}
+
+impl PassTimestamps {
+    fn new(core: &Core, num_passes: usize, label: &str) -> Self {
+        let count = (num_passes * 2) as u32;
+        let size = count as u64 * std::mem::size_of::<u64>() as u64;
+        Self {
+            query_set: core.device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some(&format!("{label} Pass Timestamps")),
+                ty: wgpu::QueryType::Timestamp,
+                count,
+            }),
+            count,
+            resolve_buffer: core.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("{label} Timestamp Resolve")),
+                size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: core.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("{label} Timestamp Readback")),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            period_ns: core.queue.get_timestamp_period(),
+            resolved: false,
+        }
+    }
+
+    fn writes(&self, pass_index: usize) -> wgpu::ComputePassTimestampWrites<'_> {
+        wgpu::ComputePassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(pass_index as u32 * 2),
+            end_of_pass_write_index: Some(pass_index as u32 * 2 + 1),
+        }
+    }
+}
+
+/// How a single stage gets its workgroup count
+enum StageDispatch<'a> {
+    Direct([u32; 3]),
+    Indirect(&'a wgpu::Buffer, wgpu::BufferAddress),
+}
 
 crate::uniform_params! {
+    /// Group 0 binding 0. `tile_offset` is where the output texture starts in the full
+    /// image and `full_size` the full image size: zero and the output size, except during
+    /// a tiled export. Shaders that declare only the first four fields keep working.
     pub struct ComputeTimeUniform {
         pub time: f32,
         pub delta: f32,
         pub frame: u32,
         pub _padding: u32,
+        pub tile_offset: [i32; 2],
+        pub full_size: [u32; 2],
     }
 }
 
@@ -22,10 +125,19 @@ pub struct ComputeShader {
     // Core resources
     pub pipelines: Vec<wgpu::ComputePipeline>,
     pub output_texture: TextureManager,
+    /// Extra named outputs from `with_output_texture`, bound in Group 1 from binding 4
+    pub output_textures: HashMap<String, TextureManager>,
+    extra_outputs: Vec<OutputTextureSpec>,
+    /// Display sampler filtering, see `set_output_filter`
+    output_filter: wgpu::FilterMode,
+    /// 3D output from `with_texture_3d`; when set, it replaces `output_texture` at Group 1 binding 0
+    pub volume_texture: Option<VolumeTexture>,
     pub time_uniform: UniformBinding<ComputeTimeUniform>,
     pub workgroup_size: [u32; 3],
     pub dispatch_once: bool,
     pub current_frame: u32,
+    /// `(tile_offset, full_size)` while a tiled export renders a tile
+    export_tile: Option<([i32; 2], [u32; 2])>,
 
     // Layouts following the 4-group convention
     pub bind_group_layouts: HashMap<u32, wgpu::BindGroupLayout>,
@@ -40,6 +152,8 @@ pub struct ComputeShader {
     // Custom uniform parameters (Group 1)
     pub custom_uniform: Option<wgpu::Buffer>,
     pub custom_uniform_size: Option<u64>,
+    /// Params last written by `set_custom_params`, to upload only what changed
+    custom_uniform_uploaded: std::sync::Mutex<Vec<u8>>,
 
     // Input texture support (Group 1)
     pub placeholder_input_texture: Option<TextureManager>,
@@ -53,10 +167,27 @@ pub struct ComputeShader {
     pub font_system: Option<FontSystem>,
     pub atomic_buffer_raw: Option<wgpu::Buffer>,
     pub atomic_buffer_channels: u32,
+    /// GPU max of the atomic buffer, created on first `encode_atomic_max`
+    atomic_max: Option<MaxReduction>,
+    /// Change between accumulation states, created on first `encode_convergence`
+    convergence: Option<ConvergenceReduction>,
     pub audio_buffer: Option<wgpu::Buffer>,
     pub audio_staging_buffer: Option<wgpu::Buffer>,
     pub audio_spectrum_buffer: Option<wgpu::Buffer>,
     pub mouse_uniform: Option<UniformBinding<crate::MouseUniform>>,
+    pub gamepad_uniform: Option<UniformBinding<crate::GamepadUniform>>,
+
+    // Indirect dispatch (Group 2): shader-writable args, copied into an INDIRECT-only buffer
+    // before each indirect dispatch since a buffer can't be both in the same pass
+    pub indirect_entry_points: Vec<String>,
+    pub indirect_args_buffer: Option<wgpu::Buffer>,
+    indirect_dispatch_buffer: Option<wgpu::Buffer>,
+
+    // Push constants: native immediates, or a Group 0 uniform per stage refilled before
+    // each dispatch, bound through that stage's own Group 0 bind group
+    pub push_constant_size: u32,
+    pub push_constant_fallback: Vec<wgpu::Buffer>,
+    push_constant_bind_groups: Vec<wgpu::BindGroup>,
 
     // Channel system for external textures (Group 2)
     pub channel_textures: HashMap<u32, Option<(wgpu::TextureView, wgpu::Sampler)>>,
@@ -64,32 +195,61 @@ pub struct ComputeShader {
 
     // User storage buffers (Group 3)
     pub storage_buffers: Vec<wgpu::Buffer>,
+    /// `StorageBufferSpec` names, parallel to `storage_buffers`
+    storage_buffer_names: Vec<String>,
 
     // Empty bind groups for contiguous layout requirement
     pub empty_bind_groups: std::collections::HashMap<u32, wgpu::BindGroup>,
 
     // Cached sampler for multi-pass dispatch
     pub multipass_sampler: wgpu::Sampler,
+    /// Per-slot samplers of passes set up with `PassDescription::with_input_samplers`
+    input_samplers: HashMap<String, Vec<wgpu::Sampler>>,
 
     // Pre-cached bind groups for multipass
-    // Group 1: [write_side=false, write_side=true] per intermediate pass
-    cached_intermediate_group1: HashMap<String, [wgpu::BindGroup; 2]>,
-    // Group 3: indexed by write_side bit pattern (0..2^max_input_deps) per pass
+    // Group 1: one per ring slot the intermediate pass can write to
+    cached_intermediate_group1: HashMap<String, Vec<wgpu::BindGroup>>,
+    // Group 3: indexed by the ring slots read by each input (mixed radix, see input_group3_key)
     cached_input_group3: HashMap<String, Vec<wgpu::BindGroup>>,
     /// Maximum number of input dependencies per pass (determines Group 3 layout size)
     max_input_deps: usize,
 
+    // Pass profiling (with_timestamps)
+    timestamps: Option<PassTimestamps>,
+    last_pass_times: Vec<(String, f32)>,
+
     // Configuration and hot reload
     pub entry_points: Vec<String>,
+    /// Per-entry-point override constants (pass group sizes), reapplied on hot reload
+    pipeline_constants: Vec<Vec<(String, f64)>>,
     pub hot_reload: Option<ShaderHotReload>,
     pub label: String,
     pub has_input_texture: bool,
     pub texture_format: wgpu::TextureFormat,
+    /// Scale the output and multi-pass contents into the new textures on `resize`,
+    /// see [`with_preserve_on_resize`](crate::compute::ComputeShaderBuilder::with_preserve_on_resize)
+    pub preserve_on_resize: bool,
 }
 
 impl ComputeShader {
     /// Create a compute shader from builder configuration
-    pub fn from_builder(core: &Core, shader_source: &str, mut config: ComputeConfiguration) -> Self {
+    ///
+    /// # Panics
+    ///
+    /// If the shader fails to compile against `config` or an include can't be expanded;
+    /// see [`try_from_builder`](Self::try_from_builder) to handle that instead
+    pub fn from_builder(core: &Core, shader_source: &str, config: ComputeConfiguration) -> Self {
+        let label = config.label.clone();
+        Self::try_from_builder(core, shader_source, config).unwrap_or_else(|e| panic!("{label}: {e}"))
+    }
+
+    /// [`from_builder`](Self::from_builder), returning shader errors with locations mapped
+    /// back to the original files instead of panicking
+    pub fn try_from_builder(
+        core: &Core,
+        shader_source: &str,
+        mut config: ComputeConfiguration,
+    ) -> Result<Self, ShaderLoadError> {
         // Step 1: Create resource layout following 4-group convention
         let mut resource_layout = ResourceLayout::new();
 
@@ -97,21 +257,34 @@ impl ComputeShader {
         resource_layout.add_time_uniform();
 
         // Group 1: Primary I/O & Parameters
-        resource_layout.add_output_texture(config.texture_format);
+        if config.texture_dimension == wgpu::TextureDimension::D3 {
+            resource_layout.add_output_texture_3d(config.texture_format);
+        } else {
+            resource_layout.add_output_texture(config.texture_format);
+        }
         if let Some(uniform_size) = config.custom_uniform_size {
             resource_layout.add_custom_uniform("params", uniform_size);
         }
         if config.has_input_texture {
             resource_layout.add_input_texture();
         }
+        for output in &config.extra_outputs {
+            resource_layout.add_extra_output_texture(&output.name, output.format);
+        }
 
         // Group 2: Engine Resources
         if config.has_mouse {
             resource_layout.add_mouse_uniform();
         }
+        if config.has_gamepad {
+            resource_layout.add_gamepad_uniform();
+        }
         if config.has_fonts {
             resource_layout.add_font_resources();
         }
+        if let Some(max_glyphs) = config.max_text_glyphs {
+            resource_layout.add_text_glyph_buffer(max_glyphs);
+        }
         if config.has_audio {
             resource_layout.add_audio_buffer(config.audio_buffer_size);
         }
@@ -125,6 +298,9 @@ impl ComputeShader {
         if let Some(num_channels) = config.num_channels {
             resource_layout.add_channel_textures(num_channels);
         }
+        if !config.indirect_entry_points.is_empty() {
+            resource_layout.add_indirect_args_buffer(config.indirect_entry_points.len());
+        }
 
         // Group 3: User-defined storage buffers with optional multi-pass input textures
         if !config.storage_buffers.is_empty() {
@@ -137,6 +313,19 @@ impl ComputeShader {
             resource_layout.add_multipass_input_textures(config.max_input_deps);
         }
 
+        // Push constants: native immediates when the device allows, otherwise a Group 0 uniform
+        let push_constant_size = config.push_constant_size.unwrap_or(0);
+        let emulate_push_constants = push_constant_size > 0
+            && (!core.device.features().contains(wgpu::Features::IMMEDIATES)
+                || push_constant_size > core.device.limits().max_immediate_size);
+        if emulate_push_constants {
+            warn!(
+                "{}: push constants not supported by this device, emulating them with a uniform buffer",
+                config.label
+            );
+            resource_layout.add_push_constant_fallback(push_constant_size);
+        }
+
         // Step 2: Create bind group layouts
         let bind_group_layouts = resource_layout.create_bind_group_layouts(&core.device);
 
@@ -166,11 +355,33 @@ impl ComputeShader {
             .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some(&format!("{} Pipeline Layout", config.label)),
                 bind_group_layouts: &layout_refs,
-                immediate_size: 0,
+                immediate_size: if emulate_push_constants { 0 } else { push_constant_size },
             });
 
         // Step 4: Create time uniform (Group 0)
-        let time_bind_group_layout = bind_group_layouts.get(&0).unwrap();
+        // With the push constant fallback in Group 0, the time uniform's own bind group
+        // needs a layout holding just binding 0
+        let time_only_layout;
+        let time_bind_group_layout = if emulate_push_constants {
+            time_only_layout = core
+                .device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some(&format!("{} Time Layout", config.label)),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                });
+            &time_only_layout
+        } else {
+            bind_group_layouts.get(&0).unwrap()
+        };
         let time_uniform = UniformBinding::new(
             &core.device,
             &format!("{} Time Uniform", config.label),
@@ -179,11 +390,45 @@ impl ComputeShader {
                 delta: 0.0,
                 frame: 0,
                 _padding: 0,
+                tile_offset: [0, 0],
+                full_size: [core.size.width, core.size.height],
             },
             time_bind_group_layout,
             0,
         );
-        let group0_bind_group = time_uniform.bind_group.clone();
+        let (push_constant_fallback, push_constant_bind_groups): (Vec<_>, Vec<_>) = if emulate_push_constants {
+            (0..config.entry_points.len().max(1))
+                .map(|stage| {
+                    let buffer = core.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&format!("{} Push Constants {stage}", config.label)),
+                        size: (push_constant_size as u64).next_multiple_of(16),
+                        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    });
+                    let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some(&format!("{} Group 0 Stage {stage}", config.label)),
+                        layout: bind_group_layouts.get(&0).unwrap(),
+                        entries: &[
+                            wgpu::BindGroupEntry {
+                                binding: 0,
+                                resource: time_uniform.buffer.as_entire_binding(),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: buffer.as_entire_binding(),
+                            },
+                        ],
+                    });
+                    (buffer, bind_group)
+                })
+                .unzip()
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        let group0_bind_group = push_constant_bind_groups
+            .first()
+            .cloned()
+            .unwrap_or_else(|| time_uniform.bind_group.clone());
 
         // Step 5: Create output texture
         let output_texture = Self::create_output_texture(
@@ -191,8 +436,26 @@ impl ComputeShader {
             core.size.width,
             core.size.height,
             config.texture_format,
+            wgpu::FilterMode::Linear,
             &format!("{} Output Texture", config.label),
         );
+        let output_textures = Self::create_extra_output_textures(
+            &core.device,
+            core.size.width,
+            core.size.height,
+            &config.extra_outputs,
+            wgpu::FilterMode::Linear,
+            &config.label,
+        );
+        let extra_output_views = Self::extra_output_views(&config.extra_outputs, &output_textures);
+        let volume_texture = (config.texture_dimension == wgpu::TextureDimension::D3).then(|| {
+            VolumeTexture::new(
+                &core.device,
+                config.texture_size_3d,
+                config.texture_format,
+                &format!("{} Volume Texture", config.label),
+            )
+        });
 
         // Step 5.5: Create custom uniform buffer if needed
         let custom_uniform = if let Some(uniform_size) = config.custom_uniform_size {
@@ -219,14 +482,40 @@ impl ComputeShader {
         let group1_bind_group = Self::create_group1_bind_group(
             &core.device,
             bind_group_layouts.get(&1).unwrap(),
-            &output_texture,
+            &Self::output_storage_view(&output_texture, volume_texture.as_ref()),
             config.custom_uniform_size,
             config.has_input_texture,
             custom_uniform.as_ref(),
             placeholder_input_texture.as_ref().map(|t| &t.view),
             placeholder_input_texture.as_ref().map(|t| &t.sampler),
+            &extra_output_views,
         );
 
+        // Step 5.6: Create indirect dispatch buffers if any entry point is dispatched indirectly
+        for entry_point in &config.indirect_entry_points {
+            if !config.entry_points.contains(entry_point) {
+                warn!("Indirect dispatch requested for unknown entry point '{entry_point}'");
+            }
+        }
+        let (indirect_args_buffer, indirect_dispatch_buffer) = if config.indirect_entry_points.is_empty() {
+            (None, None)
+        } else {
+            let size = (config.indirect_entry_points.len() * 12) as u64;
+            let args = core.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("{} Dispatch Args Buffer", config.label)),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let dispatch = core.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("{} Indirect Dispatch Buffer", config.label)),
+                size,
+                usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            (Some(args), Some(dispatch))
+        };
+
         // Step 6: Create engine resources (Group 2) if needed
         let (
             font_system,
@@ -235,8 +524,9 @@ impl ComputeShader {
             audio_staging_buffer,
             audio_spectrum_buffer,
             mouse_uniform,
+            gamepad_uniform,
             group2_bind_group,
-        ) = Self::create_engine_resources(core, &bind_group_layouts, &config);
+        ) = Self::create_engine_resources(core, &bind_group_layouts, &config, &indirect_args_buffer);
 
         // Step 7: Create user storage buffers (Group 3) if needed
         let (storage_buffers, group3_bind_group) = if !config.storage_buffers.is_empty() {
@@ -299,46 +589,126 @@ impl ComputeShader {
         };
 
         // Step 9: Create compute pipelines
-        let shader_module = core
-            .device
-            .create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: Some(&format!("{} Module", config.label)),
-                source: wgpu::ShaderSource::Wgsl(shader_source.into()),
-            });
+        // `//!include` directives resolve relative to the shader file; without a path only
+        // the built-in `<cuneus/...>` libraries can be included
+        // Without native immediates, `var<immediate>` becomes the Group 0 uniform above
+        let preprocess_options = PreprocessOptions {
+            immediates_as_uniform: emulate_push_constants.then_some((0, 1)),
+        };
+        let source_path = config
+            .hot_reload_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(format!("<{}>", config.label)));
+        let expanded = PreprocessedShader::from_source_with(shader_source, &source_path, preprocess_options)?;
+        let shader_source = expanded.source.clone();
+
+        // Name the variable when the shader uses a binding the builder didn't set up, or
+        // one of another type, rather than leave it to pipeline validation
+        if cfg!(debug_assertions) {
+            let entries: Vec<_> = (0..4).map(|group| resource_layout.layout_entries(group)).collect();
+            let labels: Vec<_> = (0..4).map(|group| format!("{} Group {group}", config.label)).collect();
+            let descriptors: Vec<_> = entries
+                .iter()
+                .zip(&labels)
+                .map(|(entries, label)| wgpu::BindGroupLayoutDescriptor {
+                    label: Some(label),
+                    entries,
+                })
+                .collect();
+            let descriptor_refs: Vec<_> = descriptors.iter().collect();
+            if let Some(problem) = crate::reflect::layout_mismatch(&shader_source, &config.label, &descriptor_refs) {
+                return Err(ShaderLoadError::Compile(format!(
+                    "{problem}; check the builder's `.with_*` resources against the shader's bindings"
+                )));
+            }
+        }
 
-        let mut pipelines = Vec::new();
-        for entry_point in &config.entry_points {
-            let pipeline = core
+        let pipeline_constants: Vec<Vec<(String, f64)>> = (0..config.entry_points.len())
+            .map(|i| {
+                let group_size = config
+                    .passes
+                    .as_ref()
+                    .and_then(|passes| passes.get(i))
+                    .and_then(|p| p.group_size);
+                group_size_constants(&shader_source, group_size)
+            })
+            .collect();
+
+        let pipelines = crate::hot::capture_validation(&core.device, || {
+            let shader_module = core
                 .device
-                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-                    label: Some(&format!("{} Pipeline - {}", config.label, entry_point)),
-                    layout: Some(&pipeline_layout),
-                    module: &shader_module,
-                    entry_point: Some(entry_point),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    cache: None,
+                .create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some(&format!("{} Module", config.label)),
+                    source: wgpu::ShaderSource::Wgsl(shader_source.as_str().into()),
                 });
-            pipelines.push(pipeline);
+            let mut pipelines = Vec::new();
+            for (entry_point, constants) in config.entry_points.iter().zip(&pipeline_constants) {
+                let constants: Vec<(&str, f64)> = constants.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+                let pipeline = core
+                    .device
+                    .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                        label: Some(&format!("{} Pipeline - {}", config.label, entry_point)),
+                        layout: Some(&pipeline_layout),
+                        module: &shader_module,
+                        entry_point: Some(entry_point),
+                        compilation_options: wgpu::PipelineCompilationOptions {
+                            constants: &constants,
+                            ..Default::default()
+                        },
+                        cache: None,
+                    });
+                pipelines.push(pipeline);
+            }
+            pipelines
+        })
+        .map_err(|e| match e {
+            ShaderLoadError::Compile(msg) => ShaderLoadError::Compile(expanded.annotate_error(&msg)),
+            e => e,
+        })?;
+
+        let multipass_sampler = SamplerConfig::default().create_sampler(&core.device);
+        // Passes with their own input samplers; equal configs share one sampler
+        let mut input_samplers = HashMap::new();
+        let mut distinct_samplers: HashMap<SamplerConfig, wgpu::Sampler> = HashMap::new();
+        for pass in config.passes.iter().flatten().filter(|pass| !pass.input_samplers.is_empty()) {
+            let samplers = pass
+                .input_samplers
+                .iter()
+                .map(|sampler_config| match distinct_samplers.get(sampler_config) {
+                    Some(sampler) => sampler.clone(),
+                    None => {
+                        let sampler = sampler_config.create_sampler(&core.device);
+                        distinct_samplers.insert(*sampler_config, sampler.clone());
+                        sampler
+                    }
+                })
+                .collect::<Vec<_>>();
+            input_samplers.insert(pass.name.clone(), samplers);
         }
 
-        let multipass_sampler = core.device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::Repeat,
-            address_mode_v: wgpu::AddressMode::Repeat,
-            address_mode_w: wgpu::AddressMode::Repeat,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            ..Default::default()
-        });
+        let timestamps = if !config.has_timestamps {
+            None
+        } else if core.device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            Some(PassTimestamps::new(core, pipelines.len(), &config.label))
+        } else {
+            warn!("{}: timestamp queries not supported by this device, pass timing disabled", config.label);
+            None
+        };
 
         let hot_reload_path = config.hot_reload_path.take();
 
         let mut shader = Self {
             pipelines,
             output_texture,
+            output_textures,
+            extra_outputs: config.extra_outputs,
+            output_filter: wgpu::FilterMode::Linear,
+            volume_texture,
             time_uniform,
             workgroup_size: config.workgroup_size,
             dispatch_once: config.dispatch_once,
             current_frame: 0,
+            export_tile: None,
             bind_group_layouts,
             pipeline_layout,
             group0_bind_group,
@@ -351,26 +721,42 @@ impl ComputeShader {
             font_system,
             atomic_buffer_raw,
             atomic_buffer_channels: config.atomic_buffer_channels,
+            atomic_max: None,
+            convergence: None,
             audio_buffer,
             audio_staging_buffer,
             audio_spectrum_buffer,
             mouse_uniform,
+            gamepad_uniform,
+            indirect_entry_points: config.indirect_entry_points.clone(),
+            indirect_args_buffer,
+            indirect_dispatch_buffer,
+            push_constant_size,
+            push_constant_fallback,
+            push_constant_bind_groups,
             storage_buffers,
+            storage_buffer_names: config.storage_buffers.iter().map(|spec| spec.name.clone()).collect(),
             empty_bind_groups,
             custom_uniform,
             custom_uniform_size: config.custom_uniform_size,
+            custom_uniform_uploaded: std::sync::Mutex::new(Vec::new()),
             placeholder_input_texture,
             channel_textures: Self::initialize_channel_textures(config.num_channels.unwrap_or(0)),
             num_channels: config.num_channels.unwrap_or(0),
             multipass_sampler,
+            input_samplers,
             cached_intermediate_group1: HashMap::new(),
             cached_input_group3: HashMap::new(),
             max_input_deps: config.max_input_deps,
             entry_points: config.entry_points,
+            pipeline_constants,
+            timestamps,
+            last_pass_times: Vec::new(),
             hot_reload: None,
             label: config.label,
             has_input_texture: config.has_input_texture,
             texture_format: config.texture_format,
+            preserve_on_resize: config.preserve_on_resize,
         };
 
         shader.rebuild_multipass_caches(&core.device);
@@ -384,10 +770,12 @@ impl ComputeShader {
                     });
             if let Err(e) = shader.enable_hot_reload(core.device.clone(), path, reload_module) {
                 warn!("Failed to enable hot reload: {e}");
+            } else if let Some(hot_reload) = &mut shader.hot_reload {
+                hot_reload.set_preprocess_options(preprocess_options);
             }
         }
 
-        shader
+        Ok(shader)
     }
 
     fn create_output_texture(
@@ -395,6 +783,7 @@ impl ComputeShader {
         width: u32,
         height: u32,
         format: wgpu::TextureFormat,
+        filter: wgpu::FilterMode,
         label: &str,
     ) -> TextureManager {
         let texture = device.create_texture(&wgpu::TextureDescriptor {
@@ -408,17 +797,37 @@ impl ComputeShader {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::COPY_SRC
+                | crate::texture::resample_target_usage(device, format),
             view_formats: &[],
         });
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let (sampler, bind_group) = Self::create_display_bind_group(device, &view, filter, label);
+
+        TextureManager {
+            texture,
+            view,
+            sampler,
+            bind_group,
+        }
+    }
+
+    /// Sampler and display-layout bind group through which the blit reads an output
+    fn create_display_bind_group(
+        device: &wgpu::Device,
+        view: &wgpu::TextureView,
+        filter: wgpu::FilterMode,
+        label: &str,
+    ) -> (wgpu::Sampler, wgpu::BindGroup) {
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
+            mag_filter: filter,
+            min_filter: filter,
             ..Default::default()
         });
 
@@ -429,7 +838,7 @@ impl ComputeShader {
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&view),
+                    resource: wgpu::BindingResource::TextureView(view),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
@@ -438,12 +847,57 @@ impl ComputeShader {
             ],
             label: Some(&format!("{label} Display Bind Group")),
         });
+        (sampler, bind_group)
+    }
 
-        TextureManager {
-            texture,
-            view,
-            sampler,
-            bind_group,
+    fn create_extra_output_textures(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        specs: &[OutputTextureSpec],
+        filter: wgpu::FilterMode,
+        label: &str,
+    ) -> HashMap<String, TextureManager> {
+        specs
+            .iter()
+            .map(|spec| {
+                let texture = Self::create_output_texture(
+                    device,
+                    width,
+                    height,
+                    spec.format,
+                    filter,
+                    &format!("{label} Output Texture - {}", spec.name),
+                );
+                (spec.name.clone(), texture)
+            })
+            .collect()
+    }
+
+    /// Storage views of the extra outputs, in binding order
+    fn extra_output_views(
+        specs: &[OutputTextureSpec],
+        textures: &HashMap<String, TextureManager>,
+    ) -> Vec<wgpu::TextureView> {
+        specs
+            .iter()
+            .map(|spec| {
+                textures[&spec.name]
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default())
+            })
+            .collect()
+    }
+
+    fn push_extra_output_entries<'a>(
+        entries: &mut Vec<wgpu::BindGroupEntry<'a>>,
+        views: &'a [wgpu::TextureView],
+    ) {
+        for (i, view) in views.iter().enumerate() {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 4 + i as u32,
+                resource: wgpu::BindingResource::TextureView(view),
+            });
         }
     }
 
@@ -496,24 +950,30 @@ impl ComputeShader {
         }
     }
 
+    /// View bound as the Group 1 output: the volume if there is one, else the 2D output
+    fn output_storage_view(output_texture: &TextureManager, volume_texture: Option<&VolumeTexture>) -> wgpu::TextureView {
+        match volume_texture {
+            Some(volume) => volume.view.clone(),
+            None => output_texture
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+        }
+    }
+
     fn create_group1_bind_group(
         device: &wgpu::Device,
         layout: &wgpu::BindGroupLayout,
-        output_texture: &TextureManager,
+        output_view: &wgpu::TextureView,
         custom_uniform_size: Option<u64>,
         has_input_texture: bool,
         custom_uniform_buffer: Option<&wgpu::Buffer>,
         input_texture_view: Option<&wgpu::TextureView>,
         input_sampler: Option<&wgpu::Sampler>,
+        extra_output_views: &[wgpu::TextureView],
     ) -> wgpu::BindGroup {
-        // Create a storage view for the compute shader
-        let storage_view = output_texture
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
         let mut entries = vec![wgpu::BindGroupEntry {
             binding: 0,
-            resource: wgpu::BindingResource::TextureView(&storage_view),
+            resource: wgpu::BindingResource::TextureView(output_view),
         }];
 
         // Add custom uniform if present
@@ -541,6 +1001,7 @@ impl ComputeShader {
                 log::error!("Input texture required but not provided during bind group creation");
             }
         }
+        Self::push_extra_output_entries(&mut entries, extra_output_views);
 
         device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout,
@@ -553,6 +1014,7 @@ impl ComputeShader {
         core: &Core,
         layouts: &HashMap<u32, wgpu::BindGroupLayout>,
         config: &ComputeConfiguration,
+        indirect_args_buffer: &Option<wgpu::Buffer>,
     ) -> (
         Option<FontSystem>,
         Option<wgpu::Buffer>,
@@ -560,20 +1022,26 @@ impl ComputeShader {
         Option<wgpu::Buffer>,
         Option<wgpu::Buffer>,
         Option<UniformBinding<crate::MouseUniform>>,
+        Option<UniformBinding<crate::GamepadUniform>>,
         Option<wgpu::BindGroup>,
     ) {
         let layout = layouts.get(&2);
         if layout.is_none() {
-            return (None, None, None, None, None, None, None);
+            return (None, None, None, None, None, None, None, None);
         }
         let layout = layout.unwrap();
 
         // Create font system if needed
-        let font_system = if config.has_fonts {
-            Some(FontSystem::new(core))
-        } else {
-            None
-        };
+        let font_system = config.has_fonts.then(|| {
+            let mut font_system = match &config.font_source {
+                Some(source) => FontSystem::from_font(core, source),
+                None => FontSystem::new(core),
+            };
+            if let Some(max_glyphs) = config.max_text_glyphs {
+                font_system.enable_text(&core.device, max_glyphs);
+            }
+            font_system
+        });
 
         // Create atomic buffer if needed
         let atomic_buffer_raw = if config.has_atomic_buffer {
@@ -581,7 +1049,7 @@ impl ComputeShader {
             Some(core.device.create_buffer(&wgpu::BufferDescriptor {
                 label: Some("Atomic Storage Buffer"),
                 size: buffer_size,
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
                 mapped_at_creation: false,
             }))
         } else {
@@ -629,35 +1097,53 @@ impl ComputeShader {
             None
         };
 
+        // Temporary bind group layout for UniformBinding compatibility
+        let temp_layout = (config.has_mouse || config.has_gamepad).then(|| {
+            core.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                    label: Some("Temp Uniform Layout"),
+                })
+        });
+
         // Create mouse uniform if needed
-        let mouse_uniform = if config.has_mouse {
-            // Create a temporary bind group layout for UniformBinding compatibility
-            let temp_layout =
-                core.device
-                    .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                        entries: &[wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::COMPUTE,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        }],
-                        label: Some("Temp Mouse Layout"),
-                    });
+        let mouse_uniform = config
+            .has_mouse
+            .then_some(temp_layout.as_ref())
+            .flatten()
+            .map(|temp_layout| {
+                UniformBinding::new(
+                    &core.device,
+                    "Mouse Uniform",
+                    crate::MouseUniform::default(),
+                    temp_layout,
+                    0,
+                )
+            });
 
-            Some(UniformBinding::new(
-                &core.device,
-                "Mouse Uniform",
-                crate::MouseUniform::default(),
-                &temp_layout,
-                0,
-            ))
-        } else {
-            None
-        };
+        // Create gamepad uniform if needed
+        let gamepad_uniform = config
+            .has_gamepad
+            .then_some(temp_layout.as_ref())
+            .flatten()
+            .map(|temp_layout| {
+                UniformBinding::new(
+                    &core.device,
+                    "Gamepad Uniform",
+                    crate::GamepadUniform::default(),
+                    temp_layout,
+                    0,
+                )
+            });
 
         // Create Group 2 bind group
         // Create empty channel textures map for initial bind group creation
@@ -673,8 +1159,10 @@ impl ComputeShader {
             &audio_buffer,
             &audio_spectrum_buffer,
             &mouse_uniform,
+            &gamepad_uniform,
             &empty_channels,
             num_channels,
+            indirect_args_buffer,
         );
 
         (
@@ -684,6 +1172,7 @@ impl ComputeShader {
             audio_staging_buffer,
             audio_spectrum_buffer,
             mouse_uniform,
+            gamepad_uniform,
             bind_group,
         )
     }
@@ -697,17 +1186,20 @@ impl ComputeShader {
         audio_buffer: &Option<wgpu::Buffer>,
         audio_spectrum_buffer: &Option<wgpu::Buffer>,
         mouse_uniform: &Option<UniformBinding<crate::MouseUniform>>,
+        gamepad_uniform: &Option<UniformBinding<crate::GamepadUniform>>,
         channel_textures: &HashMap<u32, Option<(wgpu::TextureView, wgpu::Sampler)>>,
         num_channels: u32,
+        indirect_args_buffer: &Option<wgpu::Buffer>,
     ) -> Option<wgpu::BindGroup> {
         // Create entries based on expected layout from ResourceLayout
         // Order must match ResourceLayout creation order:
-        // 1. mouse (if has_mouse) -> binding 0
-        // 2. fonts (if has_fonts) -> bindings 1,2,3
+        // 1. mouse (if has_mouse) -> binding 0, then gamepad (if has_gamepad)
+        // 2. fonts (if has_fonts) -> 2 bindings, plus glyphs (if max_text_glyphs)
         // 3. audio (if has_audio) -> binding N
         // 4. audio_spectrum (if has_audio_spectrum) -> binding N+1
         // 5. atomic_buffer (if has_atomic_buffer) -> binding N+2
         // 6. channels (if num_channels > 0) -> bindings N+3 onwards (texture + sampler pairs)
+        // 7. dispatch_args (if indirect entry points) -> last binding
 
         // Create a default 1x1 magenta texture for unassigned channels
         let default_texture = device.create_texture(&wgpu::TextureDescriptor {
@@ -763,6 +1255,15 @@ impl ComputeShader {
             binding_counter += 1;
         }
 
+        // Add gamepad uniform
+        if let Some(gamepad) = gamepad_uniform {
+            entries.push(wgpu::BindGroupEntry {
+                binding: binding_counter,
+                resource: gamepad.buffer.as_entire_binding(),
+            });
+            binding_counter += 1;
+        }
+
         // Add font texture resources
         if let Some(font_tex) = font_system {
             entries.extend_from_slice(&[
@@ -776,6 +1277,13 @@ impl ComputeShader {
                 },
             ]);
             binding_counter += 2;
+            if let Some(glyphs) = &font_tex.glyph_buffer {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: binding_counter,
+                    resource: glyphs.as_entire_binding(),
+                });
+                binding_counter += 1;
+            }
         }
 
         // Add audio buffer
@@ -829,6 +1337,14 @@ impl ComputeShader {
             binding_counter += 1;
         }
 
+        // Add indirect dispatch args
+        if let Some(args) = indirect_args_buffer {
+            entries.push(wgpu::BindGroupEntry {
+                binding: binding_counter,
+                resource: args.as_entire_binding(),
+            });
+        }
+
         if entries.is_empty() {
             return None;
         }
@@ -894,17 +1410,55 @@ impl ComputeShader {
         stage_index: usize,
         workgroup_count: [u32; 3],
     ) {
+        if self.encode_stage(encoder, stage_index, StageDispatch::Direct(workgroup_count), &[]) {
+            self.resolve_stage_timestamps(encoder, stage_index);
+        }
+    }
+
+    /// Dispatch single stage with workgroup counts read from `indirect_buffer` at `offset`
+    /// (three `u32`s, as written by an earlier pass). The buffer needs `INDIRECT` usage and
+    /// must not be bound to this shader; for engine-managed args use
+    /// [`ComputeShaderBuilder::with_indirect_dispatch`](crate::compute::ComputeShaderBuilder::with_indirect_dispatch).
+    pub fn dispatch_stage_indirect(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        stage_index: usize,
+        indirect_buffer: &wgpu::Buffer,
+        offset: wgpu::BufferAddress,
+    ) {
+        if self.encode_stage(encoder, stage_index, StageDispatch::Indirect(indirect_buffer, offset), &[]) {
+            self.resolve_stage_timestamps(encoder, stage_index);
+        }
+    }
+
+    /// Copy the shader-written dispatch args into the INDIRECT buffer and return the slot
+    /// offset for `entry_point`, or `None` if it isn't dispatched indirectly
+    fn prepare_indirect(&self, encoder: &mut wgpu::CommandEncoder, entry_point: &str) -> Option<wgpu::BufferAddress> {
+        let slot = self.indirect_entry_points.iter().position(|e| e == entry_point)?;
+        let (args, dispatch) = (self.indirect_args_buffer.as_ref()?, self.indirect_dispatch_buffer.as_ref()?);
+        encoder.copy_buffer_to_buffer(args, 0, dispatch, 0, args.size());
+        Some(slot as wgpu::BufferAddress * 12)
+    }
+
+    /// Record one stage in its own pass; `false` if nothing was encoded
+    fn encode_stage(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        stage_index: usize,
+        dispatch: StageDispatch,
+        immediates: &[u8],
+    ) -> bool {
         if stage_index >= self.pipelines.len() {
             log::error!(
                 "Stage index {} out of bounds (max: {})",
                 stage_index,
                 self.pipelines.len() - 1
             );
-            return;
+            return false;
         }
 
         if self.dispatch_once && self.current_frame > 0 {
-            return;
+            return false;
         }
 
         let pipeline = &self.pipelines[stage_index];
@@ -915,13 +1469,14 @@ impl ComputeShader {
                 "{} Stage {} - {}",
                 self.label, stage_index, entry_point
             )),
-            timestamp_writes: None,
+            timestamp_writes: self.timestamps.as_ref().map(|t| t.writes(stage_index)),
         });
 
         compute_pass.set_pipeline(pipeline);
 
         // Set bind groups following the 4-group convention
-        compute_pass.set_bind_group(0, &self.group0_bind_group, &[]); // Per-frame
+        let group0 = self.push_constant_bind_groups.get(stage_index).unwrap_or(&self.group0_bind_group);
+        compute_pass.set_bind_group(0, group0, &[]); // Per-frame
         compute_pass.set_bind_group(1, &self.group1_bind_group, &[]); // Primary I/O
 
         // Group 2: Engine resources
@@ -938,18 +1493,56 @@ impl ComputeShader {
             compute_pass.set_bind_group(3, empty_group3, &[]);
         }
 
-        compute_pass.dispatch_workgroups(
-            workgroup_count[0],
-            workgroup_count[1],
-            workgroup_count[2],
-        );
+        if !immediates.is_empty() && self.push_constant_fallback.is_empty() {
+            compute_pass.set_immediates(0, immediates);
+        }
+
+        match dispatch {
+            StageDispatch::Direct(workgroup_count) => compute_pass.dispatch_workgroups(
+                workgroup_count[0],
+                workgroup_count[1],
+                workgroup_count[2],
+            ),
+            StageDispatch::Indirect(buffer, offset) => {
+                compute_pass.dispatch_workgroups_indirect(buffer, offset)
+            }
+        }
+        true
     }
 
-    /// Compute workgroup count for a given resolution
-    fn workgroup_count_for(&self, width: u32, height: u32) -> [u32; 3] {
+    /// Compute workgroup count for one stage at the given output resolution.
+    /// Priority: explicit dispatch count > 3D volume extent > buffer resolution > output size,
+    /// each divided by the pass's own group size if it has one, else the global workgroup size.
+    fn stage_workgroup_count(&self, stage_index: usize, width: u32, height: u32) -> [u32; 3] {
+        let pass_desc = self
+            .pass_descriptions
+            .as_ref()
+            .and_then(|passes| passes.get(stage_index));
+        if let Some(custom_count) = pass_desc.and_then(|p| p.workgroup_size) {
+            return custom_count;
+        }
+        let group_size = pass_desc
+            .and_then(|p| p.group_size)
+            .unwrap_or(self.workgroup_size);
+
+        if let Some(ref volume) = self.volume_texture {
+            let size = volume.size();
+            return [
+                size[0].div_ceil(group_size[0]),
+                size[1].div_ceil(group_size[1]),
+                size[2].div_ceil(group_size[2]),
+            ];
+        }
+
+        let (width, height) = match (pass_desc, &self.multipass_manager) {
+            (Some(p), Some(multipass)) if p.resolution.is_some() || p.resolution_scale.is_some() => {
+                multipass.get_buffer_dimensions(&self.entry_points[stage_index])
+            }
+            _ => (width, height),
+        };
         [
-            width.div_ceil(self.workgroup_size[0]),
-            height.div_ceil(self.workgroup_size[1]),
+            width.div_ceil(group_size[0]),
+            height.div_ceil(group_size[1]),
             1,
         ]
     }
@@ -962,15 +1555,69 @@ impl ComputeShader {
         stage_index: usize,
     ) {
         self.check_hot_reload(&core.device);
+        self.upload_text(&core.queue);
+        let dispatch = self.stage_dispatch(encoder, stage_index);
+        if self.encode_stage(encoder, stage_index, dispatch, &[]) {
+            self.resolve_stage_timestamps(encoder, stage_index);
+        }
+    }
+
+    /// Dispatch single stage with push constants set from `data`
+    /// (see [`ComputeShaderBuilder::with_push_constants`](crate::compute::ComputeShaderBuilder::with_push_constants)).
+    ///
+    /// When the device lacks native push constants, `data` goes into this stage's fallback
+    /// uniform with `queue.write_buffer`, which lands before the next submit. Different stages
+    /// keep their own values, but repeated dispatches of one stage in the same submit all
+    /// see the last `data`.
+    #[cfg(feature = "push-constants")]
+    pub fn dispatch_stage_pc<T: bytemuck::Pod>(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        core: &Core,
+        stage_index: usize,
+        data: &T,
+    ) {
+        let bytes = bytemuck::bytes_of(data);
+        if bytes.len() as u32 != self.push_constant_size {
+            error!(
+                "{}: push constants are {} bytes, shader was built for {}",
+                self.label,
+                bytes.len(),
+                self.push_constant_size
+            );
+            return;
+        }
+
+        self.check_hot_reload(&core.device);
+        self.upload_text(&core.queue);
+        if let Some(fallback) = self.push_constant_fallback.get(stage_index) {
+            core.queue.write_buffer(fallback, 0, bytes);
+        }
+        let dispatch = self.stage_dispatch(encoder, stage_index);
+        if self.encode_stage(encoder, stage_index, dispatch, bytes) {
+            self.resolve_stage_timestamps(encoder, stage_index);
+        }
+    }
+
+    /// Pick indirect args for engine-managed indirect entry points, otherwise size the
+    /// dispatch to the output texture
+    fn stage_dispatch(&self, encoder: &mut wgpu::CommandEncoder, stage_index: usize) -> StageDispatch<'_> {
+        let indirect_offset = self
+            .entry_points
+            .get(stage_index)
+            .and_then(|entry_point| self.prepare_indirect(encoder, entry_point));
+        if let (Some(offset), Some(buffer)) = (indirect_offset, &self.indirect_dispatch_buffer) {
+            return StageDispatch::Indirect(buffer, offset);
+        }
 
         let width = self.output_texture.texture.width();
         let height = self.output_texture.texture.height();
-        let workgroup_count = self.workgroup_count_for(width, height);
-        self.dispatch_stage_with_workgroups(encoder, stage_index, workgroup_count);
+        StageDispatch::Direct(self.stage_workgroup_count(stage_index, width, height))
     }
 
     pub fn dispatch(&mut self, encoder: &mut wgpu::CommandEncoder, core: &Core) {
         self.check_hot_reload(&core.device);
+        self.upload_text(&core.queue);
 
         if self.dispatch_once && self.current_frame > 0 {
             return;
@@ -978,14 +1625,14 @@ impl ComputeShader {
 
         let width = self.output_texture.texture.width();
         let height = self.output_texture.texture.height();
-        let workgroup_count = self.workgroup_count_for(width, height);
 
         // Handle multi-pass execution
         if self.multipass_manager.is_some() {
-            self.dispatch_multipass(encoder, workgroup_count);
+            self.dispatch_multipass(encoder, width, height);
         } else {
-            self.dispatch_single_pass(encoder, core, workgroup_count);
+            self.dispatch_single_pass(encoder, core, width, height);
         }
+        self.resolve_timestamps(encoder);
 
         self.current_frame += 1;
     }
@@ -999,22 +1646,98 @@ impl ComputeShader {
         height: u32,
     ) {
         self.check_hot_reload(&core.device);
+        self.upload_text(&core.queue);
 
         if self.dispatch_once && self.current_frame > 0 {
             return;
         }
 
-        let workgroup_count = self.workgroup_count_for(width, height);
-
         if self.multipass_manager.is_some() {
-            self.dispatch_multipass(encoder, workgroup_count);
+            self.dispatch_multipass(encoder, width, height);
         } else {
-            self.dispatch_single_pass(encoder, core, workgroup_count);
+            self.dispatch_single_pass(encoder, core, width, height);
         }
+        self.resolve_timestamps(encoder);
 
         self.current_frame += 1;
     }
 
+    fn resolve_timestamps(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if let Some(ref mut timestamps) = self.timestamps {
+            encoder.resolve_query_set(&timestamps.query_set, 0..timestamps.count, &timestamps.resolve_buffer, 0);
+            encoder.copy_buffer_to_buffer(
+                &timestamps.resolve_buffer,
+                0,
+                &timestamps.readback_buffer,
+                0,
+                timestamps.resolve_buffer.size(),
+            );
+            timestamps.resolved = true;
+        }
+    }
+
+    /// Resolve just `stage_index`'s pair of queries, so stages that haven't run yet are
+    /// never resolved. The pair goes through the start of the resolve buffer, since query
+    /// resolves need 256-byte aligned offsets, and lands in the stage's readback slot.
+    fn resolve_stage_timestamps(&mut self, encoder: &mut wgpu::CommandEncoder, stage_index: usize) {
+        if let Some(ref mut timestamps) = self.timestamps {
+            let first = stage_index as u32 * 2;
+            let pair_size = 2 * std::mem::size_of::<u64>() as u64;
+            encoder.resolve_query_set(&timestamps.query_set, first..first + 2, &timestamps.resolve_buffer, 0);
+            encoder.copy_buffer_to_buffer(
+                &timestamps.resolve_buffer,
+                0,
+                &timestamps.readback_buffer,
+                stage_index as u64 * pair_size,
+                pair_size,
+            );
+            timestamps.resolved = true;
+        }
+    }
+
+    /// Per-pass GPU times in milliseconds from the last `.dispatch()`, or from single-stage
+    /// dispatches (`dispatch_stage`, `dispatch_stage_pc` and the like), as `(entry point, ms)`.
+    /// A stage dispatched several times reports its last run; stages not dispatched since
+    /// the previous read keep their earlier time (0 until they first run).
+    ///
+    /// Call after submitting the encoder that ran the dispatch; reading new results blocks
+    /// until the GPU finishes that work. Without [`with_timestamps`] or on devices lacking
+    /// `TIMESTAMP_QUERY`, returns an empty vec.
+    ///
+    /// [`with_timestamps`]: crate::compute::ComputeShaderBuilder::with_timestamps
+    pub fn last_pass_times(&mut self, device: &wgpu::Device) -> Vec<(String, f32)> {
+        let Some(ref mut timestamps) = self.timestamps else {
+            return Vec::new();
+        };
+        if !timestamps.resolved {
+            return self.last_pass_times.clone();
+        }
+        timestamps.resolved = false;
+
+        let buffer_slice = timestamps.readback_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).unwrap();
+        });
+        let _ = device.poll(wgpu::PollType::wait_indefinitely());
+        if let Ok(Ok(())) = rx.recv() {
+            let ticks: Vec<u64> = bytemuck::cast_slice(&buffer_slice.get_mapped_range()).to_vec();
+            timestamps.readback_buffer.unmap();
+            self.last_pass_times = self
+                .entry_points
+                .iter()
+                .zip(ticks.chunks_exact(2))
+                .map(|(entry_point, pair)| {
+                    let ticks = pair[1].saturating_sub(pair[0]);
+                    (entry_point.clone(), ticks as f32 * timestamps.period_ns / 1_000_000.0)
+                })
+                .collect();
+        } else {
+            error!("{}: failed to read back pass timestamps", self.label);
+        }
+        self.last_pass_times.clone()
+    }
+
     /// Flip ping-pong buffers for multi-pass rendering (call after render)
     pub fn flip_buffers(&mut self) {
         if let Some(ref mut multipass) = self.multipass_manager {
@@ -1023,14 +1746,43 @@ impl ComputeShader {
     }
 
     /// Update custom uniform parameters
+    ///
+    /// Only the bytes that changed since the previous call are uploaded, so calling
+    /// this every frame with mostly unchanged params is cheap.
     pub fn set_custom_params<T: bytemuck::Pod>(&self, params: T, queue: &wgpu::Queue) {
         if let Some(ref buffer) = self.custom_uniform {
-            queue.write_buffer(buffer, 0, bytemuck::bytes_of(&params));
+            let mut uploaded = self.custom_uniform_uploaded.lock().unwrap();
+            crate::uniforms::write_changed(queue, buffer, &mut uploaded, bytemuck::bytes_of(&params));
         } else {
             log::warn!("Attempted to set custom params but no custom uniform buffer exists");
         }
     }
 
+    /// Queue `text` for the `glyphs` buffer (see
+    /// [`ComputeShaderBuilder::with_text`](crate::compute::ComputeShaderBuilder::with_text)).
+    /// `position` is the top-left corner in output pixels and `size` the line height.
+    /// Text accumulates until [`clear_text`](Self::clear_text) and is uploaded on the next dispatch.
+    pub fn draw_text(&mut self, text: &str, position: [f32; 2], size: f32, color: [f32; 4]) {
+        match &mut self.font_system {
+            Some(font_system) if font_system.glyph_buffer.is_some() => {
+                font_system.push_text(text, position, size, color)
+            }
+            _ => warn!("{}: draw_text needs .with_text() on the builder", self.label),
+        }
+    }
+
+    pub fn clear_text(&mut self) {
+        if let Some(font_system) = &mut self.font_system {
+            font_system.clear_text();
+        }
+    }
+
+    fn upload_text(&mut self, queue: &wgpu::Queue) {
+        if let Some(font_system) = &mut self.font_system {
+            font_system.upload_text(queue);
+        }
+    }
+
     /// Get the custom uniform buffer size (if any)
     pub fn get_custom_uniform_size(&self) -> Option<u64> {
         self.custom_uniform_size
@@ -1060,10 +1812,7 @@ impl ComputeShader {
         let group1_layout = self.bind_group_layouts.get(&1).unwrap();
 
         // Create a storage view for the compute shader
-        let storage_view = self
-            .output_texture
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let storage_view = Self::output_storage_view(&self.output_texture, self.volume_texture.as_ref());
 
         let mut entries = vec![wgpu::BindGroupEntry {
             binding: 0,
@@ -1087,6 +1836,8 @@ impl ComputeShader {
             binding: 3,
             resource: wgpu::BindingResource::Sampler(sampler),
         });
+        let extra_output_views = Self::extra_output_views(&self.extra_outputs, &self.output_textures);
+        Self::push_extra_output_entries(&mut entries, &extra_output_views);
 
         self.group1_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: group1_layout,
@@ -1142,8 +1893,10 @@ impl ComputeShader {
                 &self.audio_buffer,
                 &self.audio_spectrum_buffer,
                 &self.mouse_uniform,
+                &self.gamepad_uniform,
                 &self.channel_textures,
                 self.num_channels,
+                &self.indirect_args_buffer,
             );
         }
     }
@@ -1152,26 +1905,16 @@ impl ComputeShader {
         &self,
         encoder: &mut wgpu::CommandEncoder,
         _core: &Core,
-        workgroup_count: [u32; 3],
+        width: u32,
+        height: u32,
     ) {
         for (i, pipeline) in self.pipelines.iter().enumerate() {
             // Get workgroup count for this specific pass
-            let pass_workgroup_count = if let Some(ref pass_descriptions) = self.pass_descriptions {
-                if let Some(pass_desc) = pass_descriptions.get(i) {
-                    if let Some(custom_size) = pass_desc.workgroup_size {
-                        custom_size // Use custom workgroup size from PassDescription
-                    } else {
-                        workgroup_count // Fall back to default screen-based size
-                    }
-                } else {
-                    workgroup_count // Fall back to default if no pass description
-                }
-            } else {
-                workgroup_count // Fall back to default if no pass descriptions
-            };
+            let pass_workgroup_count = self.stage_workgroup_count(i, width, height);
+            let indirect_offset = self.prepare_indirect(encoder, &self.entry_points[i]);
             let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                 label: Some(&format!("{} Compute Pass {}", self.label, i)),
-                timestamp_writes: None,
+                timestamp_writes: self.timestamps.as_ref().map(|t| t.writes(i)),
             });
 
             compute_pass.set_pipeline(pipeline);
@@ -1198,18 +1941,23 @@ impl ComputeShader {
                 log::error!("No Group 3 bind group available - this shouldn't happen with contiguous layout");
             }
 
-            compute_pass.dispatch_workgroups(
-                pass_workgroup_count[0],
-                pass_workgroup_count[1],
-                pass_workgroup_count[2],
-            );
-        }
+            if let (Some(offset), Some(buffer)) = (indirect_offset, &self.indirect_dispatch_buffer) {
+                compute_pass.dispatch_workgroups_indirect(buffer, offset);
+            } else {
+                compute_pass.dispatch_workgroups(
+                    pass_workgroup_count[0],
+                    pass_workgroup_count[1],
+                    pass_workgroup_count[2],
+                );
+            }
+        }
     }
 
     fn dispatch_multipass(
         &mut self,
         encoder: &mut wgpu::CommandEncoder,
-        workgroup_count: [u32; 3],
+        width: u32,
+        height: u32,
     ) {
         let num_passes = self.pipelines.len();
 
@@ -1219,51 +1967,14 @@ impl ComputeShader {
             let entry_point = &self.entry_points[pass_idx];
 
             // Get workgroup count for this specific pass
-            // Priority: explicit workgroup_size > buffer resolution > screen-based default
-            let pass_workgroup_count = if let Some(ref pass_descriptions) = self.pass_descriptions {
-                if let Some(pass_desc) = pass_descriptions.get(pass_idx) {
-                    if let Some(custom_size) = pass_desc.workgroup_size {
-                        custom_size // Explicit dispatch count override
-                    } else if pass_desc.resolution.is_some() || pass_desc.resolution_scale.is_some() {
-                        // Compute from buffer's actual dimensions
-                        if let Some(ref multipass) = self.multipass_manager {
-                            let (bw, bh) = multipass.get_buffer_dimensions(entry_point);
-                            self.workgroup_count_for(bw, bh)
-                        } else {
-                            workgroup_count
-                        }
-                    } else {
-                        workgroup_count // Default screen-based size
-                    }
-                } else {
-                    workgroup_count
-                }
-            } else {
-                workgroup_count
-            };
+            let pass_workgroup_count = self.stage_workgroup_count(pass_idx, width, height);
 
-            // Compute Group 3 cache key from current write_side state
+            // Compute Group 3 cache key from the current ring positions
             let group3_key = if let (Some(multipass), Some(dependencies)) =
                 (&self.multipass_manager, &self.pass_dependencies)
             {
-                let empty_deps = Vec::new();
-                let deps = dependencies.get(entry_point).unwrap_or(&empty_deps);
-                let first_buf = multipass
-                    .first_buffer_name()
-                    .cloned()
-                    .unwrap_or_else(|| "main".to_string());
-                let mut key = 0usize;
-                for i in 0..self.max_input_deps {
-                    let buf_name = if deps.is_empty() {
-                        &first_buf
-                    } else {
-                        deps.get(i).unwrap_or(&deps[0])
-                    };
-                    if multipass.get_write_side(buf_name) {
-                        key |= 1 << i;
-                    }
-                }
-                key
+                let slots = Self::input_slots(multipass, dependencies, entry_point, self.max_input_deps);
+                Self::input_group3_key(multipass, &slots)
             } else {
                 log::warn!("Skipping pass '{entry_point}': multipass manager or dependencies missing");
                 continue;
@@ -1278,16 +1989,17 @@ impl ComputeShader {
                 }
             };
 
-            // Compute Group 1 write side for intermediate passes
-            let write_side = self
+            // Compute Group 1 write slot for intermediate passes
+            let write_index = self
                 .multipass_manager
                 .as_ref()
-                .map(|m| m.get_write_side(entry_point))
-                .unwrap_or(false);
+                .map(|m| m.get_write_index(entry_point))
+                .unwrap_or(0);
 
+            let indirect_offset = self.prepare_indirect(encoder, entry_point);
             let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                 label: Some(&format!("{} Multi-Pass - {}", self.label, entry_point)),
-                timestamp_writes: None,
+                timestamp_writes: self.timestamps.as_ref().map(|t| t.writes(pass_idx)),
             });
 
             compute_pass.set_pipeline(pipeline);
@@ -1297,7 +2009,7 @@ impl ComputeShader {
             if entry_point == "main_image" {
                 compute_pass.set_bind_group(1, &self.group1_bind_group, &[]);
             } else if let Some(cached) = self.cached_intermediate_group1.get(entry_point) {
-                compute_pass.set_bind_group(1, &cached[write_side as usize], &[]);
+                compute_pass.set_bind_group(1, &cached[write_index], &[]);
             } else {
                 compute_pass.set_bind_group(1, &self.group1_bind_group, &[]);
                 log::warn!("No cached Group1 for intermediate pass {entry_point}");
@@ -1320,11 +2032,15 @@ impl ComputeShader {
             // Group 3: Multi-pass input textures (cached)
             compute_pass.set_bind_group(3, input_bind_group, &[]);
 
-            compute_pass.dispatch_workgroups(
-                pass_workgroup_count[0],
-                pass_workgroup_count[1],
-                pass_workgroup_count[2],
-            );
+            if let (Some(offset), Some(buffer)) = (indirect_offset, &self.indirect_dispatch_buffer) {
+                compute_pass.dispatch_workgroups_indirect(buffer, offset);
+            } else {
+                compute_pass.dispatch_workgroups(
+                    pass_workgroup_count[0],
+                    pass_workgroup_count[1],
+                    pass_workgroup_count[2],
+                );
+            }
 
             // Mark this buffer as written so subsequent passes can read from it
             if pass_idx < num_passes - 1 {
@@ -1354,37 +2070,95 @@ impl ComputeShader {
         Ok(())
     }
 
-    /// Check for hot reload updates
+    /// Check for hot reload updates. A shader that fails to compile leaves the
+    /// current pipelines running; the error is available from [`shader_error`](Self::shader_error).
     pub fn check_hot_reload(&mut self, device: &wgpu::Device) -> bool {
-        if let Some(hot_reload) = &mut self.hot_reload {
-            if let Some(new_module) = hot_reload.reload_compute_shader() {
-                // Recreate pipelines with updated shader
-                let mut new_pipelines = Vec::new();
-                for entry_point in &self.entry_points {
-                    let new_pipeline =
-                        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-                            label: Some(&format!(
-                                "Updated {} Pipeline - {}",
-                                self.label, entry_point
-                            )),
-                            layout: Some(&self.pipeline_layout),
-                            module: new_module,
-                            entry_point: Some(entry_point),
-                            compilation_options: wgpu::PipelineCompilationOptions::default(),
-                            cache: None,
-                        });
-                    new_pipelines.push(new_pipeline);
-                }
+        let Some(hot_reload) = &mut self.hot_reload else {
+            return false;
+        };
+        let Some(new_module) = hot_reload.reload_compute_shader().cloned() else {
+            return false;
+        };
+        let new_module = &new_module;
 
+        // Entry points come from the Rust config; a lone single-pass entry point may be
+        // renamed in the shader, anything else that went missing needs a restart
+        let available = hot_reload.compute_entry_points();
+        let missing: Vec<&str> = self
+            .entry_points
+            .iter()
+            .filter(|entry_point| !available.contains(entry_point))
+            .map(String::as_str)
+            .collect();
+        let mut entry_points = self.entry_points.clone();
+        if !available.is_empty() && !missing.is_empty() {
+            if self.multipass_manager.is_none() && entry_points.len() == 1 && available.len() == 1 {
+                info!(
+                    "{}: entry point renamed from `{}` to `{}`",
+                    self.label, entry_points[0], available[0]
+                );
+                entry_points = available.to_vec();
+            } else {
+                let message = format!(
+                    "{}: entry point(s) `{}` not found; the shader declares `{}`. \
+                     Passes are configured in Rust, so adding or renaming them needs a restart",
+                    self.label,
+                    missing.join("`, `"),
+                    available.join("`, `"),
+                );
+                hot_reload.record_error(message);
+                return false;
+            }
+        }
+
+        // Recreate pipelines with updated shader
+        let new_pipelines = crate::hot::capture_validation(device, || {
+            entry_points
+                .iter()
+                .zip(&self.pipeline_constants)
+                .map(|(entry_point, constants)| {
+                    let constants: Vec<(&str, f64)> =
+                        constants.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+                    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                        label: Some(&format!(
+                            "Updated {} Pipeline - {}",
+                            self.label, entry_point
+                        )),
+                        layout: Some(&self.pipeline_layout),
+                        module: new_module,
+                        entry_point: Some(entry_point),
+                        compilation_options: wgpu::PipelineCompilationOptions {
+                            constants: &constants,
+                            ..Default::default()
+                        },
+                        cache: None,
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+
+        match new_pipelines {
+            Ok(new_pipelines) => {
                 self.pipelines = new_pipelines;
+                self.entry_points = entry_points;
                 info!(
                     "{} shader hot-reloaded at frame: {}",
                     self.label, self.current_frame
                 );
-                return true;
+                true
+            }
+            Err(e) => {
+                hot_reload.record_error(format!("{}: {e}", self.label));
+                false
             }
         }
-        false
+    }
+
+    /// Error from the last failed hot reload, cleared once the shader compiles again.
+    /// Pass it to [`ShaderControls::render_shader_error`](crate::ShaderControls::render_shader_error)
+    /// to show it on screen.
+    pub fn shader_error(&self) -> Option<&str> {
+        self.hot_reload.as_ref()?.last_error()
     }
 
     /// Set time uniform data
@@ -1392,6 +2166,12 @@ impl ComputeShader {
         self.time_uniform.data.time = elapsed;
         self.time_uniform.data.delta = delta;
         self.time_uniform.data.frame = self.current_frame;
+        let texture = &self.output_texture.texture;
+        let (tile_offset, full_size) = self
+            .export_tile
+            .unwrap_or(([0, 0], [texture.width(), texture.height()]));
+        self.time_uniform.data.tile_offset = tile_offset;
+        self.time_uniform.data.full_size = full_size;
         self.time_uniform.update(queue);
     }
 
@@ -1442,6 +2222,63 @@ impl ComputeShader {
         &self.output_texture
     }
 
+    /// Filtering of the outputs' display samplers, used when the blit scales them to the
+    /// window or an export. `Nearest` keeps low-resolution output as crisp pixels;
+    /// [`RenderKit::set_blit_filter`](crate::RenderKit::set_blit_filter) sets it for you.
+    pub fn set_output_filter(&mut self, device: &wgpu::Device, filter: wgpu::FilterMode) {
+        if filter == self.output_filter {
+            return;
+        }
+        self.output_filter = filter;
+        let output = &mut self.output_texture;
+        let label = format!("{} Output Texture", self.label);
+        (output.sampler, output.bind_group) = Self::create_display_bind_group(device, &output.view, filter, &label);
+        for (name, output) in &mut self.output_textures {
+            let label = format!("{} Output Texture - {name}", self.label);
+            (output.sampler, output.bind_group) = Self::create_display_bind_group(device, &output.view, filter, &label);
+        }
+    }
+
+    pub fn output_filter(&self) -> wgpu::FilterMode {
+        self.output_filter
+    }
+
+    /// Latest write of a multi-pass buffer, e.g. for
+    /// [`RenderKit::egui_preview`](crate::RenderKit::egui_preview). `None` without
+    /// multi-pass or for an unknown name.
+    pub fn buffer_texture(&self, buffer_name: &str) -> Option<&wgpu::Texture> {
+        let multipass = self.multipass_manager.as_ref()?;
+        multipass.get_buffer_ring(buffer_name)?;
+        Some(multipass.get_read_texture(buffer_name))
+    }
+
+    /// Buffer name and age read by each Group 3 input slot of a pass. Passes with fewer
+    /// inputs repeat their first one; passes without inputs read the first buffer.
+    fn input_slots<'a>(
+        multipass: &'a MultiPassManager,
+        dependencies: &'a HashMap<String, Vec<String>>,
+        entry_point: &str,
+        max_input_deps: usize,
+    ) -> Vec<(&'a str, usize)> {
+        let deps = dependencies.get(entry_point).map(Vec::as_slice).unwrap_or(&[]);
+        (0..max_input_deps)
+            .map(|i| match deps.get(i).or(deps.first()) {
+                Some(input) => crate::compute::multipass::parse_pass_input(input),
+                None => (multipass.first_buffer_name().map_or("main", String::as_str), 0),
+            })
+            .collect()
+    }
+
+    /// Group 3 cache index: the ring slot each input reads, as digits of a mixed-radix
+    /// number whose bases are the ring lengths (plain bits for ping-pong pairs)
+    fn input_group3_key(multipass: &MultiPassManager, slots: &[(&str, usize)]) -> usize {
+        let mut key = 0;
+        for &(name, age) in slots.iter().rev() {
+            key = key * multipass.ring_len(name) + multipass.read_index(name, age);
+        }
+        key
+    }
+
     /// Rebuild cached bind groups for multipass dispatch.
     /// Called at init, after resize, and after clear_all_buffers.
     fn rebuild_multipass_caches(&mut self, device: &wgpu::Device) {
@@ -1458,17 +2295,13 @@ impl ComputeShader {
         };
 
         let group1_layout = self.bind_group_layouts.get(&1).unwrap();
-        let first_buf = multipass
-            .first_buffer_name()
-            .cloned()
-            .unwrap_or_else(|| "main".to_string());
+        let extra_output_views = Self::extra_output_views(&self.extra_outputs, &self.output_textures);
 
         for entry_point in &self.entry_points {
-            // --- Group 1: intermediate pass write targets (2 per pass) ---
+            // --- Group 1: intermediate pass write targets (one per ring slot) ---
             if entry_point != "main_image" {
-                if let Some(textures) = multipass.get_buffer_pair(entry_point) {
-                    // Index 0 = bind group for write_side==false (writes to .0)
-                    // Index 1 = bind group for write_side==true  (writes to .1)
+                if let Some(textures) = multipass.get_buffer_ring(entry_point) {
+                    // Index i = bind group for writing ring slot i
                     let make_bg = |texture: &wgpu::Texture, idx: usize| {
                         let view =
                             texture.create_view(&wgpu::TextureViewDescriptor::default());
@@ -1482,6 +2315,7 @@ impl ComputeShader {
                                 resource: uniform_buffer.as_entire_binding(),
                             });
                         }
+                        Self::push_extra_output_entries(&mut entries, &extra_output_views);
                         device.create_bind_group(&wgpu::BindGroupDescriptor {
                             label: Some(&format!(
                                 "{entry_point} Cached Group1 (side={idx})"
@@ -1492,43 +2326,30 @@ impl ComputeShader {
                     };
                     self.cached_intermediate_group1.insert(
                         entry_point.clone(),
-                        [make_bg(&textures.0, 0), make_bg(&textures.1, 1)],
+                        textures.iter().enumerate().map(|(i, t)| make_bg(t, i)).collect(),
                     );
                 }
             }
 
-            // --- Group 3: input textures (2^N combinations per pass) ---
-            let empty_deps = Vec::new();
-            let deps = dependencies.get(entry_point).unwrap_or(&empty_deps);
+            // --- Group 3: input textures (one per combination of ring slots read) ---
             let n = self.max_input_deps;
-
-            // Resolve which buffer each input slot references
-            let slot_buffers: Vec<&str> = (0..n)
-                .map(|i| {
-                    if deps.is_empty() {
-                        first_buf.as_str()
-                    } else {
-                        deps.get(i).unwrap_or(&deps[0]).as_str()
-                    }
-                })
-                .collect();
+            let slots = Self::input_slots(multipass, dependencies, entry_point, n);
 
             let input_layout = multipass.get_input_layout();
-            let num_combinations = 1usize << n;
+            let pass_samplers = self.input_samplers.get(entry_point).map(Vec::as_slice).unwrap_or(&[]);
+            let num_combinations: usize = slots.iter().map(|&(name, _)| multipass.ring_len(name)).product();
             let mut cached = Vec::with_capacity(num_combinations);
 
             for key in 0..num_combinations {
-                let views: Vec<wgpu::TextureView> = (0..n)
-                    .map(|i| {
-                        let write_side_val = (key >> i) & 1 == 1;
-                        // Replicate get_read_texture: write_side==true → read .0
-                        let textures = multipass.get_buffer_pair(slot_buffers[i]).unwrap();
-                        let texture = if write_side_val {
-                            &textures.0
-                        } else {
-                            &textures.1
-                        };
-                        texture.create_view(&wgpu::TextureViewDescriptor::default())
+                // Decode the ring slot each input reads, inverting input_group3_key
+                let mut rest = key;
+                let views: Vec<wgpu::TextureView> = slots
+                    .iter()
+                    .map(|&(name, _)| {
+                        let textures = multipass.get_buffer_ring(name).unwrap();
+                        let slot = rest % textures.len();
+                        rest /= textures.len();
+                        textures[slot].create_view(&wgpu::TextureViewDescriptor::default())
                     })
                     .collect();
 
@@ -1540,7 +2361,9 @@ impl ComputeShader {
                     });
                     entries.push(wgpu::BindGroupEntry {
                         binding: (i * 2 + 1) as u32,
-                        resource: wgpu::BindingResource::Sampler(&self.multipass_sampler),
+                        resource: wgpu::BindingResource::Sampler(
+                            pass_samplers.get(i).unwrap_or(&self.multipass_sampler),
+                        ),
                     });
                 }
 
@@ -1558,31 +2381,68 @@ impl ComputeShader {
 
     /// Resize resources
     pub fn resize(&mut self, core: &Core, width: u32, height: u32) {
+        let old_outputs = self.preserve_on_resize.then(|| {
+            let extras: HashMap<String, wgpu::Texture> = self
+                .output_textures
+                .iter()
+                .map(|(name, texture)| (name.clone(), texture.texture.clone()))
+                .collect();
+            (self.output_texture.texture.clone(), extras)
+        });
+
         // Recreate output texture
         self.output_texture = Self::create_output_texture(
             &core.device,
             width,
             height,
             self.texture_format,
+            self.output_filter,
             &format!("{} Output Texture", self.label),
         );
+        self.output_textures = Self::create_extra_output_textures(
+            &core.device,
+            width,
+            height,
+            &self.extra_outputs,
+            self.output_filter,
+            &self.label,
+        );
+        let extra_output_views = Self::extra_output_views(&self.extra_outputs, &self.output_textures);
 
-        // recreate Group 1 bind group with new texture
+        // recreate Group 1 bind group with new textures
         let group1_layout = self.bind_group_layouts.get(&1).unwrap();
         self.group1_bind_group = Self::create_group1_bind_group(
             &core.device,
             group1_layout,
-            &self.output_texture,
+            &Self::output_storage_view(&self.output_texture, self.volume_texture.as_ref()),
             self.custom_uniform_size,
             self.has_input_texture,
             self.custom_uniform.as_ref(),
             self.placeholder_input_texture.as_ref().map(|t| &t.view),
             self.placeholder_input_texture.as_ref().map(|t| &t.sampler),
+            &extra_output_views,
         );
 
+        if let Some((old_output, old_extras)) = &old_outputs {
+            let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Output Resize Encoder"),
+            });
+            crate::resample_texture(&core.device, &mut encoder, old_output, &self.output_texture.texture);
+            for (name, old_texture) in old_extras {
+                if let Some(new_texture) = self.output_textures.get(name) {
+                    crate::resample_texture(&core.device, &mut encoder, old_texture, &new_texture.texture);
+                }
+            }
+            core.queue.submit(Some(encoder.finish()));
+        }
+
         // Resize multi-pass buffers if present
         if let Some(multipass) = &mut self.multipass_manager {
-            multipass.resize(core, width, height);
+            if self.preserve_on_resize {
+                multipass.resize_preserving(core, width, height);
+            } else {
+                multipass.resize(core, width, height);
+            }
         }
         self.rebuild_multipass_caches(&core.device);
 
@@ -1592,29 +2452,18 @@ impl ComputeShader {
             *atomic_buffer = core.device.create_buffer(&wgpu::BufferDescriptor {
                 label: Some("Atomic Storage Buffer (resized)"),
                 size: buffer_size,
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
                 mapped_at_creation: false,
             });
 
             // Recreate group2 bind group with the new buffer
-            if let Some(layout) = self.bind_group_layouts.get(&2) {
-                self.group2_bind_group = Self::create_group2_bind_group(
-                    &core.device,
-                    &core.queue,
-                    layout,
-                    &self.font_system,
-                    &self.atomic_buffer_raw,
-                    &self.audio_buffer,
-                    &self.audio_spectrum_buffer,
-                    &self.mouse_uniform,
-                    &self.channel_textures,
-                    self.num_channels,
-                );
-            }
+            self.recreate_group2_bind_group(&core.device, &core.queue);
         }
 
-        // Reset frame counter on resize to start fresh
-        self.current_frame = 0;
+        // Reset frame counter on resize to start fresh, unless the contents were kept
+        if !self.preserve_on_resize {
+            self.current_frame = 0;
+        }
     }
 
     /// Clear all buffers (atomic or multipass)
@@ -1635,30 +2484,82 @@ impl ComputeShader {
     /// Clear atomic buffer by recreating it (like old clear_all method)
     pub fn clear_atomic_buffer(&mut self, core: &Core) {
         if self.atomic_buffer_raw.is_some() {
-            let buffer_size = (core.size.width * core.size.height * self.atomic_buffer_channels * 4) as u64;
+            let width = self.output_texture.texture.width();
+            let height = self.output_texture.texture.height();
+            let buffer_size = (width * height * self.atomic_buffer_channels * 4) as u64;
             self.atomic_buffer_raw = Some(core.device.create_buffer(&wgpu::BufferDescriptor {
                 label: Some("Atomic Storage Buffer (cleared)"),
                 size: buffer_size,
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
                 mapped_at_creation: false,
             }));
 
             // Recreate group2 bind group with the new buffer
-            if let Some(layout) = self.bind_group_layouts.get(&2) {
-                self.group2_bind_group = Self::create_group2_bind_group(
-                    &core.device,
-                    &core.queue,
-                    layout,
-                    &self.font_system,
-                    &self.atomic_buffer_raw,
-                    &self.audio_buffer,
-                    &self.audio_spectrum_buffer,
-                    &self.mouse_uniform,
-                    &self.channel_textures,
-                    self.num_channels,
-                );
-            }
+            self.recreate_group2_bind_group(&core.device, &core.queue);
+        }
+    }
+
+    /// The Group 3 storage buffer declared as `name` with `with_storage_buffer`
+    pub fn storage_buffer(&self, name: &str) -> Option<&wgpu::Buffer> {
+        let index = self.storage_buffer_names.iter().position(|n| n == name)?;
+        self.storage_buffers.get(index)
+    }
+
+    fn named_storage_buffer(&self, name: &str) -> anyhow::Result<&wgpu::Buffer> {
+        self.storage_buffer(name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "{}: no storage buffer named `{name}` (declared: {:?})",
+                self.label,
+                self.storage_buffer_names
+            )
+        })
+    }
+
+    /// Zero a storage buffer, e.g. for a "Reset Simulation" button, without
+    /// rebuilding the shader. Runs before anything submitted afterwards.
+    pub fn clear_storage_buffer(&self, name: &str, core: &Core) -> anyhow::Result<()> {
+        let buffer = self.named_storage_buffer(name)?;
+        let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Clear Storage Buffer"),
+        });
+        encoder.clear_buffer(buffer, 0, None);
+        core.queue.submit(Some(encoder.finish()));
+        Ok(())
+    }
+
+    /// Upload `data` to the start of a storage buffer, e.g. CPU-computed initial
+    /// particle positions. Fails if `data` is larger than the declared size or not a
+    /// multiple of 4 bytes; a shorter `data` leaves the rest of the buffer as it was.
+    pub fn write_storage_buffer(&self, name: &str, data: &[u8], queue: &wgpu::Queue) -> anyhow::Result<()> {
+        let buffer = self.named_storage_buffer(name)?;
+        if data.len() as u64 > buffer.size() {
+            anyhow::bail!(
+                "{}: {} bytes don't fit in storage buffer `{name}` ({} bytes)",
+                self.label,
+                data.len(),
+                buffer.size()
+            );
         }
+        if !(data.len() as u64).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            anyhow::bail!(
+                "{}: writes to storage buffer `{name}` must be a multiple of {} bytes, got {}",
+                self.label,
+                wgpu::COPY_BUFFER_ALIGNMENT,
+                data.len()
+            );
+        }
+        queue.write_buffer(buffer, 0, data);
+        Ok(())
+    }
+
+    /// [`write_storage_buffer`](Self::write_storage_buffer) for a slice of `Pod` values
+    pub fn write_storage_slice<T: bytemuck::Pod>(
+        &self,
+        name: &str,
+        data: &[T],
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<()> {
+        self.write_storage_buffer(name, bytemuck::cast_slice(data), queue)
     }
 
     /// Update mouse uniform with data from RenderKit
@@ -1672,6 +2573,17 @@ impl ComputeShader {
             mouse_uniform.update(queue);
         }
     }
+    /// Update gamepad uniform with data from `RenderKit::gamepad_tracker`
+    pub fn update_gamepad_uniform(
+        &mut self,
+        gamepad_uniform_data: &crate::GamepadUniform,
+        queue: &wgpu::Queue,
+    ) {
+        if let Some(gamepad_uniform) = &mut self.gamepad_uniform {
+            gamepad_uniform.data = *gamepad_uniform_data;
+            gamepad_uniform.update(queue);
+        }
+    }
     pub fn get_audio_buffer(&self) -> Option<&wgpu::Buffer> {
         self.audio_buffer.as_ref()
     }
@@ -1731,15 +2643,278 @@ impl ComputeShader {
         }
     }
 
+    /// Copy the atomic buffer (`.with_atomic_buffer()`) to the CPU, e.g. to inspect
+    /// histogram bins or compute exposure from a buddhabrot accumulation.
+    ///
+    /// Blocks until the GPU finishes all submitted work and copies the whole buffer,
+    /// so prefer [`encode_atomic_max`](Self::encode_atomic_max) for per-frame use.
+    /// Returns an empty vec without an atomic buffer.
+    pub fn read_atomic_buffer(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Vec<u32> {
+        let Some(atomic_buffer) = &self.atomic_buffer_raw else {
+            return Vec::new();
+        };
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{} Atomic Readback", self.label)),
+            size: atomic_buffer.size(),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Atomic Buffer Copy"),
+        });
+        encoder.copy_buffer_to_buffer(atomic_buffer, 0, &staging_buffer, 0, atomic_buffer.size());
+        queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = staging_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        let _ = device.poll(wgpu::PollType::wait_indefinitely());
+        if !matches!(rx.recv(), Ok(Ok(()))) {
+            error!("{}: failed to read back atomic buffer", self.label);
+            return Vec::new();
+        }
+        let values = bytemuck::cast_slice(&buffer_slice.get_mapped_range()).to_vec();
+        staging_buffer.unmap();
+        values
+    }
+
+    /// Largest value in the atomic buffer, 0 if it's empty or missing. Blocking; see
+    /// [`read_atomic_buffer`](Self::read_atomic_buffer).
+    pub fn atomic_buffer_max(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> u32 {
+        self.read_atomic_buffer(device, queue).into_iter().max().unwrap_or(0)
+    }
+
+    /// Record a GPU max reduction of the atomic buffer into `encoder`, after
+    /// `dispatch`. Only 4 bytes are read back, by [`poll_atomic_max`](Self::poll_atomic_max)
+    /// once the encoder is submitted. Skipped while the previous result is still in
+    /// flight, so it is safe to call every frame.
+    pub fn encode_atomic_max(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder) {
+        let Some(atomic_buffer) = &self.atomic_buffer_raw else {
+            return;
+        };
+        self.atomic_max
+            .get_or_insert_with(|| MaxReduction::new(device, &self.label))
+            .encode(device, encoder, atomic_buffer);
+    }
+
+    /// Latest atomic buffer max from [`encode_atomic_max`](Self::encode_atomic_max),
+    /// without waiting for the GPU; usually a frame or two behind. `None` until the
+    /// first result arrives.
+    pub fn poll_atomic_max(&mut self, device: &wgpu::Device) -> Option<u32> {
+        self.atomic_max.as_mut()?.poll(device)
+    }
+
+    /// Record a reduction of how much the multi-pass buffer `buffer_name` changed in its
+    /// last write, after `dispatch`. Meant for progressive renderers that accumulate
+    /// into a self-feedback buffer; alpha is read as the sample count when it's above 0.
+    /// Read the result with [`convergence_estimate`](Self::convergence_estimate). Like
+    /// [`encode_atomic_max`](Self::encode_atomic_max), it is skipped while the previous
+    /// result is in flight.
+    pub fn encode_convergence(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, buffer_name: &str) {
+        let Some(multipass) = &self.multipass_manager else {
+            return;
+        };
+        let current = multipass.get_history_texture(buffer_name, 0);
+        let previous = multipass.get_history_texture(buffer_name, 1);
+        self.convergence
+            .get_or_insert_with(|| ConvergenceReduction::new(device, &self.label))
+            .encode(device, encoder, current, previous);
+    }
+
+    /// Mean squared per-pixel luminance change of the last write from
+    /// [`encode_convergence`](Self::encode_convergence), relative to the squared mean
+    /// luminance; falls toward 0 as the image converges. A frame or two behind, `None`
+    /// until the first result arrives or after [`reset_convergence`](Self::reset_convergence).
+    pub fn convergence_estimate(&mut self, device: &wgpu::Device) -> Option<f32> {
+        self.convergence.as_mut()?.poll(device)
+    }
+
+    /// Drop the current estimate, e.g. when accumulation restarts after a camera move
+    pub fn reset_convergence(&mut self) {
+        if let Some(convergence) = &mut self.convergence {
+            convergence.reset();
+        }
+    }
+
+    /// Read the output texture back to the CPU as 8-bit RGBA.
+    ///
+    /// Pixels are converted the way the display blit shows them: linear formats
+    /// (`Rgba16Float`, `Rgba32Float`, `Rgba8Unorm`) are clamped and sRGB-encoded, sRGB formats
+    /// are copied as-is. Alpha is kept as written by the shader. Blocks until the GPU is done.
+    ///
+    /// # Panics
+    /// If the output format is not `Rgba8Unorm(Srgb)`, `Bgra8Unorm(Srgb)`, `Rgba16Float`
+    /// or `Rgba32Float`.
+    pub fn read_output_rgba(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> image::RgbaImage {
+        let texels = self.read_texels(device, queue, &self.output_texture.texture, "read_output_rgba");
+        Self::texels_to_rgba(texels)
+    }
+
+    /// [`read_output_rgba`](Self::read_output_rgba) for the latest write of a multi-pass
+    /// buffer, at the buffer's own size. `None` for an unknown name.
+    pub fn read_buffer_rgba(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        buffer_name: &str,
+    ) -> Option<image::RgbaImage> {
+        let texture = self.buffer_texture(buffer_name)?;
+        Some(Self::texels_to_rgba(self.read_texels(device, queue, texture, "read_buffer_rgba")))
+    }
+
+    fn texels_to_rgba((width, height, texels): (u32, u32, Vec<[f32; 4]>)) -> image::RgbaImage {
+        let pixels = texels
+            .iter()
+            .flat_map(|&[r, g, b, a]| {
+                [linear_to_srgb8(r), linear_to_srgb8(g), linear_to_srgb8(b), unorm8(a)]
+            })
+            .collect();
+        image::RgbaImage::from_raw(width, height, pixels).unwrap()
+    }
+
+    /// Read the output texture back as linear 32-bit float RGBA, without clamping or
+    /// tonemapping, e.g. to keep the full range of an `Rgba16Float` accumulation buffer.
+    /// sRGB formats are decoded to linear. Blocks until the GPU is done.
+    ///
+    /// # Panics
+    /// On the same unsupported formats as [`read_output_rgba`](Self::read_output_rgba).
+    pub fn read_output_hdr(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> image::Rgba32FImage {
+        let texels = self.read_texels(device, queue, &self.output_texture.texture, "read_output_hdr");
+        Self::texels_to_hdr(texels)
+    }
+
+    /// [`read_output_hdr`](Self::read_output_hdr) for the latest write of a multi-pass
+    /// buffer, at the buffer's own size. `None` for an unknown name.
+    pub fn read_buffer_hdr(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        buffer_name: &str,
+    ) -> Option<image::Rgba32FImage> {
+        let texture = self.buffer_texture(buffer_name)?;
+        Some(Self::texels_to_hdr(self.read_texels(device, queue, texture, "read_buffer_hdr")))
+    }
+
+    fn texels_to_hdr((width, height, texels): (u32, u32, Vec<[f32; 4]>)) -> image::Rgba32FImage {
+        image::Rgba32FImage::from_raw(width, height, texels.into_iter().flatten().collect()).unwrap()
+    }
+
+    /// Copy the output texture or a multi-pass buffer (both in `texture_format`) to the
+    /// CPU and decode every texel to linear RGBA
+    fn read_texels(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        caller: &str,
+    ) -> (u32, u32, Vec<[f32; 4]>) {
+        use wgpu::TextureFormat as F;
+
+        let format = self.texture_format;
+        assert!(
+            matches!(
+                format,
+                F::Rgba8Unorm | F::Rgba8UnormSrgb | F::Bgra8Unorm | F::Bgra8UnormSrgb | F::Rgba16Float | F::Rgba32Float
+            ),
+            "{caller}: unsupported output format {format:?}"
+        );
+
+        let (width, height) = (texture.width(), texture.height());
+        let bytes_per_pixel = format.block_copy_size(None).unwrap();
+        let unpadded_bytes_per_row = width * bytes_per_pixel;
+        let padded_bytes_per_row = unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{} Output Readback", self.label)),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Output Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &staging_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = staging_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).unwrap();
+        });
+        let _ = device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+        rx.recv().unwrap().unwrap();
+
+        let mut texels = Vec::with_capacity((width * height) as usize);
+        {
+            let data = buffer_slice.get_mapped_range();
+            for row in data.chunks(padded_bytes_per_row as usize) {
+                let row = &row[..unpadded_bytes_per_row as usize];
+                match format {
+                    F::Rgba8Unorm | F::Rgba8UnormSrgb | F::Bgra8Unorm | F::Bgra8UnormSrgb => {
+                        let bgra = matches!(format, F::Bgra8Unorm | F::Bgra8UnormSrgb);
+                        let decode = |v: u8| {
+                            let v = v as f32 / 255.0;
+                            if format.is_srgb() { srgb_to_linear(v) } else { v }
+                        };
+                        for px in row.chunks_exact(4) {
+                            let (r, b) = if bgra { (px[2], px[0]) } else { (px[0], px[2]) };
+                            texels.push([decode(r), decode(px[1]), decode(b), px[3] as f32 / 255.0]);
+                        }
+                    }
+                    F::Rgba16Float => {
+                        for px in row.chunks_exact(8) {
+                            let c = |i: usize| f16_to_f32(u16::from_le_bytes([px[i * 2], px[i * 2 + 1]]));
+                            texels.push([c(0), c(1), c(2), c(3)]);
+                        }
+                    }
+                    _ => {
+                        for px in row.chunks_exact(16) {
+                            let c = |i: usize| f32::from_le_bytes(px[i * 4..i * 4 + 4].try_into().unwrap());
+                            texels.push([c(0), c(1), c(2), c(3)]);
+                        }
+                    }
+                }
+            }
+        }
+        staging_buffer.unmap();
+
+        (width, height, texels)
+    }
+
     /// Automatic export - call from shader update() method
     pub fn handle_export(&mut self, core: &Core, render_kit: &mut crate::RenderKit) {
-        if let Some((frame, time)) = render_kit.export_manager.try_get_next_frame() {
+        let next_frame = render_kit.export_manager.try_get_next_frame();
+        core.suspend_frame_limit(next_frame.is_some());
+        if let Some((frame, time)) = next_frame {
             let settings = render_kit.export_manager.settings();
-            let export_w = settings.width;
-            let export_h = settings.height;
+            let (export_w, export_h) = settings.render_size(core.limits().max_texture_dimension_2d);
 
             // Resize compute to export resolution on first frame
-            if frame == 0 {
+            if frame == settings.start_frame {
                 self.current_frame = 0;
                 let current_w = self.output_texture.texture.width();
                 let current_h = self.output_texture.texture.height();
@@ -1771,8 +2946,7 @@ impl ComputeShader {
                 None::<fn(&mut Self, &mut wgpu::CommandEncoder, &Core)>,
             ) {
                 Ok(data) => {
-                    let settings = render_kit.export_manager.settings();
-                    if let Err(e) = crate::save_frame(data, frame, settings) {
+                    if let Err(e) = render_kit.export_manager.save_frame(data, frame) {
                         error!("Error saving frame: {e:?}");
                     }
                 }
@@ -1781,13 +2955,8 @@ impl ComputeShader {
                 }
             }
         } else {
-            // Export complete — resize back to window resolution
-            let current_w = self.output_texture.texture.width();
-            let current_h = self.output_texture.texture.height();
-            if current_w != core.size.width || current_h != core.size.height {
-                info!("Export complete: resizing compute back to {}x{}", core.size.width, core.size.height);
-                self.resize(core, core.size.width, core.size.height);
-            }
+            // Not exporting: keep the compute at window resolution times the render scale
+            self.match_render_size(core, render_kit);
             #[cfg(feature = "media")]
             render_kit.end_export_audio();
             render_kit.export_manager.complete_export();
@@ -1801,13 +2970,14 @@ impl ComputeShader {
         render_kit: &mut crate::RenderKit,
         custom_dispatch: impl FnOnce(&mut Self, &mut wgpu::CommandEncoder, &Core),
     ) {
-        if let Some((frame, time)) = render_kit.export_manager.try_get_next_frame() {
+        let next_frame = render_kit.export_manager.try_get_next_frame();
+        core.suspend_frame_limit(next_frame.is_some());
+        if let Some((frame, time)) = next_frame {
             let settings = render_kit.export_manager.settings();
-            let export_w = settings.width;
-            let export_h = settings.height;
+            let (export_w, export_h) = settings.render_size(core.limits().max_texture_dimension_2d);
 
             // Resize compute to export resolution on first frame
-            if frame == 0 {
+            if frame == settings.start_frame {
                 self.current_frame = 0;
                 let current_w = self.output_texture.texture.width();
                 let current_h = self.output_texture.texture.height();
@@ -1830,8 +3000,7 @@ impl ComputeShader {
 
             match self.capture_export_frame(core, time, render_kit, Some(custom_dispatch)) {
                 Ok(data) => {
-                    let settings = render_kit.export_manager.settings();
-                    if let Err(e) = crate::save_frame(data, frame, settings) {
+                    if let Err(e) = render_kit.export_manager.save_frame(data, frame) {
                         error!("Error saving frame: {e:?}");
                     }
                 }
@@ -1840,20 +3009,35 @@ impl ComputeShader {
                 }
             }
         } else {
-            // Export complete — resize back to window resolution
-            let current_w = self.output_texture.texture.width();
-            let current_h = self.output_texture.texture.height();
-            if current_w != core.size.width || current_h != core.size.height {
-                info!("Export complete: resizing compute back to {}x{}", core.size.width, core.size.height);
-                self.resize(core, core.size.width, core.size.height);
-            }
+            // Not exporting: keep the compute at window resolution times the render scale
+            self.match_render_size(core, render_kit);
             #[cfg(feature = "media")]
             render_kit.end_export_audio();
             render_kit.export_manager.complete_export();
         }
     }
 
-    /// Captures current frame with format conversion and optional custom dispatch
+    /// Resize to `render_kit.render_size(core)` if needed, after an export or when the
+    /// render scale changed
+    fn match_render_size(&mut self, core: &Core, render_kit: &crate::RenderKit) {
+        self.set_output_filter(&core.device, render_kit.blit_filter());
+        let target = render_kit.render_size(core);
+        let current_w = self.output_texture.texture.width();
+        let current_h = self.output_texture.texture.height();
+        if current_w != target.width || current_h != target.height {
+            info!(
+                "Resizing compute to {}x{} (render scale {:.2})",
+                target.width,
+                target.height,
+                render_kit.render_scale()
+            );
+            self.resize(core, target.width, target.height);
+        }
+    }
+
+    /// Captures current frame with format conversion and optional custom dispatch, at
+    /// [`ExportSettings::capture_size`](crate::ExportSettings::capture_size). Frames larger than the device texture limit (or with `tile_size` set) render in
+    /// tiles, which needs the default dispatch.
     pub fn capture_export_frame<F>(
         &mut self,
         core: &Core,
@@ -1865,10 +3049,19 @@ impl ComputeShader {
         F: FnOnce(&mut Self, &mut wgpu::CommandEncoder, &Core),
     {
         let settings = render_kit.export_manager.settings();
-        let (capture_texture, output_buffer) =
-            render_kit.create_capture_texture(&core.device, settings.width, settings.height);
-
-        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        if let Some(name) = &settings.source_buffer
+            && self.buffer_texture(name).is_none()
+        {
+            error!("Export buffer '{name}' doesn't exist; leave it empty to export the final output");
+            return Err(crate::SurfaceError::SkipFrame);
+        }
+        if let Some(extent) = settings.tile_extent(core.limits().max_texture_dimension_2d) {
+            if custom_dispatch.is_some() {
+                error!("Tiled export needs the default dispatch; lower the export size or tile_size");
+                return Err(crate::SurfaceError::SkipFrame);
+            }
+            return Ok(self.capture_export_tiles(core, time, render_kit, extent));
+        }
 
         let mut encoder = core
             .device
@@ -1879,74 +3072,112 @@ impl ComputeShader {
         let delta = 1.0 / settings.fps as f32;
         self.set_time(time, delta, &core.queue);
 
-        // Dispatch at export resolution
+        // Dispatch at export resolution, times the supersampling factor
+        let (width, height) = settings.capture_size();
         if let Some(custom_dispatch) = custom_dispatch {
             custom_dispatch(self, &mut encoder, core);
         } else {
-            self.dispatch_at_resolution(&mut encoder, core, settings.width, settings.height);
+            self.dispatch_at_resolution(&mut encoder, core, width, height);
         }
+        Ok(self.read_export_pixels(core, encoder, render_kit, width, height))
+    }
 
-        {
-            let mut render_pass = crate::Renderer::begin_render_pass(
-                &mut encoder,
-                &capture_view,
-                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                Some("Export Capture Pass"),
-            );
-
-            render_pass.set_pipeline(&render_kit.renderer.render_pipeline);
-            render_pass.set_vertex_buffer(0, render_kit.renderer.vertex_buffer.slice(..));
-            render_pass.set_bind_group(0, &self.output_texture.bind_group, &[]);
-            render_pass.draw(0..4, 0..1);
+    /// Render the frame one tile at a time, each with `overlap` extra pixels around it
+    /// that get cropped, and stitch the tiles into one frame
+    fn capture_export_tiles(
+        &mut self,
+        core: &Core,
+        time: f32,
+        render_kit: &crate::RenderKit,
+        extent: u32,
+    ) -> Vec<u8> {
+        let settings = render_kit.export_manager.settings();
+        let overlap = settings.tile_overlap;
+        let (width, height) = settings.capture_size();
+        let bytes_per_pixel = if settings.format == crate::ExportFormat::Exr { 16 } else { 4 };
+        let row_bytes = width as usize * bytes_per_pixel;
+        let mut frame_data = vec![0u8; row_bytes * height as usize];
+        let render_size = extent + 2 * overlap;
+        // Every tile is the same frame to the shader
+        let frame = self.current_frame;
+
+        for tile in settings.tiles(extent) {
+            self.export_tile = Some((
+                [tile.x as i32 - overlap as i32, tile.y as i32 - overlap as i32],
+                [width, height],
+            ));
+            self.current_frame = frame;
+            let mut encoder = core
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Export Tile Encoder"),
+                });
+            self.set_time(time, 1.0 / settings.fps as f32, &core.queue);
+            self.dispatch_at_resolution(&mut encoder, core, render_size, render_size);
+            let tile_data = self.read_export_pixels(core, encoder, render_kit, render_size, render_size);
+
+            let tile_row_bytes = render_size as usize * bytes_per_pixel;
+            let copy_bytes = tile.width as usize * bytes_per_pixel;
+            for row in 0..tile.height as usize {
+                let src = (row + overlap as usize) * tile_row_bytes + overlap as usize * bytes_per_pixel;
+                let dst = (tile.y as usize + row) * row_bytes + tile.x as usize * bytes_per_pixel;
+                frame_data[dst..dst + copy_bytes].copy_from_slice(&tile_data[src..src + copy_bytes]);
+            }
         }
+        self.export_tile = None;
+        self.current_frame = frame.wrapping_add(1);
+        frame_data
+    }
 
-        let align = 256;
-        let unpadded_bytes_per_row = settings.width * 4;
-        let padding = (align - unpadded_bytes_per_row % align) % align;
-        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
-
-        encoder.copy_texture_to_buffer(
-            wgpu::TexelCopyTextureInfo {
-                texture: &capture_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &output_buffer,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(settings.height),
-                },
-            },
-            wgpu::Extent3d {
-                width: settings.width,
-                height: settings.height,
-                depth_or_array_layers: 1,
-            },
-        );
-
-        core.queue.submit(Some(encoder.finish()));
+    /// Submit `encoder` and read the output back as RGBA8 at `width` x `height` through
+    /// the display blit, or as linear RGBA `f32` for EXR exports
+    fn read_export_pixels(
+        &self,
+        core: &Core,
+        encoder: wgpu::CommandEncoder,
+        render_kit: &crate::RenderKit,
+        width: u32,
+        height: u32,
+    ) -> Vec<u8> {
+        let settings = render_kit.export_manager.settings();
+        let source_buffer = settings.source_buffer.as_deref().and_then(|name| self.buffer_texture(name));
+
+        // HDR export reads the output texture in its own format instead of the display blit,
+        // which would quantize to the 8-bit surface format
+        if settings.format == crate::ExportFormat::Exr {
+            core.queue.submit(Some(encoder.finish()));
+            let image = match source_buffer {
+                Some(texture) => Self::texels_to_hdr(self.read_texels(&core.device, &core.queue, texture, "EXR export")),
+                None => self.read_output_hdr(&core.device, &core.queue),
+            };
+            // Buffers can have their own resolution
+            let image = if image.dimensions() != (width, height) {
+                image::imageops::resize(&image, width, height, image::imageops::FilterType::Triangle)
+            } else {
+                image
+            };
+            return bytemuck::cast_slice(image.as_raw()).to_vec();
+        }
 
-        let buffer_slice = output_buffer.slice(..);
-        let (tx, rx) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
+        let buffer_bind_group = source_buffer.map(|texture| {
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &render_kit.texture_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.output_texture.sampler),
+                    },
+                ],
+                label: Some("Export Buffer Bind Group"),
+            })
         });
+        let source = buffer_bind_group.as_ref().unwrap_or(&self.output_texture.bind_group);
 
-        let _ = core
-            .device
-            .poll(wgpu::PollType::wait_indefinitely())
-            .unwrap();
-        rx.recv().unwrap().unwrap();
-
-        let padded_data = buffer_slice.get_mapped_range().to_vec();
-        let mut unpadded_data = Vec::with_capacity((settings.width * settings.height * 4) as usize);
-        for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
-            unpadded_data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
-        }
-
-        Ok(unpadded_data)
+        render_kit.read_capture_pixels(core, encoder, source, width, height, settings.transparent)
     }
 }
@@ -6,12 +6,15 @@
 pub mod builder;
 pub mod core;
 pub mod multipass;
+mod reduce;
 pub mod resource;
+pub mod scan;
 
 pub use builder::*;
 pub use core::*;
 pub use multipass::*;
 pub use resource::*;
+pub use scan::*;
 
 // Texture format constants
 pub const COMPUTE_TEXTURE_FORMAT_RGBA16: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
@@ -72,6 +75,29 @@ impl ComputeShader {
         ComputeShaderBuilder::new()
     }
 
+    /// Create a compute shader from a WGSL file read at runtime, instead of baking the
+    /// source in with `include_str!`. Hot reload watches the same file.
+    ///
+    /// `//!include "path.wgsl"` directives are expanded relative to the file, and
+    /// compile errors point at the original file and line.
+    ///
+    /// Returns an error if the file can't be read or the shader fails to compile
+    /// against `config` (missing entry points, binding mismatches, WGSL errors).
+    pub fn from_file(
+        core: &Core,
+        path: impl AsRef<std::path::Path>,
+        mut config: ComputeConfiguration,
+    ) -> Result<Self, crate::ShaderLoadError> {
+        let path = path.as_ref();
+        let shader_source = crate::hot::read_shader(path)?;
+        config.hot_reload_path = Some(path.to_path_buf());
+        // Shader errors are captured inside; this scope catches the rest, e.g. a layout
+        // the device can't create
+        crate::hot::capture_validation(&core.device, || {
+            Self::try_from_builder(core, &shader_source, config)
+        })?
+    }
+
     /// Create a simple compute shader with basic configuration
     pub fn new(core: &Core, shader_source: &str) -> Self {
         let config = ComputeShaderBuilder::new()
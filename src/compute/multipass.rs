@@ -2,18 +2,29 @@ use crate::Core;
 use std::collections::HashMap;
 use wgpu;
 
+/// Split a pass input into its buffer name and how many writes back it reads:
+/// `"buffer_a"` is the latest result, `"buffer_a[1]"` the one before it, and so on.
+pub(crate) fn parse_pass_input(input: &str) -> (&str, usize) {
+    input
+        .split_once('[')
+        .and_then(|(name, rest)| Some((name, rest.strip_suffix(']')?.trim().parse().ok()?)))
+        .unwrap_or((input, 0))
+}
+
 /// Manages ping-pong buffers for multi-pass compute shaders.
 ///
-/// Each buffer independently tracks which side (.0 or .1) was last written.
+/// Each buffer independently tracks which texture of its ring was last written.
 /// This means any pass can read from any previous pass's output, regardless of
 /// how many passes have elapsed. The old global-flip approach only allowed
 /// reading from the immediately preceding pass.
+///
+/// A ring holds two textures, plus one per frame of
+/// [`history`](crate::compute::PassDescription::with_history) the pass keeps.
 pub struct MultiPassManager {
-    buffers: HashMap<String, (wgpu::Texture, wgpu::Texture)>,
-    bind_groups: HashMap<String, (wgpu::BindGroup, wgpu::BindGroup)>,
-    /// Per-buffer write-side tracking. `true` means the last write went to `.0`,
-    /// so the next write goes to `.1` and reads return `.0`.
-    write_side: HashMap<String, bool>,
+    buffers: HashMap<String, Vec<wgpu::Texture>>,
+    bind_groups: HashMap<String, Vec<wgpu::BindGroup>>,
+    /// Per-buffer ring slot the next write goes to; reads return the slot before it.
+    write_index: HashMap<String, usize>,
     output_texture: wgpu::Texture,
     output_bind_group: wgpu::BindGroup,
     storage_layout: wgpu::BindGroupLayout,
@@ -68,10 +79,12 @@ impl MultiPassManager {
         let mut buffer_resolution: HashMap<String, Option<[u32; 2]>> = HashMap::new();
         let mut buffer_scale: HashMap<String, Option<f32>> = HashMap::new();
         let mut buffer_dimensions: HashMap<String, (u32, u32)> = HashMap::new();
+        let mut ring_lengths: HashMap<String, usize> = HashMap::new();
 
         for pass in passes {
             buffer_resolution.insert(pass.name.clone(), pass.resolution);
             buffer_scale.insert(pass.name.clone(), pass.resolution_scale);
+            ring_lengths.insert(pass.name.clone(), 2 + pass.history as usize);
 
             let (bw, bh) = Self::compute_buffer_dims(
                 width, height, pass.resolution, pass.resolution_scale,
@@ -79,43 +92,30 @@ impl MultiPassManager {
             buffer_dimensions.insert(pass.name.clone(), (bw, bh));
         }
 
+        for pass in passes {
+            for input in &pass.inputs {
+                let (name, age) = parse_pass_input(input);
+                let history = ring_lengths.get(name).map_or(0, |len| len - 2);
+                assert!(
+                    age <= history,
+                    "pass '{}' reads '{input}', but '{name}' keeps {history} frame(s) of history; \
+                     use PassDescription::with_history",
+                    pass.name
+                );
+            }
+        }
+
         let mut buffers = HashMap::new();
         let mut bind_groups = HashMap::new();
 
-        // Create ping-pong texture pairs for each buffer at its own resolution
+        // Create ping-pong texture rings for each buffer at its own resolution
         for name in buffer_names {
             let (bw, bh) = buffer_dimensions.get(name).copied().unwrap_or((width, height));
-
-            let texture0 = Self::create_storage_texture(
-                &core.device,
-                bw,
-                bh,
-                texture_format,
-                &format!("{name}_0"),
-            );
-            let texture1 = Self::create_storage_texture(
-                &core.device,
-                bw,
-                bh,
-                texture_format,
-                &format!("{name}_1"),
-            );
-
-            let bind_group0 = Self::create_storage_bind_group(
-                &core.device,
-                &storage_layout,
-                &texture0,
-                &format!("{name}_0_bind"),
-            );
-            let bind_group1 = Self::create_storage_bind_group(
-                &core.device,
-                &storage_layout,
-                &texture1,
-                &format!("{name}_1_bind"),
-            );
-
-            buffers.insert(name.clone(), (texture0, texture1));
-            bind_groups.insert(name.clone(), (bind_group0, bind_group1));
+            let ring_len = ring_lengths.get(name).copied().unwrap_or(2);
+            let (textures, groups) =
+                Self::create_ring(&core.device, &storage_layout, name, ring_len, bw, bh, texture_format);
+            buffers.insert(name.clone(), textures);
+            bind_groups.insert(name.clone(), groups);
         }
 
         // Create output texture
@@ -133,15 +133,15 @@ impl MultiPassManager {
             "output_bind",
         );
 
-        let mut write_side = HashMap::new();
+        let mut write_index = HashMap::new();
         for name in buffer_names {
-            write_side.insert(name.clone(), false);
+            write_index.insert(name.clone(), 0);
         }
 
         Self {
             buffers,
             bind_groups,
-            write_side,
+            write_index,
             output_texture,
             output_bind_group,
             storage_layout,
@@ -176,6 +176,25 @@ impl MultiPassManager {
         }
     }
 
+    fn create_ring(
+        device: &wgpu::Device,
+        storage_layout: &wgpu::BindGroupLayout,
+        name: &str,
+        ring_len: usize,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> (Vec<wgpu::Texture>, Vec<wgpu::BindGroup>) {
+        (0..ring_len)
+            .map(|i| {
+                let texture = Self::create_storage_texture(device, width, height, format, &format!("{name}_{i}"));
+                let bind_group =
+                    Self::create_storage_bind_group(device, storage_layout, &texture, &format!("{name}_{i}_bind"));
+                (texture, bind_group)
+            })
+            .unzip()
+    }
+
     fn create_storage_texture(
         device: &wgpu::Device,
         width: u32,
@@ -197,7 +216,8 @@ impl MultiPassManager {
             usage: wgpu::TextureUsages::STORAGE_BINDING
                 | wgpu::TextureUsages::TEXTURE_BINDING
                 | wgpu::TextureUsages::COPY_SRC
-                | wgpu::TextureUsages::COPY_DST,
+                | wgpu::TextureUsages::COPY_DST
+                | crate::texture::resample_target_usage(device, format),
             view_formats: &[],
         })
     }
@@ -245,37 +265,34 @@ impl MultiPassManager {
         })
     }
 
-    /// Get the write bind group for a buffer (writes to the side not last written)
+    /// Get the write bind group for a buffer (writes to the slot after the last written)
     pub fn get_write_bind_group(&self, buffer_name: &str) -> &wgpu::BindGroup {
         let bind_groups = self.bind_groups.get(buffer_name).expect("Buffer not found");
-        let last_wrote_0 = self.write_side.get(buffer_name).copied().unwrap_or(false);
-        if last_wrote_0 {
-            &bind_groups.1 // Last write was .0, next write goes to .1
-        } else {
-            &bind_groups.0 // Last write was .1 (or never), next write goes to .0
-        }
+        &bind_groups[self.get_write_index(buffer_name)]
     }
 
-    /// Get the write texture for a buffer (writes to the side not last written)
+    /// Get the write texture for a buffer (writes to the slot after the last written)
     pub fn get_write_texture(&self, buffer_name: &str) -> &wgpu::Texture {
         let textures = self.buffers.get(buffer_name).expect("Buffer not found");
-        let last_wrote_0 = self.write_side.get(buffer_name).copied().unwrap_or(false);
-        if last_wrote_0 {
-            &textures.1
-        } else {
-            &textures.0
-        }
+        &textures[self.get_write_index(buffer_name)]
     }
 
-    /// Get the read texture for a buffer (returns the side that was last written)
+    /// Get the read texture for a buffer (returns the slot that was last written)
     pub fn get_read_texture(&self, buffer_name: &str) -> &wgpu::Texture {
+        self.get_history_texture(buffer_name, 0)
+    }
+
+    /// Get the texture written `age` writes before the latest one (`0` is the latest).
+    /// `age` can go up to the buffer's history length.
+    pub fn get_history_texture(&self, buffer_name: &str, age: usize) -> &wgpu::Texture {
         let textures = self.buffers.get(buffer_name).expect("Buffer not found");
-        let last_wrote_0 = self.write_side.get(buffer_name).copied().unwrap_or(false);
-        if last_wrote_0 {
-            &textures.0 // Last write was to .0, read from .0
-        } else {
-            &textures.1 // Last write was to .1, read from .1
-        }
+        &textures[self.read_index(buffer_name, age)]
+    }
+
+    /// Ring slot holding the result `age` writes before the latest one
+    pub fn read_index(&self, buffer_name: &str, age: usize) -> usize {
+        let len = self.ring_len(buffer_name);
+        (self.get_write_index(buffer_name) + len - 1 - age % len) % len
     }
 
 
@@ -290,19 +307,21 @@ impl MultiPassManager {
     }
 
     /// Mark a specific buffer as having been written to.
-    /// Flips that buffer's write side so the next read returns what was just written,
-    /// and the next write goes to the other side.
+    /// Advances that buffer's ring so the next read returns what was just written,
+    /// and the next write goes to the oldest slot.
     pub fn mark_written(&mut self, buffer_name: &str) {
-        if let Some(side) = self.write_side.get_mut(buffer_name) {
-            *side = !*side;
+        let len = self.ring_len(buffer_name);
+        if let Some(index) = self.write_index.get_mut(buffer_name) {
+            *index = (*index + 1) % len;
         }
     }
 
-    /// Flip all buffers (for cross-frame feedback in temporal effects).
+    /// Advance all buffers (for cross-frame feedback in temporal effects).
     /// Call this after frame presentation to preserve state for the next frame.
     pub fn flip_buffers(&mut self) {
-        for side in self.write_side.values_mut() {
-            *side = !*side;
+        let names: Vec<String> = self.write_index.keys().cloned().collect();
+        for name in names {
+            self.mark_written(&name);
         }
     }
 
@@ -310,27 +329,21 @@ impl MultiPassManager {
     pub fn clear_all(&mut self, core: &Core) {
         let names: Vec<String> = self.buffers.keys().cloned().collect();
 
-        // Recreate all buffer textures at their respective dimensions
+        // Recreate all buffer rings at their respective dimensions
         for name in &names {
             let (bw, bh) = self.buffer_dimensions.get(name).copied()
                 .unwrap_or((self.width, self.height));
-
-            let texture0 = Self::create_storage_texture(
-                &core.device, bw, bh, self.texture_format, &format!("{name}_0"),
-            );
-            let texture1 = Self::create_storage_texture(
-                &core.device, bw, bh, self.texture_format, &format!("{name}_1"),
-            );
-
-            let bind_group0 = Self::create_storage_bind_group(
-                &core.device, &self.storage_layout, &texture0, &format!("{name}_0_bind"),
-            );
-            let bind_group1 = Self::create_storage_bind_group(
-                &core.device, &self.storage_layout, &texture1, &format!("{name}_1_bind"),
+            let (textures, groups) = Self::create_ring(
+                &core.device,
+                &self.storage_layout,
+                name,
+                self.ring_len(name),
+                bw,
+                bh,
+                self.texture_format,
             );
-
-            self.buffers.insert(name.clone(), (texture0, texture1));
-            self.bind_groups.insert(name.clone(), (bind_group0, bind_group1));
+            self.buffers.insert(name.clone(), textures);
+            self.bind_groups.insert(name.clone(), groups);
         }
 
         // Recreate output texture and bind group (always at screen resolution)
@@ -348,8 +361,8 @@ impl MultiPassManager {
             "output_bind",
         );
 
-        for side in self.write_side.values_mut() {
-            *side = false;
+        for index in self.write_index.values_mut() {
+            *index = 0;
         }
     }
 
@@ -370,6 +383,30 @@ impl MultiPassManager {
         self.clear_all(core);
     }
 
+    /// Like [`resize`](Self::resize), but scales every buffer's previous contents into
+    /// its new textures instead of starting from black, so accumulated state survives.
+    pub fn resize_preserving(&mut self, core: &Core, width: u32, height: u32) {
+        let old_buffers = self.buffers.clone();
+        let old_output = self.output_texture.clone();
+        let write_index = self.write_index.clone();
+
+        self.resize(core, width, height);
+
+        let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Multi-Pass Resize Encoder"),
+        });
+        for (name, old_ring) in &old_buffers {
+            if let Some(new_ring) = self.buffers.get(name) {
+                for (old, new) in old_ring.iter().zip(new_ring) {
+                    crate::resample_texture(&core.device, &mut encoder, old, new);
+                }
+            }
+        }
+        crate::resample_texture(&core.device, &mut encoder, &old_output, &self.output_texture);
+        core.queue.submit(Some(encoder.finish()));
+        self.write_index = write_index;
+    }
+
     /// Get the input layout for pipeline creation
     pub fn get_input_layout(&self) -> &wgpu::BindGroupLayout {
         &self.input_layout
@@ -380,14 +417,19 @@ impl MultiPassManager {
         &self.storage_layout
     }
 
-    /// Get the write_side state for a buffer
-    pub fn get_write_side(&self, buffer_name: &str) -> bool {
-        self.write_side.get(buffer_name).copied().unwrap_or(false)
+    /// Ring slot the next write of a buffer goes to
+    pub fn get_write_index(&self, buffer_name: &str) -> usize {
+        self.write_index.get(buffer_name).copied().unwrap_or(0)
+    }
+
+    /// Number of textures in a buffer's ring (2 plus its history)
+    pub fn ring_len(&self, buffer_name: &str) -> usize {
+        self.buffers.get(buffer_name).map_or(2, Vec::len)
     }
 
-    /// Get both ping-pong textures for a buffer
-    pub fn get_buffer_pair(&self, buffer_name: &str) -> Option<&(wgpu::Texture, wgpu::Texture)> {
-        self.buffers.get(buffer_name)
+    /// Get all textures of a buffer's ring, indexed by ring slot
+    pub fn get_buffer_ring(&self, buffer_name: &str) -> Option<&[wgpu::Texture]> {
+        self.buffers.get(buffer_name).map(Vec::as_slice)
     }
 
     /// Get the first buffer name (for passes with no dependencies)
@@ -0,0 +1,360 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+const REDUCE_MAX_SHADER: &str = r#"
+@group(0) @binding(0) var<storage, read> values: array<u32>;
+@group(0) @binding(1) var<storage, read_write> result: atomic<u32>;
+
+var<workgroup> partial: array<u32, 256>;
+
+@compute @workgroup_size(256)
+fn main(
+    @builtin(global_invocation_id) gid: vec3<u32>,
+    @builtin(local_invocation_index) lid: u32,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let count = arrayLength(&values);
+    let stride = groups.x * 256u;
+    var m = 0u;
+    for (var i = gid.x; i < count; i += stride) {
+        m = max(m, values[i]);
+    }
+    partial[lid] = m;
+    workgroupBarrier();
+    for (var s = 128u; s > 0u; s >>= 1u) {
+        if lid < s {
+            partial[lid] = max(partial[lid], partial[lid + s]);
+        }
+        workgroupBarrier();
+    }
+    if lid == 0u {
+        atomicMax(&result, partial[0]);
+    }
+}
+"#;
+
+const WORKGROUP_SIZE: u64 = 256;
+/// Each invocation strides over the buffer, so large buffers don't need more groups
+const MAX_WORKGROUPS: u64 = 256;
+
+// Readback state, shared with the map_async callback
+const IDLE: u8 = 0;
+const ENCODED: u8 = 1;
+const MAPPING: u8 = 2;
+const MAPPED: u8 = 3;
+
+/// GPU max over a `u32` buffer, read back a frame or two late instead of stalling.
+///
+/// `encode` records the reduction into the frame's encoder; `poll` (after submit)
+/// starts the map and returns the newest finished result. While a readback is in
+/// flight, `encode` is skipped so the readback buffer is never written while mapped.
+pub(crate) struct MaxReduction {
+    pipeline: wgpu::ComputePipeline,
+    result_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    state: Arc<AtomicU8>,
+    latest: Option<u32>,
+}
+
+impl MaxReduction {
+    pub(crate) fn new(device: &wgpu::Device, label: &str) -> Self {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&format!("{label} Max Reduction")),
+            source: wgpu::ShaderSource::Wgsl(REDUCE_MAX_SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(&format!("{label} Max Reduction")),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        Self {
+            pipeline,
+            result_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("{label} Max Reduction Result")),
+                size: 4,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("{label} Max Reduction Readback")),
+                size: 4,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            state: Arc::new(AtomicU8::new(IDLE)),
+            latest: None,
+        }
+    }
+
+    pub(crate) fn encode(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, source: &wgpu::Buffer) {
+        if self.state.load(Ordering::Acquire) != IDLE {
+            return;
+        }
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Max Reduction Bind Group"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: source.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.result_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        let count = source.size() / 4;
+        let workgroups = count.div_ceil(WORKGROUP_SIZE).clamp(1, MAX_WORKGROUPS) as u32;
+
+        encoder.clear_buffer(&self.result_buffer, 0, None);
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Max Reduction"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(workgroups, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&self.result_buffer, 0, &self.readback_buffer, 0, 4);
+        self.state.store(ENCODED, Ordering::Release);
+    }
+
+    pub(crate) fn poll(&mut self, device: &wgpu::Device) -> Option<u32> {
+        if self.state.load(Ordering::Acquire) == ENCODED {
+            self.state.store(MAPPING, Ordering::Release);
+            let state = self.state.clone();
+            self.readback_buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    state.store(if result.is_ok() { MAPPED } else { IDLE }, Ordering::Release);
+                });
+        }
+        let _ = device.poll(wgpu::PollType::Poll);
+        if self.state.load(Ordering::Acquire) == MAPPED {
+            let value = bytemuck::pod_read_unaligned(&self.readback_buffer.slice(..).get_mapped_range());
+            self.readback_buffer.unmap();
+            self.state.store(IDLE, Ordering::Release);
+            self.latest = Some(value);
+        }
+        self.latest
+    }
+}
+
+const CONVERGENCE_SHADER: &str = r#"
+@group(0) @binding(0) var current: texture_2d<f32>;
+@group(0) @binding(1) var previous: texture_2d<f32>;
+@group(0) @binding(2) var<storage, read_write> partials: array<vec2<f32>>;
+
+var<workgroup> sums: array<vec2<f32>, 256>;
+
+// Accumulation buffers hold a running sum with the sample count in alpha
+fn luminance(texel: vec4<f32>) -> f32 {
+    let color = select(texel.rgb, texel.rgb / texel.a, texel.a > 0.0);
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main(
+    @builtin(global_invocation_id) gid: vec3<u32>,
+    @builtin(local_invocation_index) lid: u32,
+    @builtin(workgroup_id) group: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let size = textureDimensions(current);
+    var value = vec2<f32>(0.0);
+    if gid.x < size.x && gid.y < size.y {
+        let now = luminance(textureLoad(current, gid.xy, 0));
+        let change = now - luminance(textureLoad(previous, gid.xy, 0));
+        value = vec2<f32>(change * change, now);
+    }
+    sums[lid] = value;
+    workgroupBarrier();
+    for (var s = 128u; s > 0u; s >>= 1u) {
+        if lid < s {
+            sums[lid] += sums[lid + s];
+        }
+        workgroupBarrier();
+    }
+    if lid == 0u {
+        partials[group.y * groups.x + group.x] = sums[0];
+    }
+}
+"#;
+
+/// Per-frame change of an accumulation buffer: the mean squared luminance difference
+/// between its last two states, relative to the squared mean luminance. Each workgroup
+/// writes one partial sum and the CPU adds them up, read back like [`MaxReduction`].
+pub(crate) struct ConvergenceReduction {
+    pipeline: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    partials: Option<(wgpu::Buffer, wgpu::Buffer)>,
+    pixels: u64,
+    state: Arc<AtomicU8>,
+    /// Bumped by `reset`, so a readback encoded before it is dropped
+    generation: u32,
+    encoded_generation: u32,
+    latest: Option<f32>,
+}
+
+impl ConvergenceReduction {
+    pub(crate) fn new(device: &wgpu::Device, label: &str) -> Self {
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(&format!("{label} Convergence Layout")),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&format!("{label} Convergence Pipeline Layout")),
+            bind_group_layouts: &[Some(&layout)],
+            immediate_size: 0,
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&format!("{label} Convergence")),
+            source: wgpu::ShaderSource::Wgsl(CONVERGENCE_SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(&format!("{label} Convergence")),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        Self {
+            pipeline,
+            layout,
+            partials: None,
+            pixels: 0,
+            state: Arc::new(AtomicU8::new(IDLE)),
+            generation: 0,
+            encoded_generation: 0,
+            latest: None,
+        }
+    }
+
+    /// Forget the estimate, e.g. when the accumulation restarts
+    pub(crate) fn reset(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        self.latest = None;
+    }
+
+    pub(crate) fn encode(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        current: &wgpu::Texture,
+        previous: &wgpu::Texture,
+    ) {
+        if self.state.load(Ordering::Acquire) != IDLE {
+            return;
+        }
+        let (groups_x, groups_y) = (current.width().div_ceil(16), current.height().div_ceil(16));
+        let size = groups_x as u64 * groups_y as u64 * 8;
+        if self.partials.as_ref().is_none_or(|(partials, _)| partials.size() != size) {
+            let buffer = |label: &str, usage| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(label),
+                    size,
+                    usage,
+                    mapped_at_creation: false,
+                })
+            };
+            self.partials = Some((
+                buffer("Convergence Partials", wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC),
+                buffer("Convergence Readback", wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST),
+            ));
+        }
+        let (partials, readback) = self.partials.as_ref().expect("partials were just created");
+        let current_view = current.create_view(&Default::default());
+        let previous_view = previous.create_view(&Default::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Convergence Bind Group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&current_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&previous_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: partials.as_entire_binding(),
+                },
+            ],
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Convergence"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+        encoder.copy_buffer_to_buffer(partials, 0, readback, 0, size);
+        self.pixels = current.width() as u64 * current.height() as u64;
+        self.encoded_generation = self.generation;
+        self.state.store(ENCODED, Ordering::Release);
+    }
+
+    pub(crate) fn poll(&mut self, device: &wgpu::Device) -> Option<f32> {
+        let Some((_, readback)) = &self.partials else {
+            return self.latest;
+        };
+        if self.state.load(Ordering::Acquire) == ENCODED {
+            self.state.store(MAPPING, Ordering::Release);
+            let state = self.state.clone();
+            readback.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+                state.store(if result.is_ok() { MAPPED } else { IDLE }, Ordering::Release);
+            });
+        }
+        let _ = device.poll(wgpu::PollType::Poll);
+        if self.state.load(Ordering::Acquire) == MAPPED {
+            let (change, luminance) = {
+                let data = readback.slice(..).get_mapped_range();
+                let partials: &[[f32; 2]] = bytemuck::cast_slice(&data);
+                partials.iter().fold((0.0f64, 0.0f64), |(change, luminance), partial| {
+                    (change + partial[0] as f64, luminance + partial[1] as f64)
+                })
+            };
+            readback.unmap();
+            self.state.store(IDLE, Ordering::Release);
+            if self.encoded_generation == self.generation {
+                let pixels = self.pixels.max(1) as f64;
+                let mean = (luminance / pixels).max(1e-6);
+                self.latest = Some((change / pixels / (mean * mean)) as f32);
+            }
+        }
+        self.latest
+    }
+}
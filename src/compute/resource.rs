@@ -14,6 +14,10 @@ pub enum ResourceType {
         format: wgpu::TextureFormat,
         access: wgpu::StorageTextureAccess,
     },
+    StorageTexture3D {
+        format: wgpu::TextureFormat,
+        access: wgpu::StorageTextureAccess,
+    },
     InputTexture,
     ChannelTexture, // External texture channels (channel0, channel1, etc.)
     Sampler,
@@ -111,6 +115,13 @@ impl ResourceLayout {
                         view_dimension: wgpu::TextureViewDimension::D2,
                     }
                 }
+                ResourceType::StorageTexture3D { format, access } => {
+                    wgpu::BindingType::StorageTexture {
+                        access: *access,
+                        format: *format,
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                    }
+                }
                 ResourceType::InputTexture => wgpu::BindingType::Texture {
                     multisampled: false,
                     sample_type: wgpu::TextureSampleType::Float { filterable: true },
@@ -129,6 +140,14 @@ impl ResourceLayout {
         }
     }
 
+    /// Layout entries of `group`, as `create_bind_group_layouts` uses them
+    pub fn layout_entries(&self, group: u32) -> Vec<wgpu::BindGroupLayoutEntry> {
+        self.get_bindings_for_group(group)
+            .into_iter()
+            .map(|binding| self.create_layout_entry(binding))
+            .collect()
+    }
+
     /// Get all bindings for a specific group
     pub fn get_bindings_for_group(&self, group: u32) -> Vec<&ResourceBinding> {
         self.bindings.iter().filter(|b| b.group == group).collect()
@@ -157,6 +176,15 @@ impl ResourceLayout {
         );
     }
 
+    /// Uniform standing in for push constants on devices without `IMMEDIATES`
+    pub fn add_push_constant_fallback(&mut self, size: u32) {
+        self.add_resource(
+            0,
+            "push_constants",
+            ResourceType::UniformBuffer { size: size as u64 },
+        );
+    }
+
     // GROUP 1: Primary Pass I/O & Parameters
     pub fn add_output_texture(&mut self, format: wgpu::TextureFormat) {
         self.add_resource(
@@ -169,11 +197,36 @@ impl ResourceLayout {
         );
     }
 
+    pub fn add_output_texture_3d(&mut self, format: wgpu::TextureFormat) {
+        self.add_resource(
+            1,
+            "output",
+            ResourceType::StorageTexture3D {
+                format,
+                access: wgpu::StorageTextureAccess::WriteOnly,
+            },
+        );
+    }
+
     pub fn add_input_texture(&mut self) {
         self.add_resource(1, "input_texture", ResourceType::InputTexture);
         self.add_resource(1, "input_sampler", ResourceType::Sampler);
     }
 
+    /// Extra named outputs start at binding 4, past the fixed output/params/input slots
+    pub fn add_extra_output_texture(&mut self, name: &str, format: wgpu::TextureFormat) {
+        let binding = self.next_binding_in_group(1).max(4);
+        self.bindings.push(ResourceBinding {
+            group: 1,
+            binding,
+            name: name.to_string(),
+            resource_type: ResourceType::StorageTexture {
+                format,
+                access: wgpu::StorageTextureAccess::WriteOnly,
+            },
+        });
+    }
+
     /// Add multi-pass input textures to Group 3 (up to 3 input textures with samplers)
     // GROUP 2: Engine Resources including Channels
     /// Add channel textures (channel0-channel3) for external media accessible from all passes
@@ -210,6 +263,16 @@ impl ResourceLayout {
         );
     }
 
+    pub fn add_gamepad_uniform(&mut self) {
+        self.add_resource(
+            2,
+            "gamepad",
+            ResourceType::UniformBuffer {
+                size: std::mem::size_of::<crate::GamepadUniform>() as u64,
+            },
+        );
+    }
+
     pub fn add_font_resources(&mut self) {
         self.add_resource(
             2,
@@ -221,6 +284,17 @@ impl ResourceLayout {
         self.add_resource(2, "font_texture_atlas", ResourceType::InputTexture);
     }
 
+    pub fn add_text_glyph_buffer(&mut self, max_glyphs: u32) {
+        self.add_resource(
+            2,
+            "glyphs",
+            ResourceType::StorageBuffer {
+                size: 16 + max_glyphs as u64 * std::mem::size_of::<crate::GlyphInstance>() as u64,
+                read_only: true,
+            },
+        );
+    }
+
     pub fn add_audio_buffer(&mut self, size: usize) {
         self.add_resource(
             2,
@@ -254,6 +328,18 @@ impl ResourceLayout {
         );
     }
 
+    /// Indirect dispatch args, one `[x, y, z]` slot per indirect entry point
+    pub fn add_indirect_args_buffer(&mut self, slots: usize) {
+        self.add_resource(
+            2,
+            "dispatch_args",
+            ResourceType::StorageBuffer {
+                size: (slots * 12) as u64,
+                read_only: false,
+            },
+        );
+    }
+
     // GROUP 3: User-Defined Data Buffers
     pub fn add_storage_buffer(&mut self, name: &str, size: u64) {
         self.add_resource(
@@ -0,0 +1,311 @@
+use std::num::NonZeroU64;
+
+const SCAN_SHADER: &str = r#"
+@group(0) @binding(0) var<storage, read_write> data: array<u32>;
+@group(0) @binding(1) var<storage, read_write> block_sums: array<u32>;
+
+// Set per pipeline: add each element to its own prefix
+override inclusive: bool = false;
+
+const WG_SIZE: u32 = 256u;
+const BLOCK_SIZE: u32 = 512u;
+
+var<workgroup> temp: array<u32, BLOCK_SIZE>;
+
+fn block_index(wid: vec3<u32>, nwg: vec3<u32>) -> u32 {
+    return wid.y * nwg.x + wid.x;
+}
+
+// Work-efficient (up-sweep/down-sweep) scan of one 512-element block, two per invocation.
+// The block total goes to block_sums so the next level can scan the totals.
+@compute @workgroup_size(256)
+fn scan_blocks(
+    @builtin(local_invocation_index) lid: u32,
+    @builtin(workgroup_id) wid: vec3<u32>,
+    @builtin(num_workgroups) nwg: vec3<u32>,
+) {
+    let n = arrayLength(&data);
+    let block = block_index(wid, nwg);
+    let a = block * BLOCK_SIZE + lid;
+    let b = a + WG_SIZE;
+    var value_a = 0u;
+    var value_b = 0u;
+    if a < n {
+        value_a = data[a];
+    }
+    if b < n {
+        value_b = data[b];
+    }
+    temp[lid] = value_a;
+    temp[lid + WG_SIZE] = value_b;
+
+    var offset = 1u;
+    for (var d = WG_SIZE; d > 0u; d >>= 1u) {
+        workgroupBarrier();
+        if lid < d {
+            let ai = offset * (2u * lid + 1u) - 1u;
+            let bi = offset * (2u * lid + 2u) - 1u;
+            temp[bi] += temp[ai];
+        }
+        offset <<= 1u;
+    }
+    workgroupBarrier();
+    if lid == 0u {
+        if block < arrayLength(&block_sums) {
+            block_sums[block] = temp[BLOCK_SIZE - 1u];
+        }
+        temp[BLOCK_SIZE - 1u] = 0u;
+    }
+    for (var d = 1u; d < BLOCK_SIZE; d <<= 1u) {
+        offset >>= 1u;
+        workgroupBarrier();
+        if lid < d {
+            let ai = offset * (2u * lid + 1u) - 1u;
+            let bi = offset * (2u * lid + 2u) - 1u;
+            let t = temp[ai];
+            temp[ai] = temp[bi];
+            temp[bi] += t;
+        }
+    }
+    workgroupBarrier();
+
+    if a < n {
+        data[a] = temp[lid] + select(0u, value_a, inclusive);
+    }
+    if b < n {
+        data[b] = temp[lid + WG_SIZE] + select(0u, value_b, inclusive);
+    }
+}
+
+// Add the scanned totals of all earlier blocks to every element of a block
+@compute @workgroup_size(256)
+fn add_block_offsets(
+    @builtin(local_invocation_index) lid: u32,
+    @builtin(workgroup_id) wid: vec3<u32>,
+    @builtin(num_workgroups) nwg: vec3<u32>,
+) {
+    let n = arrayLength(&data);
+    let block = block_index(wid, nwg);
+    if block >= arrayLength(&block_sums) {
+        return;
+    }
+    let offset = block_sums[block];
+    let a = block * BLOCK_SIZE + lid;
+    if a < n {
+        data[a] += offset;
+    }
+    if a + WG_SIZE < n {
+        data[a + WG_SIZE] += offset;
+    }
+}
+"#;
+
+/// Elements scanned by one workgroup
+const BLOCK_SIZE: u32 = 512;
+const MAX_WORKGROUPS_PER_DIMENSION: u32 = 65535;
+
+/// Whether each output includes its own input
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScanMode {
+    /// `out[i] = in[0] + ... + in[i]`
+    #[default]
+    Inclusive,
+    /// `out[i] = in[0] + ... + in[i - 1]`, with `out[0] = 0` (e.g. compaction offsets)
+    Exclusive,
+}
+
+/// GPU prefix sum over `u32` buffers, e.g. for stream compaction.
+///
+/// Each workgroup scans 512 elements; block totals are scanned recursively and added
+/// back, so any count works. Sums wrap on overflow. The block-total buffers are kept
+/// and grown as needed, so one `PrefixSum` can serve scans of any size.
+///
+/// ```ignore
+/// let mut scan = PrefixSum::new(&core.device);
+/// scan.scan(&core.device, &mut encoder, &flags_buffer, count, ScanMode::Exclusive);
+/// ```
+pub struct PrefixSum {
+    scan_inclusive: wgpu::ComputePipeline,
+    scan_exclusive: wgpu::ComputePipeline,
+    add_offsets: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    /// Block totals of each level, level 0 first
+    block_sums: Vec<wgpu::Buffer>,
+    /// Bound as `block_sums` when a level fits in one block
+    placeholder: wgpu::Buffer,
+}
+
+impl PrefixSum {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let storage_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: NonZeroU64::new(4),
+            },
+            count: None,
+        };
+        let layout_desc = wgpu::BindGroupLayoutDescriptor {
+            label: Some("Prefix Sum Bind Group Layout"),
+            entries: &[storage_entry(0), storage_entry(1)],
+        };
+        crate::check_layout_order(SCAN_SHADER, "Prefix Sum", &[&layout_desc]);
+        let layout = device.create_bind_group_layout(&layout_desc);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Prefix Sum Pipeline Layout"),
+            bind_group_layouts: &[Some(&layout)],
+            immediate_size: 0,
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Prefix Sum"),
+            source: wgpu::ShaderSource::Wgsl(SCAN_SHADER.into()),
+        });
+        let create_pipeline = |name: &str, entry_point: &str, inclusive: bool| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(&format!("Prefix Sum {name}")),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: Some(entry_point),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &[("inclusive", if inclusive { 1.0 } else { 0.0 })],
+                    ..Default::default()
+                },
+                cache: None,
+            })
+        };
+        Self {
+            scan_inclusive: create_pipeline("Scan Inclusive", "scan_blocks", true),
+            scan_exclusive: create_pipeline("Scan Exclusive", "scan_blocks", false),
+            add_offsets: create_pipeline("Add Offsets", "add_block_offsets", false),
+            layout,
+            block_sums: Vec::new(),
+            placeholder: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Prefix Sum Placeholder"),
+                size: 4,
+                usage: wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            }),
+        }
+    }
+
+    /// Scan the first `count` elements of a STORAGE buffer in place
+    pub fn scan(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        buffer: &wgpu::Buffer,
+        count: u32,
+        mode: ScanMode,
+    ) {
+        if count == 0 {
+            return;
+        }
+        assert!(
+            count as u64 * 4 <= buffer.size(),
+            "scanning {count} elements in a {} byte buffer",
+            buffer.size()
+        );
+
+        // Element count of every level: the data, then the block totals of the level below
+        let mut counts = vec![count];
+        while let Some(&n) = counts.last().filter(|&&n| n > BLOCK_SIZE) {
+            counts.push(n.div_ceil(BLOCK_SIZE));
+        }
+        self.ensure_block_sums(device, &counts[1..]);
+
+        let bind_group = |data: &wgpu::Buffer, data_count: u32, sums: &wgpu::Buffer, sums_count: u32| {
+            let binding = |buffer, count: u32| {
+                wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(count as u64 * 4),
+                })
+            };
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Prefix Sum Bind Group"),
+                layout: &self.layout,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: binding(data, data_count) },
+                    wgpu::BindGroupEntry { binding: 1, resource: binding(sums, sums_count) },
+                ],
+            })
+        };
+        // Level i scans `counts[i]` elements and writes its totals into level i + 1
+        let bind_groups: Vec<_> = (0..counts.len())
+            .map(|level| {
+                let data = if level == 0 { buffer } else { &self.block_sums[level - 1] };
+                match counts.get(level + 1) {
+                    Some(&sums_count) => bind_group(data, counts[level], &self.block_sums[level], sums_count),
+                    None => bind_group(data, counts[level], &self.placeholder, 1),
+                }
+            })
+            .collect();
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Prefix Sum"),
+            timestamp_writes: None,
+        });
+        for (level, bind_group) in bind_groups.iter().enumerate() {
+            // Only the caller's data may be inclusive; the block totals need exclusive offsets
+            let pipeline = match (level, mode) {
+                (0, ScanMode::Inclusive) => &self.scan_inclusive,
+                _ => &self.scan_exclusive,
+            };
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            dispatch_blocks(&mut pass, counts[level]);
+        }
+        pass.set_pipeline(&self.add_offsets);
+        for level in (0..counts.len() - 1).rev() {
+            pass.set_bind_group(0, &bind_groups[level], &[]);
+            dispatch_blocks(&mut pass, counts[level]);
+        }
+    }
+
+    /// Scan the first `count` elements of `input` into `output`, leaving `input` unchanged.
+    /// `input` needs COPY_SRC and `output` needs COPY_DST and STORAGE usage.
+    pub fn scan_into(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        input: &wgpu::Buffer,
+        output: &wgpu::Buffer,
+        count: u32,
+        mode: ScanMode,
+    ) {
+        if count == 0 {
+            return;
+        }
+        encoder.copy_buffer_to_buffer(input, 0, output, 0, count as u64 * 4);
+        self.scan(device, encoder, output, count, mode);
+    }
+
+    fn ensure_block_sums(&mut self, device: &wgpu::Device, counts: &[u32]) {
+        for (level, &count) in counts.iter().enumerate() {
+            let size = count as u64 * 4;
+            if self.block_sums.get(level).is_some_and(|buffer| buffer.size() >= size) {
+                continue;
+            }
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("Prefix Sum Block Sums {level}")),
+                size,
+                usage: wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            });
+            if level < self.block_sums.len() {
+                self.block_sums[level] = buffer;
+            } else {
+                self.block_sums.push(buffer);
+            }
+        }
+    }
+}
+
+/// One workgroup per block, folded into y past the 65535 limit per dimension
+fn dispatch_blocks(pass: &mut wgpu::ComputePass, count: u32) {
+    let blocks = count.div_ceil(BLOCK_SIZE);
+    let x = blocks.min(MAX_WORKGROUPS_PER_DIMENSION);
+    pass.dispatch_workgroups(x, blocks.div_ceil(x), 1);
+}
@@ -9,6 +9,12 @@ pub struct ControlsRequest {
     pub should_reset: bool,
     pub should_clear_buffers: bool,
     pub current_time: Option<f32>,
+    /// Advance a paused clock by one [`ShaderControls::FRAME_STEP`]
+    pub step_frame: bool,
+    /// Jump the clock to this time, paused or not
+    pub seek_time: Option<f32>,
+    /// Latest time reached since the last reset, the end of the scrub slider
+    pub timeline_end: Option<f32>,
     pub window_size: Option<(u32, u32)>,
 
     pub current_fps: Option<f32>,
@@ -34,6 +40,18 @@ pub struct ControlsRequest {
     pub start_webcam: bool,
     pub stop_webcam: bool,
     pub webcam_device_index: Option<u32>,
+
+    // Display reqs
+    pub set_sample_count: Option<u32>,
+    pub set_present_mode: Option<wgpu::PresentMode>,
+    pub set_render_scale: Option<f32>,
+    /// Display pass clear color or `LoadOp::Load`, see `RenderKit::set_load_op`
+    pub set_load_op: Option<wgpu::LoadOp<wgpu::Color>>,
+    pub set_fit_mode: Option<crate::FitMode>,
+    /// Display blit filtering, see `RenderKit::set_blit_filter`
+    pub set_blit_filter: Option<wgpu::FilterMode>,
+    /// `Some(None)` removes the cap
+    pub set_target_fps: Option<Option<f32>>,
 }
 impl Default for ControlsRequest {
     fn default() -> Self {
@@ -54,6 +72,9 @@ impl Default for ControlsRequest {
             should_reset: false,
             should_clear_buffers: false,
             current_time: None,
+            step_frame: false,
+            seek_time: None,
+            timeline_end: None,
             window_size: None,
 
             current_fps: None,
@@ -79,6 +100,14 @@ impl Default for ControlsRequest {
             start_webcam: false,
             stop_webcam: false,
             webcam_device_index: None,
+
+            set_sample_count: None,
+            set_present_mode: None,
+            set_render_scale: None,
+            set_load_op: None,
+            set_fit_mode: None,
+            set_blit_filter: None,
+            set_target_fps: None,
         }
     }
 }
@@ -98,10 +127,12 @@ pub type VideoInfo = (
 
 pub struct ShaderControls {
     is_paused: bool,
-    pause_start: Option<std::time::Instant>,
+    pause_start: Option<web_time::Instant>,
     total_pause_duration: f32,
     current_frame: u32,
     media_loaded_once: bool,
+    pending_steps: u32,
+    timeline_end: f32,
 }
 
 impl Default for ShaderControls {
@@ -112,11 +143,16 @@ impl Default for ShaderControls {
             total_pause_duration: 0.0,
             current_frame: 0,
             media_loaded_once: false,
+            pending_steps: 0,
+            timeline_end: 0.0,
         }
     }
 }
 
 impl ShaderControls {
+    /// Clock advance of one "Step" while paused
+    pub const FRAME_STEP: f32 = 1.0 / 60.0;
+
     pub fn new() -> Self {
         Self::default()
     }
@@ -128,7 +164,43 @@ impl ShaderControls {
         self.current_frame
     }
 
-    pub fn get_time(&self, start_time: &std::time::Instant) -> f32 {
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    /// Whether a simulation should run this frame: always while playing, and once per
+    /// requested step while paused. Feedback passes gated on this hold their state when
+    /// paused instead of evolving under a frozen clock.
+    pub fn advance_frame(&mut self) -> bool {
+        if !self.is_paused {
+            return true;
+        }
+        if self.pending_steps > 0 {
+            self.pending_steps -= 1;
+            return true;
+        }
+        false
+    }
+
+    /// Stop or restart the clock; `get_time` holds its value while paused
+    pub fn set_paused(&mut self, paused: bool) {
+        if paused && !self.is_paused {
+            self.pause_start = Some(web_time::Instant::now());
+        } else if !paused && self.is_paused {
+            if let Some(pause_start) = self.pause_start {
+                self.total_pause_duration += pause_start.elapsed().as_secs_f32();
+            }
+            self.pause_start = None;
+        }
+        self.is_paused = paused;
+    }
+
+    /// Move the clock so `get_time` reports `time` from now on, keeping the pause state
+    pub fn seek(&mut self, start_time: &web_time::Instant, time: f32) {
+        self.total_pause_duration += self.get_time(start_time) - time.max(0.0);
+    }
+
+    pub fn get_time(&self, start_time: &web_time::Instant) -> f32 {
         let raw_time = start_time.elapsed().as_secs_f32();
         if self.is_paused {
             if let Some(pause_start) = self.pause_start {
@@ -143,7 +215,7 @@ impl ShaderControls {
 
     pub fn get_ui_request(
         &mut self,
-        start_time: &std::time::Instant,
+        start_time: &web_time::Instant,
         size: &winit::dpi::PhysicalSize<u32>,
         fps: f32,
     ) -> ControlsRequest {
@@ -162,11 +234,16 @@ impl ShaderControls {
                 self.media_loaded_once = true;
             }
         }
+        let current_time = self.get_time(start_time);
+        self.timeline_end = self.timeline_end.max(current_time);
         ControlsRequest {
             is_paused: self.is_paused,
             should_reset: false,
             should_clear_buffers: false,
-            current_time: Some(self.get_time(start_time)),
+            current_time: Some(current_time),
+            step_frame: false,
+            seek_time: None,
+            timeline_end: Some(self.timeline_end),
             window_size: Some((size.width, size.height)),
             current_fps: Some(fps),
 
@@ -186,6 +263,14 @@ impl ShaderControls {
             start_webcam: false,
             stop_webcam: false,
             webcam_device_index: None,
+
+            set_sample_count: None,
+            set_present_mode: None,
+            set_render_scale: None,
+            set_load_op: None,
+            set_fit_mode: None,
+            set_blit_filter: None,
+            set_target_fps: None,
         }
     }
 
@@ -196,15 +281,14 @@ impl ShaderControls {
             self.total_pause_duration = 0.0;
             self.current_frame = 0;
             self.media_loaded_once = false;
-        } else if request.is_paused && !self.is_paused {
-            self.pause_start = Some(std::time::Instant::now());
-        } else if !request.is_paused && self.is_paused {
-            if let Some(pause_start) = self.pause_start {
-                self.total_pause_duration += pause_start.elapsed().as_secs_f32();
-            }
-            self.pause_start = None;
+            self.pending_steps = 0;
+            self.timeline_end = 0.0;
+        }
+        self.set_paused(request.is_paused);
+        if request.step_frame && self.is_paused {
+            self.total_pause_duration -= Self::FRAME_STEP;
+            self.pending_steps += 1;
         }
-        self.is_paused = request.is_paused;
     }
 
     /// Extract video info from a video texture manager
@@ -253,6 +337,7 @@ impl ShaderControls {
                         request.start_webcam = true;
                     }
 
+                    #[cfg(not(target_arch = "wasm32"))]
                     if ui.button("Load").clicked() {
                         if let Some(path) = rfd::FileDialog::new()
                             .add_filter(
@@ -423,17 +508,27 @@ impl ShaderControls {
                 {
                     request.is_paused = !request.is_paused;
                 }
+                if ui.button("⏭ Step").clicked() {
+                    request.is_paused = true;
+                    request.step_frame = true;
+                }
                 if ui.button("↺ Reset").clicked() {
                     request.should_reset = true;
                     request.should_clear_buffers = true;
                 }
-                if let Some(time) = request.current_time {
-                    ui.label(format!("Time: {time:.2}s"));
-                }
                 if let Some(fps) = request.current_fps {
                     ui.label(format!("FPS: {fps:.1}"));
                 }
             });
+            if let Some(mut time) = request.current_time {
+                let end = request.timeline_end.unwrap_or(time).max(time).max(1.0);
+                let scrub = ui.add(egui::Slider::new(&mut time, 0.0..=end).text("Time").suffix("s"));
+                // Feedback and accumulation state belongs to the old time, so restart it
+                if scrub.changed() {
+                    request.seek_time = Some(time);
+                    request.should_clear_buffers = true;
+                }
+            }
             if let Some((width, height)) = request.window_size {
                 ui.horizontal(|ui| {
                     ui.label(format!("Resolution: {width}x{height}"));
@@ -441,4 +536,175 @@ impl ShaderControls {
             }
         });
     }
+
+    /// Frame rate cap; `current` is `Core::target_fps()`.
+    pub fn render_fps_limit_widget(
+        ui: &mut egui::Ui,
+        request: &mut ControlsRequest,
+        current: Option<f32>,
+    ) {
+        ui.horizontal(|ui| {
+            let mut limited = current.is_some();
+            let mut fps = current.unwrap_or(60.0);
+            let toggled = ui.checkbox(&mut limited, "Limit FPS").changed();
+            let edited = ui
+                .add_enabled(limited, egui::DragValue::new(&mut fps).range(1.0..=480.0).suffix(" fps"))
+                .changed();
+            if toggled || edited {
+                request.set_target_fps = Some(limited.then_some(fps));
+            }
+        });
+    }
+
+    /// Compute resolution relative to the window; `current` is `RenderKit::render_scale()`.
+    pub fn render_scale_widget(ui: &mut egui::Ui, request: &mut ControlsRequest, current: f32) {
+        let mut scale = current;
+        if ui
+            .add(egui::Slider::new(&mut scale, crate::RenderKit::RENDER_SCALE_RANGE).text("Render scale"))
+            .changed()
+        {
+            request.set_render_scale = Some(scale);
+        }
+    }
+
+    /// MSAA selector for the display pipeline; `current` is `RenderKit::sample_count()` and
+    /// `supported` is `RenderKit::supported_sample_counts(core)`. Applied by
+    /// `RenderKit::apply_media_requests`.
+    pub fn render_msaa_widget(ui: &mut egui::Ui, request: &mut ControlsRequest, current: u32, supported: &[u32]) {
+        ui.horizontal(|ui| {
+            ui.label("MSAA:");
+            for &count in supported {
+                if ui.selectable_label(current == count, format!("{count}x")).clicked() && current != count {
+                    request.set_sample_count = Some(count);
+                }
+            }
+        });
+    }
+
+    /// Background color of the display pass and a toggle to keep the target's previous
+    /// contents instead; `current` is `RenderKit::load_op()`. Applied by
+    /// `RenderKit::apply_media_requests`.
+    pub fn render_clear_widget(
+        ui: &mut egui::Ui,
+        request: &mut ControlsRequest,
+        current: wgpu::LoadOp<wgpu::Color>,
+    ) {
+        ui.horizontal(|ui| {
+            let (mut keep, color) = match current {
+                wgpu::LoadOp::Clear(color) => (false, color),
+                _ => (true, wgpu::Color::BLACK),
+            };
+            let mut rgb = [color.r as f32, color.g as f32, color.b as f32];
+            ui.label("Background:");
+            let recolored = ui.add_enabled_ui(!keep, |ui| ui.color_edit_button_rgb(&mut rgb)).inner.changed();
+            let toggled = ui.checkbox(&mut keep, "Keep previous").changed();
+            if recolored || toggled {
+                request.set_load_op = Some(if keep {
+                    wgpu::LoadOp::Load
+                } else {
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: rgb[0] as f64,
+                        g: rgb[1] as f64,
+                        b: rgb[2] as f64,
+                        a: 1.0,
+                    })
+                });
+            }
+        });
+    }
+
+    /// Stretch/Contain/Cover selector for the display blit; `current` is
+    /// `RenderKit::fit_mode()`. Applied by `RenderKit::apply_media_requests`.
+    pub fn render_fit_mode_widget(ui: &mut egui::Ui, request: &mut ControlsRequest, current: crate::FitMode) {
+        ui.horizontal(|ui| {
+            ui.label("Fit:");
+            for (mode, label) in [
+                (crate::FitMode::Stretch, "Stretch"),
+                (crate::FitMode::Contain, "Contain"),
+                (crate::FitMode::Cover, "Cover"),
+            ] {
+                if ui.selectable_label(current == mode, label).clicked() && current != mode {
+                    request.set_fit_mode = Some(mode);
+                }
+            }
+        });
+    }
+
+    /// Linear/Nearest selector for scaling the compute output to the window, paired with
+    /// the render scale; `current` is `RenderKit::blit_filter()`. Applied by
+    /// `RenderKit::apply_media_requests`.
+    pub fn render_blit_filter_widget(ui: &mut egui::Ui, request: &mut ControlsRequest, current: wgpu::FilterMode) {
+        ui.horizontal(|ui| {
+            ui.label("Upscale:");
+            for (filter, label) in [(wgpu::FilterMode::Linear, "Smooth"), (wgpu::FilterMode::Nearest, "Pixelated")] {
+                if ui.selectable_label(current == filter, label).clicked() && current != filter {
+                    request.set_blit_filter = Some(filter);
+                }
+            }
+        });
+    }
+
+    /// Vsync selector; `current` is `Core::present_mode()`. Unsupported modes fall back
+    /// to a supported one when applied.
+    pub fn render_present_mode_widget(
+        ui: &mut egui::Ui,
+        request: &mut ControlsRequest,
+        current: wgpu::PresentMode,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Present:");
+            for (mode, label) in [
+                (wgpu::PresentMode::Fifo, "VSync"),
+                (wgpu::PresentMode::Mailbox, "Mailbox"),
+                (wgpu::PresentMode::Immediate, "Uncapped"),
+            ] {
+                if ui.selectable_label(current == mode, label).clicked() && current != mode {
+                    request.set_present_mode = Some(mode);
+                }
+            }
+        });
+    }
+
+    /// Collapsible adapter/backend/limits summary with a button that copies it, for
+    /// including in bug reports
+    pub fn render_gpu_info_widget(ui: &mut egui::Ui, core: &crate::Core) {
+        egui::CollapsingHeader::new("GPU Info")
+            .default_open(false)
+            .show(ui, |ui| {
+                let text = core.gpu_info_text();
+                ui.label(egui::RichText::new(&text).monospace());
+                if ui.button("Copy").clicked() {
+                    ui.ctx().copy_text(text);
+                }
+            });
+    }
+
+    /// Show a shader compile error as a red banner across the top of the window.
+    /// Does nothing when `error` is `None`, so it can be called every frame with
+    /// e.g. `compute_shader.shader_error()`.
+    pub fn render_shader_error(ctx: &egui::Context, error: Option<&str>) {
+        let Some(error) = error else {
+            return;
+        };
+        egui::Area::new(egui::Id::new("shader_error_banner"))
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 8.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style())
+                    .fill(egui::Color32::from_rgb(120, 20, 20))
+                    .show(ui, |ui| {
+                        ui.set_max_width(ctx.content_rect().width() - 32.0);
+                        ui.label(
+                            egui::RichText::new("Shader error (previous version still running)")
+                                .strong()
+                                .color(egui::Color32::WHITE),
+                        );
+                        ui.label(
+                            egui::RichText::new(error)
+                                .monospace()
+                                .color(egui::Color32::from_rgb(255, 210, 210)),
+                        );
+                    });
+            });
+    }
 }
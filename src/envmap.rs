@@ -0,0 +1,200 @@
+use crate::{ShaderLayout, TextureManager};
+
+/// WGSL source of the environment map helpers (`equirect_uv`, `sample_equirect`,
+/// `sample_env`), also available to shaders loaded from a file as
+/// `//!include <cuneus/envmap.wgsl>`.
+pub const ENVMAP_WGSL: &str = include_str!("../shaders/envmap.wgsl");
+
+const EQUIRECT_TO_CUBE_SHADER: &str = r#"
+@group(0) @binding(0) var equirect: texture_2d<f32>;
+@group(0) @binding(1) var equirect_sampler: sampler;
+@group(0) @binding(2) var faces: texture_storage_2d_array<rgba16float, write>;
+
+// Direction through a face texel, in the +X, -X, +Y, -Y, +Z, -Z layer order of cube views
+fn face_dir(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let p = uv * 2.0 - 1.0;
+    switch face {
+        case 0u: { return vec3<f32>(1.0, -p.y, -p.x); }
+        case 1u: { return vec3<f32>(-1.0, -p.y, p.x); }
+        case 2u: { return vec3<f32>(p.x, 1.0, p.y); }
+        case 3u: { return vec3<f32>(p.x, -1.0, -p.y); }
+        case 4u: { return vec3<f32>(p.x, -p.y, 1.0); }
+        default: { return vec3<f32>(-p.x, -p.y, -1.0); }
+    }
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(faces);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size);
+    let color = sample_equirect(equirect, equirect_sampler, face_dir(id.z, uv));
+    textureStore(faces, id.xy, id.z, vec4<f32>(color, 1.0));
+}
+"#;
+
+/// An HDR environment as an `Rgba16Float` cubemap, for `texture_cube<f32>` bindings and
+/// `sample_env(env, env_sampler, dir)` from `<cuneus/envmap.wgsl>`.
+///
+/// ```ignore
+/// let env = EnvironmentMap::load(&core.device, &core.queue, "sky.hdr", 1024)?;
+/// // bind env.bind_group with EnvironmentMap::create_layout(&core.device)
+/// ```
+pub struct EnvironmentMap {
+    pub texture: wgpu::Texture,
+    /// Cube view of the six faces
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+    /// `texture_cube<f32>` at binding 0 and a filtering sampler at binding 1, see [`EnvironmentMap::create_layout`]
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl EnvironmentMap {
+    /// Sampling layout for cubemaps: cube texture at binding 0, sampler at binding 1,
+    /// visible to fragment and compute stages.
+    pub fn create_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("Environment Map Layout"),
+        })
+    }
+
+    /// Load an equirectangular `.hdr`/`.exr` (or 8-bit) map and convert it to a cubemap
+    /// with `face_size` pixels per face edge
+    pub fn load(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: impl AsRef<std::path::Path>,
+        face_size: u32,
+    ) -> anyhow::Result<Self> {
+        let layout = TextureManager::create_display_layout(device);
+        let equirect = TextureManager::load_equirect(device, queue, path, &layout)?;
+        Ok(Self::from_equirect(device, queue, &equirect, face_size))
+    }
+
+    /// Resample an equirectangular map, e.g. from [`TextureManager::load_equirect`], into
+    /// the six faces of a cubemap with one compute pass
+    pub fn from_equirect(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        equirect: &TextureManager,
+        face_size: u32,
+    ) -> Self {
+        let face_size = face_size.clamp(1, device.limits().max_texture_dimension_2d);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Environment Cubemap"),
+            size: wgpu::Extent3d {
+                width: face_size,
+                height: face_size,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba16Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+            view_formats: &[],
+        });
+
+        let source = format!("{ENVMAP_WGSL}\n{EQUIRECT_TO_CUBE_SHADER}");
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Equirect To Cube Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.as_str().into()),
+        });
+        let layout = ShaderLayout::from_wgsl(device, &source, "Equirect To Cube")
+            .expect("the built-in equirect to cube shader compiles");
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Equirect To Cube Pipeline"),
+            layout: Some(&layout.pipeline_layout(device)),
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let storage_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let bind_group = layout.create_bind_group(
+            device,
+            0,
+            &[
+                ("equirect", wgpu::BindingResource::TextureView(&equirect.view)),
+                ("equirect_sampler", wgpu::BindingResource::Sampler(&equirect.sampler)),
+                ("faces", wgpu::BindingResource::TextureView(&storage_view)),
+            ],
+        );
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Equirect To Cube Encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Equirect To Cube"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(face_size.div_ceil(8), face_size.div_ceil(8), 6);
+        }
+        queue.submit(Some(encoder.finish()));
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Environment Cubemap View"),
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &Self::create_layout(device),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("Environment Cubemap Bind Group"),
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+            bind_group,
+        }
+    }
+
+    pub fn face_size(&self) -> u32 {
+        self.texture.width()
+    }
+}
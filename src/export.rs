@@ -1,25 +1,58 @@
+use crate::color::{linear_to_srgb8, srgb_to_linear};
+use crate::CuneusError;
 use image::ImageError;
-use log::error;
+use log::{error, info, warn};
+use std::io::Write;
 use std::path::PathBuf;
-use std::sync::mpsc;
+use std::process::{Child, ChildStdin};
+#[cfg(not(target_arch = "wasm32"))]
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use web_time::{Duration, Instant};
 
-#[derive(Debug)]
+/// Frame intervals averaged for the ETA
+const ETA_WINDOW: usize = 30;
+/// GIF exports stop here; longer loops belong in an MP4
+const MAX_GIF_FRAMES: u32 = 1000;
+/// Uncompressed palette-index size above which a GIF export warns, in bytes
+const GIF_WARN_BYTES: u64 = 500 * 1024 * 1024;
+const DEFAULT_NAME_TEMPLATE: &str = "frame_{frame:05}";
+const DEFAULT_TILE_OVERLAP: u32 = 8;
+/// Largest export side the UI offers; sizes past the device limit render in tiles
+const MAX_EXPORT_DIMENSION: u32 = 32768;
+
+#[derive(Debug, thiserror::Error)]
 pub enum ExportError {
-    IoError(std::io::Error),
-    ImageError(ImageError),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    ImageError(#[from] ImageError),
 }
-
-impl From<std::io::Error> for ExportError {
-    fn from(err: std::io::Error) -> Self {
-        ExportError::IoError(err)
-    }
+/// What an export writes into `export_path`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ExportFormat {
+    /// One `frame_NNNNN.png` per frame
+    #[default]
+    Png,
+    /// A single H.264 `export.mp4` at the export fps, encoded by an `ffmpeg` found on PATH.
+    /// `crf` is the x264 quality (0 = lossless, 23 = ffmpeg default, 51 = worst).
+    Mp4 { crf: u8 },
+    /// One 32-bit float `frame_NNNNN.exr` per frame, read straight from the compute output
+    /// without clamping or tonemapping. Frame data is linear RGBA `f32`.
+    Exr,
+    /// A single looping `export.gif` at the export fps, quantized to 256 colors per
+    /// frame. `loop_count` 0 loops forever. Capped at 1000 frames.
+    Gif { loop_count: u16 },
 }
 
-impl From<ImageError> for ExportError {
-    fn from(err: ImageError) -> Self {
-        ExportError::ImageError(err)
+impl ExportFormat {
+    /// Formats that write one file for the whole export instead of a frame sequence
+    pub fn is_single_file(&self) -> bool {
+        matches!(self, ExportFormat::Mp4 { .. } | ExportFormat::Gif { .. })
     }
 }
+
 #[derive(Debug, Clone)]
 pub struct ExportSettings {
     pub export_path: PathBuf,
@@ -28,9 +61,43 @@ pub struct ExportSettings {
     pub start_time: f32,
     pub total_time: f32,
     pub fps: u32,
+    /// First frame to export; earlier frames are skipped, not rendered
+    pub start_frame: u32,
+    /// Last frame to export (inclusive); `None` runs to the end of `total_time`
+    pub end_frame: Option<u32>,
+    pub format: ExportFormat,
+    /// Keep the shader's alpha instead of forcing opaque frames (PNG only). Captures use
+    /// the plain blit rather than a custom display shader.
+    pub transparent: bool,
+    /// Multi-pass buffer to export instead of the final output, e.g. `buffer_a` while
+    /// debugging a pipeline. Buffers with their own resolution are scaled to the export size.
+    pub source_buffer: Option<String>,
+    /// File name (without extension) for image sequence frames. Supports `{frame}`,
+    /// `{width}`, `{height}` and `{time}` (seconds), each optionally zero-padded
+    /// like `{frame:05}`. Must contain `{frame}`.
+    pub name_template: String,
+    /// Render each frame in tiles of at most this many pixels per side and stitch them.
+    /// `None` tiles only when the export is larger than `max_texture_dimension_2d`.
+    /// Tiled shaders place pixels with `tile_offset` and `full_size` from the time uniform.
+    pub tile_size: Option<u32>,
+    /// Pixels rendered past each tile edge and cropped away, so derivatives and AA near a
+    /// seam see the same neighbours as in one large render
+    pub tile_overlap: u32,
+    /// Render at `supersample` times the export size in each direction and average
+    /// each block down before saving. 1 renders at the export size.
+    pub supersample: u32,
     pub is_exporting: bool,
 }
 
+/// The part of a tiled export one tile fills, in pixels of the full image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportTile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
 impl Default for ExportSettings {
     fn default() -> Self {
         Self {
@@ -40,10 +107,92 @@ impl Default for ExportSettings {
             start_time: 0.0,
             total_time: 5.0,
             fps: 60,
+            start_frame: 0,
+            end_frame: None,
+            format: ExportFormat::Png,
+            transparent: false,
+            source_buffer: None,
+            name_template: DEFAULT_NAME_TEMPLATE.to_string(),
+            tile_size: None,
+            tile_overlap: DEFAULT_TILE_OVERLAP,
+            supersample: 1,
             is_exporting: false,
         }
     }
 }
+
+impl ExportSettings {
+    /// Frames to export. Frame `n` is rendered at `start_time + n / fps`, so a range
+    /// starting past 0 renders exactly the frames a full export would have there.
+    pub fn frame_range(&self) -> std::ops::Range<u32> {
+        let end = match self.end_frame {
+            Some(end_frame) => end_frame.saturating_add(1),
+            None => (self.total_time * self.fps as f32) as u32,
+        };
+        self.start_frame..end.max(self.start_frame)
+    }
+
+    /// Size of the captured frames: the export size times `supersample`
+    pub fn capture_size(&self) -> (u32, u32) {
+        let factor = self.supersample.max(1);
+        (self.width * factor, self.height * factor)
+    }
+
+    /// Side of the captured area each tile covers, or `None` when a frame renders in one
+    /// piece. Tiles plus their overlap stay within `max_dimension`.
+    pub fn tile_extent(&self, max_dimension: u32) -> Option<u32> {
+        let (width, height) = self.capture_size();
+        let max_extent = max_dimension.saturating_sub(2 * self.tile_overlap).max(1);
+        let extent = match self.tile_size {
+            Some(size) => size.clamp(1, max_extent),
+            None => max_extent,
+        };
+        (extent < width || extent < height).then_some(extent)
+    }
+
+    /// Tiles of `extent` covering the captured frame row by row, cut short at the right
+    /// and bottom
+    pub fn tiles(&self, extent: u32) -> Vec<ExportTile> {
+        let (width, height) = self.capture_size();
+        let mut tiles = Vec::new();
+        for y in (0..height).step_by(extent as usize) {
+            for x in (0..width).step_by(extent as usize) {
+                tiles.push(ExportTile {
+                    x,
+                    y,
+                    width: extent.min(width - x),
+                    height: extent.min(height - y),
+                });
+            }
+        }
+        tiles
+    }
+
+    /// Size the shader renders at: the capture size, or one tile plus its overlap
+    pub fn render_size(&self, max_dimension: u32) -> (u32, u32) {
+        match self.tile_extent(max_dimension) {
+            Some(extent) => {
+                let size = extent + 2 * self.tile_overlap;
+                (size, size)
+            }
+            None => self.capture_size(),
+        }
+    }
+
+    /// Reject ranges that would export nothing
+    pub fn validate_frame_range(&self) -> Result<(), String> {
+        match self.end_frame {
+            Some(end_frame) if end_frame <= self.start_frame => Err(format!(
+                "end frame {end_frame} must be after start frame {}",
+                self.start_frame
+            )),
+            _ if self.frame_range().is_empty() => {
+                Err(format!("start frame {} is past the end of the export", self.start_frame))
+            }
+            _ => Ok(()),
+        }
+    }
+}
 #[derive(Clone)]
 pub struct ExportUiRequest {
     pub width: u32,
@@ -51,8 +200,39 @@ pub struct ExportUiRequest {
     pub start_time: f32,
     pub total_time: f32,
     pub fps: u32,
+    pub start_frame: u32,
+    pub end_frame: Option<u32>,
     pub path: PathBuf,
+    pub format: ExportFormat,
+    pub transparent: bool,
+    pub source_buffer: Option<String>,
+    pub name_template: String,
+    pub tile_size: Option<u32>,
+    pub tile_overlap: u32,
+    pub supersample: u32,
     pub is_exporting: bool,
+    /// Progress of the running export, for the progress bar
+    pub progress: Option<ExportProgress>,
+    /// Set by the widget's cancel button; `apply_ui_request` stops the export
+    pub cancel_export: bool,
+}
+
+/// How far a running export has got
+#[derive(Debug, Clone, Copy)]
+pub struct ExportProgress {
+    pub frames_done: u32,
+    pub frames_total: u32,
+    /// Estimated time left, from the average of recent frame times
+    pub eta: Option<Duration>,
+}
+
+impl ExportProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.frames_total == 0 {
+            return 1.0;
+        }
+        self.frames_done as f32 / self.frames_total as f32
+    }
 }
 #[derive(Default)]
 pub struct ExportUiState {
@@ -64,12 +244,181 @@ pub struct ExportUiState {
     pub temp_fps: u32,
 }
 
+/// Pipes raw frames into an ffmpeg process that encodes them as they arrive
+struct VideoEncoder {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    path: PathBuf,
+}
+
+impl VideoEncoder {
+    #[cfg(target_arch = "wasm32")]
+    fn spawn(_settings: &ExportSettings, _crf: u8) -> std::io::Result<Self> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "ffmpeg can't run in the browser"))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn spawn(settings: &ExportSettings, crf: u8) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&settings.export_path)?;
+        let path = settings.export_path.join("export.mp4");
+        // Captured frames are always RGBA, see `capture_export_frame`
+        let mut child = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &format!("{}x{}", settings.width, settings.height)])
+            .args(["-r", &settings.fps.to_string(), "-i", "-"])
+            // yuv420p needs even dimensions
+            .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p", "-crf", &crf.to_string()])
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take();
+        Ok(Self { child, stdin, path })
+    }
+
+    fn write_frame(&mut self, data: &[u8]) -> std::io::Result<()> {
+        match &mut self.stdin {
+            Some(stdin) => stdin.write_all(data),
+            None => Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe)),
+        }
+    }
+
+    /// Close the input and wait for ffmpeg to write the trailer
+    fn finish(mut self) -> std::io::Result<PathBuf> {
+        drop(self.stdin.take());
+        let status = self.child.wait()?;
+        if !status.success() {
+            return Err(std::io::Error::other(format!("ffmpeg exited with {status}")));
+        }
+        Ok(self.path)
+    }
+}
+
+/// Quantizes and appends frames to an animated GIF on a worker thread
+struct GifWriter {
+    sender: Option<mpsc::SyncSender<Vec<u8>>>,
+    worker: Option<JoinHandle<Result<(), ImageError>>>,
+    path: PathBuf,
+}
+
+impl GifWriter {
+    fn spawn(settings: &ExportSettings, loop_count: u16) -> std::io::Result<Self> {
+        use image::codecs::gif::{GifEncoder, Repeat};
+
+        std::fs::create_dir_all(&settings.export_path)?;
+        let path = settings.export_path.join("export.gif");
+        let file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        let (width, height) = (settings.width, settings.height);
+        let delay = image::Delay::from_numer_denom_ms(1000, settings.fps.max(1));
+        let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(4);
+        let worker = std::thread::spawn(move || {
+            // Speed 10 is the gif crate's default trade-off between NeuQuant quality and time
+            let mut encoder = GifEncoder::new_with_speed(file, 10);
+            encoder.set_repeat(match loop_count {
+                0 => Repeat::Infinite,
+                n => Repeat::Finite(n),
+            })?;
+            while let Ok(data) = receiver.recv() {
+                let Some(image) = image::RgbaImage::from_raw(width, height, data) else {
+                    continue;
+                };
+                encoder.encode_frame(image::Frame::from_parts(image, 0, 0, delay))?;
+            }
+            Ok(())
+        });
+        Ok(Self {
+            sender: Some(sender),
+            worker: Some(worker),
+            path,
+        })
+    }
+
+    fn write_frame(&self, data: Vec<u8>) -> std::io::Result<()> {
+        self.sender
+            .as_ref()
+            .and_then(|sender| sender.send(data).ok())
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+    }
+
+    /// Flush the queued frames and write the GIF trailer
+    fn finish(mut self) -> Result<PathBuf, ImageError> {
+        drop(self.sender.take());
+        match self.worker.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result.map(|_| self.path),
+            _ => Err(ImageError::IoError(std::io::Error::other("GIF encoder thread panicked"))),
+        }
+    }
+}
+
+/// Encodes and writes image frames on worker threads so the render loop doesn't wait on
+/// PNG compression. Dropping it waits for every queued frame to be written.
+struct FrameSaver {
+    sender: Option<mpsc::SyncSender<(Vec<u8>, u32)>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl FrameSaver {
+    fn new(settings: ExportSettings) -> Self {
+        let worker_count = std::thread::available_parallelism()
+            .map(|n| n.get().saturating_sub(1).clamp(1, 8))
+            .unwrap_or(2);
+        // Bounded so a fast capture loop can't pile up unbounded frame memory
+        let (sender, receiver) = mpsc::sync_channel::<(Vec<u8>, u32)>(worker_count * 2);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let settings = Arc::new(settings);
+        let workers = (0..worker_count)
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                let settings = Arc::clone(&settings);
+                std::thread::spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv();
+                    let Ok((data, frame)) = job else {
+                        break;
+                    };
+                    if let Err(e) = save_frame(data, frame, &settings) {
+                        error!("Error saving frame {frame}: {e:?}");
+                    }
+                })
+            })
+            .collect();
+        Self {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    fn submit(&self, data: Vec<u8>, frame: u32) -> Result<(), ExportError> {
+        let sender = self.sender.as_ref().expect("frame saver already finished");
+        sender
+            .send((data, frame))
+            .map_err(|_| ExportError::IoError(std::io::Error::other("frame saving workers stopped")))
+    }
+}
+
+impl Drop for FrameSaver {
+    fn drop(&mut self) {
+        // Closing the channel lets workers drain the queue and exit
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
 /// Manages the export process and UI state
 pub struct ExportManager {
     settings: ExportSettings,
     export_channel: Option<mpsc::Receiver<(u32, f32)>>,
     ui_state: ExportUiState,
     temp_state: TempExportState,
+    video_encoder: Option<VideoEncoder>,
+    gif_writer: Option<GifWriter>,
+    frame_saver: Option<FrameSaver>,
+    frames_done: u32,
+    frames_total: u32,
+    last_frame_at: Option<Instant>,
+    frame_times: std::collections::VecDeque<Duration>,
 }
 
 #[derive(Clone)]
@@ -79,7 +428,16 @@ struct TempExportState {
     start_time: f32,
     total_time: f32,
     fps: u32,
+    start_frame: u32,
+    end_frame: Option<u32>,
     path: PathBuf,
+    format: ExportFormat,
+    transparent: bool,
+    source_buffer: Option<String>,
+    name_template: String,
+    tile_size: Option<u32>,
+    tile_overlap: u32,
+    supersample: u32,
 }
 
 impl Default for ExportManager {
@@ -98,7 +456,16 @@ impl ExportManager {
             start_time: settings.start_time,
             total_time: settings.total_time,
             fps: settings.fps,
+            start_frame: settings.start_frame,
+            end_frame: settings.end_frame,
             path: settings.export_path.clone(),
+            format: settings.format,
+            transparent: settings.transparent,
+            source_buffer: settings.source_buffer.clone(),
+            name_template: settings.name_template.clone(),
+            tile_size: settings.tile_size,
+            tile_overlap: settings.tile_overlap,
+            supersample: settings.supersample,
         };
 
         Self {
@@ -106,6 +473,13 @@ impl ExportManager {
             export_channel: None,
             ui_state,
             temp_state,
+            video_encoder: None,
+            gif_writer: None,
+            frame_saver: None,
+            frames_done: 0,
+            frames_total: 0,
+            last_frame_at: None,
+            frame_times: std::collections::VecDeque::with_capacity(ETA_WINDOW),
         }
     }
     pub fn get_ui_request(&self) -> ExportUiRequest {
@@ -115,8 +489,19 @@ impl ExportManager {
             start_time: self.temp_state.start_time,
             total_time: self.temp_state.total_time,
             fps: self.temp_state.fps,
+            start_frame: self.temp_state.start_frame,
+            end_frame: self.temp_state.end_frame,
             path: self.temp_state.path.clone(),
+            format: self.temp_state.format,
+            transparent: self.temp_state.transparent,
+            source_buffer: self.temp_state.source_buffer.clone(),
+            name_template: self.temp_state.name_template.clone(),
+            tile_size: self.temp_state.tile_size,
+            tile_overlap: self.temp_state.tile_overlap,
+            supersample: self.temp_state.supersample,
             is_exporting: self.settings.is_exporting,
+            progress: self.progress(),
+            cancel_export: false,
         }
     }
     pub fn apply_ui_request(&mut self, request: ExportUiRequest) {
@@ -125,7 +510,20 @@ impl ExportManager {
         self.temp_state.start_time = request.start_time;
         self.temp_state.total_time = request.total_time;
         self.temp_state.fps = request.fps;
+        self.temp_state.start_frame = request.start_frame;
+        self.temp_state.end_frame = request.end_frame;
         self.temp_state.path = request.path;
+        self.temp_state.format = request.format;
+        self.temp_state.transparent = request.transparent;
+        self.temp_state.source_buffer = request.source_buffer;
+        self.temp_state.name_template = request.name_template;
+        self.temp_state.tile_size = request.tile_size;
+        self.temp_state.tile_overlap = request.tile_overlap;
+        self.temp_state.supersample = request.supersample;
+        if request.cancel_export && self.settings.is_exporting {
+            info!("Export cancelled after {} frames", self.frames_done);
+            self.complete_export();
+        }
     }
     /// Returns a reference to the current export settings
     pub fn settings(&self) -> &ExportSettings {
@@ -141,13 +539,44 @@ impl ExportManager {
     }
     /// Attempts to get the next frame for export
     pub fn try_get_next_frame(&mut self) -> Option<(u32, f32)> {
-        self.export_channel.as_ref()?.try_recv().ok()
+        let next = self.export_channel.as_ref()?.try_recv().ok()?;
+        // Asking for frame N means frames 0..N were handled
+        let now = Instant::now();
+        if let Some(last) = self.last_frame_at.replace(now) {
+            if self.frame_times.len() == ETA_WINDOW {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back(now - last);
+        }
+        self.frames_done = next.0 - self.settings.start_frame;
+        Some(next)
+    }
+
+    /// Progress of the running export, `None` when idle
+    pub fn progress(&self) -> Option<ExportProgress> {
+        if !self.settings.is_exporting {
+            return None;
+        }
+        let eta = (!self.frame_times.is_empty()).then(|| {
+            let average = self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32;
+            average * self.frames_total.saturating_sub(self.frames_done)
+        });
+        Some(ExportProgress {
+            frames_done: self.frames_done,
+            frames_total: self.frames_total,
+            eta,
+        })
     }
 
     pub fn start_export(&mut self) {
         if self.settings.is_exporting {
             return;
         }
+        // Frames are written to disk from worker threads, neither of which a browser has
+        if cfg!(target_arch = "wasm32") {
+            error!("Exporting isn't supported in the browser");
+            return;
+        }
 
         // Apply the temporary state to settings before starting export
         self.settings.width = self.temp_state.width;
@@ -155,17 +584,74 @@ impl ExportManager {
         self.settings.start_time = self.temp_state.start_time;
         self.settings.total_time = self.temp_state.total_time;
         self.settings.fps = self.temp_state.fps;
+        self.settings.start_frame = self.temp_state.start_frame;
+        self.settings.end_frame = self.temp_state.end_frame;
         self.settings.export_path = self.temp_state.path.clone();
+        self.settings.format = self.temp_state.format;
+        self.settings.transparent = self.temp_state.transparent;
+        self.settings.source_buffer = self.temp_state.source_buffer.clone();
+        self.settings.name_template = self.temp_state.name_template.clone();
+        self.settings.tile_size = self.temp_state.tile_size;
+        self.settings.tile_overlap = self.temp_state.tile_overlap;
+        self.settings.supersample = self.temp_state.supersample.max(1);
+
+        if !self.settings.format.is_single_file()
+            && let Err(e) = validate_name_template(&self.settings.name_template)
+        {
+            error!("Invalid export file name {:?}: {e}", self.settings.name_template);
+            return;
+        }
+        if let Err(e) = self.settings.validate_frame_range() {
+            error!("Invalid export frame range: {e}");
+            return;
+        }
+
+        let mut frame_range = self.settings.frame_range();
+        let mut total_frames = frame_range.len() as u32;
+        match self.settings.format {
+            ExportFormat::Mp4 { crf } => match VideoEncoder::spawn(&self.settings, crf) {
+                Ok(encoder) => self.video_encoder = Some(encoder),
+                Err(e) => {
+                    error!("Failed to start ffmpeg for MP4 export: {e}");
+                    return;
+                }
+            },
+            ExportFormat::Gif { loop_count } => {
+                if total_frames > MAX_GIF_FRAMES {
+                    warn!("GIF export limited to {MAX_GIF_FRAMES} of {total_frames} frames; use MP4 for long clips");
+                    total_frames = MAX_GIF_FRAMES;
+                    frame_range.end = frame_range.start + MAX_GIF_FRAMES;
+                }
+                let index_bytes = self.settings.width as u64 * self.settings.height as u64 * total_frames as u64;
+                if index_bytes > GIF_WARN_BYTES {
+                    warn!(
+                        "GIF export of {total_frames} frames at {}x{} may be several hundred MB; lower the resolution or duration",
+                        self.settings.width, self.settings.height
+                    );
+                }
+                match GifWriter::spawn(&self.settings, loop_count) {
+                    Ok(writer) => self.gif_writer = Some(writer),
+                    Err(e) => {
+                        error!("Failed to create GIF file: {e}");
+                        return;
+                    }
+                }
+            }
+            _ => self.frame_saver = Some(FrameSaver::new(self.settings.clone())),
+        }
 
         // Then start the export process
         self.settings.is_exporting = true;
         let settings = self.settings.clone();
         let (tx, rx) = mpsc::channel();
+        self.frames_done = 0;
+        self.frames_total = total_frames;
+        self.last_frame_at = None;
+        self.frame_times.clear();
 
         std::thread::spawn(move || {
-            let total_frames = (settings.total_time * settings.fps as f32) as u32;
 
-            for frame in 0..total_frames {
+            for frame in frame_range {
                 let time = settings.start_time + (frame as f32 / settings.fps as f32);
                 if tx.send((frame, time)).is_err() {
                     break;
@@ -176,10 +662,58 @@ impl ExportManager {
         self.export_channel = Some(rx);
     }
 
-    /// Completes the export process
+    /// Completes the export process: waits for queued frames to be written and
+    /// finalizes the file for MP4 and GIF exports
     pub fn complete_export(&mut self) {
         self.settings.is_exporting = false;
         self.export_channel = None;
+        self.frame_saver = None;
+        if let Some(encoder) = self.video_encoder.take() {
+            match encoder.finish() {
+                Ok(path) => info!("Video export written to {}", path.display()),
+                Err(e) => error!("Video export failed: {e}"),
+            }
+        }
+        if let Some(writer) = self.gif_writer.take() {
+            match writer.finish() {
+                Ok(path) => info!("GIF export written to {}", path.display()),
+                Err(e) => error!("GIF export failed: {e}"),
+            }
+        }
+    }
+
+    /// Write a captured frame in the current export format: image frames are queued for
+    /// the worker threads, video frames go straight to the encoder. Outside an export the
+    /// frame is saved synchronously.
+    ///
+    /// With `supersample` above 1, frames captured at [`ExportSettings::capture_size`] are
+    /// box-filtered down to the export size first; frames already at the export size
+    /// are saved as they are.
+    pub fn save_frame(&mut self, data: Vec<u8>, frame: u32) -> Result<(), CuneusError> {
+        let data = self.downsample(data);
+        if let Some(encoder) = &mut self.video_encoder {
+            encoder.write_frame(&data).map_err(ExportError::from)?;
+            return Ok(());
+        }
+        if let Some(writer) = &self.gif_writer {
+            writer.write_frame(data).map_err(ExportError::from)?;
+            return Ok(());
+        }
+        match &self.frame_saver {
+            Some(saver) => Ok(saver.submit(data, frame)?),
+            None => save_frame(data, frame, &self.settings),
+        }
+    }
+
+    fn downsample(&self, data: Vec<u8>) -> Vec<u8> {
+        let factor = self.settings.supersample;
+        let (width, height) = self.settings.capture_size();
+        let float = self.settings.format == ExportFormat::Exr;
+        let bytes_per_pixel = if float { 16 } else { 4 };
+        if factor <= 1 || data.len() != width as usize * height as usize * bytes_per_pixel {
+            return data;
+        }
+        downsample_frame(&data, self.settings.width, self.settings.height, factor, float)
     }
 
     /// Returns references to both UI state and settings for the UI to use
@@ -196,15 +730,41 @@ impl ExportManager {
                 ui.collapsing("Resolution", |ui| {
                     ui.add(
                         egui::DragValue::new(&mut request.width)
-                            .range(1..=7680)
+                            .range(1..=MAX_EXPORT_DIMENSION)
                             .prefix("Width: "),
                     );
 
                     ui.add(
                         egui::DragValue::new(&mut request.height)
-                            .range(1..=4320)
+                            .range(1..=MAX_EXPORT_DIMENSION)
                             .prefix("Height: "),
                     );
+                    ui.horizontal(|ui| {
+                        let mut tiled = request.tile_size.is_some();
+                        if ui.checkbox(&mut tiled, "Tiles").changed() {
+                            request.tile_size = tiled.then_some(2048);
+                        }
+                        if let Some(tile_size) = &mut request.tile_size {
+                            ui.add(egui::DragValue::new(tile_size).range(64..=16384).suffix(" px"));
+                        }
+                    })
+                    .response
+                    .on_hover_text("Exports past the GPU's texture limit are tiled either way");
+                    if request.tile_size.is_some() {
+                        ui.add(
+                            egui::DragValue::new(&mut request.tile_overlap)
+                                .range(0..=256)
+                                .prefix("Tile overlap: ")
+                                .suffix(" px"),
+                        );
+                    }
+                    ui.add(
+                        egui::DragValue::new(&mut request.supersample)
+                            .range(1..=4)
+                            .prefix("Supersample: ")
+                            .suffix("x"),
+                    )
+                    .on_hover_text("Render larger and average down, for smoother edges in stills");
                 });
                 ui.collapsing("Time Settings", |ui| {
                     ui.add(
@@ -224,10 +784,75 @@ impl ExportManager {
                             .range(1..=240)
                             .prefix("FPS: "),
                     );
+
+                    ui.add(egui::DragValue::new(&mut request.start_frame).prefix("Start Frame: "));
+                    ui.horizontal(|ui| {
+                        let mut limit_end = request.end_frame.is_some();
+                        if ui.checkbox(&mut limit_end, "End Frame").changed() {
+                            request.end_frame = limit_end.then_some(request.start_frame + 1);
+                        }
+                        if let Some(end_frame) = &mut request.end_frame {
+                            ui.add(egui::DragValue::new(end_frame));
+                        }
+                    });
+                    if let Some(end_frame) = request.end_frame
+                        && end_frame <= request.start_frame
+                    {
+                        ui.label(
+                            egui::RichText::new("⚠ End frame must be after start frame")
+                                .color(egui::Color32::from_rgb(255, 190, 0)),
+                        );
+                    }
                 });
                 ui.collapsing("Output", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut request.format, ExportFormat::Png, "PNG sequence");
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                            let is_mp4 = matches!(request.format, ExportFormat::Mp4 { .. });
+                            if ui.radio(is_mp4, "MP4 (ffmpeg)").clicked() && !is_mp4 {
+                                request.format = ExportFormat::Mp4 { crf: 18 };
+                            }
+                        }
+                        ui.radio_value(&mut request.format, ExportFormat::Exr, "EXR (HDR)");
+                        let is_gif = matches!(request.format, ExportFormat::Gif { .. });
+                        if ui.radio(is_gif, "GIF").clicked() && !is_gif {
+                            request.format = ExportFormat::Gif { loop_count: 0 };
+                        }
+                    });
+                    if let ExportFormat::Mp4 { crf } = &mut request.format {
+                        ui.add(egui::DragValue::new(crf).range(0..=51).prefix("Quality (CRF): "));
+                    }
+                    if let ExportFormat::Gif { loop_count } = &mut request.format {
+                        ui.add(egui::DragValue::new(loop_count).prefix("Loops (0 = forever): "));
+                    }
+                    if request.format == ExportFormat::Png {
+                        ui.checkbox(&mut request.transparent, "Transparent background");
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Buffer:");
+                        let mut name = request.source_buffer.clone().unwrap_or_default();
+                        let edit = egui::TextEdit::singleline(&mut name).hint_text("final output");
+                        if ui.add(edit).changed() {
+                            let name = name.trim();
+                            request.source_buffer = (!name.is_empty()).then(|| name.to_string());
+                        }
+                    });
+                    if !request.format.is_single_file() {
+                        ui.horizontal(|ui| {
+                            ui.label("File name:");
+                            ui.text_edit_singleline(&mut request.name_template);
+                        });
+                        if let Err(e) = validate_name_template(&request.name_template) {
+                            ui.label(
+                                egui::RichText::new(format!("⚠ {e}"))
+                                    .color(egui::Color32::from_rgb(255, 190, 0)),
+                            );
+                        }
+                    }
                     ui.horizontal(|ui| {
                         ui.label("Export Path:");
+                        #[cfg(not(target_arch = "wasm32"))]
                         if ui.button("Browse").clicked() {
                             if let Some(path) = rfd::FileDialog::new()
                                 .set_directory(&request.path)
@@ -260,11 +885,36 @@ impl ExportManager {
                     should_start_export = true;
                 }
             } else {
-                ui.label("Exporting...");
+                match request.progress {
+                    Some(progress) => {
+                        ui.add(egui::ProgressBar::new(progress.fraction()).text(format!(
+                            "{}/{} frames",
+                            progress.frames_done, progress.frames_total
+                        )));
+                        match progress.eta {
+                            Some(eta) => {
+                                let secs = eta.as_secs();
+                                ui.label(format!("ETA: {}:{:02}", secs / 60, secs % 60));
+                            }
+                            None => {
+                                ui.label("ETA: estimating...");
+                            }
+                        }
+                    }
+                    None => {
+                        ui.label("Exporting...");
+                    }
+                }
+                if ui.button("Cancel Export").clicked() {
+                    request.cancel_export = true;
+                }
             }
         });
         should_start_export
     }
+    /// Drive an export with a custom capture. `capture_fn` returns the frame as packed
+    /// RGBA8; a capture texture in `CAPTURE_FORMAT` is BGRA on some platforms and must
+    /// be swapped first, as `capture_export_frame` does.
     pub fn handle_export<F, E>(&mut self, capture_fn: F)
     where
         F: FnMut(u32, f32) -> Result<Vec<u8>, E>,
@@ -274,8 +924,7 @@ impl ExportManager {
         if let Some((frame, time)) = self.try_get_next_frame() {
             match capture_fn(frame, time) {
                 Ok(data) => {
-                    let settings = self.settings();
-                    if let Err(e) = save_frame(data, frame, settings) {
+                    if let Err(e) = self.save_frame(data, frame) {
                         error!("Error saving frame: {e:?}");
                     }
                 }
@@ -288,23 +937,25 @@ impl ExportManager {
         }
     }
 }
-#[allow(unused_mut)]
+/// Write one captured frame. `data` is tightly packed RGBA8 (or RGBA f32 for EXR), as
+/// returned by `capture_export_frame`, which already converts BGRA captures.
 pub fn save_frame(
-    mut data: Vec<u8>,
+    data: Vec<u8>,
     frame: u32,
     settings: &ExportSettings,
-) -> Result<(), ExportError> {
-    let frame_path = settings.export_path.join(format!("frame_{frame:05}.png"));
+) -> Result<(), CuneusError> {
+    Ok(write_frame_file(data, frame, settings)?)
+}
+
+fn write_frame_file(data: Vec<u8>, frame: u32, settings: &ExportSettings) -> Result<(), ExportError> {
+    if settings.format == ExportFormat::Exr {
+        return save_exr_frame(&data, frame, settings);
+    }
+    let frame_path = settings.export_path.join(frame_file_name(settings, frame, "png")?);
 
     if let Some(parent) = frame_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    #[cfg(target_os = "macos")]
-    {
-        for chunk in data.chunks_mut(4) {
-            chunk.swap(0, 2);
-        }
-    }
     let image = image::ImageBuffer::<image::Rgba<u8>, Vec<u8>>::from_raw(
         settings.width,
         settings.height,
@@ -319,3 +970,129 @@ pub fn save_frame(
     image.save(&frame_path)?;
     Ok(())
 }
+
+/// Average each `factor` x `factor` block of a supersampled frame into one pixel of a
+/// `width` x `height` frame. RGBA8 frames are sRGB, so they are averaged in linear light
+/// to keep edges from darkening; `float` frames are already linear RGBA `f32`.
+fn downsample_frame(data: &[u8], width: u32, height: u32, factor: u32, float: bool) -> Vec<u8> {
+    let (width, height, factor) = (width as usize, height as usize, factor as usize);
+    let source_width = width * factor;
+    let samples = (factor * factor) as f32;
+    let mut sums = vec![[0.0f32; 4]; width * height];
+    if float {
+        let pixels: Vec<[f32; 4]> = bytemuck::pod_collect_to_vec(data);
+        for (i, px) in pixels.iter().enumerate() {
+            let sum = &mut sums[(i / source_width / factor) * width + (i % source_width) / factor];
+            for (sum, value) in sum.iter_mut().zip(px) {
+                *sum += value;
+            }
+        }
+        let averaged: Vec<[f32; 4]> = sums.iter().map(|sum| sum.map(|c| c / samples)).collect();
+        return bytemuck::cast_slice(&averaged).to_vec();
+    }
+
+    let to_linear: [f32; 256] = std::array::from_fn(|v| srgb_to_linear(v as f32 / 255.0));
+    for (i, px) in data.chunks_exact(4).enumerate() {
+        let sum = &mut sums[(i / source_width / factor) * width + (i % source_width) / factor];
+        for (sum, &value) in sum.iter_mut().zip(&px[..3]) {
+            *sum += to_linear[value as usize];
+        }
+        sum[3] += px[3] as f32 / 255.0;
+    }
+    let to_byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let mut out = Vec::with_capacity(width * height * 4);
+    for sum in sums {
+        for &channel in &sum[..3] {
+            out.push(linear_to_srgb8(channel / samples));
+        }
+        out.push(to_byte(sum[3] / samples));
+    }
+    out
+}
+
+fn save_exr_frame(data: &[u8], frame: u32, settings: &ExportSettings) -> Result<(), ExportError> {
+    let frame_path = settings.export_path.join(frame_file_name(settings, frame, "exr")?);
+
+    if let Some(parent) = frame_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let pixels: Vec<f32> = bytemuck::pod_collect_to_vec(data);
+    let image = image::Rgba32FImage::from_raw(settings.width, settings.height, pixels).ok_or_else(|| {
+        ImageError::Parameter(image::error::ParameterError::from_kind(
+            image::error::ParameterErrorKind::Generic(
+                "EXR export needs linear RGBA f32 frame data".to_string(),
+            ),
+        ))
+    })?;
+
+    image.save(&frame_path)?;
+    Ok(())
+}
+
+/// Check an export name template: known placeholders, valid padding, balanced braces,
+/// and a `{frame}` so frames don't overwrite each other
+fn validate_name_template(template: &str) -> Result<(), String> {
+    let mut uses_frame = false;
+    expand_name_template(template, |name, _| {
+        uses_frame |= name == "frame";
+        Some(String::new())
+    })?;
+    if !uses_frame {
+        return Err("file name needs a {frame} placeholder".to_string());
+    }
+    Ok(())
+}
+
+fn frame_file_name(settings: &ExportSettings, frame: u32, extension: &str) -> Result<String, ExportError> {
+    let time = settings.start_time + frame as f32 / settings.fps as f32;
+    let name = expand_name_template(&settings.name_template, |name, pad| {
+        Some(match name {
+            "frame" => format!("{frame:0pad$}"),
+            "width" => format!("{:0pad$}", settings.width),
+            "height" => format!("{:0pad$}", settings.height),
+            "time" => format!("{time:0pad$.3}"),
+            _ => return None,
+        })
+    })
+    .map_err(|e| ExportError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidInput, e)))?;
+    Ok(format!("{name}.{extension}"))
+}
+
+/// Replace `{name}` / `{name:0N}` placeholders using `value(name, N)`
+fn expand_name_template(
+    template: &str,
+    mut value: impl FnMut(&str, usize) -> Option<String>,
+) -> Result<String, String> {
+    const NAMES: [&str; 4] = ["frame", "width", "height", "time"];
+    let mut out = String::with_capacity(template.len() + 8);
+    let mut rest = template;
+    while let Some(open) = rest.find(['{', '}']) {
+        if rest[open..].starts_with('}') {
+            return Err("unmatched '}'".to_string());
+        }
+        out.push_str(&rest[..open]);
+        let close = rest[open..].find('}').ok_or("unclosed '{'")? + open;
+        let placeholder = &rest[open + 1..close];
+        let (name, pad) = match placeholder.split_once(':') {
+            Some((name, spec)) => {
+                let width = spec
+                    .strip_prefix('0')
+                    .and_then(|w| w.parse::<usize>().ok())
+                    .filter(|w| *w <= 16)
+                    .ok_or_else(|| format!("bad padding '{spec}' in {{{placeholder}}}, expected e.g. 05"))?;
+                (name, width)
+            }
+            None => (placeholder, 0),
+        };
+        if !NAMES.contains(&name) {
+            return Err(format!("unknown placeholder {{{name}}}"));
+        }
+        out.push_str(&value(name, pad).ok_or_else(|| format!("unknown placeholder {{{name}}}"))?);
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+    if out.contains(['/', '\\']) {
+        return Err("file name can't contain path separators".to_string());
+    }
+    Ok(out)
+}
@@ -0,0 +1,152 @@
+use crate::{Core, TextureManager};
+
+/// A ping-pong pair of render targets for fragment-shader feedback.
+///
+/// Each frame, sample last frame's result through [`read`](Self::read) and render
+/// into [`write`](Self::write), then call [`swap`](Self::swap). Effects with several
+/// feedback buffers use one chain each.
+///
+/// ```ignore
+/// let chain = &mut self.feedback;
+/// self.feedback_renderer.render_to_view(&mut encoder, &chain.write().view, &chain.read().bind_group);
+/// base.renderer.render_to_view(&mut encoder, &frame.view, &chain.write().bind_group);
+/// chain.swap();
+/// ```
+///
+/// The renderer drawing into the chain must be built for [`format`](Self::format).
+/// Bind groups follow `layout` (e.g. [`TextureManager::create_display_layout`]), so
+/// the format has to be filterable: `Rgba16Float` for HDR feedback, not `Rgba32Float`.
+pub struct FeedbackChain {
+    /// Scale the old contents into the new targets on [`resize`](Self::resize)
+    /// instead of starting from black
+    pub preserve_on_resize: bool,
+    targets: [TextureManager; 2],
+    write_index: usize,
+    layout: wgpu::BindGroupLayout,
+    format: wgpu::TextureFormat,
+    label: String,
+}
+
+impl FeedbackChain {
+    pub fn new(
+        core: &Core,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        layout: &wgpu::BindGroupLayout,
+        label: &str,
+    ) -> Self {
+        let targets = [0, 1].map(|i| Self::create_target(core, width, height, format, layout, &format!("{label} {i}")));
+        Self {
+            preserve_on_resize: false,
+            targets,
+            write_index: 0,
+            layout: layout.clone(),
+            format,
+            label: label.to_string(),
+        }
+    }
+
+    fn create_target(
+        core: &Core,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        layout: &wgpu::BindGroupLayout,
+        label: &str,
+    ) -> TextureManager {
+        let texture = core.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = core.device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some(&format!("{label} Bind Group")),
+        });
+        TextureManager {
+            texture,
+            view,
+            sampler,
+            bind_group,
+        }
+    }
+
+    /// Last frame's result, to sample from
+    pub fn read(&self) -> &TextureManager {
+        &self.targets[1 - self.write_index]
+    }
+
+    /// This frame's target, to render into
+    pub fn write(&self) -> &TextureManager {
+        &self.targets[self.write_index]
+    }
+
+    /// Make this frame's result next frame's input. Call once per frame after rendering.
+    pub fn swap(&mut self) {
+        self.write_index = 1 - self.write_index;
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        let texture = &self.targets[0].texture;
+        (texture.width(), texture.height())
+    }
+
+    /// Reallocate both targets at the new size. The contents start out cleared, or
+    /// scaled from the old targets with `preserve_on_resize`.
+    pub fn resize(&mut self, core: &Core, width: u32, height: u32) {
+        if self.size() == (width.max(1), height.max(1)) {
+            return;
+        }
+        let new_targets = [0, 1].map(|i| {
+            Self::create_target(core, width, height, self.format, &self.layout, &format!("{} {i}", self.label))
+        });
+        let old_targets = std::mem::replace(&mut self.targets, new_targets);
+        if self.preserve_on_resize {
+            let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Feedback Resize Encoder"),
+            });
+            for (old, new) in old_targets.iter().zip(&self.targets) {
+                crate::resample_texture(&core.device, &mut encoder, &old.texture, &new.texture);
+            }
+            core.queue.submit(Some(encoder.finish()));
+        } else {
+            self.write_index = 0;
+        }
+    }
+}
@@ -1,9 +1,17 @@
 use crate::{Core, TextureManager, UniformBinding, UniformProvider};
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont};
 use bytemuck::{Pod, Zeroable};
+use log::warn;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 // font system using texture atlas
 
+/// WGSL source of the text helpers (`Glyph`, `Glyphs`, `sample_glyph`, `blend_glyph`), also
+/// available to shaders loaded from a file as `//!include <cuneus/text.wgsl>`. Prepend it to
+/// embedded sources that don't go through the include preprocessor.
+pub const TEXT_WGSL: &str = include_str!("../shaders/text.wgsl");
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct FontUniforms {
@@ -19,6 +27,59 @@ impl UniformProvider for FontUniforms {
     }
 }
 
+/// A TTF/OTF font to rasterize into the atlas instead of the built-in one,
+/// see [`ComputeShaderBuilder::with_font`](crate::compute::ComputeShaderBuilder::with_font).
+#[derive(Debug, Clone)]
+pub enum FontSource {
+    File(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl From<&str> for FontSource {
+    fn from(path: &str) -> Self {
+        Self::File(path.into())
+    }
+}
+
+impl From<&Path> for FontSource {
+    fn from(path: &Path) -> Self {
+        Self::File(path.to_path_buf())
+    }
+}
+
+impl From<PathBuf> for FontSource {
+    fn from(path: PathBuf) -> Self {
+        Self::File(path)
+    }
+}
+
+impl From<&[u8]> for FontSource {
+    fn from(bytes: &[u8]) -> Self {
+        Self::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for FontSource {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+/// One laid-out character in the `glyphs` buffer enabled by
+/// [`ComputeShaderBuilder::with_text`](crate::compute::ComputeShaderBuilder::with_text).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+pub struct GlyphInstance {
+    /// Top-left corner of the cell in output pixels
+    pub position: [f32; 2],
+    /// Cell size in output pixels
+    pub size: [f32; 2],
+    pub color: [f32; 4],
+    /// Atlas cell index (the ASCII code)
+    pub code: u32,
+    pub _padding: [u32; 3],
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct CharInfo {
     pub uv_min: [f32; 2],
@@ -35,9 +96,22 @@ pub struct FontSystem {
     pub atlas_height: u32,
     pub grid_size: u32,
     pub char_size: u32,
+    /// `{ count, pad x3, glyphs[] }` storage buffer, present when text drawing is enabled
+    pub glyph_buffer: Option<wgpu::Buffer>,
+    max_glyphs: u32,
+    glyphs: Vec<GlyphInstance>,
+    glyphs_dirty: bool,
 }
 
 impl FontSystem {
+    /// Horizontal advance as a fraction of the glyph height; the atlas glyphs are
+    /// monospaced and about half as wide as their square cells
+    pub const GLYPH_ADVANCE: f32 = 0.5;
+    const GLYPH_HEADER_SIZE: u64 = 16;
+    // Layout of the built-in atlas, reused for rasterized fonts so shaders work with either
+    const ATLAS_SIZE: u32 = 1024;
+    const GRID_SIZE: u32 = 16;
+
     pub fn new(core: &Core) -> Self {
         //note that: I always use following:
         // _ATLAS_SIZE: u32 = 1024;
@@ -47,10 +121,69 @@ impl FontSystem {
         let font_image = image::load_from_memory(font_texture_bytes)
             .expect("Failed to load font texture")
             .into_rgba8();
+        Self::from_atlas_image(core, font_image)
+    }
 
+    /// Build the atlas from a TTF/OTF font, falling back to the built-in atlas
+    /// if the font can't be read or parsed.
+    pub fn from_font(core: &Core, source: &FontSource) -> Self {
+        match Self::rasterize_atlas(source) {
+            Ok(font_image) => Self::from_atlas_image(core, font_image),
+            Err(e) => {
+                warn!("Failed to load font, using the built-in one: {e}");
+                Self::new(core)
+            }
+        }
+    }
+
+    /// Draw ASCII 32..127 into the same 16x16 grid as the built-in atlas, each glyph
+    /// centered on its advance and white with coverage in every channel.
+    fn rasterize_atlas(source: &FontSource) -> Result<image::RgbaImage, String> {
+        let data = match source {
+            FontSource::File(path) => {
+                std::fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?
+            }
+            FontSource::Bytes(bytes) => bytes.clone(),
+        };
+        let font = FontVec::try_from_vec(data).map_err(|e| e.to_string())?;
+
+        let cell = (Self::ATLAS_SIZE / Self::GRID_SIZE) as f32;
+        let scale = PxScale::from(cell * 0.75);
+        let scaled = font.as_scaled(scale);
+        // Center the ascent..descent span vertically (descent is negative)
+        let baseline = (cell + scaled.ascent() + scaled.descent()) * 0.5;
+
+        let mut image = image::RgbaImage::new(Self::ATLAS_SIZE, Self::ATLAS_SIZE);
+        for code in 32u32..127 {
+            let cell_x = (code % Self::GRID_SIZE) as f32 * cell;
+            let cell_y = (code / Self::GRID_SIZE) as f32 * cell;
+            let glyph_id = font.glyph_id(code as u8 as char);
+            let origin = ab_glyph::point(
+                cell_x + (cell - scaled.h_advance(glyph_id)) * 0.5,
+                cell_y + baseline,
+            );
+            let Some(outlined) = font.outline_glyph(glyph_id.with_scale_and_position(scale, origin))
+            else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|x, y, coverage| {
+                let px = bounds.min.x + x as f32;
+                let py = bounds.min.y + y as f32;
+                // Clip to the cell so wide glyphs don't bleed into neighbours
+                if px >= cell_x && px < cell_x + cell && py >= cell_y && py < cell_y + cell {
+                    let value = (coverage.clamp(0.0, 1.0) * 255.0) as u8;
+                    image.put_pixel(px as u32, py as u32, image::Rgba([value; 4]));
+                }
+            });
+        }
+        Ok(image)
+    }
+
+    fn from_atlas_image(core: &Core, font_image: image::RgbaImage) -> Self {
         let atlas_width = font_image.width();
         let atlas_height = font_image.height();
-        let grid_size = 16u32;
+        let grid_size = Self::GRID_SIZE;
         let char_size = atlas_width / grid_size;
 
         let font_bind_group_layout =
@@ -124,7 +257,95 @@ impl FontSystem {
             atlas_height,
             grid_size,
             char_size,
+            glyph_buffer: None,
+            max_glyphs: 0,
+            glyphs: Vec::new(),
+            glyphs_dirty: false,
+        }
+    }
+
+    /// Allocate the glyph buffer for up to `max_glyphs` characters.
+    pub fn enable_text(&mut self, device: &wgpu::Device, max_glyphs: u32) {
+        self.max_glyphs = max_glyphs;
+        self.glyph_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Text Glyphs"),
+            size: Self::GLYPH_HEADER_SIZE
+                + max_glyphs as u64 * std::mem::size_of::<GlyphInstance>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        }));
+    }
+
+    /// Lay out `text` as monospaced cells `size` pixels tall, starting with the top-left
+    /// corner at `position`. `\n` starts a new line; characters missing from the atlas
+    /// leave a gap.
+    pub fn layout_text(
+        &self,
+        text: &str,
+        position: [f32; 2],
+        size: f32,
+        color: [f32; 4],
+    ) -> Vec<GlyphInstance> {
+        let advance = size * Self::GLYPH_ADVANCE;
+        let mut pen = position;
+        let mut glyphs = Vec::with_capacity(text.len());
+        for ch in text.chars() {
+            if ch == '\n' {
+                pen = [position[0], pen[1] + size];
+                continue;
+            }
+            if let Some(info) = self.get_char_info(ch).filter(|_| ch != ' ') {
+                glyphs.push(GlyphInstance {
+                    // Center the square cell on the narrower advance
+                    position: [pen[0] - (size - advance) * 0.5, pen[1]],
+                    size: [size, size],
+                    color,
+                    code: info.char_code as u32,
+                    _padding: [0; 3],
+                });
+            }
+            pen[0] += advance;
+        }
+        glyphs
+    }
+
+    /// Queue `text` for the next upload. Glyphs past the buffer capacity are dropped.
+    pub fn push_text(&mut self, text: &str, position: [f32; 2], size: f32, color: [f32; 4]) {
+        let glyphs = self.layout_text(text, position, size, color);
+        let room = (self.max_glyphs as usize).saturating_sub(self.glyphs.len());
+        if glyphs.len() > room {
+            warn!(
+                "Text glyph buffer full ({} glyphs), dropping {}",
+                self.max_glyphs,
+                glyphs.len() - room
+            );
+        }
+        self.glyphs.extend(glyphs.into_iter().take(room));
+        self.glyphs_dirty = true;
+    }
+
+    pub fn clear_text(&mut self) {
+        if !self.glyphs.is_empty() {
+            self.glyphs.clear();
+            self.glyphs_dirty = true;
+        }
+    }
+
+    /// Write queued glyphs to the GPU if they changed since the last upload.
+    pub fn upload_text(&mut self, queue: &wgpu::Queue) {
+        let Some(buffer) = self.glyph_buffer.as_ref().filter(|_| self.glyphs_dirty) else {
+            return;
+        };
+        let header = [self.glyphs.len() as u32, 0, 0, 0];
+        queue.write_buffer(buffer, 0, bytemuck::cast_slice(&header));
+        if !self.glyphs.is_empty() {
+            queue.write_buffer(
+                buffer,
+                Self::GLYPH_HEADER_SIZE,
+                bytemuck::cast_slice(&self.glyphs),
+            );
         }
+        self.glyphs_dirty = false;
     }
 
     fn create_font_texture(core: &Core, font_image: &image::RgbaImage) -> TextureManager {
@@ -1,9 +1,15 @@
 use std::collections::VecDeque;
-use std::time::Instant;
+use web_time::Instant;
+/// Frames kept for the statistics and graph unless `set_window` says otherwise
+const DEFAULT_WINDOW: usize = 240;
+
 pub struct FpsTracker {
     last_frame_time: Instant,
     frame_times: VecDeque<f32>,
     current_fps: f32,
+    /// Frame times in milliseconds over the last `window` frames, oldest first
+    history: VecDeque<f32>,
+    window: usize,
 }
 
 impl Default for FpsTracker {
@@ -18,6 +24,16 @@ impl FpsTracker {
             last_frame_time: Instant::now(),
             frame_times: VecDeque::with_capacity(60),
             current_fps: 0.0,
+            history: VecDeque::with_capacity(DEFAULT_WINDOW),
+            window: DEFAULT_WINDOW,
+        }
+    }
+
+    /// Number of recent frames the statistics, histogram and graph cover
+    pub fn set_window(&mut self, frames: usize) {
+        self.window = frames.max(1);
+        while self.history.len() > self.window {
+            self.history.pop_front();
         }
     }
 
@@ -32,6 +48,10 @@ impl FpsTracker {
             if self.frame_times.len() > 30 {
                 self.frame_times.pop_front();
             }
+            self.history.push_back(frame_time * 1000.0);
+            if self.history.len() > self.window {
+                self.history.pop_front();
+            }
 
             // shouldn't happen, but who knows anyway...:
             if !self.frame_times.is_empty() {
@@ -50,4 +70,88 @@ impl FpsTracker {
     pub fn delta_time(&self) -> f32 {
         self.last_frame_time.elapsed().as_secs_f32()
     }
+
+    /// Duration of the most recent frame in milliseconds
+    pub fn frame_time_ms(&self) -> f32 {
+        self.history.back().copied().unwrap_or(0.0)
+    }
+
+    /// Frame times in milliseconds over the window, oldest first
+    pub fn frame_times_ms(&self) -> impl ExactSizeIterator<Item = f32> + '_ {
+        self.history.iter().copied()
+    }
+
+    /// Frame time in milliseconds that `percentile` (0-100) of the window's frames stay
+    /// at or under; 0 before the first frame
+    pub fn percentile_ms(&self, percentile: f32) -> f32 {
+        if self.history.is_empty() {
+            return 0.0;
+        }
+        let mut sorted: Vec<f32> = self.history.iter().copied().collect();
+        sorted.sort_by(f32::total_cmp);
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f32).round();
+        sorted[rank as usize]
+    }
+
+    /// 99th percentile frame time: the hitches, where `fps()` shows the average
+    pub fn p99_ms(&self) -> f32 {
+        self.percentile_ms(99.0)
+    }
+
+    /// Frame rate of the slowest frame in the window
+    pub fn min_fps(&self) -> f32 {
+        let slowest = self.history.iter().copied().fold(0.0, f32::max);
+        if slowest > 0.0 { 1000.0 / slowest } else { 0.0 }
+    }
+
+    /// Frame rate of the fastest frame in the window
+    pub fn max_fps(&self) -> f32 {
+        let fastest = self.history.iter().copied().fold(f32::INFINITY, f32::min);
+        if fastest.is_finite() && fastest > 0.0 { 1000.0 / fastest } else { 0.0 }
+    }
+
+    /// Count of frames in the window per `bucket_ms`-wide bucket, starting at 0 ms
+    pub fn histogram(&self, bucket_ms: f32) -> Vec<u32> {
+        let bucket_ms = bucket_ms.max(0.01);
+        let mut buckets = Vec::new();
+        for &ms in &self.history {
+            let index = (ms / bucket_ms) as usize;
+            if index >= buckets.len() {
+                buckets.resize(index + 1, 0);
+            }
+            buckets[index] += 1;
+        }
+        buckets
+    }
+
+    /// Frame-time plot of the window with the 99th percentile marked, plus a summary line
+    pub fn render_graph(&self, ui: &mut egui::Ui) {
+        let p99 = self.p99_ms();
+        ui.label(format!(
+            "{:.1} ms  ·  p99 {:.1} ms  ·  {:.0} fps (min {:.0})",
+            self.frame_time_ms(),
+            p99,
+            self.fps(),
+            self.min_fps()
+        ));
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 60.0), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+        if self.history.len() < 2 {
+            return;
+        }
+        // Scale to the slowest frame, but keep at least a 60 fps frame in view
+        let top = self.history.iter().copied().fold(1000.0 / 60.0, f32::max) * 1.1;
+        let step = rect.width() / (self.window.max(2) - 1) as f32;
+        let start = rect.right() - step * (self.history.len() - 1) as f32;
+        let y = |ms: f32| rect.bottom() - ms / top * rect.height();
+        let points: Vec<egui::Pos2> = self
+            .history
+            .iter()
+            .enumerate()
+            .map(|(i, &ms)| egui::pos2(start + step * i as f32, y(ms)))
+            .collect();
+        painter.add(egui::Shape::line(points, egui::Stroke::new(1.0, egui::Color32::LIGHT_GREEN)));
+        painter.hline(rect.x_range(), y(p99), egui::Stroke::new(1.0, egui::Color32::from_rgb(230, 120, 80)));
+    }
 }
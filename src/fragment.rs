@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use crate::{Core, FeedbackChain, RenderKit, Renderer, TextureManager};
+
+/// One draw of a [`FragmentPipeline`]: run the fragment entry point `entry_point` over
+/// the whole of `output`, reading `inputs` as `input_texture0..N`.
+#[derive(Debug, Clone)]
+pub struct FragmentPass {
+    pub entry_point: String,
+    /// Buffers this pass samples, mapped by position to `input_textureN`/`input_samplerN`
+    pub inputs: Vec<String>,
+    /// Buffer this pass renders into
+    pub output: String,
+}
+
+impl FragmentPass {
+    pub fn new(entry_point: &str, inputs: &[&str], output: &str) -> Self {
+        Self {
+            entry_point: entry_point.to_string(),
+            inputs: inputs.iter().map(|s| s.to_string()).collect(),
+            output: output.to_string(),
+        }
+    }
+}
+
+/// Several fragment passes over full-screen buffers, run in order each frame, like
+/// [`ComputeShader`](crate::compute::ComputeShader) multi-pass does for compute.
+///
+/// Every output name becomes a ping-pong buffer. An input reads the buffer's latest
+/// result: this frame's if an earlier pass already wrote it, otherwise last frame's,
+/// so a pass listing its own output as an input gets feedback.
+///
+/// The WGSL holds only fragment entry points taking `@location(0) tex_coords: vec2<f32>`.
+/// Group 0 is generated from the inputs, with `input_textureN` at binding `2N` and
+/// `input_samplerN` at `2N + 1`. Passes with fewer inputs repeat their first one. Groups
+/// 1 and up are the `layouts` passed in, bound from [`render`](Self::render)'s `bind_groups`.
+///
+/// ```ignore
+/// let pipeline = FragmentPipeline::new(
+///     core,
+///     include_str!("shaders/trails.wgsl"),
+///     vec![
+///         FragmentPass::new("fade", &["trails"], "trails"),
+///         FragmentPass::new("blur_h", &["trails"], "blur"),
+///         FragmentPass::new("composite", &["trails", "blur"], "main"),
+///     ],
+///     &[&time_layout, &params_layout],
+///     wgpu::TextureFormat::Rgba16Float,
+/// );
+/// pipeline.render(core, &mut frame.encoder, &[&time_bind_group, &params_bind_group]);
+/// base.renderer.render_to_view(&mut frame.encoder, &frame.view, &pipeline.output("main").bind_group);
+/// ```
+pub struct FragmentPipeline {
+    passes: Vec<(FragmentPass, Renderer)>,
+    buffers: HashMap<String, FeedbackChain>,
+    input_layout: wgpu::BindGroupLayout,
+    max_inputs: usize,
+}
+
+impl FragmentPipeline {
+    /// Build one render pipeline per pass and window-sized buffers in `format`, which
+    /// must be renderable and filterable (e.g. `Rgba16Float` or `Rgba8Unorm`)
+    pub fn new(
+        core: &Core,
+        shader_source: &str,
+        passes: Vec<FragmentPass>,
+        layouts: &[&wgpu::BindGroupLayout],
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        assert!(!passes.is_empty(), "FragmentPipeline needs at least one pass");
+        for pass in &passes {
+            for input in &pass.inputs {
+                assert!(
+                    passes.iter().any(|p| &p.output == input),
+                    "Pass '{}' reads '{input}', which no pass writes",
+                    pass.entry_point
+                );
+            }
+        }
+        let max_inputs = passes.iter().map(|p| p.inputs.len()).max().unwrap_or(0).max(1);
+        let input_layout = Self::create_input_layout(&core.device, max_inputs);
+        let mut bind_group_layouts = vec![Some(&input_layout)];
+        bind_group_layouts.extend(layouts.iter().map(|&layout| Some(layout)));
+        let pipeline_layout = core.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Fragment Pipeline Layout"),
+            bind_group_layouts: &bind_group_layouts,
+            immediate_size: 0,
+        });
+        let vs_module = core.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Fragment Pipeline Vertex Shader"),
+            source: wgpu::ShaderSource::Wgsl(RenderKit::VERTEX_SHADER.into()),
+        });
+        let fs_module = core.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Fragment Pipeline Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_source.into()),
+        });
+
+        let display_layout = TextureManager::create_display_layout(&core.device);
+        let mut buffers = HashMap::new();
+        for pass in &passes {
+            buffers.entry(pass.output.clone()).or_insert_with(|| {
+                let (width, height) = (core.size.width, core.size.height);
+                FeedbackChain::new(core, width, height, format, &display_layout, &pass.output)
+            });
+        }
+        let passes = passes
+            .into_iter()
+            .map(|pass| {
+                let renderer = Renderer::new(
+                    &core.device,
+                    &vs_module,
+                    &fs_module,
+                    format,
+                    &pipeline_layout,
+                    Some(&pass.entry_point),
+                );
+                (pass, renderer)
+            })
+            .collect();
+
+        Self {
+            passes,
+            buffers,
+            input_layout,
+            max_inputs,
+        }
+    }
+
+    fn create_input_layout(device: &wgpu::Device, max_inputs: usize) -> wgpu::BindGroupLayout {
+        let entries: Vec<_> = (0..max_inputs as u32)
+            .flat_map(|i| {
+                [
+                    wgpu::BindGroupLayoutEntry {
+                        binding: i * 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: i * 2 + 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ]
+            })
+            .collect();
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Fragment Pipeline Input Layout"),
+            entries: &entries,
+        })
+    }
+
+    /// Run every pass in order. `bind_groups` go to groups 1 and up, matching the
+    /// `layouts` given to [`new`](Self::new).
+    pub fn render(&mut self, core: &Core, encoder: &mut wgpu::CommandEncoder, bind_groups: &[&wgpu::BindGroup]) {
+        for (pass, renderer) in &self.passes {
+            let first = pass.inputs.first().unwrap_or(&pass.output);
+            let entries: Vec<_> = (0..self.max_inputs)
+                .flat_map(|i| {
+                    let source = self.buffers[pass.inputs.get(i).unwrap_or(first)].read();
+                    [
+                        wgpu::BindGroupEntry {
+                            binding: (i * 2) as u32,
+                            resource: wgpu::BindingResource::TextureView(&source.view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: (i * 2 + 1) as u32,
+                            resource: wgpu::BindingResource::Sampler(&source.sampler),
+                        },
+                    ]
+                })
+                .collect();
+            let input_bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(&format!("{} Inputs", pass.entry_point)),
+                layout: &self.input_layout,
+                entries: &entries,
+            });
+
+            let output = self.buffers.get_mut(&pass.output).expect("every output has a buffer");
+            {
+                let mut render_pass = Renderer::begin_render_pass(
+                    encoder,
+                    &output.write().view,
+                    wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    Some(&pass.entry_point),
+                );
+                render_pass.set_bind_group(0, &input_bind_group, &[]);
+                for (i, bind_group) in bind_groups.iter().enumerate() {
+                    render_pass.set_bind_group(i as u32 + 1, *bind_group, &[]);
+                }
+                renderer.draw(&mut render_pass);
+            }
+            output.swap();
+        }
+    }
+
+    /// Latest result of a buffer, e.g. to blit the final one to the screen
+    pub fn output(&self, name: &str) -> &TextureManager {
+        self.buffers
+            .get(name)
+            .unwrap_or_else(|| panic!("No fragment pass writes '{name}'"))
+            .read()
+    }
+
+    /// Reallocate every buffer at the window size. Feedback restarts from black unless
+    /// [`set_preserve_on_resize`](Self::set_preserve_on_resize) is on.
+    pub fn resize(&mut self, core: &Core) {
+        for buffer in self.buffers.values_mut() {
+            buffer.resize(core, core.size.width, core.size.height);
+        }
+    }
+
+    /// Scale buffer contents into the new size on [`resize`](Self::resize)
+    pub fn set_preserve_on_resize(&mut self, preserve: bool) {
+        for buffer in self.buffers.values_mut() {
+            buffer.preserve_on_resize = preserve;
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::UniformProvider;
+
+/// Gamepad state for a shader uniform, bound after the mouse by `.with_gamepad()`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GamepadUniform {
+    /// Left stick x/y, right stick x/y in -1..1, y up
+    pub axes: [f32; 4],
+    /// Left/right analog triggers, 0..1
+    pub triggers: [f32; 2],
+    /// Pressed buttons, one bit per `GamepadTracker::BUTTON_*`
+    pub buttons: u32,
+    /// 1 while a gamepad is connected, else 0 and everything above is zero
+    pub connected: u32,
+}
+
+impl UniformProvider for GamepadUniform {
+    fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+}
+
+/// Polls the first connected gamepad into a [`GamepadUniform`].
+///
+/// Needs the `gamepad` feature (gilrs); without it, or with no gamepad plugged in,
+/// the uniform stays zeroed with `connected == 0`, so shaders can fall back to mouse
+/// or keyboard input. Gamepads can be plugged and unplugged while running.
+pub struct GamepadTracker {
+    pub uniform: GamepadUniform,
+    /// Stick values below this magnitude read as 0
+    pub deadzone: f32,
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<gilrs::Gilrs>,
+    #[cfg(feature = "gamepad")]
+    init_attempted: bool,
+    #[cfg(feature = "gamepad")]
+    active: Option<gilrs::GamepadId>,
+}
+
+impl Default for GamepadTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GamepadTracker {
+    // Face buttons by position (Xbox A/B/X/Y, PlayStation cross/circle/square/triangle)
+    pub const BUTTON_SOUTH: u32 = 1 << 0;
+    pub const BUTTON_EAST: u32 = 1 << 1;
+    pub const BUTTON_WEST: u32 = 1 << 2;
+    pub const BUTTON_NORTH: u32 = 1 << 3;
+    pub const BUTTON_LEFT_BUMPER: u32 = 1 << 4;
+    pub const BUTTON_RIGHT_BUMPER: u32 = 1 << 5;
+    pub const BUTTON_SELECT: u32 = 1 << 6;
+    pub const BUTTON_START: u32 = 1 << 7;
+    pub const BUTTON_MODE: u32 = 1 << 8;
+    pub const BUTTON_LEFT_STICK: u32 = 1 << 9;
+    pub const BUTTON_RIGHT_STICK: u32 = 1 << 10;
+    pub const BUTTON_DPAD_UP: u32 = 1 << 11;
+    pub const BUTTON_DPAD_DOWN: u32 = 1 << 12;
+    pub const BUTTON_DPAD_LEFT: u32 = 1 << 13;
+    pub const BUTTON_DPAD_RIGHT: u32 = 1 << 14;
+
+    pub fn new() -> Self {
+        Self {
+            uniform: GamepadUniform::default(),
+            deadzone: 0.1,
+            #[cfg(feature = "gamepad")]
+            gilrs: None,
+            #[cfg(feature = "gamepad")]
+            init_attempted: false,
+            #[cfg(feature = "gamepad")]
+            active: None,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.uniform.connected != 0
+    }
+
+    pub fn is_pressed(&self, button: u32) -> bool {
+        self.uniform.buttons & button != 0
+    }
+
+    /// Drain gamepad events and refresh the uniform. Call once per frame. Returns true
+    /// if the uniform changed.
+    #[cfg(feature = "gamepad")]
+    pub fn update(&mut self) -> bool {
+        use gilrs::{Axis, Button, EventType};
+
+        if !self.init_attempted {
+            self.init_attempted = true;
+            match gilrs::Gilrs::new() {
+                Ok(gilrs) => self.gilrs = Some(gilrs),
+                Err(e) => log::warn!("Gamepad input unavailable: {e}"),
+            }
+        }
+        let Some(gilrs) = &mut self.gilrs else {
+            return false;
+        };
+
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::Connected if self.active.is_none() => {
+                    log::info!("Gamepad connected: {}", gilrs.gamepad(event.id).name());
+                    self.active = Some(event.id);
+                }
+                EventType::Disconnected if self.active == Some(event.id) => {
+                    log::info!("Gamepad disconnected");
+                    self.active = None;
+                }
+                _ => {}
+            }
+        }
+        // Pads present before startup don't always send Connected, and after an
+        // unplug another pad may still be there
+        if self.active.is_none() {
+            self.active = gilrs.gamepads().next().map(|(id, _)| id);
+        }
+
+        let previous = self.uniform;
+        self.uniform = GamepadUniform::default();
+        if let Some(gamepad) = self.active.and_then(|id| gilrs.connected_gamepad(id)) {
+            let deadzone = self.deadzone;
+            let axis = |axis| {
+                let value = gamepad.value(axis);
+                if value.abs() < deadzone { 0.0 } else { value }
+            };
+            let trigger = |button| gamepad.button_data(button).map_or(0.0, |data| data.value());
+            let buttons = [
+                (Button::South, Self::BUTTON_SOUTH),
+                (Button::East, Self::BUTTON_EAST),
+                (Button::West, Self::BUTTON_WEST),
+                (Button::North, Self::BUTTON_NORTH),
+                (Button::LeftTrigger, Self::BUTTON_LEFT_BUMPER),
+                (Button::RightTrigger, Self::BUTTON_RIGHT_BUMPER),
+                (Button::Select, Self::BUTTON_SELECT),
+                (Button::Start, Self::BUTTON_START),
+                (Button::Mode, Self::BUTTON_MODE),
+                (Button::LeftThumb, Self::BUTTON_LEFT_STICK),
+                (Button::RightThumb, Self::BUTTON_RIGHT_STICK),
+                (Button::DPadUp, Self::BUTTON_DPAD_UP),
+                (Button::DPadDown, Self::BUTTON_DPAD_DOWN),
+                (Button::DPadLeft, Self::BUTTON_DPAD_LEFT),
+                (Button::DPadRight, Self::BUTTON_DPAD_RIGHT),
+            ];
+
+            self.uniform = GamepadUniform {
+                axes: [
+                    axis(Axis::LeftStickX),
+                    axis(Axis::LeftStickY),
+                    axis(Axis::RightStickX),
+                    axis(Axis::RightStickY),
+                ],
+                triggers: [trigger(Button::LeftTrigger2), trigger(Button::RightTrigger2)],
+                buttons: buttons
+                    .iter()
+                    .filter(|(button, _)| gamepad.is_pressed(*button))
+                    .fold(0, |bits, (_, bit)| bits | bit),
+                connected: 1,
+            };
+        }
+        bytemuck::bytes_of(&previous) != bytemuck::bytes_of(&self.uniform)
+    }
+
+    /// Without the `gamepad` feature there is nothing to poll; the uniform stays zeroed.
+    #[cfg(not(feature = "gamepad"))]
+    pub fn update(&mut self) -> bool {
+        false
+    }
+}
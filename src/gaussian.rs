@@ -1,6 +1,8 @@
 use crate::compute::ComputeShader;
 use crate::radix_sort::RadixSorter;
-use crate::{Core, ExportSettings, ShaderHotReload};
+use crate::{Core, ExportManager, ExportSettings, ShaderHotReload};
+#[cfg(target_arch = "wasm32")]
+use crate::notify_stub as notify;
 use log::{error, info};
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -276,14 +278,30 @@ impl GaussianRenderer {
         let Some(new_module) = hot_reload.reload_compute_shader() else {
             return false;
         };
-        self.pipeline = Self::build_pipeline_from_module(
-            device,
-            &self.pipeline_layout,
-            self.texture_format,
-            new_module,
-        );
-        info!("Gaussian render shader hot reloaded");
-        true
+        let pipeline = crate::hot::capture_validation(device, || {
+            Self::build_pipeline_from_module(
+                device,
+                &self.pipeline_layout,
+                self.texture_format,
+                new_module,
+            )
+        });
+        match pipeline {
+            Ok(pipeline) => {
+                self.pipeline = pipeline;
+                info!("Gaussian render shader hot reloaded");
+                true
+            }
+            Err(e) => {
+                hot_reload.record_error(format!("Gaussian render pipeline: {e}"));
+                false
+            }
+        }
+    }
+
+    /// Error from the last failed hot reload, if any.
+    pub fn shader_error(&self) -> Option<&str> {
+        self.hot_reload.as_ref()?.last_error()
     }
 
     /// for rendering
@@ -397,7 +415,8 @@ impl GaussianCamera {
 pub struct GaussianExporter;
 
 impl GaussianExporter {
-    /// Capture a single frame of gaussian rendering to CPU memory.
+    /// Capture a single frame of gaussian rendering to CPU memory as RGBA8, whatever
+    /// the channel order of `texture_format`.
     ///
     /// preprocess → sort → render
     pub fn capture_frame(
@@ -504,6 +523,15 @@ impl GaussianExporter {
         for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
             data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
         }
+        // The renderer draws in the surface format, often BGRA; savers expect RGBA
+        if matches!(
+            texture_format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            for px in data.chunks_exact_mut(4) {
+                px.swap(0, 2);
+            }
+        }
 
         Ok(data)
     }
@@ -535,4 +563,30 @@ impl GaussianExporter {
             Err(e) => error!("Error capturing gaussian export frame {frame}: {e}"),
         }
     }
+
+    /// [`export_frame`](Self::export_frame) through `ExportManager::save_frame`, so MP4
+    /// and GIF exports get the frame too instead of only image sequences
+    pub fn export_frame_to(
+        core: &Core,
+        preprocess: &mut ComputeShader,
+        sorter: &GaussianSorter,
+        renderer: &GaussianRenderer,
+        render_bind_group: &wgpu::BindGroup,
+        count: u32,
+        frame: u32,
+        export_manager: &mut ExportManager,
+        texture_format: wgpu::TextureFormat,
+    ) {
+        match Self::capture_frame(
+            core, preprocess, sorter, renderer,
+            render_bind_group, count, export_manager.settings(), texture_format,
+        ) {
+            Ok(data) => {
+                if let Err(e) = export_manager.save_frame(data, frame) {
+                    error!("Error saving gaussian export frame {frame}: {e:?}");
+                }
+            }
+            Err(e) => error!("Error capturing gaussian export frame {frame}: {e}"),
+        }
+    }
 }
@@ -1136,6 +1136,7 @@ pub struct SynthesisUniform {
     pub master_volume: f32,
     pub waveform_type: u32,
     pub active_note_count: u32,
+    pub _padding: u32,
 }
 
 unsafe impl bytemuck::Pod for SynthesisUniform {}
@@ -1161,6 +1162,7 @@ impl SynthesisUniform {
             master_volume: 0.3,
             waveform_type: 0,
             active_note_count: 0,
+            _padding: 0,
         }
     }
 }
@@ -0,0 +1,205 @@
+use crate::gst::video::{AudioLevel, SpectrumData};
+use anyhow::{anyhow, Result};
+use gst::prelude::*;
+use gstreamer as gst;
+use log::{info, warn};
+use std::sync::{Arc, Mutex};
+
+/// Same analysis settings as the video and webcam pipelines, so the spectrum
+/// analyzer's shaping and smoothing behave identically for every source.
+const SPECTRUM_BANDS: u32 = 128;
+const SPECTRUM_THRESHOLD: i32 = -60;
+const ANALYSIS_INTERVAL_NS: u64 = 50_000_000;
+
+/// Live microphone input for audio-reactive shaders, without a webcam or media file.
+///
+/// Captures the default input device and runs it through GStreamer's spectrum and
+/// level analyzers:
+///   mic → audioconvert → audioresample → F32LE/44.1kHz/mono → spectrum → level → fakesink
+///
+/// Start it with [`RenderKit::start_mic_input`](crate::RenderKit::start_mic_input); the
+/// magnitudes then flow through `update_audio_spectrum` into `.with_audio_spectrum()`
+/// buffers just like video audio does.
+pub struct MicInput {
+    pipeline: gst::Pipeline,
+    spectrum_data: Arc<Mutex<SpectrumData>>,
+    audio_level: Arc<Mutex<AudioLevel>>,
+    device_name: String,
+}
+
+impl MicInput {
+    pub fn new() -> Result<Self> {
+        #[cfg(target_os = "linux")]
+        let mic_factory = "pulsesrc";
+        #[cfg(target_os = "macos")]
+        let mic_factory = "osxaudiosrc";
+        #[cfg(target_os = "windows")]
+        let mic_factory = "wasapisrc";
+
+        let pipeline = gst::Pipeline::new();
+
+        let mic = gst::ElementFactory::make(mic_factory)
+            .name("mic_input_source")
+            .build()
+            .map_err(|_| anyhow!("No microphone source available ({mic_factory})"))?;
+        let audioconvert = gst::ElementFactory::make("audioconvert")
+            .build()
+            .map_err(|_| anyhow!("Failed to create audioconvert element"))?;
+        let audioresample = gst::ElementFactory::make("audioresample")
+            .build()
+            .map_err(|_| anyhow!("Failed to create audioresample element"))?;
+        let caps_filter = gst::ElementFactory::make("capsfilter")
+            .build()
+            .map_err(|_| anyhow!("Failed to create capsfilter element"))?;
+        caps_filter.set_property(
+            "caps",
+            &gst::Caps::builder("audio/x-raw")
+                .field("format", "F32LE")
+                .field("rate", 44100i32)
+                .field("channels", 1i32)
+                .build(),
+        );
+        let spectrum = gst::ElementFactory::make("spectrum")
+            .name("mic_input_spectrum")
+            .property("bands", SPECTRUM_BANDS)
+            .property("threshold", SPECTRUM_THRESHOLD)
+            .property("post-messages", true)
+            .property("message-magnitude", true)
+            .property("message-phase", false)
+            .property("interval", ANALYSIS_INTERVAL_NS)
+            .build()
+            .map_err(|_| anyhow!("Failed to create spectrum element"))?;
+        let level = gst::ElementFactory::make("level")
+            .name("mic_input_level")
+            .property("interval", ANALYSIS_INTERVAL_NS)
+            .property("message", true)
+            .property("post-messages", true)
+            .build()
+            .map_err(|_| anyhow!("Failed to create level element"))?;
+        let sink = gst::ElementFactory::make("fakesink")
+            .property("sync", false)
+            .property("async", false)
+            .build()
+            .map_err(|_| anyhow!("Failed to create fakesink element"))?;
+
+        let elements = [
+            &mic,
+            &audioconvert,
+            &audioresample,
+            &caps_filter,
+            &spectrum,
+            &level,
+            &sink,
+        ];
+        pipeline
+            .add_many(elements)
+            .map_err(|_| anyhow!("Failed to add mic elements to pipeline"))?;
+        gst::Element::link_many(elements).map_err(|_| anyhow!("Failed to link mic pipeline"))?;
+
+        pipeline
+            .set_state(gst::State::Playing)
+            .map_err(|e| anyhow!("Failed to start mic capture: {:?}", e))?;
+        info!("Microphone input started ({mic_factory})");
+
+        Ok(Self {
+            pipeline,
+            spectrum_data: Arc::new(Mutex::new(SpectrumData::default())),
+            audio_level: Arc::new(Mutex::new(AudioLevel::default())),
+            device_name: mic_factory.to_string(),
+        })
+    }
+
+    /// Current spectrum magnitudes (dB) from the mic.
+    pub fn spectrum_data(&self) -> SpectrumData {
+        self.spectrum_data.lock().map(|d| d.clone()).unwrap_or_default()
+    }
+
+    /// Current RMS/peak from the mic.
+    pub fn audio_level(&self) -> AudioLevel {
+        self.audio_level.lock().map(|l| l.clone()).unwrap_or_default()
+    }
+
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
+    /// Drain spectrum/level messages from the bus. Call once per frame before
+    /// reading `spectrum_data`. Returns true if anything changed.
+    pub fn poll_audio_messages(&mut self) -> bool {
+        let mut updated = false;
+        let Some(bus) = self.pipeline.bus() else { return false };
+
+        while let Some(message) = bus.pop() {
+            match message.view() {
+                gst::MessageView::Element(element) => {
+                    let Some(structure) = element.structure() else { continue };
+                    if structure.name() == "spectrum" {
+                        updated |= self.store_spectrum(structure);
+                    } else if structure.name() == "level" {
+                        updated |= self.store_level(structure);
+                    }
+                }
+                gst::MessageView::Error(err) => {
+                    warn!("Microphone input error: {} ({:?})", err.error(), err.debug());
+                }
+                _ => {}
+            }
+        }
+        updated
+    }
+
+    fn store_spectrum(&self, structure: &gst::StructureRef) -> bool {
+        let mut magnitudes: Vec<f32> = Vec::with_capacity(SPECTRUM_BANDS as usize);
+        let struct_str = structure.to_string();
+        if let Some(start_idx) = struct_str.find("magnitude=(float){") {
+            if let Some(end_idx) = struct_str[start_idx..].find('}') {
+                let magnitude_str =
+                    &struct_str[start_idx + "magnitude=(float){".len()..start_idx + end_idx];
+                magnitudes.extend(
+                    magnitude_str
+                        .split(',')
+                        .filter_map(|value| value.trim().parse::<f32>().ok()),
+                );
+            }
+        }
+        if magnitudes.is_empty() {
+            return false;
+        }
+        if let Ok(mut data) = self.spectrum_data.lock() {
+            *data = SpectrumData {
+                bands: magnitudes.len(),
+                magnitudes,
+                phases: None,
+                timestamp: structure.get("timestamp").ok(),
+            };
+        }
+        true
+    }
+
+    fn store_level(&self, structure: &gst::StructureRef) -> bool {
+        let first_db = |field: &str| {
+            structure
+                .get::<gst::glib::ValueArray>(field)
+                .ok()
+                .and_then(|list| list.iter().find_map(|val| val.get::<f64>().ok()))
+        };
+        let (Some(rms_db), Some(peak_db)) = (first_db("rms"), first_db("peak")) else {
+            return false;
+        };
+        if let Ok(mut level) = self.audio_level.lock() {
+            *level = AudioLevel {
+                rms: 10.0_f64.powf(rms_db / 20.0),
+                rms_db,
+                peak: 10.0_f64.powf(peak_db / 20.0),
+            };
+        }
+        true
+    }
+}
+
+impl Drop for MicInput {
+    fn drop(&mut self) {
+        info!("Stopping microphone input");
+        let _ = self.pipeline.set_state(gst::State::Null);
+    }
+}
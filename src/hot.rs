@@ -1,11 +1,43 @@
+use crate::{PreprocessOptions, PreprocessedShader};
 use log::{error, warn};
+#[cfg(target_arch = "wasm32")]
+use crate::notify_stub as notify;
 use notify::{Event, EventKind, RecursiveMode, Watcher};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use web_time::{Duration, Instant};
+
+/// Failure to load a shader from disk at runtime.
+#[derive(Debug, thiserror::Error)]
+pub enum ShaderLoadError {
+    /// The shader file couldn't be read.
+    #[error("Failed to read shader {path}: {1}", path = .0.display())]
+    Io(PathBuf, #[source] std::io::Error),
+    /// WGSL parsing/validation or pipeline creation failed.
+    #[error("Shader compilation failed: {0}")]
+    Compile(String),
+}
+
+pub(crate) fn read_shader(path: &Path) -> Result<String, ShaderLoadError> {
+    fs::read_to_string(path).map_err(|e| ShaderLoadError::Io(path.to_path_buf(), e))
+}
+
+/// Run `create` inside a validation error scope, turning shader/pipeline errors into
+/// `ShaderLoadError::Compile` instead of the device's uncaptured-error panic
+pub(crate) fn capture_validation<T>(
+    device: &wgpu::Device,
+    create: impl FnOnce() -> T,
+) -> Result<T, ShaderLoadError> {
+    let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = create();
+    match pollster::block_on(scope.pop()) {
+        Some(e) => Err(ShaderLoadError::Compile(e.to_string())),
+        None => Ok(value),
+    }
+}
 
 pub enum ShaderType {
     RenderPair, // Vertex + Fragment
@@ -21,14 +53,19 @@ pub struct ShaderHotReload {
     last_vs_content: String,
     last_fs_content: String,
     last_compute_content: Option<String>,
-    #[allow(dead_code)]
     watcher: notify::RecommendedWatcher,
+    /// Directories already covered by the watcher, canonicalized
+    watched_dirs: Vec<PathBuf>,
     rx: Receiver<notify::Event>,
     _watcher_tx: std::sync::mpsc::Sender<notify::Event>,
     last_update_times: HashMap<PathBuf, Instant>,
     debounce_duration: Duration,
     shader_type: ShaderType,
     entry_point: Option<String>,
+    preprocess_options: PreprocessOptions,
+    last_error: Option<String>,
+    /// `@compute` entry points of the last successfully reloaded compute shader
+    compute_entry_points: Vec<String>,
 }
 
 impl ShaderHotReload {
@@ -58,11 +95,13 @@ impl ShaderHotReload {
             .map(|path| Self::normalize_path(path))
             .collect();
 
+        let mut watched_dirs = Vec::new();
         for path in &normalized_paths {
             if let Some(parent) = path.parent() {
                 if !parent.exists() {
                     continue;
                 }
+                watched_dirs.push(parent.canonicalize().unwrap_or_else(|_| parent.to_path_buf()));
 
                 if let Err(e) = watcher.watch(parent, RecursiveMode::Recursive) {
                     warn!(
@@ -79,10 +118,12 @@ impl ShaderHotReload {
             }
         }
 
-        let last_vs_content = fs::read_to_string(&normalized_paths[0]).unwrap_or_default();
-        let last_fs_content = fs::read_to_string(&normalized_paths[1]).unwrap_or_default();
+        let vs_shader = PreprocessedShader::load(&normalized_paths[0]).ok();
+        let fs_shader = PreprocessedShader::load(&normalized_paths[1]).ok();
+        let last_vs_content = vs_shader.as_ref().map(|s| s.source.clone()).unwrap_or_default();
+        let last_fs_content = fs_shader.as_ref().map(|s| s.source.clone()).unwrap_or_default();
 
-        Ok(Self {
+        let mut hot_reload = Self {
             vs_module,
             fs_module,
             compute_module: None,
@@ -92,13 +133,21 @@ impl ShaderHotReload {
             last_fs_content,
             last_compute_content: None,
             watcher,
+            watched_dirs,
             rx,
             _watcher_tx: watcher_tx,
             last_update_times: HashMap::new(),
             debounce_duration: Duration::from_millis(100),
             shader_type: ShaderType::RenderPair,
             entry_point: None,
-        })
+            preprocess_options: PreprocessOptions::default(),
+            last_error: None,
+            compute_entry_points: Vec::new(),
+        };
+        for shader in vs_shader.iter().chain(fs_shader.iter()) {
+            hot_reload.watch_includes(shader);
+        }
+        Ok(hot_reload)
     }
     pub fn new_compute(
         device: Arc<wgpu::Device>,
@@ -123,9 +172,11 @@ impl ShaderHotReload {
         let normalized_path = Self::normalize_path(&shader_path);
         let shader_paths = vec![normalized_path.clone()];
 
+        let mut watched_dirs = Vec::new();
         if let Some(parent) = normalized_path.parent() {
 
             if parent.exists() {
+                watched_dirs.push(parent.canonicalize().unwrap_or_else(|_| parent.to_path_buf()));
                 if let Err(e) = watcher.watch(parent, RecursiveMode::Recursive) {
                     warn!(
                         "Could not watch shader directory {}: {}",
@@ -141,13 +192,17 @@ impl ShaderHotReload {
             }
         }
 
-        let last_compute_content = fs::read_to_string(&normalized_path).unwrap_or_default();
+        let compute_shader = PreprocessedShader::load(&normalized_path).ok();
+        let last_compute_content = compute_shader
+            .as_ref()
+            .map(|s| s.source.clone())
+            .unwrap_or_default();
         let dummy_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Dummy Shader Module"),
             source: wgpu::ShaderSource::Wgsl("".into()),
         });
 
-        Ok(Self {
+        let mut hot_reload = Self {
             vs_module: dummy_shader.clone(),
             fs_module: dummy_shader,
             compute_module: Some(compute_module),
@@ -157,13 +212,39 @@ impl ShaderHotReload {
             last_fs_content: String::new(),
             last_compute_content: Some(last_compute_content),
             watcher,
+            watched_dirs,
             rx,
             _watcher_tx: watcher_tx,
             last_update_times: HashMap::new(),
             debounce_duration: Duration::from_millis(100),
             shader_type: ShaderType::Compute,
             entry_point: Some(entry_point.to_string()),
-        })
+            preprocess_options: PreprocessOptions::default(),
+            last_error: None,
+            compute_entry_points: Vec::new(),
+        };
+        if let Some(shader) = &compute_shader {
+            hot_reload.watch_includes(shader);
+        }
+        Ok(hot_reload)
+    }
+
+    /// Watch directories of included files that the shader directories don't already cover,
+    /// so editing a shared include triggers a reload too
+    fn watch_includes(&mut self, shader: &PreprocessedShader) {
+        for file in shader.included_files() {
+            let Some(dir) = file.parent() else {
+                continue;
+            };
+            let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+            if self.watched_dirs.iter().any(|watched| dir.starts_with(watched)) {
+                continue;
+            }
+            match self.watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                Ok(()) => self.watched_dirs.push(dir),
+                Err(e) => warn!("Could not watch shader include directory {}: {e}", dir.display()),
+            }
+        }
     }
 
     fn normalize_path(path: &Path) -> PathBuf {
@@ -210,33 +291,37 @@ impl ShaderHotReload {
             return None;
         }
 
-        let vs_content = match fs::read_to_string(&self.shader_paths[0]) {
-            Ok(content) => content,
+        let vs_shader = match PreprocessedShader::load(&self.shader_paths[0]) {
+            Ok(shader) => shader,
             Err(e) => {
-                error!("Failed to read vertex shader: {e}");
+                self.record_error(format!("Vertex shader: {e}"));
                 return None;
             }
         };
 
-        let fs_content = match fs::read_to_string(&self.shader_paths[1]) {
-            Ok(content) => content,
+        let fs_shader = match PreprocessedShader::load(&self.shader_paths[1]) {
+            Ok(shader) => shader,
             Err(e) => {
-                error!("Failed to read fragment shader: {e}");
+                self.record_error(format!("Fragment shader: {e}"));
                 return None;
             }
         };
 
-        if vs_content == self.last_vs_content && fs_content == self.last_fs_content {
+        self.watch_includes(&vs_shader);
+        self.watch_includes(&fs_shader);
+
+        if vs_shader.source == self.last_vs_content && fs_shader.source == self.last_fs_content {
             return None;
         }
 
-        let new_vs = self.create_shader_module(&vs_content, "Vertex Shader")?;
+        let new_vs = self.create_shader_module(&vs_shader, "Vertex Shader")?;
 
-        let new_fs = self.create_shader_module(&fs_content, "Fragment Shader")?;
-        self.last_vs_content = vs_content;
-        self.last_fs_content = fs_content;
+        let new_fs = self.create_shader_module(&fs_shader, "Fragment Shader")?;
+        self.last_vs_content = vs_shader.source;
+        self.last_fs_content = fs_shader.source;
         self.vs_module = new_vs;
         self.fs_module = new_fs;
+        self.last_error = None;
 
         Some((&self.vs_module, &self.fs_module))
     }
@@ -261,51 +346,71 @@ impl ShaderHotReload {
             return None;
         }
 
-        let compute_content = match fs::read_to_string(&self.shader_paths[0]) {
-            Ok(content) => content,
+        let compute_shader = match PreprocessedShader::load_with(&self.shader_paths[0], self.preprocess_options) {
+            Ok(shader) => shader,
             Err(e) => {
-                error!("Failed to read compute shader: {e}");
+                self.record_error(format!("Compute shader: {e}"));
                 return None;
             }
         };
 
+        self.watch_includes(&compute_shader);
+
         if let Some(ref last_content) = self.last_compute_content {
-            if compute_content == *last_content {
+            if compute_shader.source == *last_content {
                 return None;
             }
         }
 
-        let new_compute = self.create_shader_module(&compute_content, "Compute Shader")?;
+        let new_compute = self.create_shader_module(&compute_shader, "Compute Shader")?;
 
-        self.last_compute_content = Some(compute_content);
+        self.compute_entry_points = compute_entry_points(&compute_shader.source);
+        self.last_compute_content = Some(compute_shader.source);
         self.compute_module = Some(new_compute);
+        self.last_error = None;
 
         self.compute_module.as_ref()
     }
 
-    fn create_shader_module(&self, source: &str, label: &str) -> Option<wgpu::ShaderModule> {
+    /// `shader` maps error locations back to the original files
+    fn create_shader_module(
+        &mut self,
+        shader: &PreprocessedShader,
+        label: &str,
+    ) -> Option<wgpu::ShaderModule> {
         let desc = wgpu::ShaderModuleDescriptor {
             label: Some(label),
-            source: wgpu::ShaderSource::Wgsl(source.into()),
+            source: wgpu::ShaderSource::Wgsl(shader.source.as_str().into()),
         };
 
-        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            self.device.create_shader_module(desc)
-        }));
-
-        match result {
+        match capture_validation(&self.device, || self.device.create_shader_module(desc)) {
             Ok(module) => Some(module),
             Err(e) => {
-                if let Some(error_msg) = e.downcast_ref::<String>() {
-                    error!("Shader compilation error in {label}: {error_msg}");
-                } else {
-                    error!("Shader compilation error in {label}");
-                }
+                self.record_error(format!("{label}: {}", shader.annotate_error(&e.to_string())));
                 None
             }
         }
     }
 
+    /// The error from the latest reload attempt, if it failed. The previous shader
+    /// stays in use until the file compiles again, so this is meant for an on-screen
+    /// banner (see `ShaderControls::render_shader_error`).
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Record a reload failure that happened after the module compiled (pipeline creation)
+    pub(crate) fn record_error(&mut self, message: String) {
+        error!("{message}");
+        self.last_error = Some(message);
+    }
+
+    /// Preprocess reloaded compute shaders with `options`, so they get the same treatment
+    /// as the one the pipeline was first built from
+    pub fn set_preprocess_options(&mut self, options: PreprocessOptions) {
+        self.preprocess_options = options;
+    }
+
     pub fn entry_point(&self) -> Option<&str> {
         self.entry_point.as_deref()
     }
@@ -313,4 +418,91 @@ impl ShaderHotReload {
     pub fn get_compute_module(&self) -> Option<&wgpu::ShaderModule> {
         self.compute_module.as_ref()
     }
+
+    /// Names of the `@compute` functions in the most recently reloaded module, in
+    /// source order. Empty until the first reload.
+    pub fn compute_entry_points(&self) -> &[String] {
+        &self.compute_entry_points
+    }
+}
+
+/// `@compute` entry point names declared in a WGSL source that already compiled
+fn compute_entry_points(source: &str) -> Vec<String> {
+    match wgpu::naga::front::wgsl::parse_str(source) {
+        Ok(module) => module
+            .entry_points
+            .iter()
+            .filter(|entry_point| entry_point.stage == wgpu::naga::ShaderStage::Compute)
+            .map(|entry_point| entry_point.name.clone())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Watches a single file, e.g. a media file sampled by a shader, and reports when it
+/// changed on disk and then stayed untouched for the debounce time, so a file that is
+/// still being written isn't read half-finished.
+pub(crate) struct FileWatch {
+    path: PathBuf,
+    _watcher: notify::RecommendedWatcher,
+    rx: Receiver<notify::Event>,
+    changed_at: Option<Instant>,
+}
+
+impl FileWatch {
+    const DEBOUNCE: Duration = Duration::from_millis(200);
+
+    pub(crate) fn new(path: &Path) -> notify::Result<Self> {
+        let path = Self::absolute(path);
+        let (tx, rx) = channel();
+        let mut watcher = notify::recommended_watcher(move |res: Result<Event, _>| {
+            if let Ok(event) = res {
+                if matches!(
+                    event.kind,
+                    EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
+                ) {
+                    tx.send(event).unwrap_or_default();
+                }
+            }
+        })?;
+        // Watch the folder rather than the file: editors often save by replacing it
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        Ok(Self {
+            path,
+            _watcher: watcher,
+            rx,
+            changed_at: None,
+        })
+    }
+
+    fn absolute(path: &Path) -> PathBuf {
+        fs::canonicalize(path)
+            .or_else(|_| std::path::absolute(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// True once per settled change of the file
+    pub(crate) fn poll(&mut self) -> bool {
+        while let Ok(event) = self.rx.try_recv() {
+            if event
+                .paths
+                .iter()
+                .any(|path| Self::absolute(path) == self.path)
+            {
+                self.changed_at = Some(Instant::now());
+            }
+        }
+        match self.changed_at {
+            Some(changed_at) if changed_at.elapsed() >= Self::DEBOUNCE && self.path.exists() => {
+                self.changed_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
 }
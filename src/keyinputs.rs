@@ -1,10 +1,14 @@
 use winit::event::{ElementState, KeyEvent};
-use winit::keyboard::Key;
+use winit::keyboard::{Key, NamedKey};
 use winit::window::Window;
 
 pub struct KeyInputHandler {
     is_fullscreen: bool,
     pub show_ui: bool,
+    /// Set by F12; `RenderKit::end_frame` saves the next frame to a PNG and clears it
+    pub screenshot_requested: bool,
+    /// Set by C; like `screenshot_requested`, but the frame goes to the clipboard
+    pub clipboard_requested: bool,
 }
 impl Default for KeyInputHandler {
     fn default() -> Self {
@@ -17,16 +21,26 @@ impl KeyInputHandler {
         Self {
             is_fullscreen: false,
             show_ui: true,
+            screenshot_requested: false,
+            clipboard_requested: false,
         }
     }
     pub fn handle_keyboard_input(&mut self, window: &Window, event: &KeyEvent) -> bool {
         if event.state == ElementState::Pressed && !event.repeat {
+            if event.logical_key == Key::Named(NamedKey::F12) {
+                self.screenshot_requested = true;
+                return true;
+            }
             if let Key::Character(ch) = &event.logical_key {
                 match ch.as_str() {
                     "f" | "F" => {
                         self.toggle_fullscreen(window);
                         return true;
                     }
+                    "c" | "C" => {
+                        self.clipboard_requested = true;
+                        return true;
+                    }
                     "h" | "H" => {
                         self.show_ui = !self.show_ui;
                         return true;
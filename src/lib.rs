@@ -1,5 +1,6 @@
-use log::debug;
-use std::sync::Arc;
+use log::{debug, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use winit::window::Window;
 
 pub use anyhow;
@@ -10,8 +11,12 @@ pub use wgpu;
 pub use winit;
 
 pub use bytemuck::{Pod, Zeroable};
+pub use cuneus_derive::UniformProvider;
 pub use winit::event::WindowEvent;
 
+// The derive macros name this crate as `::cuneus`, including when used inside it
+extern crate self as cuneus;
+
 /// Represents surface acquisition failures during rendering.
 #[derive(Debug)]
 pub enum SurfaceError {
@@ -38,39 +43,101 @@ impl std::fmt::Display for SurfaceError {
 
 impl std::error::Error for SurfaceError {}
 
+/// Error type of the public APIs: device setup, shader loading, exports and media.
+/// `?` converts the subsystem errors into it, so app code can return one type from
+/// `ShaderManager::init_result` or `main`.
+#[derive(Debug, thiserror::Error)]
+pub enum CuneusError {
+    #[error("No suitable GPU adapter found")]
+    NoAdapter,
+    #[error("Failed to create the GPU device: {0}")]
+    Device(#[from] wgpu::RequestDeviceError),
+    /// A shader couldn't be read or failed to compile
+    #[error(transparent)]
+    Shader(#[from] ShaderLoadError),
+    #[error(transparent)]
+    Surface(#[from] SurfaceError),
+    #[error("Export failed: {0}")]
+    Export(#[from] ExportError),
+    /// Loading or controlling an image, video, webcam or microphone input
+    #[error("Media error: {0:#}")]
+    Media(anyhow::Error),
+    /// Saving or loading a params preset, e.g. a layout that doesn't match the struct
+    #[error("Preset error: {0:#}")]
+    Preset(anyhow::Error),
+    /// Reading or writing a saved [`Session`]
+    #[error("Session error: {0:#}")]
+    Session(anyhow::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Anything else, e.g. a missing asset in `init_result`
+    #[error("{0:#}")]
+    Other(#[from] anyhow::Error),
+}
+
+pub mod anim;
 mod app;
+mod beat;
+pub mod camera;
+mod color;
 pub mod compute;
 mod controls;
+mod envmap;
 mod export;
+mod feedback;
 mod font;
+mod fragment;
 mod fps;
+mod gamepad;
 #[cfg(feature = "media")]
 pub mod gst;
 pub mod hdri;
 mod hot;
 mod keyinputs;
 mod mouse;
+#[cfg(target_arch = "wasm32")]
+mod notify_stub;
+mod preprocess;
+mod preset;
+mod reflect;
 pub mod gaussian;
 pub mod ply;
 pub mod radix_sort;
 mod renderer;
 mod renderkit;
+mod session;
 mod shader;
+mod shader_ui;
 mod spectrum;
 mod texture;
+mod tonemap;
 mod uniforms;
 pub use app::*;
+pub use beat::{BeatDetector, BeatUniform};
 pub use controls::{ControlsRequest, ShaderControls};
-pub use export::{save_frame, ExportError, ExportManager, ExportSettings, ExportUiState};
-pub use font::{CharInfo, FontSystem, FontUniforms};
+pub use export::{
+    save_frame, ExportError, ExportFormat, ExportManager, ExportProgress, ExportSettings,
+    ExportTile, ExportUiState,
+};
+pub use envmap::{EnvironmentMap, ENVMAP_WGSL};
+pub use feedback::FeedbackChain;
+pub use shader_ui::{ShaderUi, UiField, UiSpec};
+pub use tonemap::{ToneMapCurve, ToneMapParams, TONEMAP_WGSL};
+pub use fragment::{FragmentPass, FragmentPipeline};
+pub use font::{CharInfo, FontSource, FontSystem, FontUniforms, GlyphInstance, TEXT_WGSL};
+pub use gamepad::{GamepadTracker, GamepadUniform};
 pub use hdri::*;
-pub use hot::ShaderHotReload;
+pub use hot::{ShaderHotReload, ShaderLoadError};
 pub use keyinputs::KeyInputHandler;
 pub use mouse::*;
+pub use preprocess::{PreprocessOptions, PreprocessedShader};
+pub use reflect::{check_layout_order, ReflectedBinding, ShaderLayout};
+pub use preset::{load_preset, save_preset, PresetManager};
 pub use gaussian::*;
 pub use ply::*;
 pub use renderer::*;
 pub use renderkit::*;
+pub use session::{Session, NO_RESTORE_FLAG};
 pub use shader::*;
 pub use texture::*;
 pub use uniforms::*;
@@ -81,6 +148,8 @@ pub mod audio {
         AudioDataProvider, AudioSynthManager, AudioSynthUniform, AudioWaveform, EnvelopeConfig,
         MusicalNote, PcmStreamManager, SynthesisManager, SynthesisUniform, SynthesisWaveform,
     };
+    pub use crate::gst::mic::MicInput;
+    pub use crate::beat::{BeatDetector, BeatUniform};
 }
 
 pub mod prelude {
@@ -88,7 +157,7 @@ pub mod prelude {
         compute::ComputeShader, compute::ComputeShaderBuilder, compute::MultiPassManager,
         compute::PassDescription, compute::StorageBufferSpec,
         compute::COMPUTE_TEXTURE_FORMAT_RGBA16, compute::COMPUTE_TEXTURE_FORMAT_RGBA8,
-        save_frame, CharInfo, ControlsRequest, Core, ExportManager, FontSystem,
+        save_frame, CharInfo, ControlsRequest, Core, ExportFormat, ExportManager, FontSystem,
         FontUniforms, KeyInputHandler, RenderKit, Renderer, ShaderApp, ShaderControls,
         FrameContext, ShaderHotReload, ShaderManager, TextureManager, UniformBinding,
         UniformProvider,
@@ -125,7 +194,12 @@ pub mod prelude {
 /// a macro for defining GPU uniform parameter structs.
 ///
 /// Automatically adds `#[repr(C)]`, `Copy`, `Clone`, `Debug`, `Pod`, `Zeroable`,
-/// implements `UniformProvider`, and asserts 16-byte alignment at compile time.
+/// derives [`UniformProvider`] and adds `FIELD_NAMES` and `FIELD_OFFSETS` consts. The derive asserts at
+/// compile time that the size is a multiple of 16 bytes and every field sits where
+/// WGSL's uniform layout expects it (vec2 on 8 bytes, vec3/vec4/arrays on 16).
+///
+/// Fields marked `#[ui(...)]` also get a widget in the generated
+/// [`ShaderUi::render_ui`]; see [`ShaderUi`] for the options.
 ///
 /// ```rust,no_run
 /// cuneus::uniform_params! {
@@ -141,11 +215,11 @@ macro_rules! uniform_params {
     (
         $(#[$meta:meta])*
         $vis:vis struct $name:ident {
-            $($field_vis:vis $field:ident : $ty:ty),* $(,)?
+            $($(#[ui $(($($ui:tt)*))?])? $field_vis:vis $field:ident : $ty:ty),* $(,)?
         }
     ) => {
         #[repr(C)]
-        #[derive(Copy, Clone, Debug)]
+        #[derive(Copy, Clone, Debug, $crate::UniformProvider)]
         $(#[$meta])*
         $vis struct $name {
             $($field_vis $field : $ty),*
@@ -164,22 +238,28 @@ macro_rules! uniform_params {
         unsafe impl $crate::bytemuck::Zeroable for $name {}
         unsafe impl $crate::bytemuck::Pod for $name {}
 
-        impl $crate::UniformProvider for $name {
-            fn as_bytes(&self) -> &[u8] {
-                $crate::bytemuck::bytes_of(self)
-            }
+        impl $name {
+            /// Field names in declaration order, e.g. for preset manifests
+            #[allow(dead_code)]
+            pub const FIELD_NAMES: &'static [&'static str] = &[$(stringify!($field)),*];
+            /// `(name, byte offset)` of each field, e.g. for `Timeline::apply_to_params`
+            #[allow(dead_code)]
+            pub const FIELD_OFFSETS: &'static [(&'static str, usize)] =
+                &[$((stringify!($field), ::core::mem::offset_of!($name, $field))),*];
         }
 
-        const _: () = {
-            assert!(
-                ::core::mem::size_of::<$name>() % 16 == 0,
-                concat!(
-                    "uniform_params!: struct `",
-                    stringify!($name),
-                    "` size must be a multiple of 16 bytes (add padding fields)"
-                )
-            );
-        };
+        impl $crate::ShaderUi for $name {
+            #[allow(unused_variables, unused_mut)]
+            fn render_ui(&mut self, ui: &mut $crate::egui::Ui) -> bool {
+                let mut changed = false;
+                $($(
+                    let mut spec = $crate::UiSpec::new(stringify!($field));
+                    $($crate::__ui_spec!(spec $($ui)*);)?
+                    changed |= $crate::UiField::ui_field(&mut self.$field, ui, &spec);
+                )?)*
+                changed
+            }
+        }
     };
 }
 
@@ -218,55 +298,121 @@ macro_rules! compute_shader {
     }};
 }
 
+/// Which backends and GPU `Core::new_with_adapter` may pick. `None` keeps the default:
+/// all backends, and the discrete GPU when one is present.
+///
+/// The `WGPU_BACKEND` (e.g. `vulkan`, `metal,gl`) and `WGPU_POWER_PREF` (`low`/`high`)
+/// environment variables override these, so a build can be pointed at another GPU
+/// without recompiling.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AdapterSelection {
+    pub backends: Option<wgpu::Backends>,
+    pub power_preference: Option<wgpu::PowerPreference>,
+}
+
+impl AdapterSelection {
+    fn with_env(self) -> Self {
+        Self {
+            backends: wgpu::Backends::from_env().or(self.backends),
+            power_preference: wgpu::PowerPreference::from_env().or(self.power_preference),
+        }
+    }
+}
+
 pub struct Core {
     pub surface: wgpu::Surface<'static>,
+    /// Kept for capability queries after startup (sample counts, present modes)
+    pub adapter: wgpu::Adapter,
     pub device: Arc<wgpu::Device>,
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
     pub size: winit::dpi::PhysicalSize<u32>,
     pub window: Window,
+    requested_present_mode: Mutex<Option<wgpu::PresentMode>>,
+    target_fps: Mutex<Option<f32>>,
+    frame_limit_suspended: AtomicBool,
 }
 impl Core {
     pub async fn new(window: Window) -> Self {
+        Self::new_with_adapter(window, AdapterSelection::default()).await
+    }
+
+    /// Like [`new`](Self::new), restricted to the given backends/power preference.
+    /// Falls back to the default choice with a warning when nothing matches.
+    pub async fn new_with_adapter(window: Window, selection: AdapterSelection) -> Self {
+        Self::try_new_with_adapter(window, selection)
+            .await
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// [`new_with_adapter`](Self::new_with_adapter) that returns
+    /// [`CuneusError::NoAdapter`] or [`CuneusError::Device`] instead of panicking
+    pub async fn try_new_with_adapter(window: Window, selection: AdapterSelection) -> Result<Self, CuneusError> {
         let size = window.inner_size();
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
         let window_box = Box::new(window);
         let window_ptr = Box::into_raw(window_box);
-        // SAFETY: window_ptr is valid as we just created it
-        let surface = unsafe { instance.create_surface(&*window_ptr) }.unwrap();
-        let adapters = instance.enumerate_adapters(wgpu::Backends::all()).await;
-        let power_preference = adapters
-            .iter()
-            .find(|p| p.get_info().device_type == wgpu::DeviceType::DiscreteGpu)
-            .map(|_| wgpu::PowerPreference::HighPerformance)
-            .unwrap_or(wgpu::PowerPreference::default());
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-        let (device, queue) = adapter
+        let selection = selection.with_env();
+        // SAFETY: window_ptr is valid as we just created it, and is only freed at the end
+        let found = match Self::request_adapter(unsafe { &*window_ptr }, selection).await {
+            Some(found) => Some(found),
+            None => {
+                warn!("No adapter matches {selection:?}, falling back to the default");
+                Self::request_adapter(unsafe { &*window_ptr }, AdapterSelection::default()).await
+            }
+        };
+        let Some((surface, adapter)) = found else {
+            // SAFETY: no surface borrows the window any more
+            drop(unsafe { Box::from_raw(window_ptr) });
+            return Err(CuneusError::NoAdapter);
+        };
+        #[allow(unused_mut)]
+        let (mut required_features, mut required_limits) =
+            (wgpu::Features::empty(), wgpu::Limits::default());
+        // Opt-in pass profiling (ComputeShaderBuilder::with_timestamps) needs timestamp queries
+        required_features |= adapter.features() & wgpu::Features::TIMESTAMP_QUERY;
+        // RadixSorter ranks keys with subgroup ballots when they're available
+        required_features |= adapter.features() & wgpu::Features::SUBGROUP;
+        // MSAA counts other than 1 and 4 (RenderKit::set_sample_count) are adapter specific
+        required_features |= adapter.features() & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+        // Push constants are optional: without adapter support, ComputeShader emulates them with a uniform
+        #[cfg(feature = "push-constants")]
+        if adapter.features().contains(wgpu::Features::IMMEDIATES) {
+            required_features |= wgpu::Features::IMMEDIATES;
+            required_limits.max_immediate_size = adapter.limits().max_immediate_size;
+        }
+        let device = adapter
             .request_device(&wgpu::DeviceDescriptor {
                 label: None,
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
+                required_features,
+                required_limits,
                 memory_hints: Default::default(),
                 experimental_features: Default::default(),
                 trace: wgpu::Trace::default(),
             })
-            .await
-            .unwrap();
+            .await;
+        let (device, queue) = match device {
+            Ok(device) => device,
+            Err(e) => {
+                drop(surface);
+                // SAFETY: the surface borrowing the window was just dropped
+                drop(unsafe { Box::from_raw(window_ptr) });
+                return Err(e.into());
+            }
+        };
         let device = Arc::new(device);
         let surface_caps = surface.get_capabilities(&adapter);
+        // Prefer the capture format, then any sRGB format; the display blit encodes by
+        // hand when the platform only offers linear ones
         let surface_format = surface_caps
             .formats
             .iter()
             .copied()
-            .find(|f| f.is_srgb() && *f == CAPTURE_FORMAT)
+            .find(|f| *f == CAPTURE_FORMAT)
+            .or_else(|| surface_caps.formats.iter().copied().find(|f| f.is_srgb()))
             .unwrap_or(surface_caps.formats[0]);
+        if !surface_format.is_srgb() {
+            info!("No sRGB surface format available, using {surface_format:?}");
+        }
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
@@ -280,18 +426,59 @@ impl Core {
         surface.configure(&device, &config);
         // SAFETY: window_ptr is still valid and we're taking back ownership
         let window = unsafe { *Box::from_raw(window_ptr) };
-        Self {
+        Ok(Self {
             surface,
+            adapter,
             device,
             queue,
             config,
             size,
             window,
-        }
+            requested_present_mode: Mutex::new(None),
+            target_fps: Mutex::new(None),
+            frame_limit_suspended: AtomicBool::new(false),
+        })
+    }
+    async fn request_adapter(
+        window: &'static Window,
+        selection: AdapterSelection,
+    ) -> Option<(wgpu::Surface<'static>, wgpu::Adapter)> {
+        let backends = selection.backends.unwrap_or(wgpu::Backends::all());
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends,
+            ..wgpu::InstanceDescriptor::new_without_display_handle()
+        });
+        let surface = instance.create_surface(window).ok()?;
+        let power_preference = match selection.power_preference {
+            Some(preference) => preference,
+            None => {
+                let adapters = instance.enumerate_adapters(backends).await;
+                adapters
+                    .iter()
+                    .find(|p| p.get_info().device_type == wgpu::DeviceType::DiscreteGpu)
+                    .map(|_| wgpu::PowerPreference::HighPerformance)
+                    .unwrap_or(wgpu::PowerPreference::default())
+            }
+        };
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference,
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: false,
+            })
+            .await
+            .ok()?;
+        Some((surface, adapter))
     }
     pub fn window(&self) -> &Window {
         &self.window
     }
+
+    /// Whether the surface format encodes to sRGB on write. When it doesn't, the built-in
+    /// display blit applies the sRGB curve itself, so shaders always output linear color.
+    pub fn is_srgb_surface(&self) -> bool {
+        self.config.format.is_srgb()
+    }
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         debug!("Core resize: {new_size:?}");
         if new_size.width > 0 && new_size.height > 0 {
@@ -303,6 +490,135 @@ impl Core {
         }
     }
 
+    /// Reconfigure the surface with `mode` (e.g. `Immediate` for uncapped frames).
+    /// Modes the adapter doesn't offer fall back to the closest supported one, ending at
+    /// `Fifo` which is always available. Returns the mode actually used.
+    pub fn set_present_mode(&mut self, mode: wgpu::PresentMode) -> wgpu::PresentMode {
+        use wgpu::PresentMode::*;
+        let supported = self.surface.get_capabilities(&self.adapter).present_modes;
+        let candidates: &[wgpu::PresentMode] = match mode {
+            Immediate => &[Immediate, Mailbox, Fifo],
+            Mailbox => &[Mailbox, Immediate, Fifo],
+            FifoRelaxed => &[FifoRelaxed, Fifo],
+            AutoNoVsync => &[AutoNoVsync],
+            AutoVsync => &[AutoVsync],
+            _ => &[Fifo],
+        };
+        let chosen = candidates
+            .iter()
+            .copied()
+            .find(|m| matches!(m, AutoVsync | AutoNoVsync) || supported.contains(m))
+            .unwrap_or(Fifo);
+        if chosen != mode {
+            warn!("Present mode {mode:?} not supported, using {chosen:?}");
+        }
+        self.config.present_mode = chosen;
+        self.surface.configure(&self.device, &self.config);
+        chosen
+    }
+
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.config.present_mode
+    }
+
+    /// Adapter name, backend (Vulkan/Metal/DX12/GL), device type and driver
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.adapter.get_info()
+    }
+
+    /// Limits the device was created with, e.g. `max_texture_dimension_3d`
+    pub fn limits(&self) -> wgpu::Limits {
+        self.device.limits()
+    }
+
+    /// Features enabled on the device, e.g. `TIMESTAMP_QUERY`
+    pub fn features(&self) -> wgpu::Features {
+        self.device.features()
+    }
+
+    /// Adapter, backend and the limits that matter most for shaders, as plain text
+    /// for bug reports
+    pub fn gpu_info_text(&self) -> String {
+        let info = self.adapter_info();
+        let limits = self.limits();
+        let features = self.features();
+        let has = |feature| if features.contains(feature) { "yes" } else { "no" };
+        format!(
+            "Adapter: {} ({:?})\n\
+             Backend: {:?}\n\
+             Driver: {} {}\n\
+             Max texture 2D: {}\n\
+             Max texture 3D: {}\n\
+             Max storage buffer: {} MB\n\
+             Max workgroup size: {}x{}x{} ({} invocations)\n\
+             Timestamp queries: {}\n\
+             Immediates: {}\n\
+             Subgroups: {}",
+            info.name,
+            info.device_type,
+            info.backend,
+            info.driver,
+            info.driver_info,
+            limits.max_texture_dimension_2d,
+            limits.max_texture_dimension_3d,
+            limits.max_storage_buffer_binding_size / (1024 * 1024),
+            limits.max_compute_workgroup_size_x,
+            limits.max_compute_workgroup_size_y,
+            limits.max_compute_workgroup_size_z,
+            limits.max_compute_invocations_per_workgroup,
+            has(wgpu::Features::TIMESTAMP_QUERY),
+            has(wgpu::Features::IMMEDIATES),
+            has(wgpu::Features::SUBGROUP),
+        )
+    }
+
+    /// Queue a present mode change from code that only has `&Core` (UI, `render`);
+    /// the app applies it after the current frame.
+    pub fn request_present_mode(&self, mode: wgpu::PresentMode) {
+        if let Ok(mut requested) = self.requested_present_mode.lock() {
+            *requested = Some(mode);
+        }
+    }
+
+    /// Cap the redraw rate, e.g. to keep a static scene from spinning the GPU at
+    /// 100%. `None` (or a non-positive value) redraws as fast as the present mode
+    /// allows. Input is still handled between frames.
+    pub fn set_target_fps(&self, fps: Option<f32>) {
+        if let Ok(mut target) = self.target_fps.lock() {
+            *target = fps.filter(|fps| *fps > 0.0);
+        }
+    }
+
+    pub fn target_fps(&self) -> Option<f32> {
+        self.target_fps.lock().ok().and_then(|target| *target)
+    }
+
+    /// Ignore the target FPS while `suspended`; exports set this so they run
+    /// uncapped.
+    pub fn suspend_frame_limit(&self, suspended: bool) {
+        self.frame_limit_suspended.store(suspended, Ordering::Relaxed);
+    }
+
+    /// The FPS cap currently in effect
+    pub(crate) fn frame_limit(&self) -> Option<f32> {
+        if self.frame_limit_suspended.load(Ordering::Relaxed) {
+            None
+        } else {
+            self.target_fps()
+        }
+    }
+
+    pub(crate) fn apply_requested_present_mode(&mut self) {
+        let requested = self
+            .requested_present_mode
+            .get_mut()
+            .ok()
+            .and_then(|requested| requested.take());
+        if let Some(mode) = requested {
+            self.set_present_mode(mode);
+        }
+    }
+
     /// Submit the current encoder and create a new one.
     ///
     /// Useful for multi-pass simulations where you need buffer updates to take effect
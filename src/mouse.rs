@@ -1,4 +1,5 @@
 use crate::UniformProvider;
+use web_time::{Duration, Instant};
 use winit::event::WindowEvent;
 
 #[repr(C)]
@@ -7,6 +8,7 @@ pub struct MouseUniform {
     pub position: [f32; 2],
     pub click_position: [f32; 2],
     pub wheel: [f32; 2],
+    /// `[pressed button bits, click count of the last press]`
     pub buttons: [u32; 2],
 }
 
@@ -31,6 +33,16 @@ pub struct MouseTracker {
     pub uniform: MouseUniform,
     pub raw_position: [f32; 2],
     pub is_inside_window: bool,
+    /// Max time between presses for them to count as a double/triple click
+    pub click_threshold: Duration,
+    /// Max cursor travel in pixels between presses of a multi-click
+    pub click_distance: f32,
+    /// Accumulated two-finger rotation in degrees, counterclockwise positive
+    pub rotation: f32,
+    last_click: Option<(Instant, [f32; 2], u32)>,
+    click_count: u32,
+    /// Fingers currently on a touchscreen: `(id, raw position)`, first one drives the cursor
+    touches: Vec<(u64, [f32; 2])>,
 }
 
 impl Default for MouseTracker {
@@ -39,11 +51,22 @@ impl Default for MouseTracker {
             uniform: MouseUniform::default(),
             raw_position: [0.0, 0.0],
             is_inside_window: false,
+            click_threshold: Duration::from_millis(300),
+            click_distance: 4.0,
+            rotation: 0.0,
+            last_click: None,
+            click_count: 0,
+            touches: Vec::new(),
         }
     }
 }
 
 impl MouseTracker {
+    /// Wheel steps per unit of trackpad magnification
+    const PINCH_TO_WHEEL: f32 = 10.0;
+    /// Pixels of finger travel per wheel step, as with `MouseScrollDelta::PixelDelta`
+    const PIXELS_PER_WHEEL_STEP: f32 = 100.0;
+
     pub fn new() -> Self {
         Self::default()
     }
@@ -61,12 +84,7 @@ impl MouseTracker {
 
         match event {
             WindowEvent::CursorMoved { position, .. } => {
-                let x = position.x as f32;
-                let y = position.y as f32;
-                self.raw_position = [x, y];
-
-                self.uniform.position[0] = x / window_size[0];
-                self.uniform.position[1] = y / window_size[1];
+                self.set_position([position.x as f32, position.y as f32], window_size);
                 true
             }
             WindowEvent::MouseInput { state, button, .. } => {
@@ -91,6 +109,7 @@ impl MouseTracker {
                 if pressed {
                     self.uniform.buttons[0] |= bit_mask;
                     self.uniform.click_position = self.uniform.position;
+                    self.register_click(bit_mask);
                 } else {
                     self.uniform.buttons[0] &= !bit_mask;
                 }
@@ -105,12 +124,32 @@ impl MouseTracker {
                         self.uniform.wheel[1] += *y;
                     }
                     MouseScrollDelta::PixelDelta(pos) => {
-                        self.uniform.wheel[0] += pos.x as f32 / 100.0;
-                        self.uniform.wheel[1] += pos.y as f32 / 100.0;
+                        self.uniform.wheel[0] += pos.x as f32 / Self::PIXELS_PER_WHEEL_STEP;
+                        self.uniform.wheel[1] += pos.y as f32 / Self::PIXELS_PER_WHEEL_STEP;
                     }
                 }
                 true
             }
+            WindowEvent::Touch(touch) => {
+                self.handle_touch(touch, window_size);
+                true
+            }
+            WindowEvent::PinchGesture { delta, .. } => {
+                // May be NaN on some platforms
+                if delta.is_finite() {
+                    self.uniform.wheel[1] += *delta as f32 * Self::PINCH_TO_WHEEL;
+                }
+                true
+            }
+            WindowEvent::PanGesture { delta, .. } => {
+                self.uniform.wheel[0] += delta.x / Self::PIXELS_PER_WHEEL_STEP;
+                self.uniform.wheel[1] += delta.y / Self::PIXELS_PER_WHEEL_STEP;
+                true
+            }
+            WindowEvent::RotationGesture { delta, .. } => {
+                self.rotation += *delta;
+                true
+            }
             WindowEvent::CursorLeft { .. } => {
                 self.is_inside_window = false;
                 true
@@ -126,4 +165,77 @@ impl MouseTracker {
     pub fn reset_wheel(&mut self) {
         self.uniform.wheel = [0.0, 0.0];
     }
+
+    /// Click count of the most recent press: 1 for a single click, 2 for a double click, and so on.
+    /// 0 before the first press. Also available to shaders as `mouse.buttons.y`.
+    pub fn click_count(&self) -> u32 {
+        self.click_count
+    }
+
+    fn set_position(&mut self, raw: [f32; 2], window_size: [f32; 2]) {
+        self.raw_position = raw;
+        self.uniform.position[0] = raw[0] / window_size[0];
+        self.uniform.position[1] = raw[1] / window_size[1];
+    }
+
+    /// One finger acts as the left button at the touch point. With two fingers down,
+    /// spreading or pinching them scrolls the wheel like a trackpad pinch.
+    fn handle_touch(&mut self, touch: &winit::event::Touch, window_size: [f32; 2]) {
+        use winit::event::TouchPhase;
+
+        let location = [touch.location.x as f32, touch.location.y as f32];
+        let index = self.touches.iter().position(|(id, _)| *id == touch.id);
+        match (touch.phase, index) {
+            (TouchPhase::Started, None) => {
+                self.touches.push((touch.id, location));
+                if self.touches.len() == 1 {
+                    self.set_position(location, window_size);
+                    self.uniform.buttons[0] |= 1;
+                    self.uniform.click_position = self.uniform.position;
+                    self.register_click(1);
+                }
+            }
+            (TouchPhase::Moved, Some(index)) => {
+                let spread_before = self.touch_spread();
+                self.touches[index].1 = location;
+                if let (Some(before), Some(after)) = (spread_before, self.touch_spread()) {
+                    self.uniform.wheel[1] += (after - before) / Self::PIXELS_PER_WHEEL_STEP;
+                }
+                if index == 0 {
+                    self.set_position(location, window_size);
+                }
+            }
+            (TouchPhase::Ended | TouchPhase::Cancelled, Some(index)) => {
+                self.touches.remove(index);
+                if self.touches.is_empty() {
+                    self.set_position(location, window_size);
+                    self.uniform.buttons[0] &= !1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Distance between the first two fingers, if at least two are down
+    fn touch_spread(&self) -> Option<f32> {
+        let [(_, a), (_, b), ..] = self.touches.as_slice() else {
+            return None;
+        };
+        Some(((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt())
+    }
+
+    fn register_click(&mut self, bit_mask: u32) {
+        let now = Instant::now();
+        let position = self.raw_position;
+        let continues = self.last_click.is_some_and(|(time, last_position, last_mask)| {
+            let dx = position[0] - last_position[0];
+            let dy = position[1] - last_position[1];
+            last_mask == bit_mask
+                && now.duration_since(time) <= self.click_threshold
+                && (dx * dx + dy * dy).sqrt() <= self.click_distance
+        });
+        self.click_count = if continues { self.click_count + 1 } else { 1 };
+        self.last_click = Some((now, position, bit_mask));
+        self.uniform.buttons[1] = self.click_count;
+    }
 }
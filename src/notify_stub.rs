@@ -0,0 +1,49 @@
+//! The parts of `notify` that cuneus uses, for wasm32 where there are no files to watch.
+//! Watchers never start, so hot reload and media reloading stay off in the browser.
+
+use std::path::{Path, PathBuf};
+
+pub type Error = std::io::Error;
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub struct Event {
+    pub kind: EventKind,
+    pub paths: Vec<PathBuf>,
+}
+
+#[allow(dead_code)]
+pub enum EventKind {
+    Create(()),
+    Modify(()),
+    Remove(()),
+    Other,
+}
+
+#[allow(dead_code)]
+pub enum RecursiveMode {
+    Recursive,
+    NonRecursive,
+}
+
+pub trait Watcher {
+    fn watch(&mut self, path: &Path, mode: RecursiveMode) -> Result<()>;
+}
+
+/// Never constructed: [`recommended_watcher`] always fails
+pub enum RecommendedWatcher {}
+
+impl Watcher for RecommendedWatcher {
+    fn watch(&mut self, _path: &Path, _mode: RecursiveMode) -> Result<()> {
+        match *self {}
+    }
+}
+
+pub fn recommended_watcher<F>(_handler: F) -> Result<RecommendedWatcher>
+where
+    F: FnMut(Result<Event>) + Send + 'static,
+{
+    Err(Error::new(
+        std::io::ErrorKind::Unsupported,
+        "file watching isn't available on wasm32",
+    ))
+}
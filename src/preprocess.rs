@@ -0,0 +1,261 @@
+//! Minimal WGSL preprocessor: `//!include "path.wgsl"` directives.
+//!
+//! Includes resolve relative to the file containing the directive. Each file is pulled in at
+//! most once (the first occurrence wins), so a shared `lib.wgsl` can be included from several
+//! helpers without duplicate definitions. Since the directive is a WGSL comment, shaders
+//! stay valid for editors and tools that don't know about it.
+//!
+//! `//!include <cuneus/name.wgsl>` pulls in a library shipped with the crate instead.
+//!
+//! [`PreprocessOptions`] can also turn `var<immediate>` declarations into uniforms while
+//! the files are expanded, for devices without immediates.
+
+use crate::ShaderLoadError;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+const INCLUDE_DIRECTIVE: &str = "//!include";
+/// Libraries for `//!include <...>`
+const BUILTIN_INCLUDES: &[(&str, &str)] = &[
+    ("cuneus/tonemap.wgsl", crate::TONEMAP_WGSL),
+    ("cuneus/envmap.wgsl", crate::ENVMAP_WGSL),
+    ("cuneus/text.wgsl", crate::TEXT_WGSL),
+];
+
+/// Rewrites applied while expanding a shader
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PreprocessOptions {
+    /// Declare `var<immediate>` variables as `@group(g) @binding(b) var<uniform>` instead.
+    /// Comments are left alone, and lines don't move, so error locations still map back.
+    pub immediates_as_uniform: Option<(u32, u32)>,
+}
+
+impl PreprocessOptions {
+    /// Apply the rewrites to `source` without expanding includes, for shaders with no
+    /// path to resolve them against
+    pub fn apply<'a>(&self, source: &'a str) -> Cow<'a, str> {
+        if *self == Self::default() {
+            return Cow::Borrowed(source);
+        }
+        let mut out = String::with_capacity(source.len());
+        let mut comment_depth = 0;
+        for line in source.lines() {
+            out.push_str(&self.rewrite_line(line, &mut comment_depth));
+            out.push('\n');
+        }
+        Cow::Owned(out)
+    }
+
+    /// `line` with the rewrites applied. `comment_depth` carries `/* */` nesting across lines.
+    fn rewrite_line<'a>(&self, line: &'a str, comment_depth: &mut u32) -> Cow<'a, str> {
+        let Some((group, binding)) = self.immediates_as_uniform else {
+            return Cow::Borrowed(line);
+        };
+        let is_ident = |c: u8| c.is_ascii_alphanumeric() || c == b'_';
+        let bytes = line.as_bytes();
+        let mut out = String::new();
+        let mut copied = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            let rest = &line[i..];
+            if rest.starts_with("/*") {
+                *comment_depth += 1;
+                i += 2;
+            } else if *comment_depth > 0 {
+                if rest.starts_with("*/") {
+                    *comment_depth -= 1;
+                    i += 2;
+                } else {
+                    i += 1;
+                }
+            } else if rest.starts_with("//") {
+                break;
+            } else if rest.starts_with("var") && (i == 0 || !is_ident(bytes[i - 1])) {
+                match immediate_address_space_len(&rest[3..]) {
+                    Some(len) => {
+                        out.push_str(&line[copied..i]);
+                        out.push_str(&format!("@group({group}) @binding({binding}) var<uniform>"));
+                        i += 3 + len;
+                        copied = i;
+                    }
+                    None => i += 3,
+                }
+            } else {
+                i += 1;
+            }
+        }
+        if copied == 0 {
+            return Cow::Borrowed(line);
+        }
+        out.push_str(&line[copied..]);
+        Cow::Owned(out)
+    }
+}
+
+/// Length of a leading `<immediate>` (whitespace allowed around the parts), if `s` starts with one
+fn immediate_address_space_len(s: &str) -> Option<usize> {
+    let after_open = s.trim_start().strip_prefix('<')?;
+    let after_name = after_open.trim_start().strip_prefix("immediate")?;
+    let after_close = after_name.trim_start().strip_prefix('>')?;
+    Some(s.len() - after_close.len())
+}
+
+/// WGSL with includes expanded, plus a map back to the original files for error messages.
+#[derive(Debug, Clone)]
+pub struct PreprocessedShader {
+    pub source: String,
+    /// Origin `(file, 1-based line)` of each output line
+    line_origins: Vec<(PathBuf, usize)>,
+}
+
+impl PreprocessedShader {
+    /// Read `path` and expand its includes.
+    pub fn load(path: &Path) -> Result<Self, ShaderLoadError> {
+        Self::load_with(path, PreprocessOptions::default())
+    }
+
+    /// [`load`](Self::load) with `options` applied to every expanded file
+    pub fn load_with(path: &Path, options: PreprocessOptions) -> Result<Self, ShaderLoadError> {
+        let source = crate::hot::read_shader(path)?;
+        Self::from_source_with(&source, path, options)
+    }
+
+    /// Expand includes in `source`, treating it as the contents of `path`
+    /// (e.g. a shader embedded with `include_str!`).
+    pub fn from_source(source: &str, path: &Path) -> Result<Self, ShaderLoadError> {
+        Self::from_source_with(source, path, PreprocessOptions::default())
+    }
+
+    /// [`from_source`](Self::from_source) with `options` applied to every expanded file
+    pub fn from_source_with(source: &str, path: &Path, options: PreprocessOptions) -> Result<Self, ShaderLoadError> {
+        let mut shader = Self {
+            source: String::with_capacity(source.len()),
+            line_origins: Vec::new(),
+        };
+        let mut seen = HashSet::new();
+        seen.insert(Self::identity(path));
+        shader.expand(source, path, &options, &mut seen)?;
+        Ok(shader)
+    }
+
+    /// Files pulled in through includes, for watching. Built-in libraries aren't listed.
+    pub fn included_files(&self) -> Vec<&Path> {
+        let mut files: Vec<&Path> = Vec::new();
+        for (file, _) in self.line_origins.iter().skip(1) {
+            if !Self::is_builtin(file) && !files.contains(&file.as_path()) {
+                files.push(file);
+            }
+        }
+        files
+    }
+
+    /// Original `(file, line)` for a 1-based line of the expanded source.
+    pub fn origin(&self, line: usize) -> Option<(&Path, usize)> {
+        let (file, line) = self.line_origins.get(line.checked_sub(1)?)?;
+        Some((file, *line))
+    }
+
+    /// Rewrite `wgsl:LINE:COL` locations in a naga/wgpu error message to the original file and line.
+    pub fn annotate_error(&self, message: &str) -> String {
+        let mut out = String::with_capacity(message.len());
+        let mut rest = message;
+        while let Some(pos) = rest.find("wgsl:") {
+            out.push_str(&rest[..pos]);
+            let after = &rest[pos + "wgsl:".len()..];
+            let digits = after.find(|c: char| !c.is_ascii_digit()).unwrap_or(after.len());
+            match after[..digits].parse().ok().and_then(|line| self.origin(line)) {
+                Some((file, line)) => {
+                    out.push_str(&format!("{}:{line}", file.display()));
+                    rest = &after[digits..];
+                }
+                None => {
+                    out.push_str("wgsl:");
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn expand(
+        &mut self,
+        source: &str,
+        path: &Path,
+        options: &PreprocessOptions,
+        seen: &mut HashSet<PathBuf>,
+    ) -> Result<(), ShaderLoadError> {
+        let mut comment_depth = 0;
+        for (index, line) in source.lines().enumerate() {
+            let Some(directive) = line.trim_start().strip_prefix(INCLUDE_DIRECTIVE) else {
+                self.push_line(&options.rewrite_line(line, &mut comment_depth), path, index + 1);
+                continue;
+            };
+            let target = directive.trim();
+            let builtin = target.strip_prefix('<').and_then(|name| name.strip_suffix('>'));
+            let target = target.trim_matches('"');
+            if target.is_empty() {
+                return Err(ShaderLoadError::Compile(format!(
+                    "{}:{}: empty include directive",
+                    path.display(),
+                    index + 1
+                )));
+            }
+            let include_path = match builtin {
+                Some(name) => PathBuf::from(format!("<{name}>")),
+                None if Self::is_builtin(path) => {
+                    return Err(ShaderLoadError::Compile(format!(
+                        "{}:{}: cannot include \"{target}\" without the shader's file path, \
+                         load it with `compute_shader!` or `from_file`",
+                        path.display(),
+                        index + 1
+                    )));
+                }
+                None => path.parent().unwrap_or(Path::new("")).join(target),
+            };
+            // Keep the directive as a comment so line numbers in this file stay meaningful
+            self.push_line(line, path, index + 1);
+            if !seen.insert(Self::identity(&include_path)) {
+                continue;
+            }
+            let included = match builtin {
+                Some(name) => BUILTIN_INCLUDES
+                    .iter()
+                    .find(|(builtin_name, _)| *builtin_name == name)
+                    .map(|(_, source)| source.to_string())
+                    .ok_or_else(|| {
+                        ShaderLoadError::Compile(format!(
+                            "{}:{}: no built-in shader library <{name}>",
+                            path.display(),
+                            index + 1
+                        ))
+                    })?,
+                None => std::fs::read_to_string(&include_path).map_err(|e| {
+                    ShaderLoadError::Compile(format!(
+                        "{}:{}: cannot include {}: {e}",
+                        path.display(),
+                        index + 1,
+                        include_path.display()
+                    ))
+                })?,
+            };
+            self.expand(&included, &include_path, options, seen)?;
+        }
+        Ok(())
+    }
+
+    fn push_line(&mut self, line: &str, path: &Path, line_number: usize) {
+        self.source.push_str(line);
+        self.source.push('\n');
+        self.line_origins.push((path.to_path_buf(), line_number));
+    }
+
+    fn is_builtin(path: &Path) -> bool {
+        path.to_str().is_some_and(|path| path.starts_with('<'))
+    }
+
+    fn identity(path: &Path) -> PathBuf {
+        path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+    }
+}
@@ -0,0 +1,244 @@
+use crate::CuneusError;
+use anyhow::{anyhow, bail, Context, Result};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Bumped when the file layout changes; older files are rejected with a clear error
+const PRESET_VERSION: u32 = 1;
+const PRESET_EXTENSION: &str = "json";
+
+/// On-disk form of a preset: the params struct as 32-bit words, with its size and field
+/// names so a preset saved for a different layout is refused on load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PresetFile {
+    version: u32,
+    /// For people reading the file; not checked, since type names aren't stable
+    /// across compiler versions
+    type_name: String,
+    size: usize,
+    /// Field names of the params struct in order, compared with the caller's on load
+    #[serde(default)]
+    fields: Vec<String>,
+    words: Vec<u32>,
+}
+
+impl PresetFile {
+    pub(crate) fn encode<T: bytemuck::Pod>(params: &T, fields: &[&str]) -> Result<Self> {
+        let bytes = bytemuck::bytes_of(params);
+        if !bytes.len().is_multiple_of(4) {
+            bail!("{} is not a multiple of 4 bytes", std::any::type_name::<T>());
+        }
+        Ok(Self {
+            version: PRESET_VERSION,
+            type_name: std::any::type_name::<T>().to_string(),
+            size: bytes.len(),
+            fields: fields.iter().map(|field| field.to_string()).collect(),
+            words: bytes
+                .chunks_exact(4)
+                .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+                .collect(),
+        })
+    }
+
+    /// Fails if the preset was saved with a different version, struct size or, unless
+    /// `fields` is empty, different field names or order
+    pub(crate) fn decode<T: bytemuck::Pod>(&self, fields: &[&str]) -> Result<T> {
+        if self.version != PRESET_VERSION {
+            bail!("Preset version {} is not supported (expected {PRESET_VERSION})", self.version);
+        }
+        let type_name = std::any::type_name::<T>();
+        if !fields.is_empty() && self.fields != fields {
+            bail!(
+                "Preset fields {:?} don't match {type_name}'s {fields:?}; the struct changed",
+                self.fields
+            );
+        }
+        let size = std::mem::size_of::<T>();
+        if self.size != size || self.words.len() * 4 != size {
+            bail!(
+                "Preset holds {} bytes but {type_name} is {size}; the struct layout changed",
+                self.words.len() * 4
+            );
+        }
+
+        let bytes: Vec<u8> = self.words.iter().flat_map(|word| word.to_le_bytes()).collect();
+        bytemuck::try_pod_read_unaligned(&bytes).map_err(|e| anyhow!("Failed to read preset data: {e}"))
+    }
+}
+
+/// Save and restore `#[repr(C)] Pod` params (the same structs passed to
+/// `set_custom_params`) as JSON files in a folder.
+///
+/// ```ignore
+/// let mut presets = PresetManager::new("presets/mandelbulb");
+/// // in the UI:
+/// if presets.render_preset_widget(ui, &mut self.current_params, FIELDS) {
+///     self.compute_shader.set_custom_params(self.current_params, &core.queue);
+/// }
+/// ```
+pub struct PresetManager {
+    dir: PathBuf,
+    names: Vec<String>,
+    selected: Option<String>,
+    new_name: String,
+    status: Option<Result<String, String>>,
+}
+
+impl PresetManager {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let mut manager = Self {
+            dir: dir.into(),
+            names: Vec::new(),
+            selected: None,
+            new_name: String::from("preset"),
+            status: None,
+        };
+        manager.refresh();
+        manager
+    }
+
+    /// Rescan the folder for `*.json` presets
+    pub fn refresh(&mut self) {
+        self.names = std::fs::read_dir(&self.dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == PRESET_EXTENSION))
+                    .filter_map(|path| path.file_stem()?.to_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.names.sort();
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn path_for(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.{PRESET_EXTENSION}"))
+    }
+
+    /// Save `params` as `<dir>/<name>.json`, creating the folder if needed. `name` must
+    /// be a plain file name, without path separators.
+    pub fn save<T: bytemuck::Pod>(
+        &mut self,
+        name: &str,
+        params: &T,
+        fields: &[&str],
+    ) -> Result<PathBuf, CuneusError> {
+        if name.is_empty() || name.contains(['/', '\\']) {
+            return Err(CuneusError::Preset(anyhow!("Invalid preset name {name:?}")));
+        }
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create preset folder {:?}", self.dir))
+            .map_err(CuneusError::Preset)?;
+        let path = self.path_for(name);
+        save_preset(&path, params, fields)?;
+        self.refresh();
+        self.selected = Some(name.to_string());
+        Ok(path)
+    }
+
+    /// [`load_preset`] from `<dir>/<name>.json`
+    pub fn load<T: bytemuck::Pod>(&self, name: &str, fields: &[&str]) -> Result<T, CuneusError> {
+        load_preset(self.path_for(name), fields)
+    }
+
+    /// Preset dropdown with Load, plus a name field with Save. Returns true when
+    /// `params` was replaced by a loaded preset, so the caller can upload it.
+    /// `fields` is stored as the file's manifest and checked on load, e.g.
+    /// `MyParams::FIELD_NAMES` from `uniform_params!`. If empty, only the size is checked.
+    pub fn render_preset_widget<T: bytemuck::Pod>(
+        &mut self,
+        ui: &mut egui::Ui,
+        params: &mut T,
+        fields: &[&str],
+    ) -> bool {
+        let mut loaded = false;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt(("presets", &self.dir))
+                .selected_text(self.selected.as_deref().unwrap_or("Select preset"))
+                .show_ui(ui, |ui| {
+                    for name in &self.names {
+                        ui.selectable_value(&mut self.selected, Some(name.clone()), name);
+                    }
+                });
+            let load_clicked = ui
+                .add_enabled(self.selected.is_some(), egui::Button::new("Load"))
+                .clicked();
+            if let Some(name) = self.selected.clone().filter(|_| load_clicked) {
+                match self.load::<T>(&name, fields) {
+                    Ok(preset) => {
+                        *params = preset;
+                        loaded = true;
+                        self.status = Some(Ok(format!("Loaded {name}")));
+                    }
+                    Err(e) => {
+                        error!("Failed to load preset {name}: {e:#}");
+                        self.status = Some(Err(format!("{e:#}")));
+                    }
+                }
+            }
+            if ui.small_button("⟳").on_hover_text("Rescan folder").clicked() {
+                self.refresh();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.new_name);
+            let name = self.new_name.trim().to_string();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                .clicked()
+            {
+                self.status = Some(match self.save(&name, params, fields) {
+                    Ok(path) => Ok(format!("Saved {}", path.display())),
+                    Err(e) => {
+                        error!("Failed to save preset {name}: {e:#}");
+                        Err(format!("{e:#}"))
+                    }
+                });
+            }
+        });
+        match &self.status {
+            Some(Ok(message)) => {
+                ui.label(message);
+            }
+            Some(Err(message)) => {
+                ui.colored_label(egui::Color32::from_rgb(255, 100, 100), message);
+            }
+            None => {}
+        }
+        loaded
+    }
+}
+
+/// Write `params` to a JSON preset at `path`
+pub fn save_preset<T: bytemuck::Pod>(path: impl AsRef<Path>, params: &T, fields: &[&str]) -> Result<(), CuneusError> {
+    write_preset(path.as_ref(), params, fields).map_err(CuneusError::Preset)
+}
+
+fn write_preset<T: bytemuck::Pod>(path: &Path, params: &T, fields: &[&str]) -> Result<()> {
+    let file = PresetFile::encode(params, fields)?;
+    let json = serde_json::to_string_pretty(&file)?;
+    std::fs::write(path, json).with_context(|| format!("Failed to write preset {path:?}"))?;
+    info!("Saved preset {path:?}");
+    Ok(())
+}
+
+/// Read a preset written by [`save_preset`]. Fails if the file was saved with a
+/// different preset version or struct size, or if its field manifest differs from
+/// `fields` (pass the same names as when saving, e.g. `MyParams::FIELD_NAMES`), so
+/// reordered fields of the same size aren't silently reinterpreted. Empty `fields`
+/// skips the manifest check.
+pub fn load_preset<T: bytemuck::Pod>(path: impl AsRef<Path>, fields: &[&str]) -> Result<T, CuneusError> {
+    read_preset(path.as_ref(), fields).map_err(CuneusError::Preset)
+}
+
+fn read_preset<T: bytemuck::Pod>(path: &Path, fields: &[&str]) -> Result<T> {
+    let json = std::fs::read_to_string(path).with_context(|| format!("Failed to read preset {path:?}"))?;
+    let file: PresetFile =
+        serde_json::from_str(&json).with_context(|| format!("Invalid preset file {path:?}"))?;
+    file.decode(fields)
+}
@@ -1,7 +1,7 @@
   //! GPU Radix Sort for cuneus                                                                                                                                
   //!                                                                                                                                                          
   //! Based on wgpu_sort (BSD 2-Clause License): https://github.com/KeKsBoTer/wgpu_sort/tree/master/src                                                        
-  //! I extended it with a 16-bit key mode (2 passes) for faster depth sorting.   
+  //! I extended it with a 16-bit key mode (2 passes) for faster depth sorting, and a 64-bit key mode (8 passes).
 
 /*
 BSD 2-Clause License
@@ -53,238 +53,260 @@ pub struct SorterState {
     pub even_pass: u32,
     pub odd_pass: u32,
     pub sort_failed: u32,
+    /// Zero/histogram workgroup counts, written on the GPU by `sort_indirect`
+    pub histogram_dispatch: [u32; 3],
+    /// Scatter workgroup counts, written on the GPU by `sort_indirect`
+    pub scatter_dispatch: [u32; 3],
+    /// Number of payload planes carried along with the keys
+    pub payload_count: u32,
+    /// Element count the buffers were created for; `sort_indirect` clamps to it
+    pub capacity: u32,
+}
+
+/// Order of the sorted output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Smallest key first
+    #[default]
+    Ascending,
+    /// Largest key first (e.g. back-to-front depth sorting)
+    Descending,
+}
+
+/// Where the zero/histogram/scatter workgroup counts come from
+#[derive(Clone, Copy)]
+enum SortDispatch<'a> {
+    /// Computed on the CPU from a known count
+    Direct(u32),
+    /// Read from `SortBuffers::dispatch_args`, filled by `setup_indirect`
+    Indirect(&'a wgpu::Buffer),
+}
+
+impl SortDispatch<'_> {
+    fn histogram(&self, pass: &mut wgpu::ComputePass) {
+        match *self {
+            SortDispatch::Direct(count) => pass.dispatch_workgroups(histo_blocks_ru(count), 1, 1),
+            SortDispatch::Indirect(args) => pass.dispatch_workgroups_indirect(args, 0),
+        }
+    }
+
+    fn scatter(&self, pass: &mut wgpu::ComputePass) {
+        match *self {
+            SortDispatch::Direct(count) => pass.dispatch_workgroups(scatter_blocks_ru(count), 1, 1),
+            SortDispatch::Indirect(args) => pass.dispatch_workgroups_indirect(args, 12),
+        }
+    }
+}
+
+/// Pipelines that depend on the sort order (the prefix pass is order independent)
+struct OrderedPipelines {
+    zero: wgpu::ComputePipeline,
+    histogram: wgpu::ComputePipeline,
+    scatter_even: wgpu::ComputePipeline,
+    scatter_odd: wgpu::ComputePipeline,
+    scatter_even_keys_only: wgpu::ComputePipeline,
+    scatter_odd_keys_only: wgpu::ComputePipeline,
 }
 
 /// GPU Radix Sorter for key-value pairs
+///
+/// The sort is stable in every mode: payloads of equal keys keep their input order,
+/// so it can order layers that share a depth. LSD radix sorting relies on this. Each
+/// scatter pass hands out ranks in input order inside a block (each invocation owns a
+/// contiguous run of keys and the invocations take turns in order), and across blocks
+/// through the in-order lookback. `SortOrder::Descending` flips digits rather than
+/// reversing, so equal keys stay in input order there too.
 pub struct RadixSorter {
-    zero_pipeline: wgpu::ComputePipeline,
-    histogram_pipeline: wgpu::ComputePipeline,
+    ascending: OrderedPipelines,
+    descending: OrderedPipelines,
     prefix_pipeline: wgpu::ComputePipeline,
-    scatter_even_pipeline: wgpu::ComputePipeline,
-    scatter_odd_pipeline: wgpu::ComputePipeline,
+    indirect_setup_pipeline: wgpu::ComputePipeline,
+    encode_f32_pipeline: wgpu::ComputePipeline,
+    decode_f32_pipeline: wgpu::ComputePipeline,
+    /// Segment tagging for `sort_segmented` (64-bit sorter only)
+    segment_pipeline: Option<wgpu::ComputePipeline>,
+    segment_bind_group_layout: wgpu::BindGroupLayout,
     bind_group_layout: wgpu::BindGroupLayout,
     key_val_size: u32,
+    /// Scatter passes rank keys with subgroup ballots instead of one invocation at a time
+    subgroups: bool,
 }
 
 impl RadixSorter {
+    /// Create a 32-bit radix sorter (4 passes). Uses subgroup operations when the device
+    /// has `Features::SUBGROUP` enabled, as `Core` does wherever the adapter supports it.
     pub fn new(device: &wgpu::Device) -> Self {
-        let bind_group_layout = Self::create_bind_group_layout(device);
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Radix Sort Pipeline Layout"),
-            bind_group_layouts: &[Some(&bind_group_layout)],
-            immediate_size: 0,
-        });
-
-        let subgroup_size = 1u32;
-        let rs_sweep_0_size = RS_RADIX_SIZE / subgroup_size.max(1);
-        let rs_sweep_1_size = rs_sweep_0_size / subgroup_size.max(1);
-        let _rs_sweep_2_size = rs_sweep_1_size / subgroup_size.max(1);
-        let rs_smem_phase_2 = RS_RADIX_SIZE + RS_SCATTER_BLOCK_ROWS * SCATTER_WG_SIZE;
-        let rs_mem_dwords = rs_smem_phase_2;
-
-        // Build shader with constants
-        let shader_source = format!(
-            "const histogram_sg_size: u32 = {}u;\n\
-             const histogram_wg_size: u32 = {}u;\n\
-             const rs_radix_log2: u32 = {}u;\n\
-             const rs_radix_size: u32 = {}u;\n\
-             const rs_keyval_size: u32 = {}u;\n\
-             const rs_histogram_block_rows: u32 = {}u;\n\
-             const rs_scatter_block_rows: u32 = {}u;\n\
-             const rs_mem_dwords: u32 = {}u;\n\
-             const rs_mem_sweep_0_offset: u32 = 0u;\n\
-             const rs_mem_sweep_1_offset: u32 = {}u;\n\
-             const rs_mem_sweep_2_offset: u32 = {}u;\n\
-             {}",
-            subgroup_size.max(1),
-            HISTOGRAM_WG_SIZE,
-            RS_RADIX_LOG2,
-            RS_RADIX_SIZE,
-            RS_KEYVAL_SIZE,
-            RS_HISTOGRAM_BLOCK_ROWS,
-            RS_SCATTER_BLOCK_ROWS,
-            rs_mem_dwords,
-            rs_sweep_0_size,
-            rs_sweep_0_size + rs_sweep_1_size,
-            include_str!("shader.wgsl")
-        );
-
-        let shader_code = shader_source
-            .replace("{histogram_wg_size}", &HISTOGRAM_WG_SIZE.to_string())
-            .replace("{prefix_wg_size}", &PREFIX_WG_SIZE.to_string())
-            .replace("{scatter_wg_size}", &SCATTER_WG_SIZE.to_string());
-
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Radix Sort Shader"),
-            source: wgpu::ShaderSource::Wgsl(shader_code.into()),
-        });
-
-        let zero_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Radix Sort Zero"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("zero_histograms"),
-            compilation_options: Default::default(),
-            cache: None,
-        });
-
-        let histogram_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Radix Sort Histogram"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("calculate_histogram"),
-            compilation_options: Default::default(),
-            cache: None,
-        });
-
-        let prefix_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Radix Sort Prefix"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("prefix_histogram"),
-            compilation_options: Default::default(),
-            cache: None,
-        });
-
-        let scatter_even_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Radix Sort Scatter Even"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("scatter_even"),
-            compilation_options: Default::default(),
-            cache: None,
-        });
-
-        let scatter_odd_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Radix Sort Scatter Odd"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("scatter_odd"),
-            compilation_options: Default::default(),
-            cache: None,
-        });
+        Self::with_key_val_size(device, RS_KEYVAL_SIZE, "Radix Sort", true)
+    }
 
-        Self {
-            zero_pipeline,
-            histogram_pipeline,
-            prefix_pipeline,
-            scatter_even_pipeline,
-            scatter_odd_pipeline,
-            bind_group_layout,
-            key_val_size: RS_KEYVAL_SIZE,
-        }
+    /// Create a 32-bit radix sorter that never uses subgroup operations, e.g. to compare
+    /// against the subgroup path or to rule it out when debugging.
+    pub fn new_without_subgroups(device: &wgpu::Device) -> Self {
+        Self::with_key_val_size(device, RS_KEYVAL_SIZE, "Radix Sort", false)
     }
 
     /// Create a 16-bit radix sorter 2 passes.
     /// Use with 16-bit depth keys for faster gaussian splatting sort.
     pub fn new_16bit(device: &wgpu::Device) -> Self {
-        let key_val_size: u32 = 2;
+        Self::with_key_val_size(device, 2, "Radix Sort 16-bit", true)
+    }
+
+    /// Create a 64-bit radix sorter (8 passes).
+    ///
+    /// Keys are stored as little-endian `u64` (low word first), so a `&[u64]` can be
+    /// uploaded into `keys_a` as-is. Useful for full-range Morton codes.
+    pub fn new_u64(device: &wgpu::Device) -> Self {
+        Self::with_key_val_size(device, 8, "Radix Sort 64-bit", true)
+    }
+
+    fn with_key_val_size(device: &wgpu::Device, key_val_size: u32, label: &str, allow_subgroups: bool) -> Self {
         let bind_group_layout = Self::create_bind_group_layout(device);
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Radix Sort 16-bit Pipeline Layout"),
+            label: Some(&format!("{label} Pipeline Layout")),
             bind_group_layouts: &[Some(&bind_group_layout)],
             immediate_size: 0,
         });
 
-        let subgroup_size = 1u32;
-        let rs_sweep_0_size = RS_RADIX_SIZE / subgroup_size.max(1);
-        let rs_sweep_1_size = rs_sweep_0_size / subgroup_size.max(1);
+        // The subgroup size isn't fixed on every adapter (e.g. 32 or 64 on RDNA), so the
+        // shader reads it from the builtin; ballots cover wgpu's maximum of 128 lanes
+        let subgroups = allow_subgroups && device.features().contains(wgpu::Features::SUBGROUP);
         let rs_smem_phase_2 = RS_RADIX_SIZE + RS_SCATTER_BLOCK_ROWS * SCATTER_WG_SIZE;
         let rs_mem_dwords = rs_smem_phase_2;
+        // 64-bit keys occupy two u32 words (low, high) per element
+        let key_words = key_val_size.div_ceil(4);
+
+        // Naga takes subgroup builtins without `enable subgroups;` once the feature is on
+        let (rank_source, subgroup_builtins, subgroup_layout) = if subgroups {
+            (
+                include_str!("rank_subgroup.wgsl"),
+                ", @builtin(subgroup_id) subgroup_id: u32,\n    \
+                 @builtin(subgroup_invocation_id) subgroup_lane: u32, @builtin(subgroup_size) subgroup_size: u32",
+                "vec3<u32>(subgroup_id, subgroup_lane, subgroup_size)",
+            )
+        } else {
+            (include_str!("rank.wgsl"), "", "vec3<u32>(lid.x, 0u, 1u)")
+        };
 
+        // Build shader with constants
         let shader_source = format!(
-            "const histogram_sg_size: u32 = {}u;\n\
-             const histogram_wg_size: u32 = {}u;\n\
+            "const histogram_wg_size: u32 = {}u;\n\
              const rs_radix_log2: u32 = {}u;\n\
              const rs_radix_size: u32 = {}u;\n\
              const rs_keyval_size: u32 = {}u;\n\
+             const rs_key_words: u32 = {}u;\n\
              const rs_histogram_block_rows: u32 = {}u;\n\
              const rs_scatter_block_rows: u32 = {}u;\n\
              const rs_mem_dwords: u32 = {}u;\n\
-             const rs_mem_sweep_0_offset: u32 = 0u;\n\
-             const rs_mem_sweep_1_offset: u32 = {}u;\n\
-             const rs_mem_sweep_2_offset: u32 = {}u;\n\
-             {}",
-            subgroup_size.max(1),
+             {}\n{}",
             HISTOGRAM_WG_SIZE,
             RS_RADIX_LOG2,
             RS_RADIX_SIZE,
             key_val_size,
+            key_words,
             RS_HISTOGRAM_BLOCK_ROWS,
             RS_SCATTER_BLOCK_ROWS,
             rs_mem_dwords,
-            rs_sweep_0_size,
-            rs_sweep_0_size + rs_sweep_1_size,
-            include_str!("shader.wgsl")
+            include_str!("shader.wgsl"),
+            rank_source
         );
 
+        // One histogram pass per key byte, most significant first
+        let histogram_passes = (0..key_val_size)
+            .rev()
+            .map(|pass| format!("histogram_pass({pass}u, lid.x);"))
+            .collect::<Vec<_>>()
+            .join("\n    ");
+
         let shader_code = shader_source
             .replace("{histogram_wg_size}", &HISTOGRAM_WG_SIZE.to_string())
             .replace("{prefix_wg_size}", &PREFIX_WG_SIZE.to_string())
             .replace("{scatter_wg_size}", &SCATTER_WG_SIZE.to_string())
-            .replace(
-                "histogram_pass(3u, lid.x);\n    histogram_pass(2u, lid.x);\n    histogram_pass(1u, lid.x);\n    histogram_pass(0u, lid.x);",
-                "histogram_pass(1u, lid.x);\n    histogram_pass(0u, lid.x);"
-            );
+            .replace("{histogram_passes}", &histogram_passes)
+            .replace("{subgroup_builtins}", subgroup_builtins)
+            .replace("{subgroup_layout}", subgroup_layout);
 
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Radix Sort 16-bit Shader"),
+            label: Some(&format!("{label} Shader")),
             source: wgpu::ShaderSource::Wgsl(shader_code.into()),
         });
 
-        let zero_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Radix Sort 16-bit Zero"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("zero_histograms"),
-            compilation_options: Default::default(),
-            cache: None,
-        });
-
-        let histogram_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Radix Sort 16-bit Histogram"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("calculate_histogram"),
-            compilation_options: Default::default(),
-            cache: None,
-        });
-
-        let prefix_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Radix Sort 16-bit Prefix"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("prefix_histogram"),
-            compilation_options: Default::default(),
-            cache: None,
-        });
-
-        let scatter_even_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Radix Sort 16-bit Scatter Even"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("scatter_even"),
-            compilation_options: Default::default(),
-            cache: None,
+        // Descending order flips every radix digit via the `sort_descending` override,
+        // `sort_keys_only` skips all payload traffic in the scatter passes
+        let create_variant = |name: &str, entry_point: &str, order: SortOrder, keys_only: bool| {
+            let descending = order == SortOrder::Descending;
+            let constants = [
+                ("sort_descending", if descending { 1.0 } else { 0.0 }),
+                ("sort_keys_only", if keys_only { 1.0 } else { 0.0 }),
+            ];
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(&format!(
+                    "{label} {name}{}{}",
+                    if descending { " Descending" } else { "" },
+                    if keys_only { " Keys Only" } else { "" }
+                )),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &constants,
+                    ..Default::default()
+                },
+                cache: None,
+            })
+        };
+        let create_pipeline = |name: &str, entry_point: &str, order: SortOrder| {
+            create_variant(name, entry_point, order, false)
+        };
+        let create_ordered = |order: SortOrder| OrderedPipelines {
+            zero: create_pipeline("Zero", "zero_histograms", order),
+            histogram: create_pipeline("Histogram", "calculate_histogram", order),
+            scatter_even: create_pipeline("Scatter Even", "scatter_even", order),
+            scatter_odd: create_pipeline("Scatter Odd", "scatter_odd", order),
+            scatter_even_keys_only: create_variant("Scatter Even", "scatter_even", order, true),
+            scatter_odd_keys_only: create_variant("Scatter Odd", "scatter_odd", order, true),
+        };
+
+        let segment_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Radix Sort Segment Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: NonZeroU64::new(4),
+                },
+                count: None,
+            }],
         });
-
-        let scatter_odd_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Radix Sort 16-bit Scatter Odd"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("scatter_odd"),
-            compilation_options: Default::default(),
-            cache: None,
+        let segment_pipeline = (key_val_size == 8).then(|| {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(&format!("{label} Segment Pipeline Layout")),
+                bind_group_layouts: &[Some(&bind_group_layout), Some(&segment_bind_group_layout)],
+                immediate_size: 0,
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(&format!("{label} Tag Segments")),
+                layout: Some(&layout),
+                module: &shader,
+                entry_point: Some("tag_segments"),
+                compilation_options: Default::default(),
+                cache: None,
+            })
         });
 
         Self {
-            zero_pipeline,
-            histogram_pipeline,
-            prefix_pipeline,
-            scatter_even_pipeline,
-            scatter_odd_pipeline,
+            ascending: create_ordered(SortOrder::Ascending),
+            descending: create_ordered(SortOrder::Descending),
+            prefix_pipeline: create_pipeline("Prefix", "prefix_histogram", SortOrder::Ascending),
+            indirect_setup_pipeline: create_pipeline("Indirect Setup", "setup_indirect", SortOrder::Ascending),
+            encode_f32_pipeline: create_pipeline("Encode F32", "encode_f32_keys", SortOrder::Ascending),
+            decode_f32_pipeline: create_pipeline("Decode F32", "decode_f32_keys", SortOrder::Ascending),
+            segment_pipeline,
+            segment_bind_group_layout,
             bind_group_layout,
             key_val_size,
+            subgroups,
         }
     }
 
@@ -362,11 +384,40 @@ impl RadixSorter {
         })
     }
 
-    /// Create sort buffers for a given number of elements
+    /// Create sort buffers holding up to `count` elements.
+    ///
+    /// `count` is a capacity: `sort` and friends take the live count each time, so a
+    /// changing element count doesn't need new buffers.
     pub fn create_sort_buffers(&self, device: &wgpu::Device, count: u32) -> SortBuffers {
+        self.create_buffers(device, count, 1)
+    }
+
+    /// Create sort buffers carrying `payload_count` payload arrays that are scattered together.
+    ///
+    /// The payloads are stored as planes in `payload_a`/`payload_b`, one every
+    /// `padded_size` elements; use `SortBuffers::values_at` to address a single plane.
+    pub fn create_sort_buffers_multi(&self, device: &wgpu::Device, count: u32, payload_count: u32) -> SortBuffers {
+        assert!(payload_count >= 1, "create_sort_buffers_multi needs at least one payload");
+        self.create_buffers(device, count, payload_count)
+    }
+
+    /// Create sort buffers without a payload, for use with `sort_keys_only`.
+    /// The payload buffers are 4-byte placeholders so the bind group layout stays the same.
+    pub fn create_keys_only_buffers(&self, device: &wgpu::Device, count: u32) -> SortBuffers {
+        self.create_buffers(device, count, 0)
+    }
+
+    /// `payload_count` of 0 creates keys-only buffers
+    fn create_buffers(&self, device: &wgpu::Device, count: u32, payload_count: u32) -> SortBuffers {
         let padded_size = keys_buffer_size(count);
-        let keys_size = (padded_size * self.key_val_size * 4) as u64;
-        let payload_size = (count * 4) as u64;
+        let keys_size = self.keys_buffer_bytes(padded_size);
+        let keys_only = payload_count == 0;
+        let payload_size = match payload_count {
+            0 => 4,
+            1 => (count * 4) as u64,
+            // Planes start every padded_size elements, which keeps them 256-byte aligned
+            n => n as u64 * padded_size as u64 * 4,
+        };
 
         let state_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Radix Sort State"),
@@ -376,8 +427,12 @@ impl RadixSorter {
                 even_pass: 0,
                 odd_pass: 0,
                 sort_failed: 0,
+                histogram_dispatch: [0; 3],
+                scatter_dispatch: [0; 3],
+                payload_count,
+                capacity: count,
             }),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
         });
 
         let internal_size = self.internal_buffer_size(count);
@@ -416,6 +471,13 @@ impl RadixSorter {
             mapped_at_creation: false,
         });
 
+        let dispatch_args = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Radix Sort Dispatch Args"),
+            size: 24,
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Radix Sort Bind Group"),
             layout: &self.bind_group_layout,
@@ -436,11 +498,20 @@ impl RadixSorter {
             keys_b,
             payload_a,
             payload_b,
+            dispatch_args,
             bind_group,
             count,
+            keys_only,
+            payload_count,
+            key_words: self.key_val_size.div_ceil(4),
         }
     }
 
+    /// Bytes needed for a keys buffer holding `padded_size` keys (two u32 words per 64-bit key)
+    fn keys_buffer_bytes(&self, padded_size: u32) -> u64 {
+        padded_size as u64 * self.key_val_size.div_ceil(4) as u64 * 4
+    }
+
     fn internal_buffer_size(&self, count: u32) -> u32 {
         let scatter_blocks_ru = scatter_blocks_ru(count);
         let histo_size = RS_RADIX_SIZE * 4;
@@ -455,65 +526,83 @@ impl RadixSorter {
         buffers: &SortBuffers,
         count: u32,
     ) {
-        // Update count
-        queue.write_buffer(&buffers.state_buffer, 0, bytemuck::bytes_of(&count));
+        self.set_count(queue, buffers, count);
+        self.sort_with_bind_group(encoder, &buffers.bind_group, count);
+    }
 
-        let hist_blocks = histo_blocks_ru(count);
-        let scatter_blocks = scatter_blocks_ru(count);
+    /// Sort only the keys, leaving the payload buffers untouched.
+    /// Works with buffers from either `create_keys_only_buffers` or `create_sort_buffers`.
+    pub fn sort_keys_only(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        queue: &wgpu::Queue,
+        buffers: &SortBuffers,
+        count: u32,
+    ) {
+        self.set_count(queue, buffers, count);
+        self.encode_sort(encoder, &buffers.bind_group, SortDispatch::Direct(count), SortOrder::Ascending, true);
+    }
 
-        // Zero histograms
-        {
-            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Radix Sort Zero"),
-                timestamp_writes: None,
-            });
-            pass.set_pipeline(&self.zero_pipeline);
-            pass.set_bind_group(0, &buffers.bind_group, &[]);
-            pass.dispatch_workgroups(hist_blocks, 1, 1);
-        }
+    fn set_count(&self, queue: &wgpu::Queue, buffers: &SortBuffers, count: u32) {
+        assert!(
+            count <= buffers.capacity(),
+            "sorting {count} elements in buffers created for {}",
+            buffers.capacity()
+        );
+        Self::write_count(queue, &buffers.state_buffer, count);
+    }
 
-        // Calculate histogram
-        {
-            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Radix Sort Histogram"),
-                timestamp_writes: None,
-            });
-            pass.set_pipeline(&self.histogram_pipeline);
-            pass.set_bind_group(0, &buffers.bind_group, &[]);
-            pass.dispatch_workgroups(hist_blocks, 1, 1);
-        }
+    /// Set the number of elements the next sort covers, for bind groups from
+    /// `create_direct_bind_group` (pass its state buffer). Must not exceed the count the
+    /// bind group was created with; elements past it are left alone.
+    pub fn write_count(queue: &wgpu::Queue, state_buffer: &wgpu::Buffer, count: u32) {
+        // num_keys is the first field of SorterState
+        queue.write_buffer(state_buffer, 0, bytemuck::bytes_of(&count));
+    }
 
-        // Prefix sum
-        {
-            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Radix Sort Prefix"),
-                timestamp_writes: None,
-            });
-            pass.set_pipeline(&self.prefix_pipeline);
-            pass.set_bind_group(0, &buffers.bind_group, &[]);
-            pass.dispatch_workgroups(self.key_val_size, 1, 1);
-        }
+    /// Sort with the element count taken from a GPU buffer, so no CPU readback is needed.
+    ///
+    /// `buffers` must come from `create_sort_buffers` with the maximum expected count; the
+    /// live count (a `u32` at `count_offset`, a multiple of 4, in a COPY_SRC buffer) is
+    /// clamped to that capacity. The zero/histogram/scatter stages use indirect dispatch.
+    pub fn sort_indirect(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        buffers: &SortBuffers,
+        count_buffer: &wgpu::Buffer,
+        count_offset: wgpu::BufferAddress,
+    ) {
+        // num_keys is the first field of SorterState
+        encoder.copy_buffer_to_buffer(count_buffer, count_offset, &buffers.state_buffer, 0, 4);
 
-        // Scatter passes
         {
             let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Radix Sort Scatter"),
+                label: Some("Radix Sort Indirect Setup"),
                 timestamp_writes: None,
             });
+            pass.set_pipeline(&self.indirect_setup_pipeline);
             pass.set_bind_group(0, &buffers.bind_group, &[]);
+            pass.dispatch_workgroups(1, 1, 1);
+        }
 
-            for _i in 0..self.key_val_size / 2 {
-                pass.set_pipeline(&self.scatter_even_pipeline);
-                pass.dispatch_workgroups(scatter_blocks, 1, 1);
+        // The state buffer is bound read-write, so it can't double as the indirect buffer
+        let args_offset = std::mem::offset_of!(SorterState, histogram_dispatch) as u64;
+        encoder.copy_buffer_to_buffer(&buffers.state_buffer, args_offset, &buffers.dispatch_args, 0, 24);
 
-                pass.set_pipeline(&self.scatter_odd_pipeline);
-                pass.dispatch_workgroups(scatter_blocks, 1, 1);
-            }
-        }
+        self.encode_sort(
+            encoder,
+            &buffers.bind_group,
+            SortDispatch::Indirect(&buffers.dispatch_args),
+            SortOrder::Ascending,
+            false,
+        );
     }
 
     /// Create a bind group that directly binds to external depth_keys and sorted_indices buffers
     /// Returns (bind_group, aux_keys, aux_payload, internal_buffer, state_buffer)
+    ///
+    /// The state starts out sorting all `count` elements. To reuse the bind group for fewer,
+    /// call `write_count` with the live count before `sort_with_bind_group`.
     pub fn create_direct_bind_group(
         &self,
         device: &wgpu::Device,
@@ -522,7 +611,7 @@ impl RadixSorter {
         count: u32,
     ) -> (wgpu::Buffer, wgpu::Buffer, wgpu::Buffer, wgpu::Buffer, wgpu::BindGroup) {
         let padded_size = keys_buffer_size(count);
-        let keys_aux_size = (padded_size * self.key_val_size * 4) as u64;
+        let keys_aux_size = self.keys_buffer_bytes(padded_size);
         let payload_aux_size = (count * 4) as u64;
 
         let state_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -533,8 +622,12 @@ impl RadixSorter {
                 even_pass: 0,
                 odd_pass: 0,
                 sort_failed: 0,
+                histogram_dispatch: [0; 3],
+                scatter_dispatch: [0; 3],
+                payload_count: 1,
+                capacity: count,
             }),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
         });
 
         let internal_size = self.internal_buffer_size(count);
@@ -575,25 +668,55 @@ impl RadixSorter {
         (state_buffer, keys_aux, payload_aux, internal_buffer, bind_group)
     }
 
-    /// Sort using a pre-created bind group (no CPU buffer writes during sort)
+    /// Sort using a pre-created bind group (no CPU buffer writes during sort).
+    /// `count` must match the count stored in the bind group's state buffer.
     pub fn sort_with_bind_group(
         &self,
         encoder: &mut wgpu::CommandEncoder,
         bind_group: &wgpu::BindGroup,
         count: u32,
     ) {
-        let hist_blocks = histo_blocks_ru(count);
-        let scatter_blocks = scatter_blocks_ru(count);
+        self.sort_with_order(encoder, bind_group, count, SortOrder::Ascending);
+    }
 
+    /// Sort using a pre-created bind group in the given order.
+    /// With `SortOrder::Descending` the largest keys end up first in `keys()`/`values()`.
+    pub fn sort_with_order(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        bind_group: &wgpu::BindGroup,
+        count: u32,
+        order: SortOrder,
+    ) {
+        self.encode_sort(encoder, bind_group, SortDispatch::Direct(count), order, false);
+    }
+
+    fn encode_sort(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        bind_group: &wgpu::BindGroup,
+        dispatch: SortDispatch,
+        order: SortOrder,
+        keys_only: bool,
+    ) {
+        let pipelines = match order {
+            SortOrder::Ascending => &self.ascending,
+            SortOrder::Descending => &self.descending,
+        };
+        let (scatter_even, scatter_odd) = if keys_only {
+            (&pipelines.scatter_even_keys_only, &pipelines.scatter_odd_keys_only)
+        } else {
+            (&pipelines.scatter_even, &pipelines.scatter_odd)
+        };
         // Zero histograms
         {
             let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                 label: Some("Radix Sort Zero"),
                 timestamp_writes: None,
             });
-            pass.set_pipeline(&self.zero_pipeline);
+            pass.set_pipeline(&pipelines.zero);
             pass.set_bind_group(0, bind_group, &[]);
-            pass.dispatch_workgroups(hist_blocks, 1, 1);
+            dispatch.histogram(&mut pass);
         }
 
         // Calculate histogram
@@ -602,9 +725,9 @@ impl RadixSorter {
                 label: Some("Radix Sort Histogram"),
                 timestamp_writes: None,
             });
-            pass.set_pipeline(&self.histogram_pipeline);
+            pass.set_pipeline(&pipelines.histogram);
             pass.set_bind_group(0, bind_group, &[]);
-            pass.dispatch_workgroups(hist_blocks, 1, 1);
+            dispatch.histogram(&mut pass);
         }
 
         // Prefix sum
@@ -627,19 +750,126 @@ impl RadixSorter {
             pass.set_bind_group(0, bind_group, &[]);
 
             for _i in 0..self.key_val_size / 2 {
-                pass.set_pipeline(&self.scatter_even_pipeline);
-                pass.dispatch_workgroups(scatter_blocks, 1, 1);
+                pass.set_pipeline(scatter_even);
+                dispatch.scatter(&mut pass);
 
-                pass.set_pipeline(&self.scatter_odd_pipeline);
-                pass.dispatch_workgroups(scatter_blocks, 1, 1);
+                pass.set_pipeline(scatter_odd);
+                dispatch.scatter(&mut pass);
             }
         }
     }
 
-    /// Get the key-value size (number of bytes per key, 4 for 32-bit, 2 for 16-bit)
+    /// Sort keys that hold raw `f32` bit patterns (32-bit sorter only).
+    ///
+    /// Keys are mapped in place to an order-preserving u32 form, sorted ascending and mapped back,
+    /// so `keys()` ends up holding sorted floats. -0.0 sorts before +0.0; NaNs with the sign bit set
+    /// land before -inf and the others after +inf.
+    pub fn sort_f32(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        bind_group: &wgpu::BindGroup,
+        count: u32,
+    ) {
+        assert_eq!(self.key_val_size, 4, "sort_f32 requires a 32-bit RadixSorter");
+        let workgroups = count.div_ceil(HISTOGRAM_WG_SIZE);
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Radix Sort Encode F32"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.encode_f32_pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(workgroups, 1, 1);
+        }
+
+        self.sort_with_bind_group(encoder, bind_group, count);
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Radix Sort Decode F32"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.decode_f32_pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(workgroups, 1, 1);
+        }
+    }
+
+    /// Bind the segment boundaries for `sort_segmented`: `segment_count` ascending `u32`
+    /// start indices at the beginning of `segment_starts`. Segment `i` covers
+    /// `starts[i]..starts[i + 1]`, the last one runs to the end of the keys, and the first
+    /// start should be 0. Equal starts make empty segments, so sizes can be uneven.
+    pub fn create_segment_bind_group(
+        &self,
+        device: &wgpu::Device,
+        segment_starts: &wgpu::Buffer,
+        segment_count: u32,
+    ) -> wgpu::BindGroup {
+        assert!(segment_count > 0, "create_segment_bind_group needs at least one segment");
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Radix Sort Segment Bind Group"),
+            layout: &self.segment_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: segment_starts,
+                    offset: 0,
+                    size: NonZeroU64::new(segment_count as u64 * 4),
+                }),
+            }],
+        })
+    }
+
+    /// Sort many packed arrays independently in one sort (64-bit sorter only).
+    ///
+    /// Keys are `u32` values in the low word of each 64-bit key. The high word is overwritten
+    /// with the segment index before sorting, so every key stays inside its segment and is
+    /// sorted ascending there, payload included. Afterwards the high words still hold the
+    /// segment indices.
+    pub fn sort_segmented(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        bind_group: &wgpu::BindGroup,
+        segment_bind_group: &wgpu::BindGroup,
+        count: u32,
+    ) {
+        let pipeline = self
+            .segment_pipeline
+            .as_ref()
+            .expect("sort_segmented requires a 64-bit RadixSorter (RadixSorter::new_u64)");
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Radix Sort Tag Segments"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.set_bind_group(1, segment_bind_group, &[]);
+            pass.dispatch_workgroups(count.div_ceil(HISTOGRAM_WG_SIZE), 1, 1);
+        }
+
+        self.sort_with_bind_group(encoder, bind_group, count);
+    }
+
+    /// Read back `SorterState` and report whether the last sort gave up on a stalled scatter pass.
+    /// When this returns true the output order is not guaranteed to be sorted.
+    pub fn check_failed(&self, device: &wgpu::Device, queue: &wgpu::Queue, buffers: &SortBuffers) -> bool {
+        let size = std::mem::size_of::<SorterState>() as u64;
+        let words = read_buffer_u32(device, queue, &buffers.state_buffer, size);
+        let state: &SorterState = bytemuck::from_bytes(bytemuck::cast_slice(&words));
+        state.sort_failed != 0
+    }
+
+    /// Get the key-value size (number of bytes per key, 8 for 64-bit, 4 for 32-bit, 2 for 16-bit)
     pub fn key_val_size(&self) -> u32 {
         self.key_val_size
     }
+
+    /// Whether the scatter passes use subgroup operations
+    pub fn uses_subgroups(&self) -> bool {
+        self.subgroups
+    }
 }
 
 /// Buffers for radix sorting
@@ -650,20 +880,96 @@ pub struct SortBuffers {
     pub keys_b: wgpu::Buffer,
     pub payload_a: wgpu::Buffer,
     pub payload_b: wgpu::Buffer,
+    /// Histogram and scatter workgroup counts used by `sort_indirect`
+    pub dispatch_args: wgpu::Buffer,
     pub bind_group: wgpu::BindGroup,
+    /// Capacity the buffers were created for; each sort covers any count up to it
     pub count: u32,
+    /// Created by `create_keys_only_buffers`; the payload buffers are placeholders
+    pub keys_only: bool,
+    /// Number of payload planes (0 for keys-only buffers)
+    pub payload_count: u32,
+    /// u32 words per key: 1, or 2 for 64-bit keys
+    pub key_words: u32,
 }
 
 impl SortBuffers {
+    /// Largest element count these buffers can sort
+    pub fn capacity(&self) -> u32 {
+        self.count
+    }
+
     /// Get the keys buffer (sorted output)
     pub fn keys(&self) -> &wgpu::Buffer {
         &self.keys_a
     }
 
     /// Get the payload/values buffer (sorted output)
+    ///
+    /// Panics for buffers made by `create_keys_only_buffers`, which have no payload.
     pub fn values(&self) -> &wgpu::Buffer {
+        assert!(!self.keys_only, "SortBuffers::values() called on keys-only sort buffers");
         &self.payload_a
     }
+
+    /// Get one sorted payload plane of buffers made by `create_sort_buffers_multi`.
+    /// The slice spans the capacity; entries past the last sort's count are stale.
+    pub fn values_at(&self, index: u32) -> wgpu::BufferSlice<'_> {
+        assert!(
+            index < self.payload_count,
+            "payload index {index} out of range ({} payloads)",
+            self.payload_count
+        );
+        let offset = index as u64 * keys_buffer_size(self.count) as u64 * 4;
+        self.payload_a.slice(offset..offset + self.count as u64 * 4)
+    }
+
+    /// Copy the sorted keys and payload back to the CPU (blocks until the GPU is done).
+    /// Returns the entries covered by the last sort; 64-bit keys come back as (low, high)
+    /// word pairs. The values are empty for keys-only buffers.
+    pub fn read_back(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> (Vec<u32>, Vec<u32>) {
+        let num_keys = read_buffer_u32(device, queue, &self.state_buffer, 4)[0].min(self.count) as u64;
+        let keys = read_buffer_u32(device, queue, &self.keys_a, num_keys * self.key_words as u64 * 4);
+        let values = if self.keys_only {
+            Vec::new()
+        } else {
+            read_buffer_u32(device, queue, &self.payload_a, num_keys * 4)
+        };
+        (keys, values)
+    }
+}
+
+/// Copy the first `size` bytes of a COPY_SRC buffer into a staging buffer and map it
+fn read_buffer_u32(device: &wgpu::Device, queue: &wgpu::Queue, buffer: &wgpu::Buffer, size: u64) -> Vec<u32> {
+    if size == 0 {
+        return Vec::new();
+    }
+    let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Radix Sort Readback Buffer"),
+        size,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Radix Sort Readback Encoder"),
+    });
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging_buffer, 0, size);
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let buffer_slice = staging_buffer.slice(..);
+    let (tx, rx) = std::sync::mpsc::channel();
+    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = tx.send(result);
+    });
+    let _ = device.poll(wgpu::PollType::wait_indefinitely());
+    rx.recv()
+        .expect("Radix sort readback channel closed")
+        .expect("Failed to map radix sort readback buffer");
+
+    let data = bytemuck::cast_slice(&buffer_slice.get_mapped_range()).to_vec();
+    staging_buffer.unmap();
+    data
 }
 
 fn scatter_blocks_ru(n: u32) -> u32 {
@@ -0,0 +1,24 @@
+// Scatter ranking without subgroup operations: every invocation is a subgroup of one
+// (`sg` is (local index, 0, 1)), so each key is ranked alone and the invocations take
+// turns claiming histogram slots.
+
+fn rank_digits(pass_: u32, sg: vec3<u32>) {
+    for (var i = 0u; i < rs_scatter_block_rows; i++) {
+        kr[i] = (1u << 16u) | 1u;
+    }
+}
+
+fn claim_ranks(pass_: u32, sg: vec3<u32>) {
+    // Invocations (and their rows) take ranks strictly in key order, which keeps the sort stable
+    for (var i = 0u; i < {scatter_wg_size}u; i++) {
+        if sg.x == i {
+            for (var j = 0u; j < rs_scatter_block_rows; j++) {
+                let digit = kv_digit(j, pass_);
+                let prev = histogram_load(digit);
+                kr[j] = prev + 1u;
+                histogram_store(digit, prev + 1u);
+            }
+        }
+        workgroupBarrier();
+    }
+}
@@ -0,0 +1,76 @@
+// Scatter ranking with subgroup operations (needs `Features::SUBGROUP`). Lanes holding
+// the same digit in a row find each other with one ballot per digit bit, and whole
+// subgroups take turns claiming histogram slots, which needs
+// `scatter_wg_size / subgroup_size` turns instead of one per invocation.
+
+// Last lane of each row's group of equal digits, the one that updates the histogram
+var<private> kr_leader: array<u32, rs_scatter_block_rows>;
+
+// Ballot mask of lanes 0..=lane
+fn lanes_up_to(lane: u32) -> vec4<u32> {
+    var mask = vec4<u32>(0u);
+    for (var c = 0u; c < 4u; c++) {
+        let first = c * 32u;
+        if lane >= first + 31u {
+            mask[c] = 0xFFFFFFFFu;
+        } else if lane >= first {
+            mask[c] = (2u << (lane - first)) - 1u;
+        }
+    }
+    return mask;
+}
+
+fn count_lanes(mask: vec4<u32>) -> u32 {
+    let counts = countOneBits(mask);
+    return counts.x + counts.y + counts.z + counts.w;
+}
+
+fn highest_lane(mask: vec4<u32>) -> u32 {
+    for (var c = 3i; c >= 0i; c--) {
+        if mask[c] != 0u {
+            return u32(c) * 32u + firstLeadingBit(mask[c]);
+        }
+    }
+    return 0u;
+}
+
+fn rank_digits(pass_: u32, sg: vec3<u32>) {
+    let lanes = subgroupBallot(true);
+    let up_to = lanes_up_to(sg.y);
+    for (var i = 0u; i < rs_scatter_block_rows; i++) {
+        let digit = kv_digit(i, pass_);
+        var peers = lanes;
+        for (var b = 0u; b < rs_radix_log2; b++) {
+            let bit_set = ((digit >> b) & 1u) == 1u;
+            let ballot = subgroupBallot(bit_set);
+            peers &= select(~ballot, ballot, bit_set);
+        }
+        kr[i] = (count_lanes(peers) << 16u) | count_lanes(peers & up_to);
+        kr_leader[i] = highest_lane(peers);
+    }
+}
+
+fn claim_ranks(pass_: u32, sg: vec3<u32>) {
+    let subgroup_count = {scatter_wg_size}u / sg.z;
+    // Subgroups (and their rows) take ranks strictly in key order, which keeps the sort stable
+    for (var i = 0u; i < subgroup_count; i++) {
+        let turn = sg.x == i;
+        for (var j = 0u; j < rs_scatter_block_rows; j++) {
+            let rank = kr[j] & 0xFFFFu;
+            let count = kr[j] >> 16u;
+            // Only the leader touches the histogram and hands its base to the others, so
+            // lanes don't need to run in lockstep
+            var base = 0u;
+            if turn && rank == count {
+                let digit = kv_digit(j, pass_);
+                base = histogram_load(digit);
+                histogram_store(digit, base + count);
+            }
+            base = subgroupShuffle(base, kr_leader[j]);
+            if turn {
+                kr[j] = base + rank;
+            }
+        }
+        workgroupBarrier();
+    }
+}
@@ -35,6 +35,10 @@ struct GeneralInfo {
     even_pass: u32,
     odd_pass: u32,
     sort_failed: atomic<u32>,
+    histogram_dispatch: array<u32, 3>,
+    scatter_dispatch: array<u32, 3>,
+    payload_count: u32,
+    capacity: u32,
 };
 
 @group(0) @binding(0) var<storage, read_write> infos: GeneralInfo;
@@ -46,6 +50,27 @@ struct GeneralInfo {
 
 var<workgroup> smem: array<atomic<u32>, rs_radix_size>;
 var<private> kv: array<u32, rs_histogram_block_rows>;
+// High words of 64-bit keys (only used when rs_key_words == 2)
+var<private> kv_hi: array<u32, rs_histogram_block_rows>;
+
+// Set per pipeline: flips every digit so the largest keys sort first
+override sort_descending: bool = false;
+// Set per pipeline: scatter passes neither read nor write the payload
+override sort_keys_only: bool = false;
+
+// Radix digit of the i-th key for the given pass, reading the high word for 64-bit keys
+fn kv_digit(i: u32, pass_: u32) -> u32 {
+    var digit: u32;
+    if pass_ >= 4u {
+        digit = extractBits(kv_hi[i], (pass_ - 4u) * rs_radix_log2, rs_radix_log2);
+    } else {
+        digit = extractBits(kv[i], pass_ * rs_radix_log2, rs_radix_log2);
+    }
+    if sort_descending {
+        digit ^= rs_radix_size - 1u;
+    }
+    return digit;
+}
 
 fn zero_smem(lid: u32) {
     if lid < rs_radix_size {
@@ -53,11 +78,26 @@ fn zero_smem(lid: u32) {
     }
 }
 
+// Single-thread pass for sort_indirect: clamp the GPU-written count and fill the dispatch args
+@compute @workgroup_size(1)
+fn setup_indirect() {
+    let num_keys = min(infos.num_keys, infos.capacity);
+    infos.num_keys = num_keys;
+
+    let scatter_block_kvs = histogram_wg_size * rs_scatter_block_rows;
+    let histo_block_kvs = histogram_wg_size * rs_histogram_block_rows;
+    let scatter_blocks = (num_keys + scatter_block_kvs - 1u) / scatter_block_kvs;
+    let histo_blocks = (scatter_blocks * scatter_block_kvs + histo_block_kvs - 1u) / histo_block_kvs;
+
+    infos.histogram_dispatch = array<u32, 3>(histo_blocks, 1u, 1u);
+    infos.scatter_dispatch = array<u32, 3>(scatter_blocks, 1u, 1u);
+}
+
 @compute @workgroup_size({histogram_wg_size})
 fn zero_histograms(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) nwg: vec3<u32>) {
     if gid.x == 0u {
         infos.even_pass = 0u;
-        infos.odd_pass = 1u;
+        infos.odd_pass = rs_keyval_size / 2u - 1u;
         atomicStore(&infos.sort_failed, 0u);  // Reset global failure flag
     }
 
@@ -68,8 +108,13 @@ fn zero_histograms(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_w
 
     var n = (rs_keyval_size + scatter_blocks_ru - 1u) * histo_size;
     let b = n;
-    if infos.num_keys < infos.padded_size {
-        n += infos.padded_size - infos.num_keys;
+    // Pad only the blocks this sort reads; with buffers sized for a larger capacity the
+    // keys past that are never touched
+    let histo_block_kvs = histogram_wg_size * rs_histogram_block_rows;
+    let histo_blocks_ru = (scatter_blocks_ru * scatter_block_kvs + histo_block_kvs - 1u) / histo_block_kvs;
+    let padded_end = min(histo_blocks_ru * histo_block_kvs, infos.padded_size);
+    if infos.num_keys < padded_end {
+        n += padded_end - infos.num_keys;
     }
 
     let line_size = nwg.x * {histogram_wg_size}u;
@@ -79,9 +124,58 @@ fn zero_histograms(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_w
         } else if cur_index < b {
             atomicStore(&histograms[cur_index], 0u);
         } else {
-            keys[infos.num_keys + cur_index - b] = 0xFFFFFFFFu;
+            // Padding keys must land at the end in either order
+            let pad_key = select(0xFFFFFFFFu, 0u, sort_descending);
+            let key_idx = (infos.num_keys + cur_index - b) * rs_key_words;
+            keys[key_idx] = pad_key;
+            if rs_key_words == 2u {
+                keys[key_idx + 1u] = pad_key;
+            }
+        }
+    }
+}
+
+// Monotonic f32 <-> u32 mapping: flip the sign bit of positives, all bits of negatives
+@compute @workgroup_size({histogram_wg_size})
+fn encode_f32_keys(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if gid.x < infos.num_keys {
+        let bits = keys[gid.x];
+        let mask = select(0x80000000u, 0xFFFFFFFFu, (bits & 0x80000000u) != 0u);
+        keys[gid.x] = bits ^ mask;
+    }
+}
+
+@compute @workgroup_size({histogram_wg_size})
+fn decode_f32_keys(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if gid.x < infos.num_keys {
+        let bits = keys[gid.x];
+        let mask = select(0xFFFFFFFFu, 0x80000000u, (bits & 0x80000000u) != 0u);
+        keys[gid.x] = bits ^ mask;
+    }
+}
+
+// Start index of each segment, ascending; only bound by RadixSorter::sort_segmented
+@group(1) @binding(0) var<storage, read> segment_starts: array<u32>;
+
+// Write the segment index into the high word of every 64-bit key, so one sort over the
+// whole buffer keeps segments in place and orders keys within each of them
+@compute @workgroup_size({histogram_wg_size})
+fn tag_segments(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if rs_key_words != 2u || gid.x >= infos.num_keys {
+        return;
+    }
+    // Last segment starting at or before this key; empty segments share a start and are skipped
+    var lo = 0u;
+    var hi = arrayLength(&segment_starts);
+    while lo + 1u < hi {
+        let mid = (lo + hi) / 2u;
+        if segment_starts[mid] <= gid.x {
+            lo = mid;
+        } else {
+            hi = mid;
         }
     }
+    keys[gid.x * 2u + 1u] = lo;
 }
 
 fn histogram_pass(pass_: u32, lid: u32) {
@@ -89,8 +183,7 @@ fn histogram_pass(pass_: u32, lid: u32) {
     workgroupBarrier();
 
     for (var j = 0u; j < rs_histogram_block_rows; j++) {
-        let u_val = kv[j];
-        let digit = extractBits(u_val, pass_ * rs_radix_log2, rs_radix_log2);
+        let digit = kv_digit(j, pass_);
         atomicAdd(&smem[digit], 1u);
     }
 
@@ -106,7 +199,10 @@ fn fill_kv(wid: u32, lid: u32) {
     let kv_in_offset = wid * rs_block_keyvals + lid;
     for (var i = 0u; i < rs_histogram_block_rows; i++) {
         let pos = kv_in_offset + i * histogram_wg_size;
-        kv[i] = keys[pos];
+        kv[i] = keys[pos * rs_key_words];
+        if rs_key_words == 2u {
+            kv_hi[i] = keys[pos * 2u + 1u];
+        }
     }
 }
 
@@ -115,17 +211,17 @@ fn fill_kv_keys_b(wid: u32, lid: u32) {
     let kv_in_offset = wid * rs_block_keyvals + lid;
     for (var i = 0u; i < rs_histogram_block_rows; i++) {
         let pos = kv_in_offset + i * histogram_wg_size;
-        kv[i] = keys_b[pos];
+        kv[i] = keys_b[pos * rs_key_words];
+        if rs_key_words == 2u {
+            kv_hi[i] = keys_b[pos * 2u + 1u];
+        }
     }
 }
 
 @compute @workgroup_size({histogram_wg_size})
 fn calculate_histogram(@builtin(workgroup_id) wid: vec3<u32>, @builtin(local_invocation_id) lid: vec3<u32>) {
     fill_kv(wid.x, lid.x);
-    histogram_pass(3u, lid.x);
-    histogram_pass(2u, lid.x);
-    histogram_pass(1u, lid.x);
-    histogram_pass(0u, lid.x);
+    {histogram_passes}
 }
 
 fn prefix_reduce_smem(lid: u32) {
@@ -175,8 +271,11 @@ fn prefix_histogram(@builtin(workgroup_id) wid: vec3<u32>, @builtin(local_invoca
 // Scatter variables
 var<workgroup> scatter_smem: array<u32, rs_mem_dwords>;
 var<workgroup> scatter_failed: atomic<u32>;
+var<workgroup> scatter_payload_count: u32;
 var<private> kr: array<u32, rs_scatter_block_rows>;
 var<private> pv: array<u32, rs_scatter_block_rows>;
+// Local reorder slots, kept for scattering payload planes beyond the first
+var<private> kr_pre: array<u32, rs_scatter_block_rows>;
 
 const rs_partition_mask_status: u32 = 0xC0000000u;
 const rs_partition_mask_count: u32 = 0x3FFFFFFFu;
@@ -192,45 +291,57 @@ fn histogram_store(digit: u32, count: u32) {
     atomicStore(&smem[digit], count);
 }
 
-fn fill_kv_even(wid: u32, lid: u32) {
-    let subgroup_id = lid / histogram_sg_size;
-    let subgroup_invoc_id = lid - subgroup_id * histogram_sg_size;
-    let subgroup_keyvals = rs_scatter_block_rows * histogram_sg_size;
+// First key of this invocation in its scatter block; `sg` is (subgroup, lane, subgroup size).
+// Each subgroup reads a contiguous run row by row, so ranking lanes in order and subgroups
+// in turn keeps the sort stable
+fn payload_in_offset(wid: u32, sg: vec3<u32>) -> u32 {
+    let subgroup_keyvals = rs_scatter_block_rows * sg.z;
     let rs_block_keyvals = rs_histogram_block_rows * histogram_wg_size;
-    let kv_in_offset = wid * rs_block_keyvals + subgroup_id * subgroup_keyvals + subgroup_invoc_id;
+    return wid * rs_block_keyvals + sg.x * subgroup_keyvals + sg.y;
+}
+
+fn fill_kv_even(wid: u32, sg: vec3<u32>) {
+    let kv_in_offset = payload_in_offset(wid, sg);
 
     for (var i = 0u; i < rs_histogram_block_rows; i++) {
-        let pos = kv_in_offset + i * histogram_sg_size;
-        kv[i] = keys[pos];
+        let pos = kv_in_offset + i * sg.z;
+        kv[i] = keys[pos * rs_key_words];
+        if rs_key_words == 2u {
+            kv_hi[i] = keys[pos * 2u + 1u];
+        }
     }
-    for (var i = 0u; i < rs_histogram_block_rows; i++) {
-        let pos = kv_in_offset + i * histogram_sg_size;
-        pv[i] = payload_a[pos];
+    if !sort_keys_only {
+        for (var i = 0u; i < rs_histogram_block_rows; i++) {
+            let pos = kv_in_offset + i * sg.z;
+            pv[i] = payload_a[pos];
+        }
     }
 }
 
-fn fill_kv_odd(wid: u32, lid: u32) {
-    let subgroup_id = lid / histogram_sg_size;
-    let subgroup_invoc_id = lid - subgroup_id * histogram_sg_size;
-    let subgroup_keyvals = rs_scatter_block_rows * histogram_sg_size;
-    let rs_block_keyvals = rs_histogram_block_rows * histogram_wg_size;
-    let kv_in_offset = wid * rs_block_keyvals + subgroup_id * subgroup_keyvals + subgroup_invoc_id;
+fn fill_kv_odd(wid: u32, sg: vec3<u32>) {
+    let kv_in_offset = payload_in_offset(wid, sg);
 
     for (var i = 0u; i < rs_histogram_block_rows; i++) {
-        let pos = kv_in_offset + i * histogram_sg_size;
-        kv[i] = keys_b[pos];
+        let pos = kv_in_offset + i * sg.z;
+        kv[i] = keys_b[pos * rs_key_words];
+        if rs_key_words == 2u {
+            kv_hi[i] = keys_b[pos * 2u + 1u];
+        }
     }
-    for (var i = 0u; i < rs_histogram_block_rows; i++) {
-        let pos = kv_in_offset + i * histogram_sg_size;
-        pv[i] = payload_b[pos];
+    if !sort_keys_only {
+        for (var i = 0u; i < rs_histogram_block_rows; i++) {
+            let pos = kv_in_offset + i * sg.z;
+            pv[i] = payload_b[pos];
+        }
     }
 }
 
-fn scatter(pass_: u32, lid: vec3<u32>, gid: vec3<u32>, wid: vec3<u32>, nwg: vec3<u32>,
+fn scatter(pass_: u32, lid: vec3<u32>, gid: vec3<u32>, wid: vec3<u32>, nwg: vec3<u32>, sg: vec3<u32>,
            partition_status_invalid: u32, partition_status_reduction: u32, partition_status_prefix: u32) -> bool {
     // Initialize failure flag
     if lid.x == 0u {
         atomicStore(&scatter_failed, 0u);
+        scatter_payload_count = infos.payload_count;
     }
     workgroupBarrier();
 
@@ -238,48 +349,12 @@ fn scatter(pass_: u32, lid: vec3<u32>, gid: vec3<u32>, wid: vec3<u32>, nwg: vec3
     let partition_mask_reduction = partition_status_reduction << 30u;
     let partition_mask_prefix = partition_status_prefix << 30u;
 
-    let subgroup_id = lid.x / histogram_sg_size;
-    let subgroup_offset = subgroup_id * histogram_sg_size;
-    let subgroup_tid = lid.x - subgroup_offset;
-    let subgroup_count = {scatter_wg_size}u / histogram_sg_size;
-
-    for (var i = 0u; i < rs_scatter_block_rows; i++) {
-        let u_val = kv[i];
-        let digit = extractBits(u_val, pass_ * rs_radix_log2, rs_radix_log2);
-        atomicStore(&smem[lid.x], digit);
-        var count = 0u;
-        var rank = 0u;
-
-        for (var j = 0u; j < histogram_sg_size; j++) {
-            if atomicLoad(&smem[subgroup_offset + j]) == digit {
-                count += 1u;
-                if j <= subgroup_tid {
-                    rank += 1u;
-                }
-            }
-        }
-        kr[i] = (count << 16u) | rank;
-    }
+    rank_digits(pass_, sg);
 
     zero_smem(lid.x);
     workgroupBarrier();
 
-    for (var i = 0u; i < subgroup_count; i++) {
-        if subgroup_id == i {
-            for (var j = 0u; j < rs_scatter_block_rows; j++) {
-                let v = kv[j];
-                let digit = extractBits(v, pass_ * rs_radix_log2, rs_radix_log2);
-                let prev = histogram_load(digit);
-                let rank = kr[j] & 0xFFFFu;
-                let count = kr[j] >> 16u;
-                kr[j] = prev + rank;
-                if rank == count {
-                    histogram_store(digit, prev + count);
-                }
-            }
-        }
-        workgroupBarrier();
-    }
+    claim_ranks(pass_, sg);
 
     let partition_offset = lid.x + partitions_base_offset();
     let partition_base = wid.x * rs_radix_size;
@@ -340,8 +415,7 @@ fn scatter(pass_: u32, lid: vec3<u32>, gid: vec3<u32>, wid: vec3<u32>, nwg: vec3
     workgroupBarrier();
 
     for (var i = 0u; i < rs_scatter_block_rows; i++) {
-        let v = kv[i];
-        let digit = extractBits(v, pass_ * rs_radix_log2, rs_radix_log2);
+        let digit = kv_digit(i, pass_);
         let exc = histogram_load(digit);
         let idx = exc + kr[i];
         kr[i] |= (idx << 16u);
@@ -362,16 +436,36 @@ fn scatter(pass_: u32, lid: vec3<u32>, gid: vec3<u32>, wid: vec3<u32>, nwg: vec3
     }
     workgroupBarrier();
 
-    for (var j = 0u; j < rs_scatter_block_rows; j++) {
-        let smem_idx = smem_reorder_offset + (kr[j] >> 16u) - 1u;
-        scatter_smem[smem_idx] = pv[j];
+    if rs_key_words == 2u {
+        for (var j = 0u; j < rs_scatter_block_rows; j++) {
+            let smem_idx = smem_reorder_offset + (kr[j] >> 16u) - 1u;
+            scatter_smem[smem_idx] = kv_hi[j];
+        }
+        workgroupBarrier();
+
+        for (var j = 0u; j < rs_scatter_block_rows; j++) {
+            kv_hi[j] = scatter_smem[smem_base + j * {scatter_wg_size}u];
+        }
+        workgroupBarrier();
     }
-    workgroupBarrier();
 
-    for (var j = 0u; j < rs_scatter_block_rows; j++) {
-        pv[j] = scatter_smem[smem_base + j * {scatter_wg_size}u];
+    if !sort_keys_only {
+        if scatter_payload_count > 1u {
+            for (var j = 0u; j < rs_scatter_block_rows; j++) {
+                kr_pre[j] = kr[j];
+            }
+        }
+        for (var j = 0u; j < rs_scatter_block_rows; j++) {
+            let smem_idx = smem_reorder_offset + (kr[j] >> 16u) - 1u;
+            scatter_smem[smem_idx] = pv[j];
+        }
+        workgroupBarrier();
+
+        for (var j = 0u; j < rs_scatter_block_rows; j++) {
+            pv[j] = scatter_smem[smem_base + j * {scatter_wg_size}u];
+        }
+        workgroupBarrier();
     }
-    workgroupBarrier();
 
     for (var i = 0u; i < rs_scatter_block_rows; i++) {
         let smem_idx = smem_reorder_offset + (kr[i] >> 16u) - 1u;
@@ -384,8 +478,7 @@ fn scatter(pass_: u32, lid: vec3<u32>, gid: vec3<u32>, wid: vec3<u32>, nwg: vec3
     }
 
     for (var i = 0u; i < rs_scatter_block_rows; i++) {
-        let v = kv[i];
-        let digit = extractBits(v, pass_ * rs_radix_log2, rs_radix_log2);
+        let digit = kv_digit(i, pass_);
         let exc = scatter_smem[digit];
         kr[i] += exc - 1u;
     }
@@ -395,46 +488,111 @@ fn scatter(pass_: u32, lid: vec3<u32>, gid: vec3<u32>, wid: vec3<u32>, nwg: vec3
     return atomicLoad(&scatter_failed) == 0u;
 }
 
+// Same local reorder scatter() applies to the first payload, using the saved slots
+fn reorder_pv(lid: u32) {
+    let smem_reorder_offset = rs_radix_size;
+    let smem_base = smem_reorder_offset + lid;
+
+    for (var j = 0u; j < rs_scatter_block_rows; j++) {
+        let smem_idx = smem_reorder_offset + (kr_pre[j] >> 16u) - 1u;
+        scatter_smem[smem_idx] = pv[j];
+    }
+    workgroupBarrier();
+
+    for (var j = 0u; j < rs_scatter_block_rows; j++) {
+        pv[j] = scatter_smem[smem_base + j * {scatter_wg_size}u];
+    }
+    workgroupBarrier();
+}
+
 @compute @workgroup_size({scatter_wg_size})
 fn scatter_even(@builtin(workgroup_id) wid: vec3<u32>, @builtin(local_invocation_id) lid: vec3<u32>,
-                @builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) nwg: vec3<u32>) {
+                @builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) nwg: vec3<u32>{subgroup_builtins}) {
+    let sg = {subgroup_layout};
     if gid.x == 0u {
-        infos.odd_pass = (infos.odd_pass + 1u) % 2u;
+        infos.odd_pass = (infos.odd_pass + 1u) % (rs_keyval_size / 2u);
     }
     let cur_pass = infos.even_pass * 2u;
 
-    fill_kv_even(wid.x, lid.x);
-    let success = scatter(cur_pass, lid, gid, wid, nwg, 0u, 1u, 2u);
+    fill_kv_even(wid.x, sg);
+    let success = scatter(cur_pass, lid, gid, wid, nwg, sg, 0u, 1u, 2u);
 
     // Only write if scatter succeeded - otherwise keep previous order
     if success {
         for (var i = 0u; i < rs_scatter_block_rows; i++) {
-            keys_b[kr[i]] = kv[i];
+            keys_b[kr[i] * rs_key_words] = kv[i];
+            if rs_key_words == 2u {
+                keys_b[kr[i] * 2u + 1u] = kv_hi[i];
+            }
         }
-        for (var i = 0u; i < rs_scatter_block_rows; i++) {
-            payload_b[kr[i]] = pv[i];
+        if !sort_keys_only {
+            for (var i = 0u; i < rs_scatter_block_rows; i++) {
+                payload_b[kr[i]] = pv[i];
+            }
+        }
+    }
+
+    // Remaining payload planes follow the same permutation
+    if !sort_keys_only {
+        let payload_count = workgroupUniformLoad(&scatter_payload_count);
+        let kv_in_offset = payload_in_offset(wid.x, sg);
+        for (var p = 1u; p < payload_count; p++) {
+            let plane = p * infos.padded_size;
+            for (var i = 0u; i < rs_scatter_block_rows; i++) {
+                pv[i] = payload_a[plane + kv_in_offset + i * sg.z];
+            }
+            reorder_pv(lid.x);
+            if success {
+                for (var i = 0u; i < rs_scatter_block_rows; i++) {
+                    payload_b[plane + kr[i]] = pv[i];
+                }
+            }
         }
     }
 }
 
 @compute @workgroup_size({scatter_wg_size})
 fn scatter_odd(@builtin(workgroup_id) wid: vec3<u32>, @builtin(local_invocation_id) lid: vec3<u32>,
-               @builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) nwg: vec3<u32>) {
+               @builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) nwg: vec3<u32>{subgroup_builtins}) {
+    let sg = {subgroup_layout};
     if gid.x == 0u {
-        infos.even_pass = (infos.even_pass + 1u) % 2u;
+        infos.even_pass = (infos.even_pass + 1u) % (rs_keyval_size / 2u);
     }
     let cur_pass = infos.odd_pass * 2u + 1u;
 
-    fill_kv_odd(wid.x, lid.x);
-    let success = scatter(cur_pass, lid, gid, wid, nwg, 2u, 3u, 0u);
+    fill_kv_odd(wid.x, sg);
+    let success = scatter(cur_pass, lid, gid, wid, nwg, sg, 2u, 3u, 0u);
 
     // Only write if scatter succeeded - otherwise keep previous order
     if success {
         for (var i = 0u; i < rs_scatter_block_rows; i++) {
-            keys[kr[i]] = kv[i];
+            keys[kr[i] * rs_key_words] = kv[i];
+            if rs_key_words == 2u {
+                keys[kr[i] * 2u + 1u] = kv_hi[i];
+            }
         }
-        for (var i = 0u; i < rs_scatter_block_rows; i++) {
-            payload_a[kr[i]] = pv[i];
+        if !sort_keys_only {
+            for (var i = 0u; i < rs_scatter_block_rows; i++) {
+                payload_a[kr[i]] = pv[i];
+            }
+        }
+    }
+
+    // Remaining payload planes follow the same permutation
+    if !sort_keys_only {
+        let payload_count = workgroupUniformLoad(&scatter_payload_count);
+        let kv_in_offset = payload_in_offset(wid.x, sg);
+        for (var p = 1u; p < payload_count; p++) {
+            let plane = p * infos.padded_size;
+            for (var i = 0u; i < rs_scatter_block_rows; i++) {
+                pv[i] = payload_b[plane + kv_in_offset + i * sg.z];
+            }
+            reorder_pv(lid.x);
+            if success {
+                for (var i = 0u; i < rs_scatter_block_rows; i++) {
+                    payload_a[plane + kr[i]] = pv[i];
+                }
+            }
         }
     }
 }
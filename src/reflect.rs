@@ -0,0 +1,455 @@
+use std::collections::BTreeMap;
+
+use wgpu::naga;
+
+use crate::ShaderLoadError;
+
+/// One `@group(g) @binding(b)` variable found in a shader
+#[derive(Debug, Clone)]
+pub struct ReflectedBinding {
+    pub name: String,
+    pub binding: u32,
+    pub entry: wgpu::BindGroupLayoutEntry,
+    /// Whether any entry point uses it; unused variables get every stage as visibility
+    pub used: bool,
+}
+
+/// Bind group layouts generated from the `@group`/`@binding` declarations in WGSL, so
+/// pipelines don't need hand-written `BindGroupLayoutDescriptor`s that can drift from
+/// the shader.
+///
+/// Visibility comes from the entry points that actually use each variable, and uniform
+/// and fixed-size storage buffers get their size as `min_binding_size`. Sampled float
+/// textures are assumed filterable and samplers filtering.
+///
+/// ```ignore
+/// let layout = ShaderLayout::from_wgsl(&core.device, source, "Orbits")?;
+/// let pipeline_layout = layout.pipeline_layout(&core.device);
+/// let params_group = layout.create_bind_group(&core.device, 1, &[
+///     ("params", params_buffer.as_entire_binding()),
+///     ("noise", wgpu::BindingResource::TextureView(&noise.view)),
+///     ("noise_sampler", wgpu::BindingResource::Sampler(&noise.sampler)),
+/// ]);
+/// ```
+pub struct ShaderLayout {
+    /// Bindings of each group, by group index; groups the shader skips are empty
+    groups: Vec<Vec<ReflectedBinding>>,
+    layouts: Vec<wgpu::BindGroupLayout>,
+    label: String,
+}
+
+impl ShaderLayout {
+    /// Parse and validate `source` and build a layout for every group up to the highest
+    /// one declared. WGSL errors come back as [`ShaderLoadError::Compile`].
+    pub fn from_wgsl(device: &wgpu::Device, source: &str, label: &str) -> Result<Self, ShaderLoadError> {
+        let groups = reflect_bindings(source)?;
+        let layouts = groups
+            .iter()
+            .enumerate()
+            .map(|(group, bindings)| {
+                let entries: Vec<_> = bindings.iter().map(|binding| binding.entry).collect();
+                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some(&format!("{label} Group {group} Layout")),
+                    entries: &entries,
+                })
+            })
+            .collect();
+        Ok(Self {
+            groups,
+            layouts,
+            label: label.to_string(),
+        })
+    }
+
+    /// Layouts of groups 0, 1, ... in order
+    pub fn bind_group_layouts(&self) -> &[wgpu::BindGroupLayout] {
+        &self.layouts
+    }
+
+    pub fn bind_group_layout(&self, group: u32) -> Option<&wgpu::BindGroupLayout> {
+        self.layouts.get(group as usize)
+    }
+
+    /// Bindings declared in `group`, in binding order
+    pub fn bindings(&self, group: u32) -> &[ReflectedBinding] {
+        self.groups.get(group as usize).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn pipeline_layout(&self, device: &wgpu::Device) -> wgpu::PipelineLayout {
+        let layouts: Vec<_> = self.layouts.iter().map(Some).collect();
+        device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&format!("{} Pipeline Layout", self.label)),
+            bind_group_layouts: &layouts,
+            immediate_size: 0,
+        })
+    }
+
+    /// Bind group for `group` from resources named like the WGSL variables, in any order.
+    /// Panics naming the variable if one is missing or a name isn't in the group, since
+    /// either means the Rust side and the shader disagree.
+    pub fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        group: u32,
+        resources: &[(&str, wgpu::BindingResource)],
+    ) -> wgpu::BindGroup {
+        let bindings = self.bindings(group);
+        for (name, _) in resources {
+            assert!(
+                bindings.iter().any(|binding| binding.name == *name),
+                "{}: group {group} has no variable '{name}'",
+                self.label
+            );
+        }
+        let entries: Vec<_> = bindings
+            .iter()
+            .map(|binding| {
+                let (_, resource) = resources
+                    .iter()
+                    .find(|(name, _)| *name == binding.name)
+                    .unwrap_or_else(|| panic!("{}: no resource for '{}' (group {group})", self.label, binding.name));
+                wgpu::BindGroupEntry {
+                    binding: binding.binding,
+                    resource: resource.clone(),
+                }
+            })
+            .collect();
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("{} Group {group}", self.label)),
+            layout: &self.layouts[group as usize],
+            entries: &entries,
+        })
+    }
+}
+
+/// Check in debug builds that hand-written layout descriptors, in pipeline layout
+/// order, fit the `@group`/`@binding` declarations of `source`. Panics on the first
+/// mismatch with the group, variable and both sides spelled out. Does nothing in
+/// release builds or if `source` doesn't compile (pipeline creation reports that).
+///
+/// Each variable an entry point uses is matched to the layout entry with its binding
+/// index. Layouts that are structurally identical, e.g. two uniform buffers without
+/// `min_binding_size`, can't be told apart; give buffers their `min_binding_size` so
+/// a swap shows up as a size mismatch.
+///
+/// ```ignore
+/// check_layout_order(source, "Droste", &[&texture_desc, &time_desc, &params_desc, &resolution_desc]);
+/// ```
+pub fn check_layout_order(source: &str, label: &str, layouts: &[&wgpu::BindGroupLayoutDescriptor]) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    if let Some(problem) = layout_mismatch(source, label, layouts) {
+        panic!("{problem}; is the layout order the same as the WGSL @group numbers?");
+    }
+}
+
+/// The first used variable of `source` that `layouts` (indexed by group) has no
+/// matching entry for, described for an error message
+pub(crate) fn layout_mismatch(
+    source: &str,
+    label: &str,
+    layouts: &[&wgpu::BindGroupLayoutDescriptor],
+) -> Option<String> {
+    let groups = reflect_bindings(source).ok()?;
+    for (group, bindings) in groups.iter().enumerate() {
+        for binding in bindings.iter().filter(|binding| binding.used) {
+            let Some(layout) = layouts.get(group) else {
+                return Some(format!(
+                    "{label}: shader uses group {group} ('{}') but only {} layouts were supplied",
+                    binding.name,
+                    layouts.len()
+                ));
+            };
+            let layout_label = layout.label.unwrap_or("unlabelled");
+            let Some(entry) = layout.entries.iter().find(|entry| entry.binding == binding.binding) else {
+                return Some(format!(
+                    "{label}: layout '{layout_label}' at group {group} has no binding {} for '{}'",
+                    binding.binding, binding.name
+                ));
+            };
+            if let Some(problem) = entry_mismatch(&binding.entry.ty, &entry.ty) {
+                return Some(format!(
+                    "{label}: group {group} binding {} ('{}') {problem}: shader wants {:?}, layout \
+                     '{layout_label}' has {:?}",
+                    binding.binding, binding.name, binding.entry.ty, entry.ty
+                ));
+            }
+        }
+    }
+    None
+}
+
+/// Why a layout entry can't serve a shader binding, if it can't
+fn entry_mismatch(shader: &wgpu::BindingType, layout: &wgpu::BindingType) -> Option<&'static str> {
+    use wgpu::BindingType as Bt;
+    match (shader, layout) {
+        (
+            Bt::Buffer {
+                ty: shader_ty,
+                min_binding_size: shader_size,
+                ..
+            },
+            Bt::Buffer {
+                ty: layout_ty,
+                min_binding_size: layout_size,
+                ..
+            },
+        ) => {
+            let access_ok = match (shader_ty, layout_ty) {
+                (wgpu::BufferBindingType::Uniform, wgpu::BufferBindingType::Uniform) => true,
+                (
+                    wgpu::BufferBindingType::Storage { read_only: shader_ro },
+                    wgpu::BufferBindingType::Storage { read_only: layout_ro },
+                ) => *shader_ro || !*layout_ro,
+                _ => false,
+            };
+            if !access_ok {
+                Some("has the wrong buffer type")
+            } else if matches!((shader_size, layout_size), (Some(s), Some(l)) if l < s) {
+                Some("is larger than the layout's min_binding_size")
+            } else {
+                None
+            }
+        }
+        (
+            Bt::Texture {
+                view_dimension: shader_dim,
+                multisampled: shader_ms,
+                ..
+            },
+            Bt::Texture {
+                view_dimension: layout_dim,
+                multisampled: layout_ms,
+                ..
+            },
+        ) => (shader_dim != layout_dim || shader_ms != layout_ms).then_some("has a different texture dimension"),
+        (
+            Bt::StorageTexture {
+                format: shader_format,
+                view_dimension: shader_dim,
+                ..
+            },
+            Bt::StorageTexture {
+                format: layout_format,
+                view_dimension: layout_dim,
+                ..
+            },
+        ) => (shader_format != layout_format || shader_dim != layout_dim)
+            .then_some("has a different storage texture format or dimension"),
+        (Bt::Sampler(shader_ty), Bt::Sampler(layout_ty)) => {
+            let comparison = |ty: &wgpu::SamplerBindingType| *ty == wgpu::SamplerBindingType::Comparison;
+            (comparison(shader_ty) != comparison(layout_ty)).then_some("has a different sampler type")
+        }
+        _ if std::mem::discriminant(shader) == std::mem::discriminant(layout) => None,
+        _ => Some("is a different kind of resource"),
+    }
+}
+
+/// Resource bindings of a WGSL module, grouped by `@group` and sorted by `@binding`
+pub(crate) fn reflect_bindings(source: &str) -> Result<Vec<Vec<ReflectedBinding>>, ShaderLoadError> {
+    let module = naga::front::wgsl::parse_str(source)
+        .map_err(|e| ShaderLoadError::Compile(e.emit_to_string(source)))?;
+    let info = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+        .validate(&module)
+        .map_err(|e| ShaderLoadError::Compile(e.emit_to_string(source)))?;
+    let mut layouter = naga::proc::Layouter::default();
+    layouter
+        .update(module.to_ctx())
+        .map_err(|e| ShaderLoadError::Compile(e.to_string()))?;
+
+    let mut groups: BTreeMap<u32, Vec<ReflectedBinding>> = BTreeMap::new();
+    for (handle, global) in module.global_variables.iter() {
+        let Some(resource_binding) = &global.binding else {
+            continue;
+        };
+        let name = global.name.clone().unwrap_or_default();
+        let visibility = module
+            .entry_points
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !info.get_entry_point(*index)[handle].is_empty())
+            .fold(wgpu::ShaderStages::empty(), |stages, (_, entry_point)| {
+                stages | map_stage(entry_point.stage)
+            });
+        let ty = binding_type(&module, &layouter, global).map_err(|e| ShaderLoadError::Compile(format!("'{name}': {e}")))?;
+        groups.entry(resource_binding.group).or_default().push(ReflectedBinding {
+            name,
+            binding: resource_binding.binding,
+            entry: wgpu::BindGroupLayoutEntry {
+                binding: resource_binding.binding,
+                // Declared but unused variables still need a valid entry
+                visibility: if visibility.is_empty() { all_stages(&module) } else { visibility },
+                ty,
+                count: None,
+            },
+            used: !visibility.is_empty(),
+        });
+    }
+
+    let group_count = groups.keys().last().map_or(0, |&group| group as usize + 1);
+    let mut result = vec![Vec::new(); group_count];
+    for (group, mut bindings) in groups {
+        bindings.sort_by_key(|binding| binding.binding);
+        result[group as usize] = bindings;
+    }
+    Ok(result)
+}
+
+fn all_stages(module: &naga::Module) -> wgpu::ShaderStages {
+    module
+        .entry_points
+        .iter()
+        .fold(wgpu::ShaderStages::empty(), |stages, entry_point| stages | map_stage(entry_point.stage))
+}
+
+fn map_stage(stage: naga::ShaderStage) -> wgpu::ShaderStages {
+    match stage {
+        naga::ShaderStage::Vertex => wgpu::ShaderStages::VERTEX,
+        naga::ShaderStage::Fragment => wgpu::ShaderStages::FRAGMENT,
+        naga::ShaderStage::Compute => wgpu::ShaderStages::COMPUTE,
+        naga::ShaderStage::Task => wgpu::ShaderStages::TASK,
+        naga::ShaderStage::Mesh => wgpu::ShaderStages::MESH,
+        naga::ShaderStage::RayGeneration => wgpu::ShaderStages::RAY_GENERATION,
+        naga::ShaderStage::Miss => wgpu::ShaderStages::MISS,
+        naga::ShaderStage::AnyHit => wgpu::ShaderStages::ANY_HIT,
+        naga::ShaderStage::ClosestHit => wgpu::ShaderStages::CLOSEST_HIT,
+    }
+}
+
+fn binding_type(
+    module: &naga::Module,
+    layouter: &naga::proc::Layouter,
+    global: &naga::GlobalVariable,
+) -> Result<wgpu::BindingType, String> {
+    let inner = &module.types[global.ty].inner;
+    match global.space {
+        naga::AddressSpace::Uniform => Ok(wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: wgpu::BufferSize::new(layouter[global.ty].size as u64),
+        }),
+        naga::AddressSpace::Storage { access } => Ok(wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage {
+                read_only: !access.contains(naga::StorageAccess::STORE),
+            },
+            has_dynamic_offset: false,
+            // Runtime-sized arrays take whatever length the buffer has
+            min_binding_size: if is_runtime_sized(module, inner) {
+                None
+            } else {
+                wgpu::BufferSize::new(layouter[global.ty].size as u64)
+            },
+        }),
+        naga::AddressSpace::Handle => match *inner {
+            naga::TypeInner::Sampler { comparison } => Ok(wgpu::BindingType::Sampler(if comparison {
+                wgpu::SamplerBindingType::Comparison
+            } else {
+                wgpu::SamplerBindingType::Filtering
+            })),
+            naga::TypeInner::Image { dim, arrayed, class } => {
+                let view_dimension = match (dim, arrayed) {
+                    (naga::ImageDimension::D1, _) => wgpu::TextureViewDimension::D1,
+                    (naga::ImageDimension::D2, false) => wgpu::TextureViewDimension::D2,
+                    (naga::ImageDimension::D2, true) => wgpu::TextureViewDimension::D2Array,
+                    (naga::ImageDimension::D3, _) => wgpu::TextureViewDimension::D3,
+                    (naga::ImageDimension::Cube, false) => wgpu::TextureViewDimension::Cube,
+                    (naga::ImageDimension::Cube, true) => wgpu::TextureViewDimension::CubeArray,
+                };
+                match class {
+                    naga::ImageClass::Sampled { kind, multi } => Ok(wgpu::BindingType::Texture {
+                        sample_type: match kind {
+                            naga::ScalarKind::Sint => wgpu::TextureSampleType::Sint,
+                            naga::ScalarKind::Uint => wgpu::TextureSampleType::Uint,
+                            _ => wgpu::TextureSampleType::Float { filterable: !multi },
+                        },
+                        view_dimension,
+                        multisampled: multi,
+                    }),
+                    naga::ImageClass::Depth { multi } => Ok(wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension,
+                        multisampled: multi,
+                    }),
+                    naga::ImageClass::Storage { format, access } => Ok(wgpu::BindingType::StorageTexture {
+                        access: if access.contains(naga::StorageAccess::ATOMIC) {
+                            wgpu::StorageTextureAccess::Atomic
+                        } else if access.contains(naga::StorageAccess::LOAD | naga::StorageAccess::STORE) {
+                            wgpu::StorageTextureAccess::ReadWrite
+                        } else if access.contains(naga::StorageAccess::STORE) {
+                            wgpu::StorageTextureAccess::WriteOnly
+                        } else {
+                            wgpu::StorageTextureAccess::ReadOnly
+                        },
+                        format: map_storage_format(format),
+                        view_dimension,
+                    }),
+                    naga::ImageClass::External => Err("external textures aren't supported".to_string()),
+                }
+            }
+            naga::TypeInner::AccelerationStructure { .. } => Ok(wgpu::BindingType::AccelerationStructure {
+                vertex_return: false,
+            }),
+            _ => Err("binding arrays aren't supported".to_string()),
+        },
+        space => Err(format!("unexpected address space {space:?} for a bound variable")),
+    }
+}
+
+fn is_runtime_sized(module: &naga::Module, inner: &naga::TypeInner) -> bool {
+    match inner {
+        naga::TypeInner::Array { size: naga::ArraySize::Dynamic, .. } => true,
+        naga::TypeInner::Struct { members, .. } => members
+            .last()
+            .is_some_and(|member| is_runtime_sized(module, &module.types[member.ty].inner)),
+        _ => false,
+    }
+}
+
+fn map_storage_format(format: naga::StorageFormat) -> wgpu::TextureFormat {
+    use naga::StorageFormat as Sf;
+    use wgpu::TextureFormat as Tf;
+    match format {
+        Sf::R8Unorm => Tf::R8Unorm,
+        Sf::R8Snorm => Tf::R8Snorm,
+        Sf::R8Uint => Tf::R8Uint,
+        Sf::R8Sint => Tf::R8Sint,
+        Sf::R16Uint => Tf::R16Uint,
+        Sf::R16Sint => Tf::R16Sint,
+        Sf::R16Float => Tf::R16Float,
+        Sf::Rg8Unorm => Tf::Rg8Unorm,
+        Sf::Rg8Snorm => Tf::Rg8Snorm,
+        Sf::Rg8Uint => Tf::Rg8Uint,
+        Sf::Rg8Sint => Tf::Rg8Sint,
+        Sf::R32Uint => Tf::R32Uint,
+        Sf::R32Sint => Tf::R32Sint,
+        Sf::R32Float => Tf::R32Float,
+        Sf::Rg16Uint => Tf::Rg16Uint,
+        Sf::Rg16Sint => Tf::Rg16Sint,
+        Sf::Rg16Float => Tf::Rg16Float,
+        Sf::Rgba8Unorm => Tf::Rgba8Unorm,
+        Sf::Rgba8Snorm => Tf::Rgba8Snorm,
+        Sf::Rgba8Uint => Tf::Rgba8Uint,
+        Sf::Rgba8Sint => Tf::Rgba8Sint,
+        Sf::Bgra8Unorm => Tf::Bgra8Unorm,
+        Sf::Rgb10a2Uint => Tf::Rgb10a2Uint,
+        Sf::Rgb10a2Unorm => Tf::Rgb10a2Unorm,
+        Sf::Rg11b10Ufloat => Tf::Rg11b10Ufloat,
+        Sf::R64Uint => Tf::R64Uint,
+        Sf::Rg32Uint => Tf::Rg32Uint,
+        Sf::Rg32Sint => Tf::Rg32Sint,
+        Sf::Rg32Float => Tf::Rg32Float,
+        Sf::Rgba16Uint => Tf::Rgba16Uint,
+        Sf::Rgba16Sint => Tf::Rgba16Sint,
+        Sf::Rgba16Float => Tf::Rgba16Float,
+        Sf::Rgba32Uint => Tf::Rgba32Uint,
+        Sf::Rgba32Sint => Tf::Rgba32Sint,
+        Sf::Rgba32Float => Tf::Rgba32Float,
+        Sf::R16Unorm => Tf::R16Unorm,
+        Sf::R16Snorm => Tf::R16Snorm,
+        Sf::Rg16Unorm => Tf::Rg16Unorm,
+        Sf::Rg16Snorm => Tf::Rg16Snorm,
+        Sf::Rgba16Unorm => Tf::Rgba16Unorm,
+        Sf::Rgba16Snorm => Tf::Rgba16Snorm,
+    }
+}
@@ -1,4 +1,6 @@
 use log::info;
+use std::ops::Range;
+use std::sync::Mutex;
 use wgpu::util::DeviceExt;
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -30,11 +32,57 @@ impl<'a> RenderPassWrapper<'a> {
         self.render_pass
     }
 }
+/// How [`Renderer::render_to_view`] fits a source whose aspect ratio differs from the target's
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FitMode {
+    /// Fill the target, distorting the source
+    #[default]
+    Stretch,
+    /// Show the whole source, leaving bars in the clear color
+    Contain,
+    /// Fill the target, cropping the source's overflowing sides
+    Cover,
+}
+
+/// Everything needed to rebuild the pipeline when an option changes
+struct PipelineSource {
+    vs_module: wgpu::ShaderModule,
+    fs_module: wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    layout: wgpu::PipelineLayout,
+    fragment_entry: String,
+    blend: wgpu::BlendState,
+}
+
 pub struct Renderer {
     pub render_pipeline: wgpu::RenderPipeline,
     pub vertex_buffer: wgpu::Buffer,
+    /// `u16` indices set by [`with_vertices`](Renderer::with_vertices)
+    pub index_buffer: Option<wgpu::Buffer>,
+    /// Vertices per draw, or indices when `index_buffer` is set
+    draw_count: u32,
+    topology: wgpu::PrimitiveTopology,
+    sample_count: u32,
+    depth_format: Option<wgpu::TextureFormat>,
+    /// What [`render_to_view`](Renderer::render_to_view) does with the target first
+    load_op: wgpu::LoadOp<wgpu::Color>,
+    fit_mode: FitMode,
+    /// Size of what `render_to_view` draws, needed by every fit mode but `Stretch`
+    source_size: Option<(u32, u32)>,
+    source: PipelineSource,
+    device: wgpu::Device,
+    /// Multisampled color targets resolved into the views we draw to, one per target size
+    /// (the window and possibly an export resolution)
+    msaa_targets: Mutex<Vec<wgpu::TextureView>>,
+    /// Depth textures for `depth_format`, one per target size like `msaa_targets`
+    depth_targets: Mutex<Vec<wgpu::TextureView>>,
+    /// Bind group last drawn by `render_to_view`, re-rendered for screenshots
+    last_source: Mutex<Option<wgpu::BindGroup>>,
 }
 impl Renderer {
+    /// Most targets alive at once: the surface plus an export capture
+    const MAX_MSAA_TARGETS: usize = 2;
+
     pub fn new(
         device: &wgpu::Device,
         vs_module: &wgpu::ShaderModule,
@@ -42,6 +90,22 @@ impl Renderer {
         format: wgpu::TextureFormat,
         layout: &wgpu::PipelineLayout,
         fragment_entry: Option<&str>,
+    ) -> Self {
+        Self::new_multisampled(device, vs_module, fs_module, format, layout, fragment_entry, 1)
+    }
+
+    /// Like [`new`](Self::new), but draws into a `sample_count`-sample target that is
+    /// resolved into the destination view. Triangle edges get antialiased as usual;
+    /// fragment shaders that read `@builtin(sample_index)` run per sample, which
+    /// supersamples in-shader edges such as SDFs.
+    pub fn new_multisampled(
+        device: &wgpu::Device,
+        vs_module: &wgpu::ShaderModule,
+        fs_module: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        layout: &wgpu::PipelineLayout,
+        fragment_entry: Option<&str>,
+        sample_count: u32,
     ) -> Self {
         const VERTICES: &[Vertex] = &[
             Vertex {
@@ -63,42 +127,171 @@ impl Renderer {
             contents: bytemuck::cast_slice(VERTICES),
             usage: wgpu::BufferUsages::VERTEX,
         });
-        let color_target_state = [Some(wgpu::ColorTargetState {
+        let source = PipelineSource {
+            vs_module: vs_module.clone(),
+            fs_module: fs_module.clone(),
             format,
-            blend: Some(wgpu::BlendState {
-                color: wgpu::BlendComponent::REPLACE,
-                alpha: wgpu::BlendComponent::REPLACE,
-            }),
+            layout: layout.clone(),
+            fragment_entry: fragment_entry.unwrap_or("fs_main").to_string(),
+            blend: wgpu::BlendState::REPLACE,
+        };
+        let topology = wgpu::PrimitiveTopology::TriangleStrip;
+        let render_pipeline = Self::create_pipeline(device, &source, sample_count, None, topology, false);
+
+        Self {
+            render_pipeline,
+            vertex_buffer,
+            index_buffer: None,
+            draw_count: VERTICES.len() as u32,
+            topology,
+            sample_count,
+            depth_format: None,
+            load_op: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            fit_mode: FitMode::Stretch,
+            source_size: None,
+            source,
+            device: device.clone(),
+            msaa_targets: Mutex::new(Vec::new()),
+            depth_targets: Mutex::new(Vec::new()),
+            last_source: Mutex::new(None),
+        }
+    }
+
+    /// Depth-test and write depth into a `depth_format` texture (e.g. `Depth32Float`),
+    /// for drawing geometry rather than a fullscreen quad. The depth texture is created
+    /// per target size, so it follows resizes, and is cleared to 1.0 each pass; nearer
+    /// fragments (smaller depth) win.
+    pub fn with_depth(mut self, depth_format: wgpu::TextureFormat) -> Self {
+        self.depth_format = Some(depth_format);
+        self.depth_targets.lock().unwrap().clear();
+        self.rebuild_pipeline();
+        self
+    }
+
+    /// Draw `vertices` (optionally through `indices`) with `topology` instead of the
+    /// fullscreen quad, e.g. `PointList` for particles or `TriangleList` for a mesh.
+    /// Positions go to `@location(0)` of `vs_main` as before.
+    pub fn with_vertices(
+        mut self,
+        vertices: &[Vertex],
+        indices: Option<&[u16]>,
+        topology: wgpu::PrimitiveTopology,
+    ) -> Self {
+        self.vertex_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+        self.index_buffer = indices.map(|indices| {
+            // Buffer writes and sizes must be multiples of 4 bytes
+            let mut padded = indices.to_vec();
+            if padded.len() % 2 == 1 {
+                padded.push(0);
+            }
+            self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Index Buffer"),
+                contents: bytemuck::cast_slice(&padded),
+                usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            })
+        });
+        self.draw_count = indices.map_or(vertices.len(), <[u16]>::len) as u32;
+        self.topology = topology;
+        self.rebuild_pipeline();
+        self
+    }
+
+    /// Blend fragments into the target instead of replacing it, e.g.
+    /// `wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING` for back-to-front sorted splats
+    pub fn with_blend(mut self, blend: wgpu::BlendState) -> Self {
+        self.source.blend = blend;
+        self.rebuild_pipeline();
+        self
+    }
+
+    fn rebuild_pipeline(&mut self) {
+        self.render_pipeline = Self::create_pipeline(
+            &self.device,
+            &self.source,
+            self.sample_count,
+            self.depth_format,
+            self.topology,
+            self.index_buffer.is_some(),
+        );
+    }
+
+    /// Set the pipeline and geometry and issue the draw. Bind groups are left to the
+    /// caller, so this works inside passes with several of them.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        self.draw_instanced(render_pass, 0..1);
+    }
+
+    /// [`draw`](Self::draw) the geometry once per instance. The vertex shader tells
+    /// instances apart with `@builtin(instance_index)` and pulls per-instance data from
+    /// a storage buffer in its bind group, e.g. the sorted indices from a `RadixSorter`.
+    pub fn draw_instanced(&self, render_pass: &mut wgpu::RenderPass, instances: Range<u32>) {
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        match &self.index_buffer {
+            Some(index_buffer) => {
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                render_pass.draw_indexed(0..self.draw_count, 0, instances);
+            }
+            None => render_pass.draw(0..self.draw_count, instances),
+        }
+    }
+
+    pub fn depth_format(&self) -> Option<wgpu::TextureFormat> {
+        self.depth_format
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        source: &PipelineSource,
+        sample_count: u32,
+        depth_format: Option<wgpu::TextureFormat>,
+        topology: wgpu::PrimitiveTopology,
+        indexed: bool,
+    ) -> wgpu::RenderPipeline {
+        let color_target_state = [Some(wgpu::ColorTargetState {
+            format: source.format,
+            blend: Some(source.blend),
             write_mask: wgpu::ColorWrites::ALL,
         })];
         info!("Creating render pipeline");
         let pipeline_desc = wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
-            layout: Some(layout),
+            layout: Some(&source.layout),
             vertex: wgpu::VertexState {
-                module: vs_module,
+                module: &source.vs_module,
                 entry_point: Some("vs_main"),
                 buffers: &[Vertex::desc()],
                 compilation_options: Default::default(),
             },
             fragment: Some(wgpu::FragmentState {
-                module: fs_module,
-                entry_point: Some(fragment_entry.unwrap_or("fs_main")),
+                module: &source.fs_module,
+                entry_point: Some(&source.fragment_entry),
                 targets: &color_target_state,
                 compilation_options: Default::default(),
             }),
             primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleStrip,
-                strip_index_format: None,
+                topology,
+                // Indexed strips need to know the format to recognise restart indices
+                strip_index_format: (indexed && topology.is_strip()).then_some(wgpu::IndexFormat::Uint16),
                 front_face: wgpu::FrontFace::Ccw,
                 cull_mode: Some(wgpu::Face::Back),
                 unclipped_depth: false,
                 polygon_mode: wgpu::PolygonMode::Fill,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
+                format,
+                depth_write_enabled: Some(true),
+                depth_compare: Some(wgpu::CompareFunction::Less),
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -106,30 +299,225 @@ impl Renderer {
             cache: None,
         };
 
-        let render_pipeline = device.create_render_pipeline(&pipeline_desc);
+        device.create_render_pipeline(&pipeline_desc)
+    }
 
-        Self {
-            render_pipeline,
-            vertex_buffer,
-        }
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Start [`render_to_view`](Self::render_to_view) passes with `load_op` instead of
+    /// clearing to black: another clear color for areas the draw doesn't cover, or
+    /// `LoadOp::Load` to draw over what an earlier pass left in the target. With MSAA
+    /// the draw goes to a separate multisampled target, so `Load` clears to black there.
+    pub fn with_load_op(mut self, load_op: wgpu::LoadOp<wgpu::Color>) -> Self {
+        self.load_op = load_op;
+        self
+    }
+
+    /// Change the load op set by [`with_load_op`](Self::with_load_op)
+    pub fn set_load_op(&mut self, load_op: wgpu::LoadOp<wgpu::Color>) {
+        self.load_op = load_op;
+    }
+
+    pub fn load_op(&self) -> wgpu::LoadOp<wgpu::Color> {
+        self.load_op
+    }
+
+    /// Keep the source's aspect ratio in [`render_to_view`](Self::render_to_view) by
+    /// drawing into a viewport sized for it. Needs the source size from
+    /// [`set_source_size`](Self::set_source_size); without one the draw stretches.
+    pub fn with_fit_mode(mut self, fit_mode: FitMode) -> Self {
+        self.fit_mode = fit_mode;
+        self
+    }
+
+    pub fn set_fit_mode(&mut self, fit_mode: FitMode) {
+        self.fit_mode = fit_mode;
+    }
+
+    pub fn fit_mode(&self) -> FitMode {
+        self.fit_mode
+    }
+
+    /// Pixel size of the texture `render_to_view` draws, for the fit mode
+    pub fn set_source_size(&mut self, width: u32, height: u32) {
+        self.source_size = Some((width, height));
+    }
+
+    pub fn source_size(&self) -> Option<(u32, u32)> {
+        self.source_size
     }
-    /// Blit a bind group's texture to the screen in one call.
+
+    /// Viewport that fits `source_size` into a `target`-sized view, `None` to fill it.
+    /// `Cover` viewports reach past the target's edges, which wgpu clips.
+    fn fit_viewport(&self, source_size: Option<(u32, u32)>, target: wgpu::Extent3d) -> Option<[f32; 4]> {
+        let (width, height) = source_size.filter(|&(w, h)| w > 0 && h > 0)?;
+        let (target_width, target_height) = (target.width as f32, target.height as f32);
+        let (scale_x, scale_y) = (target_width / width as f32, target_height / height as f32);
+        let scale = match self.fit_mode {
+            FitMode::Stretch => return None,
+            FitMode::Contain => scale_x.min(scale_y),
+            FitMode::Cover => scale_x.max(scale_y),
+        };
+        let max = self.device.limits().max_texture_dimension_2d as f32;
+        let (w, h) = ((width as f32 * scale).min(max), (height as f32 * scale).min(max));
+        Some([(target_width - w) / 2.0, (target_height - h) / 2.0, w, h])
+    }
+
+    /// Blit a bind group's texture to the screen in one call, starting from
+    /// [`load_op`](Self::load_op) (a black clear by default) and fitted by
+    /// [`fit_mode`](Self::fit_mode).
     pub fn render_to_view(
         &self,
         encoder: &mut wgpu::CommandEncoder,
         view: &wgpu::TextureView,
         bind_group: &wgpu::BindGroup,
     ) {
-        let mut render_pass = Self::begin_render_pass(
+        *self.last_source.lock().unwrap() = Some(bind_group.clone());
+        self.render_pass_to_view(encoder, view, bind_group, 1, self.load_op, self.source_size);
+    }
+
+    /// The bind group most recently drawn by [`render_to_view`](Self::render_to_view)
+    pub(crate) fn last_source(&self) -> Option<wgpu::BindGroup> {
+        self.last_source.lock().unwrap().clone()
+    }
+
+    /// [`render_to_view`](Self::render_to_view) for a source other than the one given to
+    /// [`set_source_size`](Self::set_source_size)
+    pub fn render_sized_to_view(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        bind_group: &wgpu::BindGroup,
+        source_size: (u32, u32),
+    ) {
+        self.render_pass_to_view(encoder, view, bind_group, 1, self.load_op, Some(source_size));
+    }
+
+    /// [`render_to_view`](Self::render_to_view) with a custom clear color, resolving
+    /// through a multisampled target when `sample_count > 1`.
+    pub fn render_to_view_with_clear(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        bind_group: &wgpu::BindGroup,
+        clear: wgpu::Color,
+    ) {
+        self.render_instances_to_view(encoder, view, bind_group, 1, clear);
+    }
+
+    /// Clear `view` and draw `instance_count` instances of the geometry in one pass
+    /// (see [`draw_instanced`](Self::draw_instanced))
+    pub fn render_instances_to_view(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        bind_group: &wgpu::BindGroup,
+        instance_count: u32,
+        clear: wgpu::Color,
+    ) {
+        self.render_pass_to_view(
             encoder,
             view,
-            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            bind_group,
+            instance_count,
+            wgpu::LoadOp::Clear(clear),
+            self.source_size,
+        );
+    }
+
+    /// Draw one pass into `view`, fitting `source_size` by the fit mode
+    pub(crate) fn render_pass_to_view(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        bind_group: &wgpu::BindGroup,
+        instance_count: u32,
+        load_op: wgpu::LoadOp<wgpu::Color>,
+        source_size: Option<(u32, u32)>,
+    ) {
+        let msaa_view = (self.sample_count > 1).then(|| self.msaa_target_for(view));
+        // The multisampled target is discarded after every pass, so there's nothing to load
+        let load_op = match load_op {
+            wgpu::LoadOp::Load if msaa_view.is_some() => wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            load_op => load_op,
+        };
+        let depth_view = self.depth_target_for(view);
+        let (color_view, resolve_target) = match &msaa_view {
+            Some(msaa_view) => (msaa_view, Some(view)),
+            None => (view, None),
+        };
+        let mut render_pass = Self::begin_render_pass_with_depth(
+            encoder,
+            color_view,
+            resolve_target,
+            depth_view.as_ref(),
+            load_op,
             Some("Blit Pass"),
         );
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        if let Some([x, y, width, height]) = self.fit_viewport(source_size, view.texture().size()) {
+            render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        }
         render_pass.set_bind_group(0, bind_group, &[]);
-        render_pass.draw(0..4, 0..1);
+        self.draw_instanced(&mut render_pass, 0..instance_count);
+    }
+
+    /// Multisampled texture matching `view`'s size and format, created on first use
+    fn msaa_target_for(&self, view: &wgpu::TextureView) -> wgpu::TextureView {
+        let target = view.texture();
+        let mut targets = self.msaa_targets.lock().unwrap();
+        if let Some(existing) = targets.iter().find(|t| {
+            let texture = t.texture();
+            texture.size() == target.size() && texture.format() == target.format()
+        }) {
+            return existing.clone();
+        }
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("MSAA Color Target"),
+            size: target.size(),
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: target.format(),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let msaa_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        if targets.len() >= Self::MAX_MSAA_TARGETS {
+            targets.remove(0);
+        }
+        targets.push(msaa_view.clone());
+        msaa_view
+    }
+
+    /// Depth texture matching `view`'s size and this renderer's sample count, created on
+    /// first use. `None` without [`with_depth`](Self::with_depth). Use it with
+    /// [`begin_render_pass_with_depth`](Self::begin_render_pass_with_depth) when
+    /// recording your own pass.
+    pub fn depth_target_for(&self, view: &wgpu::TextureView) -> Option<wgpu::TextureView> {
+        let format = self.depth_format?;
+        let size = view.texture().size();
+        let mut targets = self.depth_targets.lock().unwrap();
+        if let Some(existing) = targets.iter().find(|t| t.texture().size() == size) {
+            return Some(existing.clone());
+        }
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Target"),
+            size,
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let depth_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        if targets.len() >= Self::MAX_MSAA_TARGETS {
+            targets.remove(0);
+        }
+        targets.push(depth_view.clone());
+        Some(depth_view)
     }
 
     pub fn begin_render_pass<'a>(
@@ -137,19 +525,56 @@ impl Renderer {
         view: &'a wgpu::TextureView,
         load_op: wgpu::LoadOp<wgpu::Color>,
         label: Option<&'a str>,
+    ) -> RenderPassWrapper<'a> {
+        Self::begin_resolving_render_pass(encoder, view, None, load_op, label)
+    }
+
+    /// Render pass into `view`, resolving into `resolve_target` when `view` is multisampled
+    pub fn begin_resolving_render_pass<'a>(
+        encoder: &'a mut wgpu::CommandEncoder,
+        view: &'a wgpu::TextureView,
+        resolve_target: Option<&'a wgpu::TextureView>,
+        load_op: wgpu::LoadOp<wgpu::Color>,
+        label: Option<&'a str>,
+    ) -> RenderPassWrapper<'a> {
+        Self::begin_render_pass_with_depth(encoder, view, resolve_target, None, load_op, label)
+    }
+
+    /// [`begin_resolving_render_pass`](Self::begin_resolving_render_pass) that also
+    /// clears `depth_view` to 1.0 and attaches it, for pipelines built
+    /// [`with_depth`](Self::with_depth)
+    pub fn begin_render_pass_with_depth<'a>(
+        encoder: &'a mut wgpu::CommandEncoder,
+        view: &'a wgpu::TextureView,
+        resolve_target: Option<&'a wgpu::TextureView>,
+        depth_view: Option<&'a wgpu::TextureView>,
+        load_op: wgpu::LoadOp<wgpu::Color>,
+        label: Option<&'a str>,
     ) -> RenderPassWrapper<'a> {
         let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label,
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                 view,
-                resolve_target: None,
+                resolve_target,
                 ops: wgpu::Operations {
                     load: load_op,
-                    store: wgpu::StoreOp::Store,
+                    // Only the resolved image is needed afterwards
+                    store: if resolve_target.is_some() {
+                        wgpu::StoreOp::Discard
+                    } else {
+                        wgpu::StoreOp::Store
+                    },
                 },
                 depth_slice: None,
             })],
-            depth_stencil_attachment: None,
+            depth_stencil_attachment: depth_view.map(|view| wgpu::RenderPassDepthStencilAttachment {
+                view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
             timestamp_writes: None,
             occlusion_query_set: None,
             multiview_mask: None,
@@ -1,24 +1,31 @@
+use crate::color::linear_to_srgb;
 use crate::compute::ComputeShader;
 #[cfg(feature = "media")]
+use crate::gst::mic::MicInput;
+#[cfg(feature = "media")]
 use crate::gst::video::VideoTextureManager;
 #[cfg(feature = "media")]
 use crate::gst::webcam::WebcamTextureManager;
 use crate::load_hdri_texture;
+use crate::gamepad::GamepadTracker;
+use crate::hot::FileWatch;
 use crate::mouse::MouseTracker;
 use crate::mouse::MouseUniform;
 use crate::spectrum::SpectrumAnalyzer;
 use crate::HdriMetadata;
 use crate::{
-    fps, ControlsRequest, Core, ExportManager, KeyInputHandler, Renderer, ResolutionUniform,
-    ShaderControls, TextureManager, UniformBinding, UniformProvider,
+    fps, ControlsRequest, Core, CuneusError, ExportManager, FitMode, KeyInputHandler, Renderer, ResolutionUniform,
+    PreprocessedShader, Session, ShaderControls, ShaderHotReload, ShaderLoadError, TextureManager,
+    UniformBinding, UniformProvider,
 };
+#[cfg(target_arch = "wasm32")]
+use crate::notify_stub as notify;
 use egui::ViewportId;
 use egui_wgpu::ScreenDescriptor;
-#[cfg(feature = "media")]
-use log::warn;
-use log::{error, info};
-use std::path::Path;
-use std::time::Instant;
+use log::{error, info, warn};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use web_time::{Duration, Instant};
 use winit::event::WindowEvent;
 
 pub struct FrameContext {
@@ -31,19 +38,34 @@ pub struct FrameContext {
 pub const CAPTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
 #[cfg(not(target_os = "macos"))]
 pub const CAPTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+/// `delta` comes after `frame` so shaders declaring only `time` and `frame` keep working
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct TimeUniform {
     pub time: f32,
     pub frame: u32,
+    /// Seconds since the last presented frame, see [`RenderKit::frame_delta`]
+    pub delta: f32,
+    pub _padding: u32,
 }
 impl UniformProvider for TimeUniform {
     fn as_bytes(&self) -> &[u8] {
         bytemuck::bytes_of(self)
     }
 }
+/// A thumbnail drawn by [`RenderKit::egui_preview`] and registered with egui
+struct EguiPreview {
+    texture: wgpu::Texture,
+    id: egui::TextureId,
+}
+
 pub struct RenderKit {
     pub renderer: Renderer,
+    /// Plain blit that keeps the output's alpha, used for transparent export captures
+    pub(crate) alpha_capture_renderer: Renderer,
+    /// Plain opaque blit for export captures when `renderer` targets a surface format
+    /// other than `CAPTURE_FORMAT`
+    pub(crate) capture_renderer: Option<Renderer>,
     #[cfg(feature = "media")]
     pub video_texture_manager: Option<VideoTextureManager>,
     #[cfg(feature = "media")]
@@ -52,6 +74,9 @@ pub struct RenderKit {
     pub webcam_texture_manager: Option<WebcamTextureManager>,
     #[cfg(feature = "media")]
     pub using_webcam_texture: bool,
+    /// Standalone microphone feeding the spectrum when no video or webcam audio is active
+    #[cfg(feature = "media")]
+    pub mic_input: Option<MicInput>,
     pub texture_manager: Option<TextureManager>,
     pub egui_renderer: egui_wgpu::Renderer,
     pub egui_state: egui_winit::State,
@@ -71,17 +96,47 @@ pub struct RenderKit {
     pub compute_shader: Option<ComputeShader>,
     pub fps_tracker: fps::FpsTracker,
     pub mouse_tracker: MouseTracker,
+    pub gamepad_tracker: GamepadTracker,
     pub mouse_uniform: Option<UniformBinding<MouseUniform>>,
     pub mouse_bind_group_layout: Option<wgpu::BindGroupLayout>,
     pub using_hdri_texture: bool,
     pub hdri_metadata: Option<HdriMetadata>,
     pub hdri_file_data: Option<Vec<u8>>,
     initial_logical_height: f32,
+    // Kept to rebuild the renderer when runtime-loaded shaders change
+    render_pipeline_layout: wgpu::PipelineLayout,
+    fragment_entry: Option<String>,
+    render_shaders: (wgpu::ShaderModule, wgpu::ShaderModule),
+    shader_hot_reload: Option<ShaderHotReload>,
+    render_scale: f32,
+    /// Compute output size used instead of the window's, see `set_output_size`
+    output_size: Option<(u32, u32)>,
+    blit_filter: wgpu::FilterMode,
+    media_watch: Option<FileWatch>,
+    /// Last file opened with [`load_media`](Self::load_media), kept for [`save_session`](Self::save_session)
+    media_path: Option<PathBuf>,
+    /// Kept open so the copied frame stays available on X11/Wayland, where the
+    /// owning process serves clipboard requests
+    #[cfg(not(target_arch = "wasm32"))]
+    clipboard: Option<arboard::Clipboard>,
+    /// Target of [`render_to_texture`](Self::render_to_texture), created on first use
+    offscreen: Option<TextureManager>,
+    /// Thumbnails registered with egui by [`egui_preview`](Self::egui_preview), by name
+    egui_previews: HashMap<String, EguiPreview>,
+    preview_sampler: wgpu::Sampler,
+    /// Shader time when the last frame was presented, for [`frame_delta`](Self::frame_delta)
+    last_frame_time: f32,
+    /// Display pass load op with a linear clear color, see [`set_load_op`](Self::set_load_op)
+    load_op: wgpu::LoadOp<wgpu::Color>,
 }
 
 impl RenderKit {
-    const VERTEX_SHADER: &'static str = include_str!("../shaders/vertex.wgsl");
+    pub(crate) const VERTEX_SHADER: &'static str = include_str!("../shaders/vertex.wgsl");
     const BLIT_SHADER: &'static str = include_str!("../shaders/blit.wgsl");
+    pub const RENDER_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.25..=2.0;
+    /// Longest step `frame_delta` reports, so a stall (window drag, export, breakpoint)
+    /// doesn't make simulations jump
+    pub const MAX_FRAME_DELTA: f32 = 0.1;
 
     /// Creates a bind group layout with texture (binding 0) and sampler (binding 1) for displaying compute shader output
     pub fn create_standard_texture_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
@@ -96,6 +151,254 @@ impl RenderKit {
 
     /// Create RenderKit with a custom bind group layout and optional fragment entry point.
     pub fn new_with_layout(core: &Core, layout: &wgpu::BindGroupLayout, fragment_entry: Option<&str>) -> Self {
+        Self::with_shader_sources(core, layout, fragment_entry, Self::VERTEX_SHADER, Self::BLIT_SHADER)
+    }
+
+    /// Create RenderKit with vertex/fragment WGSL read from disk at runtime, so users can
+    /// drop in their own shaders without recompiling. The shaders need the same interface
+    /// as the built-in ones (`vs_main`, and `fs_main` unless `fragment_entry` is given).
+    ///
+    /// Both files are watched for changes; call [`check_hot_reload`](Self::check_hot_reload)
+    /// each frame to pick up edits. Returns an error if a file can't be read or the shaders
+    /// fail to compile.
+    pub fn from_files(
+        core: &Core,
+        vs_path: impl AsRef<Path>,
+        fs_path: impl AsRef<Path>,
+        layout: &wgpu::BindGroupLayout,
+        fragment_entry: Option<&str>,
+    ) -> Result<Self, ShaderLoadError> {
+        let (vs_path, fs_path) = (vs_path.as_ref(), fs_path.as_ref());
+        let vs_shader = PreprocessedShader::load(vs_path)?;
+        let fs_shader = PreprocessedShader::load(fs_path)?;
+        let (vs_source, fs_source) = (&vs_shader.source, &fs_shader.source);
+        let mut render_kit = crate::hot::capture_validation(&core.device, || {
+            Self::with_shader_sources(core, layout, fragment_entry, vs_source, fs_source)
+        })
+        .map_err(|e| match e {
+            // The error names the module by label, which tells us whose line map applies
+            ShaderLoadError::Compile(msg) if msg.contains("Fragment Shader") => {
+                ShaderLoadError::Compile(fs_shader.annotate_error(&msg))
+            }
+            ShaderLoadError::Compile(msg) => ShaderLoadError::Compile(vs_shader.annotate_error(&msg)),
+            e => e,
+        })?;
+
+        let create_module = |label: &str, source: &str| {
+            core.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(label),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            })
+        };
+        match ShaderHotReload::new(
+            core.device.clone(),
+            vec![vs_path.to_path_buf(), fs_path.to_path_buf()],
+            create_module("Vertex Shader", vs_source),
+            create_module("Fragment Shader", fs_source),
+        ) {
+            Ok(hot_reload) => render_kit.shader_hot_reload = Some(hot_reload),
+            Err(e) => warn!("Failed to enable hot reload: {e}"),
+        }
+        Ok(render_kit)
+    }
+
+    /// Rebuild the render pipeline if shaders loaded with [`from_files`](Self::from_files)
+    /// changed on disk. A failed compile is logged and the previous pipeline is kept; the
+    /// message is available from [`shader_error`](Self::shader_error).
+    pub fn check_hot_reload(&mut self, core: &Core) -> bool {
+        let Some(hot_reload) = &mut self.shader_hot_reload else {
+            return false;
+        };
+        let Some((vs_module, fs_module)) = hot_reload.check_and_reload() else {
+            return false;
+        };
+        let reloaded = crate::hot::capture_validation(&core.device, || {
+            Renderer::new_multisampled(
+                &core.device,
+                vs_module,
+                fs_module,
+                core.config.format,
+                &self.render_pipeline_layout,
+                self.fragment_entry.as_deref(),
+                self.renderer.sample_count(),
+            )
+        });
+        match reloaded {
+            Ok(renderer) => {
+                self.render_shaders = (vs_module.clone(), fs_module.clone());
+                self.replace_renderer(renderer);
+                info!("Render shaders hot-reloaded");
+                true
+            }
+            Err(e) => {
+                hot_reload.record_error(format!("Render pipeline: {e}"));
+                false
+            }
+        }
+    }
+
+    /// Rebuild the display pipeline with `sample_count` MSAA samples (1 turns it off).
+    /// Counts the adapter can't render with the surface format fall back to the
+    /// highest supported one below it. Returns the count now in use.
+    pub fn set_sample_count(&mut self, core: &Core, sample_count: u32) -> u32 {
+        let supported = Self::supported_sample_counts(core)
+            .into_iter()
+            .rfind(|&count| count <= sample_count)
+            .unwrap_or(1);
+        if supported != sample_count {
+            warn!("{sample_count}x MSAA isn't supported for {:?}, using {supported}x", core.config.format);
+        }
+        if supported != self.renderer.sample_count() {
+            let (vs_module, fs_module) = &self.render_shaders;
+            let renderer = Renderer::new_multisampled(
+                &core.device,
+                vs_module,
+                fs_module,
+                core.config.format,
+                &self.render_pipeline_layout,
+                self.fragment_entry.as_deref(),
+                supported,
+            );
+            self.replace_renderer(renderer);
+            info!("Display pipeline now uses {supported}x MSAA");
+        }
+        supported
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.renderer.sample_count()
+    }
+
+    /// MSAA counts the display pipeline can use on this device, ascending. Counts other
+    /// than 1 and 4 need `TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`, which `Core`
+    /// requests when the adapter has it.
+    pub fn supported_sample_counts(core: &Core) -> Vec<u32> {
+        if !core.device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+            return vec![1, 4];
+        }
+        let flags = core.adapter.get_texture_format_features(core.config.format).flags;
+        [1, 2, 4, 8, 16]
+            .into_iter()
+            .filter(|&count| count == 1 || flags.sample_count_supported(count))
+            .collect()
+    }
+
+    /// Swap in a rebuilt display renderer, keeping the load op and fit of the old one
+    fn replace_renderer(&mut self, renderer: Renderer) {
+        let mut renderer = renderer
+            .with_load_op(self.renderer.load_op())
+            .with_fit_mode(self.renderer.fit_mode());
+        if let Some((width, height)) = self.renderer.source_size() {
+            renderer.set_source_size(width, height);
+        }
+        self.renderer = renderer;
+    }
+
+    /// Keep the compute output's aspect ratio on screen (`Contain` letterboxes in the
+    /// clear color, `Cover` crops) instead of stretching it to the window. By default the
+    /// output follows the window, so this only matters once its aspect differs: give it
+    /// one with [`set_output_size`](Self::set_output_size), which
+    /// [`default_resize`](Self::default_resize) picks up, or size it yourself and call
+    /// `renderer.set_source_size`.
+    pub fn set_fit_mode(&mut self, fit_mode: FitMode) {
+        self.renderer.set_fit_mode(fit_mode);
+    }
+
+    pub fn fit_mode(&self) -> FitMode {
+        self.renderer.fit_mode()
+    }
+
+    /// How the display pass starts: `LoadOp::Clear` with a linear color for whatever
+    /// the blit leaves uncovered (letterboxing, geometry renderers), or `LoadOp::Load`
+    /// to draw over an earlier pass into the same view. Exports use the same clear color.
+    pub fn set_load_op(&mut self, core: &Core, load_op: wgpu::LoadOp<wgpu::Color>) {
+        self.load_op = load_op;
+        let surface_op = match load_op {
+            wgpu::LoadOp::Clear(color) if needs_srgb_encode(core.config.format) => wgpu::LoadOp::Clear(wgpu::Color {
+                r: linear_to_srgb(color.r as f32) as f64,
+                g: linear_to_srgb(color.g as f32) as f64,
+                b: linear_to_srgb(color.b as f32) as f64,
+                a: color.a,
+            }),
+            load_op => load_op,
+        };
+        self.renderer.set_load_op(surface_op);
+    }
+
+    /// Load op from [`set_load_op`](Self::set_load_op), black by default
+    pub fn load_op(&self) -> wgpu::LoadOp<wgpu::Color> {
+        self.load_op
+    }
+
+    /// Linear clear color of the display pass; black while it loads instead
+    pub fn clear_color(&self) -> wgpu::Color {
+        match self.load_op {
+            wgpu::LoadOp::Clear(color) => color,
+            _ => wgpu::Color::BLACK,
+        }
+    }
+
+    /// Render the compute output at `scale` times the window size (clamped to
+    /// `RENDER_SCALE_RANGE`); the blit pass stretches it back to the window. Takes
+    /// effect on the next `handle_export` or resize.
+    pub fn set_render_scale(&mut self, scale: f32) {
+        let range = Self::RENDER_SCALE_RANGE;
+        self.render_scale = scale.clamp(*range.start(), *range.end());
+    }
+
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Filtering the blit uses to scale the compute output to the window. `Nearest`
+    /// keeps a low render scale as crisp, blocky pixels for a pixel-art look; exports
+    /// scale the same way. Takes effect on the next `handle_export` or resize.
+    pub fn set_blit_filter(&mut self, filter: wgpu::FilterMode) {
+        self.blit_filter = filter;
+    }
+
+    /// Filter from [`set_blit_filter`](Self::set_blit_filter), `Linear` by default
+    pub fn blit_filter(&self) -> wgpu::FilterMode {
+        self.blit_filter
+    }
+
+    /// Render the compute output at a fixed `(width, height)` whatever the window size,
+    /// e.g. 16:9 in a square window; `None` follows the window again. The render scale
+    /// still applies. Takes effect on the next `handle_export` or resize.
+    pub fn set_output_size(&mut self, size: Option<(u32, u32)>) {
+        self.output_size = size.map(|(width, height)| (width.max(1), height.max(1)));
+    }
+
+    pub fn output_size(&self) -> Option<(u32, u32)> {
+        self.output_size
+    }
+
+    /// Compute output size for the fixed output size or the window, times the render scale
+    pub fn render_size(&self, core: &Core) -> winit::dpi::PhysicalSize<u32> {
+        let (width, height) = self.output_size.unwrap_or((core.size.width, core.size.height));
+        let scale = |v: u32| ((v as f32 * self.render_scale).round() as u32).max(1);
+        winit::dpi::PhysicalSize::new(scale(width), scale(height))
+    }
+
+    /// Error from the last failed render shader reload, if any.
+    pub fn shader_error(&self) -> Option<&str> {
+        self.shader_hot_reload.as_ref()?.last_error()
+    }
+
+    fn with_shader_sources(
+        core: &Core,
+        layout: &wgpu::BindGroupLayout,
+        fragment_entry: Option<&str>,
+        vs_source: &str,
+        fs_source: &str,
+    ) -> Self {
+        // The built-in blit outputs linear color; encode it by hand for linear 8/10-bit surfaces
+        let fragment_entry = match fragment_entry {
+            None if fs_source == Self::BLIT_SHADER && needs_srgb_encode(core.config.format) => {
+                Some("fs_main_srgb")
+            }
+            entry => entry,
+        };
         let bind_group_layouts: &[Option<&wgpu::BindGroupLayout>] = &[Some(layout)];
         let time_bind_group_layout =
             core.device
@@ -133,6 +436,8 @@ impl RenderKit {
             TimeUniform {
                 time: 0.0,
                 frame: 0,
+                delta: 0.0,
+                _padding: 0,
             },
             &time_bind_group_layout,
             0,
@@ -149,7 +454,8 @@ impl RenderKit {
                 mid_energy: 0.0,
                 high_energy: 0.0,
                 total_energy: 0.0,
-                _energy_padding: [0.0, 0.0, 0.0],
+                _energy_padding: 0.0,
+                _energy_padding2: [0.0, 0.0],
             },
             &resolution_bind_group_layout,
             0,
@@ -158,13 +464,13 @@ impl RenderKit {
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
                 label: Some("Vertex Shader"),
-                source: wgpu::ShaderSource::Wgsl(Self::VERTEX_SHADER.into()),
+                source: wgpu::ShaderSource::Wgsl(vs_source.into()),
             });
         let fs_shader = core
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
                 label: Some("Fragment Shader"),
-                source: wgpu::ShaderSource::Wgsl(Self::BLIT_SHADER.into()),
+                source: wgpu::ShaderSource::Wgsl(fs_source.into()),
             });
         let texture_bind_group_layout = TextureManager::create_display_layout(&core.device);
         let pipeline_layout = core
@@ -182,6 +488,38 @@ impl RenderKit {
             &pipeline_layout,
             fragment_entry,
         );
+        let alpha_capture_renderer = {
+            let vs = core.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Alpha Capture Vertex Shader"),
+                source: wgpu::ShaderSource::Wgsl(Self::VERTEX_SHADER.into()),
+            });
+            let fs = core.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Alpha Capture Fragment Shader"),
+                source: wgpu::ShaderSource::Wgsl(Self::BLIT_SHADER.into()),
+            });
+            let layout = core.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Alpha Capture Pipeline Layout"),
+                bind_group_layouts: &[Some(&texture_bind_group_layout)],
+                immediate_size: 0,
+            });
+            Renderer::new(&core.device, &vs, &fs, CAPTURE_FORMAT, &layout, Some("fs_main_alpha"))
+        };
+        let capture_renderer = (core.config.format != CAPTURE_FORMAT).then(|| {
+            let vs = core.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Capture Vertex Shader"),
+                source: wgpu::ShaderSource::Wgsl(Self::VERTEX_SHADER.into()),
+            });
+            let fs = core.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Capture Fragment Shader"),
+                source: wgpu::ShaderSource::Wgsl(Self::BLIT_SHADER.into()),
+            });
+            let layout = core.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Capture Pipeline Layout"),
+                bind_group_layouts: &[Some(&texture_bind_group_layout)],
+                immediate_size: 0,
+            });
+            Renderer::new(&core.device, &vs, &fs, CAPTURE_FORMAT, &layout, None)
+        });
         let context = egui::Context::default();
         let egui_state = egui_winit::State::new(
             context.clone(),
@@ -205,6 +543,8 @@ impl RenderKit {
 
         Self {
             renderer,
+            alpha_capture_renderer,
+            capture_renderer,
             #[cfg(feature = "media")]
             video_texture_manager: None,
             #[cfg(feature = "media")]
@@ -213,6 +553,8 @@ impl RenderKit {
             webcam_texture_manager: None,
             #[cfg(feature = "media")]
             using_webcam_texture: false,
+            #[cfg(feature = "media")]
+            mic_input: None,
             texture_manager: Some(texture_manager),
             egui_renderer,
             egui_state,
@@ -232,19 +574,79 @@ impl RenderKit {
             compute_shader: None,
             fps_tracker,
             mouse_tracker,
+            gamepad_tracker: GamepadTracker::new(),
             mouse_uniform: None,
             mouse_bind_group_layout: None,
             using_hdri_texture: false,
             hdri_metadata: None,
             hdri_file_data: None,
             initial_logical_height: core.size.height as f32 / core.window().scale_factor() as f32,
+            render_pipeline_layout: pipeline_layout,
+            fragment_entry: fragment_entry.map(str::to_string),
+            render_shaders: (vs_shader, fs_shader),
+            shader_hot_reload: None,
+            render_scale: 1.0,
+            output_size: None,
+            blit_filter: wgpu::FilterMode::Linear,
+            media_watch: None,
+            media_path: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            clipboard: None,
+            offscreen: None,
+            egui_previews: HashMap::new(),
+            preview_sampler: core.device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Preview Sampler"),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            last_frame_time: 0.0,
+            load_op: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+        }
+    }
+
+    /// Add the last media file and the clock to `session`, for `ShaderManager::save_session`
+    pub fn save_session(&self, session: &mut Session) {
+        session.media_path = self.media_path.clone();
+        session.time = self.controls.get_time(&self.start_time);
+        session.paused = self.controls.is_paused();
+    }
+
+    /// Reopen the saved media file and put the clock back where it was
+    pub fn load_session(&mut self, core: &Core, session: &Session) {
+        if let Some(path) = &session.media_path
+            && let Err(e) = self.load_media(core, path)
+        {
+            warn!("Failed to reopen {path:?} from the session: {e}");
+        }
+        self.controls.set_paused(session.paused);
+        self.controls.seek(&self.start_time, session.time);
+        self.last_frame_time = session.time;
+    }
+
+    /// Move the clock forward so `paused_for` doesn't show up as a jump in `time`.
+    /// A pause from the controls already holds the time, so nothing changes then.
+    pub fn skip_time(&mut self, paused_for: Duration) {
+        if !self.controls.is_paused() {
+            self.start_time += paused_for;
         }
     }
 
     pub fn update_time(&mut self, queue: &wgpu::Queue) {
         self.time_uniform.data.time = self.start_time.elapsed().as_secs_f32();
+        self.time_uniform.data.delta = self.frame_delta();
         self.time_uniform.update(queue);
     }
+
+    /// Shader time that passed since the last presented frame, for frame-rate independent
+    /// motion: 0 while paused or right after a reset, at most `MAX_FRAME_DELTA`. Pass it
+    /// to `ComputeShader::set_time`; exports replace it with the fixed `1 / fps`.
+    pub fn frame_delta(&self) -> f32 {
+        let now = self.controls.get_time(&self.start_time);
+        (now - self.last_frame_time).clamp(0.0, Self::MAX_FRAME_DELTA)
+    }
     pub fn update_resolution(
         &mut self,
         queue: &wgpu::Queue,
@@ -406,10 +808,277 @@ impl RenderKit {
         full_output: egui::FullOutput,
     ) {
         let mut encoder = frame.encoder;
+        let save_screenshot = std::mem::take(&mut self.key_handler.screenshot_requested);
+        let copy_to_clipboard = std::mem::take(&mut self.key_handler.clipboard_requested);
         self.handle_render_output(core, &frame.view, full_output, &mut encoder);
-        core.queue.submit(std::iter::once(encoder.finish()));
+        if save_screenshot || copy_to_clipboard {
+            // Redrawn into a capture texture, so the UI stays out and the surface format
+            // and usage don't matter
+            if let Some(image) = self.capture_screenshot(core, encoder) {
+                if save_screenshot {
+                    Self::save_screenshot(&image);
+                }
+                if copy_to_clipboard {
+                    self.set_clipboard_image(&image);
+                }
+            }
+        } else {
+            core.queue.submit(std::iter::once(encoder.finish()));
+        }
         frame.output.present();
         self.fps_tracker.update();
+        self.last_frame_time = self.controls.get_time(&self.start_time);
+    }
+
+    /// Run the display pass into an internal texture instead of the surface and return it,
+    /// so one shader's output can feed another (e.g. `update_input_texture(&t.view, &t.sampler, ..)`).
+    /// Draws the RenderKit's compute output, or else the current media texture, at window
+    /// size. The texture is reused across frames and recreated when the window resizes.
+    pub fn render_to_texture(&mut self, core: &Core, encoder: &mut wgpu::CommandEncoder) -> &TextureManager {
+        self.ensure_offscreen(core);
+        let source = match &self.compute_shader {
+            Some(compute) => Some(&compute.get_output_texture().bind_group),
+            None => self.get_current_texture_manager().map(|texture| &texture.bind_group),
+        };
+        let target = self.offscreen.as_ref().expect("offscreen target was just created");
+        if let Some(source) = source {
+            self.renderer.render_to_view(encoder, &target.texture.create_view(&Default::default()), source);
+        }
+        target
+    }
+
+    /// [`render_to_texture`](Self::render_to_texture) for a source this RenderKit doesn't own,
+    /// such as a `ComputeShader` kept next to it
+    pub fn render_bind_group_to_texture(
+        &mut self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        bind_group: &wgpu::BindGroup,
+    ) -> &TextureManager {
+        self.ensure_offscreen(core);
+        let target = self.offscreen.as_ref().expect("offscreen target was just created");
+        self.renderer.render_to_view(encoder, &target.texture.create_view(&Default::default()), bind_group);
+        target
+    }
+
+    /// The display pipeline writes the surface format, so the offscreen texture uses it too.
+    /// Its sampled view reads it as sRGB where that's a reinterpretation away, which undoes
+    /// the blit's manual encode on linear surfaces and hands the next shader linear color.
+    fn ensure_offscreen(&mut self, core: &Core) {
+        let format = core.config.format;
+        let size = (core.size.width.max(1), core.size.height.max(1));
+        if let Some(target) = &self.offscreen {
+            let current = target.texture.size();
+            if (current.width, current.height) == size && target.texture.format() == format {
+                return;
+            }
+        }
+        let (texture, view) = Self::create_display_target(core, size, "Offscreen Render Texture");
+        let sampler = core.device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("Offscreen Render Bind Group"),
+        });
+        self.offscreen = Some(TextureManager {
+            texture,
+            view,
+            sampler,
+            bind_group,
+        });
+    }
+
+    /// Texture in the surface format for the display pass to draw into, with a sampled
+    /// view that reads it back as linear color (see [`ensure_offscreen`](Self::ensure_offscreen))
+    fn create_display_target(core: &Core, size: (u32, u32), label: &str) -> (wgpu::Texture, wgpu::TextureView) {
+        let format = core.config.format;
+        let sampled_format = format.add_srgb_suffix();
+        let view_formats = [sampled_format];
+        let texture = core.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: if sampled_format != format { &view_formats } else { &[] },
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(sampled_format),
+            ..Default::default()
+        });
+        (texture, view)
+    }
+
+    /// Show `texture` in the UI: draw it with the display pass into a thumbnail `width`
+    /// pixels wide, registered with egui under `name`, and return it for `ui.image(..)`.
+    /// Useful for watching an intermediate buffer, e.g. a multi-pass
+    /// `get_read_texture("buffer_a")`. Call it every frame before building the UI, so it
+    /// follows textures recreated on resize; the returned id stays the same while the
+    /// thumbnail is re-registered behind it.
+    pub fn egui_preview(
+        &mut self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        name: &str,
+        texture: &wgpu::Texture,
+        width: u32,
+    ) -> egui::load::SizedTexture {
+        let width = width.max(1);
+        let height = ((width as u64 * texture.height() as u64 / texture.width().max(1) as u64) as u32).max(1);
+        let stale = self.egui_previews.get(name).is_none_or(|preview| {
+            let current = preview.texture.size();
+            (current.width, current.height) != (width, height) || preview.texture.format() != core.config.format
+        });
+        if stale {
+            let (target, view) = Self::create_display_target(core, (width, height), &format!("{name} Preview"));
+            let id = match self.egui_previews.get(name) {
+                Some(preview) => {
+                    self.egui_renderer.update_egui_texture_from_wgpu_texture(
+                        &core.device,
+                        &view,
+                        wgpu::FilterMode::Linear,
+                        preview.id,
+                    );
+                    preview.id
+                }
+                None => self
+                    .egui_renderer
+                    .register_native_texture(&core.device, &view, wgpu::FilterMode::Linear),
+            };
+            self.egui_previews.insert(name.to_string(), EguiPreview { texture: target, id });
+        }
+        let preview = &self.egui_previews[name];
+
+        let source_view = texture.create_view(&Default::default());
+        let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&source_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.preview_sampler),
+                },
+            ],
+            label: Some(&format!("{name} Preview Source")),
+        });
+        self.renderer.render_sized_to_view(
+            encoder,
+            &preview.texture.create_view(&Default::default()),
+            &bind_group,
+            (texture.width(), texture.height()),
+        );
+        egui::load::SizedTexture::new(preview.id, [width as f32, height as f32])
+    }
+
+    /// Unregister a thumbnail from [`egui_preview`](Self::egui_preview)
+    pub fn free_egui_preview(&mut self, name: &str) {
+        if let Some(preview) = self.egui_previews.remove(name) {
+            self.egui_renderer.free_texture(&preview.id);
+        }
+    }
+
+    /// Put the next frame on the system clipboard, as the C key does. The capture is
+    /// the same as the F12 screenshot, so the UI is left out.
+    pub fn copy_to_clipboard(&mut self) {
+        self.key_handler.clipboard_requested = true;
+    }
+
+    /// Submit `encoder` and read back what the renderer last displayed (or else the
+    /// compute output or media texture) at its source size
+    fn capture_screenshot(&self, core: &Core, encoder: wgpu::CommandEncoder) -> Option<image::RgbaImage> {
+        let source = self.renderer.last_source().or_else(|| match &self.compute_shader {
+            Some(compute) => Some(compute.get_output_texture().bind_group.clone()),
+            None => self.get_current_texture_manager().map(|texture| texture.bind_group.clone()),
+        });
+        let Some(source) = source else {
+            core.queue.submit(std::iter::once(encoder.finish()));
+            warn!("Screenshot unavailable: nothing has been drawn yet");
+            return None;
+        };
+        let (width, height) = self
+            .renderer
+            .source_size()
+            .unwrap_or((core.config.width, core.config.height));
+        let pixels = self.read_capture_pixels(core, encoder, &source, width, height, false);
+        let image = image::RgbaImage::from_raw(width, height, pixels);
+        if image.is_none() {
+            error!("Screenshot readback failed: bad image size");
+        }
+        image
+    }
+
+    /// Write a screenshot to `screenshot_<unix time>.png` in the working directory
+    fn save_screenshot(image: &image::RgbaImage) {
+        let timestamp = web_time::SystemTime::now()
+            .duration_since(web_time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let path = format!(
+            "screenshot_{}_{:03}.png",
+            timestamp.as_secs(),
+            timestamp.subsec_millis()
+        );
+        match image.save(&path) {
+            Ok(()) => info!("Screenshot saved to {path}"),
+            Err(e) => error!("Failed to save screenshot {path}: {e}"),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn set_clipboard_image(&mut self, _image: &image::RgbaImage) {
+        warn!("Copying frames to the clipboard isn't supported in the browser");
+    }
+
+    /// Logs and gives up where the platform has no image clipboard
+    #[cfg(not(target_arch = "wasm32"))]
+    fn set_clipboard_image(&mut self, image: &image::RgbaImage) {
+        if self.clipboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.clipboard = Some(clipboard),
+                Err(e) => {
+                    warn!("Clipboard unavailable: {e}");
+                    return;
+                }
+            }
+        }
+        let Some(clipboard) = &mut self.clipboard else {
+            return;
+        };
+        let data = arboard::ImageData {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            bytes: std::borrow::Cow::Borrowed(image.as_raw()),
+        };
+        match clipboard.set_image(data) {
+            Ok(()) => info!("Frame copied to the clipboard"),
+            Err(e) => warn!("Failed to copy the frame to the clipboard: {e}"),
+        }
     }
 
     pub fn apply_default_style(ctx: &egui::Context) {
@@ -429,8 +1098,14 @@ impl RenderKit {
         });
     }
 
-    pub fn load_media<P: AsRef<Path>>(&mut self, core: &Core, path: P) -> anyhow::Result<()> {
-        let path_ref = path.as_ref();
+    pub fn load_media<P: AsRef<Path>>(&mut self, core: &Core, path: P) -> Result<(), CuneusError> {
+        let path = path.as_ref();
+        self.open_media(core, path).map_err(CuneusError::Media)?;
+        self.media_path = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    fn open_media(&mut self, core: &Core, path_ref: &Path) -> anyhow::Result<()> {
         let extension = path_ref
             .extension()
             .and_then(|ext| ext.to_str())
@@ -524,6 +1199,43 @@ impl RenderKit {
             _ => Err(anyhow::anyhow!("Unsupported media format: {:?}", path_ref)),
         }
     }
+
+    /// [`load_media`](Self::load_media) `path` and load it again whenever the file
+    /// changes on disk, so an input image or video can be edited while the shader runs.
+    /// The reload happens in [`update_current_texture`](Self::update_current_texture),
+    /// which already hands the new texture on. Replaces any previously watched file.
+    pub fn watch_media<P: AsRef<Path>>(&mut self, core: &Core, path: P) -> Result<(), CuneusError> {
+        let path = path.as_ref();
+        self.load_media(core, path)?;
+        self.media_watch = Some(FileWatch::new(path).map_err(|e| CuneusError::Media(e.into()))?);
+        info!("Watching {path:?} for changes");
+        Ok(())
+    }
+
+    pub fn unwatch_media(&mut self) {
+        self.media_watch = None;
+    }
+
+    /// Reload the watched media file if it changed; a failed load keeps the old texture
+    fn reload_watched_media(&mut self, core: &Core) -> bool {
+        let Some(watch) = &mut self.media_watch else {
+            return false;
+        };
+        if !watch.poll() {
+            return false;
+        }
+        let path = watch.path().to_path_buf();
+        match self.load_media(core, &path) {
+            Ok(()) => {
+                info!("Reloaded {path:?}");
+                true
+            }
+            Err(e) => {
+                warn!("Failed to reload {path:?}: {e}");
+                false
+            }
+        }
+    }
     #[cfg(feature = "media")]
     pub fn update_video_texture(&mut self, core: &Core, queue: &wgpu::Queue) -> bool {
         if self.using_video_texture {
@@ -540,24 +1252,24 @@ impl RenderKit {
         false
     }
     #[cfg(feature = "media")]
-    pub fn play_video(&mut self) -> anyhow::Result<()> {
+    pub fn play_video(&mut self) -> Result<(), CuneusError> {
         if let Some(video_manager) = &mut self.video_texture_manager {
-            video_manager.play()?;
+            video_manager.play().map_err(CuneusError::Media)?;
         }
         Ok(())
     }
     #[cfg(feature = "media")]
-    pub fn pause_video(&mut self) -> anyhow::Result<()> {
+    pub fn pause_video(&mut self) -> Result<(), CuneusError> {
         if let Some(video_manager) = &mut self.video_texture_manager {
-            video_manager.pause()?;
+            video_manager.pause().map_err(CuneusError::Media)?;
         }
         Ok(())
     }
     #[cfg(feature = "media")]
-    pub fn seek_video(&mut self, position_seconds: f64) -> anyhow::Result<()> {
+    pub fn seek_video(&mut self, position_seconds: f64) -> Result<(), CuneusError> {
         if let Some(video_manager) = &mut self.video_texture_manager {
             let position = gstreamer::ClockTime::from_seconds(position_seconds as u64);
-            video_manager.seek(position)?;
+            video_manager.seek(position).map_err(CuneusError::Media)?;
         }
         Ok(())
     }
@@ -570,17 +1282,18 @@ impl RenderKit {
     }
 
     #[cfg(feature = "media")]
-    pub fn start_webcam(&mut self, core: &Core, device_index: Option<u32>) -> anyhow::Result<()> {
+    pub fn start_webcam(&mut self, core: &Core, device_index: Option<u32>) -> Result<(), CuneusError> {
         info!("Starting webcam");
         let webcam_manager = WebcamTextureManager::new(
             &core.device,
             &core.queue,
             &self.texture_bind_group_layout,
             device_index,
-        )?;
+        )
+        .map_err(CuneusError::Media)?;
 
         let mut manager = webcam_manager;
-        manager.start()?;
+        manager.start().map_err(CuneusError::Media)?;
 
         self.webcam_texture_manager = Some(manager);
         self.using_webcam_texture = true;
@@ -591,11 +1304,25 @@ impl RenderKit {
         Ok(())
     }
 
+    /// Start capturing the default microphone into the audio spectrum. Video or webcam
+    /// audio takes precedence while either is playing.
+    #[cfg(feature = "media")]
+    pub fn start_mic_input(&mut self) -> Result<(), CuneusError> {
+        self.mic_input = Some(MicInput::new().map_err(CuneusError::Media)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "media")]
+    pub fn stop_mic_input(&mut self) {
+        self.mic_input = None;
+        self.spectrum_analyzer.reset_smoothing();
+    }
+
     #[cfg(feature = "media")]
-    pub fn stop_webcam(&mut self) -> anyhow::Result<()> {
+    pub fn stop_webcam(&mut self) -> Result<(), CuneusError> {
         info!("Stopping webcam");
         if let Some(webcam_manager) = &mut self.webcam_texture_manager {
-            webcam_manager.stop()?;
+            webcam_manager.stop().map_err(CuneusError::Media)?;
         }
         self.using_webcam_texture = false;
         self.webcam_texture_manager = None;
@@ -669,6 +1396,99 @@ impl RenderKit {
         });
         (capture_texture, output_buffer)
     }
+
+    /// Submit `encoder` plus a draw of `source` into a capture texture, and read it back
+    /// as RGBA8 at `width` x `height`. Shared by exports and screenshots, so neither
+    /// depends on the surface format or on the surface allowing copies.
+    pub(crate) fn read_capture_pixels(
+        &self,
+        core: &Core,
+        mut encoder: wgpu::CommandEncoder,
+        source: &wgpu::BindGroup,
+        width: u32,
+        height: u32,
+        transparent: bool,
+    ) -> Vec<u8> {
+        let (capture_texture, output_buffer) = self.create_capture_texture(&core.device, width, height);
+        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        {
+            let (renderer, clear) = if transparent {
+                (&self.alpha_capture_renderer, wgpu::Color::TRANSPARENT)
+            } else {
+                let renderer = self.capture_renderer.as_ref().unwrap_or(&self.renderer);
+                // Capture targets are sRGB, so the linear clear color applies as is
+                (renderer, self.clear_color())
+            };
+            // The output is at the capture size, so a fit mode set for the window is a no-op
+            renderer.render_pass_to_view(
+                &mut encoder,
+                &capture_view,
+                source,
+                1,
+                wgpu::LoadOp::Clear(clear),
+                Some((width, height)),
+            );
+        }
+
+        let align = 256;
+        let unpadded_bytes_per_row = width * 4;
+        let padding = (align - unpadded_bytes_per_row % align) % align;
+        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &capture_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &output_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        core.queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = output_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).unwrap();
+        });
+
+        let _ = core
+            .device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .unwrap();
+        rx.recv().unwrap().unwrap();
+
+        let padded_data = buffer_slice.get_mapped_range().to_vec();
+        let mut unpadded_data = Vec::with_capacity((width * height * 4) as usize);
+        for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
+            unpadded_data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
+        }
+        // Hand out RGBA whatever the capture format is, so savers and encoders never swap
+        if matches!(
+            capture_texture.format(),
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            for px in unpadded_data.chunks_exact_mut(4) {
+                px.swap(0, 2);
+            }
+        }
+
+        unpadded_data
+    }
     pub fn default_handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
         if self.forward_to_egui(core, event) {
             return true;
@@ -717,11 +1537,36 @@ impl RenderKit {
 
     pub fn default_resize(&mut self, core: &Core, compute_shader: &mut ComputeShader) {
         self.update_resolution(&core.queue, core.size);
-        compute_shader.resize(core, core.size.width, core.size.height);
+        let size = self.render_size(core);
+        compute_shader.resize(core, size.width, size.height);
+        compute_shader.set_output_filter(&core.device, self.blit_filter);
+        let output = compute_shader.get_output_texture().texture.size();
+        self.renderer.set_source_size(output.width, output.height);
     }
 
     pub fn apply_media_requests(&mut self, core: &Core, controls_request: &ControlsRequest) {
         self.apply_control_request(controls_request.clone());
+        if let Some(sample_count) = controls_request.set_sample_count {
+            self.set_sample_count(core, sample_count);
+        }
+        if let Some(load_op) = controls_request.set_load_op {
+            self.set_load_op(core, load_op);
+        }
+        if let Some(fit_mode) = controls_request.set_fit_mode {
+            self.set_fit_mode(fit_mode);
+        }
+        if let Some(mode) = controls_request.set_present_mode {
+            core.request_present_mode(mode);
+        }
+        if let Some(scale) = controls_request.set_render_scale {
+            self.set_render_scale(scale);
+        }
+        if let Some(filter) = controls_request.set_blit_filter {
+            self.set_blit_filter(filter);
+        }
+        if let Some(target_fps) = controls_request.set_target_fps {
+            core.set_target_fps(target_fps);
+        }
         #[cfg(feature = "media")]
         {
             self.handle_video_requests(core, controls_request);
@@ -734,7 +1579,11 @@ impl RenderKit {
         if request.should_reset {
             self.start_time = Instant::now();
         }
+        let seek_time = request.seek_time;
         self.controls.apply_ui_request(request);
+        if let Some(time) = seek_time {
+            self.controls.seek(&self.start_time, time);
+        }
     }
     #[cfg(feature = "media")]
     pub fn update_audio_spectrum(&mut self, queue: &wgpu::Queue) {
@@ -744,6 +1593,14 @@ impl RenderKit {
         if self.export_audio_active {
             return;
         }
+        if !self.using_video_texture && !self.using_webcam_texture {
+            if let Some(mic) = &mut self.mic_input {
+                mic.poll_audio_messages();
+                self.spectrum_analyzer
+                    .update_spectrum_from_mic(queue, &mut self.resolution_uniform, mic);
+                return;
+            }
+        }
         self.spectrum_analyzer.update_spectrum(
             queue,
             &mut self.resolution_uniform,
@@ -975,7 +1832,7 @@ impl RenderKit {
             );
             Ok(())
         } else {
-            Err(notify::Error::generic("No compute shader initialized"))
+            Err(std::io::Error::other("No compute shader initialized").into())
         }
     }
 
@@ -992,8 +1849,9 @@ impl RenderKit {
     }
 
     pub fn resize_compute_shader(&mut self, core: &Core) {
+        let size = self.render_size(core);
         if let Some(compute) = &mut self.compute_shader {
-            compute.resize(core, core.size.width, core.size.height);
+            compute.resize(core, size.width, size.height);
         }
     }
 
@@ -1116,6 +1974,9 @@ impl RenderKit {
 
     /// Update current active texture and return whether an external texture update is needed
     pub fn update_current_texture(&mut self, core: &Core, queue: &wgpu::Queue) -> bool {
+        if self.reload_watched_media(core) {
+            return true;
+        }
         #[cfg(feature = "media")]
         {
             if self.using_video_texture {
@@ -1127,4 +1988,10 @@ impl RenderKit {
         // Static textures don't need updates
         false
     }
-}
\ No newline at end of file
+}
+
+/// Linear 8/10-bit formats store values as written, so sRGB has to be applied in the shader.
+/// Float surfaces expect linear (extended sRGB) values and are left alone.
+fn needs_srgb_encode(format: wgpu::TextureFormat) -> bool {
+    !format.is_srgb() && !matches!(format, wgpu::TextureFormat::Rgba16Float | wgpu::TextureFormat::Rgba32Float)
+}
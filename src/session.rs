@@ -0,0 +1,120 @@
+use crate::preset::PresetFile;
+use crate::CuneusError;
+use anyhow::{Context, Result};
+use log::{info, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Command-line flag that makes [`ShaderApp::with_session`](crate::ShaderApp::with_session)
+/// start clean instead of restoring the saved session
+pub const NO_RESTORE_FLAG: &str = "--no-restore";
+
+/// App state kept across launches by [`ShaderApp::with_session`](crate::ShaderApp::with_session):
+/// window geometry, the last media file, the clock, the params struct (stored like a
+/// preset) and any named values a shader adds, such as a camera.
+///
+/// ```ignore
+/// fn save_session(&self, session: &mut Session) {
+///     self.base.save_session(session);
+///     let _ = session.set_params(&self.current_params, MyParams::FIELD_NAMES);
+///     session.set("camera", &self.camera.rotation);
+/// }
+///
+/// fn load_session(&mut self, core: &Core, session: &Session) {
+///     self.base.load_session(core, session);
+///     if let Some(params) = session.params::<MyParams>(MyParams::FIELD_NAMES) {
+///         self.current_params = params;
+///         self.compute_shader.set_custom_params(params, &core.queue);
+///     }
+///     if let Some(rotation) = session.get("camera") {
+///         self.camera.rotation = rotation;
+///     }
+/// }
+/// ```
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Logical inner size of the window
+    pub window_size: Option<(u32, u32)>,
+    /// Physical outer position of the window; platforms like Wayland don't report it
+    pub window_position: Option<(i32, i32)>,
+    /// Last file opened with [`RenderKit::load_media`](crate::RenderKit::load_media)
+    pub media_path: Option<PathBuf>,
+    /// Shader time from `ShaderControls::get_time`
+    pub time: f32,
+    pub paused: bool,
+    params: Option<PresetFile>,
+    values: BTreeMap<String, serde_json::Value>,
+}
+
+impl Session {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CuneusError> {
+        Self::read(path.as_ref()).map_err(CuneusError::Session)
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path).with_context(|| format!("Failed to read session {path:?}"))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid session file {path:?}"))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CuneusError> {
+        self.write(path.as_ref()).map_err(CuneusError::Session)
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create session folder {dir:?}"))?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write session {path:?}"))?;
+        info!("Saved session {path:?}");
+        Ok(())
+    }
+
+    /// Record the window's current size and position; a minimized window keeps the old values
+    pub fn capture_window(&mut self, window: &winit::window::Window) {
+        let size = window.inner_size();
+        if size.width > 0 && size.height > 0 {
+            let logical = size.to_logical::<u32>(window.scale_factor());
+            self.window_size = Some((logical.width, logical.height));
+        }
+        if let Ok(position) = window.outer_position() {
+            self.window_position = Some((position.x, position.y));
+        }
+    }
+
+    /// Store `params` the way [`save_preset`](crate::save_preset) does
+    pub fn set_params<T: bytemuck::Pod>(&mut self, params: &T, fields: &[&str]) -> Result<(), CuneusError> {
+        self.params = Some(PresetFile::encode(params, fields).map_err(CuneusError::Session)?);
+        Ok(())
+    }
+
+    /// The saved params, or `None` if there are none or they were saved for a different
+    /// struct layout (logged, so a changed struct starts from its defaults). `fields` is
+    /// checked like [`load_preset`](crate::load_preset) does.
+    pub fn params<T: bytemuck::Pod>(&self, fields: &[&str]) -> Option<T> {
+        self.params
+            .as_ref()?
+            .decode(fields)
+            .inspect_err(|e| warn!("Ignoring saved params: {e:#}"))
+            .ok()
+    }
+
+    /// Store any serializable value under `key`, e.g. a camera position
+    pub fn set<T: Serialize>(&mut self, key: &str, value: &T) {
+        match serde_json::to_value(value) {
+            Ok(value) => {
+                self.values.insert(key.to_string(), value);
+            }
+            Err(e) => warn!("Failed to store session value {key}: {e}"),
+        }
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.values.get(key)?;
+        serde_json::from_value(value.clone())
+            .inspect_err(|e| warn!("Ignoring session value {key}: {e}"))
+            .ok()
+    }
+}
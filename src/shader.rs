@@ -1,14 +1,52 @@
-use crate::Core;
+use crate::{Core, CuneusError, Session};
+use std::time::Duration;
 use winit::event::WindowEvent;
 
 pub trait ShaderManager {
     fn init(core: &Core) -> Self
     where
         Self: Sized;
+    /// Fallible `init`: override it to return shader or asset errors with `?`
+    /// (e.g. from `ComputeShader::from_file`), then pass `MyShader::init_result` to
+    /// `ShaderApp::run`, which reports the error and exits instead of panicking.
+    fn init_result(core: &Core) -> Result<Self, CuneusError>
+    where
+        Self: Sized,
+    {
+        Ok(Self::init(core))
+    }
     fn resize(&mut self, _core: &Core) {}
     fn update(&mut self, _core: &Core) {}
     fn render(&mut self, core: &Core) -> Result<(), crate::SurfaceError>;
     fn handle_input(&mut self, _core: &Core, _event: &WindowEvent) -> bool {
         false
     }
+    /// Rendering resumes after `paused_for` without redraws (see
+    /// `ShaderApp::with_pause_when_unfocused`). Forward it to
+    /// `RenderKit::skip_time` so animations continue where they stopped.
+    fn resume(&mut self, _core: &Core, _paused_for: Duration) {}
+    /// Add state to the session written on exit (see `ShaderApp::with_session`).
+    /// The window geometry is already filled in; `RenderKit::save_session` adds the
+    /// media path and clock.
+    fn save_session(&self, _session: &mut Session) {}
+    /// Restore a saved session, called right after `init` on launch
+    fn load_session(&mut self, _core: &Core, _session: &Session) {}
+}
+
+/// What a shader constructor passed to `ShaderApp::run` may return: the shader itself,
+/// as `init` does, or a `Result` like `init_result`'s.
+pub trait IntoShader<S> {
+    fn into_shader(self) -> Result<S, CuneusError>;
+}
+
+impl<S: ShaderManager> IntoShader<S> for S {
+    fn into_shader(self) -> Result<S, CuneusError> {
+        Ok(self)
+    }
+}
+
+impl<S: ShaderManager, E: Into<CuneusError>> IntoShader<S> for Result<S, E> {
+    fn into_shader(self) -> Result<S, CuneusError> {
+        self.map_err(Into::into)
+    }
 }
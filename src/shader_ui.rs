@@ -0,0 +1,179 @@
+use std::ops::RangeInclusive;
+
+/// Widgets for params structs, generated by [`uniform_params!`](crate::uniform_params)
+/// from `#[ui(...)]` field attributes. Fields without one are left out.
+///
+/// ```rust,no_run
+/// cuneus::uniform_params! {
+///     pub struct Params {
+///         #[ui(name = "Exposure", range = 0.0..=5.0)]
+///         exposure: f32,
+///         #[ui(name = "Tint", color)]
+///         tint: [f32; 3],
+///         #[ui(range = 1..=512, logarithmic)]
+///         max_steps: u32,
+///         #[ui(toggle)]
+///         shadows: u32,
+///         _padding: [f32; 2],
+///     }
+/// }
+/// ```
+///
+/// `changed |= params.render_ui(ui);` then lays out one widget per field.
+pub trait ShaderUi {
+    /// Returns true if any value changed
+    fn render_ui(&mut self, ui: &mut egui::Ui) -> bool;
+}
+
+/// Options from a field's `#[ui(...)]` attribute
+#[derive(Clone, Debug)]
+pub struct UiSpec {
+    /// Label, `name = "..."`; the field name by default
+    pub name: &'static str,
+    /// Slider bounds, `range = lo..=hi`; without it numbers get a drag value
+    pub range: Option<RangeInclusive<f64>>,
+    /// `color`: color picker for `[f32; 3]` and `[f32; 4]`
+    pub color: bool,
+    /// `logarithmic`: slider steps scale with the value
+    pub logarithmic: bool,
+    /// `toggle`: checkbox for a `u32` flag (0 or 1)
+    pub toggle: bool,
+}
+
+impl UiSpec {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            range: None,
+            color: false,
+            logarithmic: false,
+            toggle: false,
+        }
+    }
+}
+
+/// A uniform field type [`ShaderUi`] can draw
+pub trait UiField {
+    fn ui_field(&mut self, ui: &mut egui::Ui, spec: &UiSpec) -> bool;
+}
+
+fn number_ui<N: egui::emath::Numeric>(value: &mut N, ui: &mut egui::Ui, spec: &UiSpec) -> bool {
+    match &spec.range {
+        Some(range) => {
+            let range = N::from_f64(*range.start())..=N::from_f64(*range.end());
+            ui.add(egui::Slider::new(value, range).logarithmic(spec.logarithmic).text(spec.name))
+                .changed()
+        }
+        None => {
+            ui.horizontal(|ui| {
+                let speed = if N::INTEGRAL { 1.0 } else { 0.01 };
+                let changed = ui.add(egui::DragValue::new(value).speed(speed)).changed();
+                ui.label(spec.name);
+                changed
+            })
+            .inner
+        }
+    }
+}
+
+fn vector_ui(values: &mut [f32], ui: &mut egui::Ui, spec: &UiSpec) -> bool {
+    ui.horizontal(|ui| {
+        let mut changed = false;
+        for value in values {
+            changed |= match &spec.range {
+                Some(range) => ui
+                    .add(egui::DragValue::new(value).speed(0.01).range(range.clone()))
+                    .changed(),
+                None => ui.add(egui::DragValue::new(value).speed(0.01)).changed(),
+            };
+        }
+        ui.label(spec.name);
+        changed
+    })
+    .inner
+}
+
+impl UiField for f32 {
+    fn ui_field(&mut self, ui: &mut egui::Ui, spec: &UiSpec) -> bool {
+        number_ui(self, ui, spec)
+    }
+}
+
+impl UiField for i32 {
+    fn ui_field(&mut self, ui: &mut egui::Ui, spec: &UiSpec) -> bool {
+        number_ui(self, ui, spec)
+    }
+}
+
+impl UiField for u32 {
+    fn ui_field(&mut self, ui: &mut egui::Ui, spec: &UiSpec) -> bool {
+        if !spec.toggle {
+            return number_ui(self, ui, spec);
+        }
+        let mut on = *self != 0;
+        let changed = ui.checkbox(&mut on, spec.name).changed();
+        *self = on as u32;
+        changed
+    }
+}
+
+impl UiField for [f32; 2] {
+    fn ui_field(&mut self, ui: &mut egui::Ui, spec: &UiSpec) -> bool {
+        vector_ui(self, ui, spec)
+    }
+}
+
+impl UiField for [f32; 3] {
+    fn ui_field(&mut self, ui: &mut egui::Ui, spec: &UiSpec) -> bool {
+        if !spec.color {
+            return vector_ui(self, ui, spec);
+        }
+        ui.horizontal(|ui| {
+            let changed = ui.color_edit_button_rgb(self).changed();
+            ui.label(spec.name);
+            changed
+        })
+        .inner
+    }
+}
+
+impl UiField for [f32; 4] {
+    fn ui_field(&mut self, ui: &mut egui::Ui, spec: &UiSpec) -> bool {
+        if !spec.color {
+            return vector_ui(self, ui, spec);
+        }
+        ui.horizontal(|ui| {
+            let changed = ui.color_edit_button_rgba_unmultiplied(self).changed();
+            ui.label(spec.name);
+            changed
+        })
+        .inner
+    }
+}
+
+/// Apply `#[ui(...)]` options to a [`UiSpec`], for [`uniform_params!`](crate::uniform_params)
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ui_spec {
+    ($spec:ident) => {};
+    ($spec:ident name = $name:literal $(, $($rest:tt)*)?) => {
+        $spec.name = $name;
+        $crate::__ui_spec!($spec $($($rest)*)?);
+    };
+    ($spec:ident range = $lo:literal ..= $hi:literal $(, $($rest:tt)*)?) => {
+        $spec.range = Some($lo as f64..=$hi as f64);
+        $crate::__ui_spec!($spec $($($rest)*)?);
+    };
+    ($spec:ident color $(, $($rest:tt)*)?) => {
+        $spec.color = true;
+        $crate::__ui_spec!($spec $($($rest)*)?);
+    };
+    ($spec:ident logarithmic $(, $($rest:tt)*)?) => {
+        $spec.logarithmic = true;
+        $crate::__ui_spec!($spec $($($rest)*)?);
+    };
+    ($spec:ident toggle $(, $($rest:tt)*)?) => {
+        $spec.toggle = true;
+        $crate::__ui_spec!($spec $($($rest)*)?);
+    };
+}
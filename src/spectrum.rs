@@ -1,6 +1,8 @@
 // This file is part of the gstreamer, and its inits the spectrum analyzer and bpm.
 // I also did some smoothing related to audio data for the spectrum analyzer.
 #[cfg(feature = "media")]
+use crate::gst::mic::MicInput;
+#[cfg(feature = "media")]
 use crate::gst::offline_audio::AudioSample;
 #[cfg(feature = "media")]
 use crate::gst::video::VideoTextureManager;
@@ -100,6 +102,34 @@ impl SpectrumAnalyzer {
         }
     }
 
+    /// Standalone mic path: like the webcam mic, BPM stays at 0
+    pub fn update_spectrum_from_mic(
+        &mut self,
+        queue: &wgpu::Queue,
+        resolution_uniform: &mut UniformBinding<ResolutionUniform>,
+        mic: &MicInput,
+    ) {
+        for i in 0..32 {
+            for j in 0..4 {
+                resolution_uniform.data.audio_data[i][j] = 0.0;
+            }
+        }
+
+        let spectrum_data = mic.spectrum_data();
+        if !spectrum_data.magnitudes.is_empty() {
+            self.process_audio_sample(
+                resolution_uniform,
+                &spectrum_data.magnitudes,
+                spectrum_data.bands,
+                mic.audio_level().rms_db as f32,
+                0.0,
+                /* log_live = */ true,
+            );
+        }
+
+        resolution_uniform.update(queue);
+    }
+
     /// Offline path: feed a single timestamped sample collected by the offline
     /// analyzer into the same processing pipeline that live updates use, so the
     /// visual output is identical between preview and export.
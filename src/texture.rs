@@ -136,3 +136,377 @@ impl TextureManager {
         );
     }
 }
+
+impl TextureManager {
+    /// Load an equirectangular (latitude/longitude) environment map into an `Rgba16Float`
+    /// texture, keeping `.hdr`/`.exr` radiance above 1.0. 8-bit images are decoded from sRGB
+    /// so every map samples as linear color. Maps wider than the device allows are scaled
+    /// down. The sampler wraps horizontally, so `sample_equirect` from
+    /// `<cuneus/envmap.wgsl>` has no seam; it can also be fed to
+    /// `ComputeShader::update_input_texture`.
+    pub fn load_equirect(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: impl AsRef<std::path::Path>,
+        layout: &wgpu::BindGroupLayout,
+    ) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let image = image::open(path)
+            .map_err(|e| anyhow::anyhow!("Failed to load environment map {}: {e}", path.display()))?;
+        let is_hdr = matches!(
+            image,
+            image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_)
+        );
+        let mut pixels = image.to_rgba32f();
+        if !is_hdr {
+            for pixel in pixels.pixels_mut() {
+                for channel in &mut pixel.0[..3] {
+                    *channel = crate::color::srgb_to_linear(*channel);
+                }
+            }
+        }
+        let max_width = device.limits().max_texture_dimension_2d;
+        if pixels.width() > max_width {
+            let height = (pixels.height() as u64 * max_width as u64 / pixels.width() as u64).max(1) as u32;
+            log::warn!(
+                "Environment map {} is {}x{}, scaling to {max_width}x{height}",
+                path.display(),
+                pixels.width(),
+                pixels.height()
+            );
+            pixels = image::imageops::resize(&pixels, max_width, height, image::imageops::FilterType::Triangle);
+        }
+        let (width, height) = pixels.dimensions();
+        let half_floats: Vec<u16> = pixels.as_raw().iter().map(|&value| f32_to_f16(value)).collect();
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Environment Map"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba16Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&half_floats),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(8 * width),
+                rows_per_image: Some(height),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("Environment Map Bind Group"),
+        });
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+            bind_group,
+        })
+    }
+}
+
+/// Encode an IEEE half float, rounding to nearest and saturating at the largest finite half
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    if value.is_nan() {
+        return sign | 0x7e00;
+    }
+    let magnitude = value.abs();
+    if magnitude >= 65504.0 {
+        return sign | 0x7bff;
+    }
+    // Below the smallest normal half: multiples of 2^-24
+    if magnitude < 2f32.powi(-14) {
+        return sign | (magnitude * 2f32.powi(24)).round() as u16;
+    }
+    let exponent = ((bits >> 23) & 0xff) + 15 - 127;
+    let mantissa = bits & 0x7f_ffff;
+    // A rounding carry into the exponent still gives the right value
+    let half = (exponent << 10 | mantissa >> 13) + ((mantissa >> 12) & 1);
+    sign | half.min(0x7bff) as u16
+}
+
+/// A 3D texture written by a compute shader and sampled by later passes (e.g. raymarching).
+pub struct VolumeTexture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+    /// `texture_3d<f32>` at binding 0 and a filtering sampler at binding 1, see [`VolumeTexture::create_layout`]
+    pub bind_group: wgpu::BindGroup,
+}
+impl VolumeTexture {
+    /// Sampling layout for volumes: 3D texture at binding 0, sampler at binding 1,
+    /// visible to fragment and compute stages.
+    pub fn create_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("Volume Texture Layout"),
+        })
+    }
+
+    /// Create a `size[0] x size[1] x size[2]` storage volume. `format` must support storage
+    /// writes and filtering (e.g. `Rgba16Float`).
+    pub fn new(device: &wgpu::Device, size: [u32; 3], format: wgpu::TextureFormat, label: &str) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: size[2],
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &Self::create_layout(device),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some(&format!("{label} Sample Bind Group")),
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+            bind_group,
+        }
+    }
+
+    pub fn size(&self) -> [u32; 3] {
+        let size = self.texture.size();
+        [size.width, size.height, size.depth_or_array_layers]
+    }
+}
+
+const RESAMPLE_SHADER: &str = r#"
+override dst_width: f32;
+override dst_height: f32;
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+fn load(c: vec2<i32>, max_c: vec2<i32>) -> vec4<f32> {
+    return textureLoad(source, clamp(c, vec2<i32>(0), max_c), 0);
+}
+
+// Manual bilinear filtering, so unfilterable formats like Rgba32Float work too
+@fragment
+fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let src_size = vec2<f32>(textureDimensions(source));
+    let p = pos.xy / vec2<f32>(dst_width, dst_height) * src_size - 0.5;
+    let base = floor(p);
+    let f = p - base;
+    let c = vec2<i32>(base);
+    let max_c = vec2<i32>(src_size) - 1;
+    let top = mix(load(c, max_c), load(c + vec2<i32>(1, 0), max_c), f.x);
+    let bottom = mix(load(c + vec2<i32>(0, 1), max_c), load(c + vec2<i32>(1, 1), max_c), f.x);
+    return mix(top, bottom, f.y);
+}
+"#;
+
+/// `RENDER_ATTACHMENT` if `format` allows it, so storage textures can be
+/// [`resample_texture`] targets without breaking formats that can't be rendered to
+pub(crate) fn resample_target_usage(device: &wgpu::Device, format: wgpu::TextureFormat) -> wgpu::TextureUsages {
+    let allowed = format.guaranteed_format_features(device.features()).allowed_usages;
+    allowed & wgpu::TextureUsages::RENDER_ATTACHMENT
+}
+
+/// Draw `src` stretched over all of `dst` with bilinear filtering, e.g. to carry
+/// accumulated state into a texture reallocated at a new size.
+///
+/// `src` needs `TEXTURE_BINDING` and `dst` `RENDER_ATTACHMENT`; both must be 2D with
+/// float sample types. Returns false, leaving `dst` untouched, for other formats.
+pub fn resample_texture(
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    src: &wgpu::Texture,
+    dst: &wgpu::Texture,
+) -> bool {
+    let is_float = |texture: &wgpu::Texture| {
+        matches!(
+            texture.format().sample_type(None, None),
+            Some(wgpu::TextureSampleType::Float { .. })
+        )
+    };
+    let usable = src.usage().contains(wgpu::TextureUsages::TEXTURE_BINDING)
+        && dst.usage().contains(wgpu::TextureUsages::RENDER_ATTACHMENT);
+    if !usable || !is_float(src) || !is_float(dst) {
+        log::warn!(
+            "resample_texture: can't resample {:?} into {:?}",
+            src.format(),
+            dst.format()
+        );
+        return false;
+    }
+
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Resample Shader"),
+        source: wgpu::ShaderSource::Wgsl(RESAMPLE_SHADER.into()),
+    });
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Resample Layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
+            count: None,
+        }],
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Resample Pipeline Layout"),
+        bind_group_layouts: &[Some(&bind_group_layout)],
+        immediate_size: 0,
+    });
+    let constants = [
+        ("dst_width", dst.width() as f64),
+        ("dst_height", dst.height() as f64),
+    ];
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Resample Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some("fs_main"),
+            targets: &[Some(dst.format().into())],
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants: &constants,
+                ..Default::default()
+            },
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview_mask: None,
+        cache: None,
+    });
+
+    let src_view = src.create_view(&wgpu::TextureViewDescriptor::default());
+    let dst_view = dst.create_view(&wgpu::TextureViewDescriptor::default());
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Resample Bind Group"),
+        layout: &bind_group_layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::TextureView(&src_view),
+        }],
+    });
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Resample Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: &dst_view,
+            depth_slice: None,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+        multiview_mask: None,
+    });
+    pass.set_pipeline(&pipeline);
+    pass.set_bind_group(0, &bind_group, &[]);
+    pass.draw(0..3, 0..1);
+    true
+}
@@ -0,0 +1,92 @@
+/// WGSL source of the shared tonemapping library, also available to shaders loaded from
+/// a file as `//!include <cuneus/tonemap.wgsl>`. Prepend it to embedded sources that
+/// don't go through the include preprocessor.
+pub const TONEMAP_WGSL: &str = include_str!("../shaders/tonemap.wgsl");
+
+/// The curve `tonemap()` applies, stored in [`ToneMapParams::curve`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMapCurve {
+    /// Narkowicz's ACES approximation, `aces()`
+    #[default]
+    Aces = 0,
+    /// Hill's ACES RRT + ODT fit, `aces_fitted()`
+    AcesFitted = 1,
+    Reinhard = 2,
+    /// Hable's Uncharted 2 curve, `filmic()`
+    Filmic = 3,
+    /// Clamp to [0, 1] only
+    None = 4,
+}
+
+impl ToneMapCurve {
+    pub const ALL: [ToneMapCurve; 5] = [
+        ToneMapCurve::Aces,
+        ToneMapCurve::AcesFitted,
+        ToneMapCurve::Reinhard,
+        ToneMapCurve::Filmic,
+        ToneMapCurve::None,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ToneMapCurve::Aces => "ACES",
+            ToneMapCurve::AcesFitted => "ACES (fitted)",
+            ToneMapCurve::Reinhard => "Reinhard",
+            ToneMapCurve::Filmic => "Filmic",
+            ToneMapCurve::None => "None",
+        }
+    }
+}
+
+crate::uniform_params! {
+    /// Matches `ToneMapParams` in `<cuneus/tonemap.wgsl>`, for `tonemap(color, params)`
+    pub struct ToneMapParams {
+        #[ui(name = "Exposure", range = 0.0..=8.0, logarithmic)]
+        pub exposure: f32,
+        #[ui(name = "Gamma", range = 1.0..=3.0)]
+        pub gamma: f32,
+        #[ui(name = "Saturation", range = 0.0..=2.0)]
+        pub saturation: f32,
+        pub curve: u32,
+    }
+}
+
+impl Default for ToneMapParams {
+    fn default() -> Self {
+        Self {
+            exposure: 1.0,
+            gamma: 2.2,
+            saturation: 1.0,
+            curve: ToneMapCurve::Aces as u32,
+        }
+    }
+}
+
+impl ToneMapParams {
+    pub fn curve(&self) -> ToneMapCurve {
+        ToneMapCurve::ALL
+            .get(self.curve as usize)
+            .copied()
+            .unwrap_or(ToneMapCurve::None)
+    }
+
+    pub fn set_curve(&mut self, curve: ToneMapCurve) {
+        self.curve = curve as u32;
+    }
+
+    /// Sliders for every field plus a curve dropdown; returns true if anything changed
+    pub fn render_tonemap_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut curve = self.curve();
+        egui::ComboBox::from_label("Tonemap")
+            .selected_text(curve.name())
+            .show_ui(ui, |ui| {
+                for option in ToneMapCurve::ALL {
+                    ui.selectable_value(&mut curve, option, option.name());
+                }
+            });
+        let mut changed = curve != self.curve();
+        self.set_curve(curve);
+        changed |= crate::ShaderUi::render_ui(self, ui);
+        changed
+    }
+}
@@ -1,7 +1,36 @@
+use std::ops::Range;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use wgpu::util::DeviceExt;
 pub trait UniformProvider {
     fn as_bytes(&self) -> &[u8];
 }
+
+/// Alignment of a field type in WGSL's uniform layout, which `#[derive(UniformProvider)]`
+/// checks each field's offset against. Implement it for your own field types to use
+/// them in derived structs.
+pub trait WgslAlign {
+    const ALIGN: usize;
+    /// Whether 2-4 element arrays of this type are WGSL vectors
+    const SCALAR: bool = false;
+}
+
+macro_rules! impl_wgsl_scalar {
+    ($($ty:ty),*) => {
+        $(impl WgslAlign for $ty {
+            const ALIGN: usize = 4;
+            const SCALAR: bool = true;
+        })*
+    };
+}
+impl_wgsl_scalar!(f32, u32, i32);
+
+impl<T: WgslAlign, const N: usize> WgslAlign for [T; N] {
+    // vec2 aligns on 8; vec3, vec4, matrices and arrays (whose elements are padded
+    // to 16 in uniforms) on 16
+    const ALIGN: usize = if T::SCALAR && N == 2 { 8 } else { 16 };
+}
+
 crate::uniform_params! {
     pub struct ResolutionUniform {
         pub dimensions: [f32; 2],
@@ -12,14 +41,41 @@ crate::uniform_params! {
         pub mid_energy: f32,
         pub high_energy: f32,
         pub total_energy: f32,
-        pub _energy_padding: [f32; 3],
+        pub _energy_padding: f32,
+        pub _energy_padding2: [f32; 2],
+    }
+}
+
+/// Write the bytes of `bytes` that differ from `uploaded` (what the buffer last
+/// received) as one 4-byte aligned range, then remember them. Uploads everything when
+/// the sizes don't match, and nothing when nothing changed.
+pub(crate) fn write_changed(queue: &wgpu::Queue, buffer: &wgpu::Buffer, uploaded: &mut Vec<u8>, bytes: &[u8]) {
+    if uploaded.len() != bytes.len() {
+        queue.write_buffer(buffer, 0, bytes);
+        uploaded.clear();
+        uploaded.extend_from_slice(bytes);
+        return;
     }
+    let differs = |(a, b): (&u8, &u8)| a != b;
+    let Some(first) = bytes.iter().zip(uploaded.iter()).position(differs) else {
+        return;
+    };
+    let last = bytes.iter().zip(uploaded.iter()).rposition(differs).unwrap_or(first);
+    let align = wgpu::COPY_BUFFER_ALIGNMENT as usize;
+    let start = first / align * align;
+    let end = (last + 1).next_multiple_of(align).min(bytes.len());
+    queue.write_buffer(buffer, start as u64, &bytes[start..end]);
+    uploaded[start..end].copy_from_slice(&bytes[start..end]);
 }
 
 pub struct UniformBinding<T: UniformProvider> {
     pub buffer: wgpu::Buffer,
     pub bind_group: wgpu::BindGroup,
     pub data: T,
+    /// Bytes the buffer last received, kept only once `update_changed` is used
+    uploaded: Mutex<Vec<u8>>,
+    /// Set when an upload bypassed `uploaded`, so `update_changed` sends everything next
+    uploaded_stale: AtomicBool,
 }
 impl<T: UniformProvider> UniformBinding<T> {
     pub fn new(
@@ -29,6 +85,12 @@ impl<T: UniformProvider> UniformBinding<T> {
         layout: &wgpu::BindGroupLayout,
         binding: u32,
     ) -> Self {
+        // Derived impls check this at compile time; hand-written ones only here
+        debug_assert!(
+            data.as_bytes().len().is_multiple_of(16),
+            "{label}: uniform size {} is not a multiple of 16 bytes (add padding fields)",
+            data.as_bytes().len()
+        );
         let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some(label),
             contents: data.as_bytes(),
@@ -45,10 +107,161 @@ impl<T: UniformProvider> UniformBinding<T> {
         Self {
             buffer,
             bind_group,
+            uploaded: Mutex::new(Vec::new()),
+            uploaded_stale: AtomicBool::new(true),
             data,
         }
     }
     pub fn update(&self, queue: &wgpu::Queue) {
         queue.write_buffer(&self.buffer, 0, self.data.as_bytes());
+        self.uploaded_stale.store(true, Ordering::Relaxed);
+    }
+
+    /// Upload only the bytes of `data` that changed since the last upload, as one
+    /// range from the first to the last changed byte. Does nothing if nothing changed,
+    /// so it's cheap to call every frame on a large struct where one field moves.
+    /// The first call uploads everything, as does the first after `update` or `update_range`.
+    pub fn update_changed(&self, queue: &wgpu::Queue) {
+        let mut uploaded = self.uploaded.lock().unwrap();
+        if self.uploaded_stale.swap(false, Ordering::Relaxed) {
+            uploaded.clear();
+        }
+        write_changed(queue, &self.buffer, &mut uploaded, self.data.as_bytes());
+    }
+
+    /// Upload just `bytes` of `data`, e.g. one field found with `std::mem::offset_of!`.
+    /// The range is widened to 4-byte alignment; an out-of-bounds range uploads
+    /// everything instead.
+    pub fn update_range(&self, queue: &wgpu::Queue, bytes: Range<usize>) {
+        let data = self.data.as_bytes();
+        if bytes.start > bytes.end || bytes.end > data.len() {
+            self.update(queue);
+            return;
+        }
+        let align = wgpu::COPY_BUFFER_ALIGNMENT as usize;
+        let start = bytes.start / align * align;
+        let end = bytes.end.next_multiple_of(align).min(data.len());
+        queue.write_buffer(&self.buffer, start as u64, &data[start..end]);
+        self.uploaded_stale.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Many copies of one uniform struct in a single buffer, selected per draw or dispatch
+/// with a dynamic offset, so one bind group serves every parameter set:
+///
+/// ```ignore
+/// for i in 0..variants.len() {
+///     pass.set_bind_group(1, &variants.bind_group, &[variants.offset(i)]);
+///     pass.draw(0..4, 0..1);
+/// }
+/// ```
+///
+/// Entries are spaced by the device's `min_uniform_buffer_offset_alignment`. The
+/// binding must be declared with `has_dynamic_offset: true`, see
+/// [`create_layout`](Self::create_layout).
+pub struct UniformArrayBinding<T: UniformProvider> {
+    pub buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+    /// Fixed at creation; the buffer has room for exactly these entries
+    data: Vec<T>,
+    stride: u64,
+}
+impl<T: UniformProvider> UniformArrayBinding<T> {
+    /// Layout with a single dynamic-offset uniform of `T` at `binding`
+    pub fn create_layout(
+        device: &wgpu::Device,
+        binding: u32,
+        visibility: wgpu::ShaderStages,
+        label: &str,
+    ) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<T>() as u64),
+                },
+                count: None,
+            }],
+        })
+    }
+
+    /// # Panics
+    /// If `data` is empty.
+    pub fn new(
+        device: &wgpu::Device,
+        label: &str,
+        data: Vec<T>,
+        layout: &wgpu::BindGroupLayout,
+        binding: u32,
+    ) -> Self {
+        assert!(!data.is_empty(), "UniformArrayBinding needs at least one entry");
+        let size = data[0].as_bytes().len() as u64;
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let stride = size.next_multiple_of(alignment);
+        let mut contents = vec![0u8; (stride * data.len() as u64) as usize];
+        for (chunk, entry) in contents.chunks_exact_mut(stride as usize).zip(&data) {
+            chunk[..size as usize].copy_from_slice(entry.as_bytes());
+        }
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: &contents,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(size),
+                }),
+            }],
+            label: Some(label),
+        });
+        Self {
+            buffer,
+            bind_group,
+            data,
+            stride,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// The entries, to edit before [`update`](Self::update). The count can't change,
+    /// since the buffer was sized for it.
+    pub fn data_mut(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// Dynamic offset selecting entry `index`, for `set_bind_group`
+    pub fn offset(&self, index: usize) -> u32 {
+        (index as u64 * self.stride) as u32
+    }
+
+    /// Upload every entry. Call once after changing the entries, before the passes that use it.
+    pub fn update(&self, queue: &wgpu::Queue) {
+        for index in 0..self.data.len() {
+            self.update_index(queue, index);
+        }
+    }
+
+    pub fn update_index(&self, queue: &wgpu::Queue, index: usize) {
+        queue.write_buffer(&self.buffer, self.offset(index) as u64, self.data[index].as_bytes());
     }
 }
@@ -0,0 +1,85 @@
+//! GPU radix sort stability: equal keys keep their payloads in input order.
+//! Skipped when there's no adapter to run on.
+
+use cuneus::radix_sort::RadixSorter;
+
+/// A device for sorting, or `None` to skip. CPU rasterizers don't guarantee forward
+/// progress between workgroups, which the sort's decoupled lookback waits on.
+fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+    let Ok(adapter) = pollster::block_on(instance.request_adapter(&Default::default())) else {
+        eprintln!("no GPU adapter, skipping");
+        return None;
+    };
+    if adapter.get_info().device_type == wgpu::DeviceType::Cpu {
+        eprintln!("{} is a CPU adapter, skipping", adapter.get_info().name);
+        return None;
+    }
+    pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        label: Some("Radix Sort Test"),
+        required_features: adapter.features() & wgpu::Features::SUBGROUP,
+        ..Default::default()
+    }))
+    .ok()
+}
+
+/// xorshift keys from a range much smaller than `count`, as in the radix sort bench,
+/// so most keys repeat
+fn duplicate_keys(count: u32) -> Vec<u32> {
+    let mut state = 0x9E37_79B9u32;
+    (0..count)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state % (count / 64).max(1)
+        })
+        .collect()
+}
+
+fn assert_stable(name: &str, keys: &[u32], sorted_keys: &[u32], sorted_payload: &[u32]) {
+    let mut expected: Vec<u32> = keys.to_vec();
+    expected.sort();
+    assert_eq!(sorted_keys, expected.as_slice(), "{name}: keys aren't sorted");
+    for (i, pair) in sorted_payload.windows(2).enumerate() {
+        if sorted_keys[i] == sorted_keys[i + 1] {
+            assert!(
+                pair[0] < pair[1],
+                "{name}: key {} has payload {} before {}",
+                sorted_keys[i],
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+}
+
+#[test]
+fn equal_keys_keep_payload_order() {
+    let Some((device, queue)) = device() else {
+        return;
+    };
+    // Several blocks, so runs of equal keys cross workgroup boundaries
+    let count = 100_000;
+    let keys = duplicate_keys(count);
+    let payload: Vec<u32> = (0..count).collect();
+
+    for (name, sorter) in [
+        ("portable", RadixSorter::new_without_subgroups(&device)),
+        ("subgroups", RadixSorter::new(&device)),
+    ] {
+        if name == "subgroups" && !sorter.uses_subgroups() {
+            continue;
+        }
+        let buffers = sorter.create_sort_buffers(&device, count);
+        queue.write_buffer(&buffers.keys_a, 0, bytemuck::cast_slice(&keys));
+        queue.write_buffer(&buffers.payload_a, 0, bytemuck::cast_slice(&payload));
+        let mut encoder = device.create_command_encoder(&Default::default());
+        sorter.sort(&mut encoder, &queue, &buffers, count);
+        queue.submit(Some(encoder.finish()));
+
+        let (sorted_keys, sorted_payload) = buffers.read_back(&device, &queue);
+        assert!(!sorter.check_failed(&device, &queue, &buffers), "{name}: sort reported failure");
+        assert_stable(name, &keys, &sorted_keys, &sorted_payload);
+    }
+}